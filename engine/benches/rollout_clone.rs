@@ -0,0 +1,66 @@
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+use engine::{Deck, GameState, Zone};
+
+const ITERATIONS: u32 = 20_000;
+
+// Time `run` over `ITERATIONS` calls and print the mean
+fn bench(name: &str, mut run: impl FnMut())
+{
+    for _ in 0..ITERATIONS / 10 {
+        run();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let nanos = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    println!("{:<40} {:>10.0} ns/iter", name, nanos);
+}
+
+// A copy with every fragment duplicated, as cloning a game cost before fragments were shared
+fn deep_clone(game: &GameState) -> GameState
+{
+    let mut copy = game.clone();
+    for player in copy.players.iter_mut() {
+        for card in player.zones.values_mut().flatten() {
+            for fragment in card.fragments.values_mut() {
+                *fragment = Arc::from(fragment.box_clone());
+            }
+        }
+    }
+    copy
+}
+
+fn main()
+{
+    // Midgame: both players have lands and creatures out and most of their library left
+    let deck = Deck::example();
+    let mut game = GameState::new_seeded(2, &deck, 1);
+    while game.turns < 8 {
+        game.step();
+    }
+    let permanents: usize = game
+        .players
+        .iter()
+        .map(|p| p.zones.get(&Zone::Battlefield).unwrap().len())
+        .sum();
+    println!("Turn {} game with {} permanents", game.turns, permanents);
+
+    bench("clone with every fragment copied", || {
+        black_box(deep_clone(black_box(&game)));
+    });
+    bench("clone_for_rollout", || {
+        black_box(black_box(&game).clone_for_rollout());
+    });
+    bench("clone_for_rollout + play one turn", || {
+        let mut copy = black_box(&game).clone_for_rollout();
+        let turn = copy.turns;
+        while copy.turns == turn && !copy.is_game_over() {
+            copy.step();
+        }
+        black_box(copy);
+    });
+}
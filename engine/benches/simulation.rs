@@ -0,0 +1,156 @@
+use std::hint::black_box;
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use engine::components::Components;
+use engine::{BatchConfig, Deck, GameState, Zone, card_by_name, combat, mana, run_game, tappable};
+
+// A deck of `count` copies of each named card
+fn deck(cards: &[(&str, usize)]) -> Deck
+{
+    Deck::new(
+        cards
+            .iter()
+            .flat_map(|&(name, count)| (0..count).map(move |_| card_by_name(name).unwrap()))
+            .collect(),
+    )
+}
+
+// Red-green beatdown: mana dorks, burn, pump spells and auras, so games go through most of the
+// engine (the stack, triggers, combat keywords and attachments)
+fn gruul() -> Deck
+{
+    deck(&[
+        ("Mountain", 10),
+        ("Forest", 10),
+        ("Taiga", 4),
+        ("Llanowar Elves", 4),
+        ("Raging Goblin", 4),
+        ("Grizzly Bears", 4),
+        ("Centaur Courser", 4),
+        ("Hill Giant", 4),
+        ("Craw Wurm", 2),
+        ("Lightning Bolt", 4),
+        ("Giant Growth", 4),
+        ("Rancor", 4),
+        ("Lava Axe", 2),
+    ])
+}
+
+// Whole games, one per iteration, each from a different seed
+fn games(c: &mut Criterion)
+{
+    let scenarios = [
+        (
+            "bears goldfish",
+            BatchConfig::with_opponent(Deck::example(), Deck::goldfish()),
+        ),
+        (
+            "gruul goldfish",
+            BatchConfig::with_opponent(gruul(), Deck::goldfish()),
+        ),
+        (
+            "gruul vs bears",
+            BatchConfig::with_opponent(gruul(), Deck::example()),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("games");
+    group.throughput(Throughput::Elements(1));
+    for (name, config) in scenarios.iter() {
+        let mut index = 0;
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                index += 1;
+                black_box(run_game(config, index))
+            })
+        });
+    }
+    group.finish();
+}
+
+// Copying a midgame state, as search does before every rollout
+fn state_clone(c: &mut Criterion)
+{
+    let config = BatchConfig::with_opponent(gruul(), Deck::example());
+    let mut game = config.new_game(1, true);
+    while game.turns < 8 && !game.is_game_over() {
+        game.step();
+    }
+
+    let mut group = c.benchmark_group("state clone");
+    group.bench_function("clone", |b| b.iter(|| black_box(black_box(&game).clone())));
+    group.bench_function("clone_for_rollout", |b| {
+        b.iter(|| black_box(black_box(&game).clone_for_rollout()))
+    });
+    group.bench_function("play one turn", |b| {
+        b.iter_batched(
+            || game.clone_for_rollout(),
+            |mut copy: GameState| {
+                let turn = copy.turns;
+                while copy.turns == turn && !copy.is_game_over() {
+                    copy.step();
+                }
+                copy
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+// What the turn loop asks of a midgame battlefield over and over: what can attack, and what can
+// tap for mana. Looking each card up per question, building columns per question, and reading
+// the columns the game keeps with its battlefield
+fn battlefield_queries(c: &mut Criterion)
+{
+    let config = BatchConfig::with_opponent(gruul(), Deck::example());
+    let mut game = config.new_game(1, true);
+    while game.turns < 8 && !game.is_game_over() {
+        game.step();
+    }
+    let player = game.current_player_index;
+    let battlefield = &game.players[player].zones[&Zone::Battlefield];
+
+    let mut group = c.benchmark_group("battlefield queries");
+    group.bench_function("card lookups", |b| {
+        b.iter(|| {
+            let attackers: Vec<usize> = (0..battlefield.len())
+                .filter(|i| combat::can_attack(&battlefield[*i]))
+                .collect();
+            let sources: Vec<(usize, Vec<mana::ManaColor>)> = battlefield
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| {
+                    mana::is_mana_source(card)
+                        && !tappable::is_tapped(card)
+                        && !engine::has_summoning_sickness(card)
+                })
+                .flat_map(|(i, card)| {
+                    std::iter::repeat_n(
+                        (i, mana::produced_colors(card)),
+                        mana::produced_amount(card) as usize,
+                    )
+                })
+                .collect();
+            black_box((attackers, sources))
+        })
+    });
+    group.bench_function("columns per query", |b| {
+        b.iter(|| {
+            black_box((
+                Components::of(black_box(battlefield)).ready_attackers(),
+                Components::of(black_box(battlefield)).untapped_mana(),
+            ))
+        })
+    });
+    group.bench_function("kept columns", |b| {
+        b.iter(|| {
+            let components = black_box(&game).components(player);
+            black_box((components.ready_attackers(), components.untapped_mana()))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, games, state_clone, battlefield_queries);
+criterion_main!(benches);
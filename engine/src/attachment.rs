@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::{AuraFragment, Card, CardFragmentKind, CardId, EquipmentFragment, StatModifier};
+use crate::keyword::Keyword;
+use crate::mana::ManaCost;
+
+/// What an Aura or Equipment gives the creature it's attached to.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentBonus
+{
+    #[serde(default)]
+    pub power: i32,
+    #[serde(default)]
+    pub toughness: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<Keyword>,
+}
+
+pub fn is_aura(card: &Card) -> bool
+{
+    card.fragments.contains_key(&CardFragmentKind::Aura)
+}
+
+pub fn is_equipment(card: &Card) -> bool
+{
+    card.fragments.contains_key(&CardFragmentKind::Equipment)
+}
+
+/// The creature an Aura or Equipment is attached to.
+pub fn attached_to(card: &Card) -> Option<CardId>
+{
+    card.fragment::<AuraFragment>()
+        .and_then(|af| af.attached_to)
+        .or_else(|| {
+            card.fragment::<EquipmentFragment>()
+                .and_then(|ef| ef.attached_to)
+        })
+}
+
+/// Attach an Aura or Equipment to `host`, or unattach it with `None`.
+pub fn attach(card: &mut Card, host: Option<CardId>)
+{
+    if let Some(af) = card.fragment_mut::<AuraFragment>() {
+        af.attached_to = host;
+    }
+    if let Some(ef) = card.fragment_mut::<EquipmentFragment>() {
+        ef.attached_to = host;
+    }
+}
+
+pub fn bonus(card: &Card) -> Option<&AttachmentBonus>
+{
+    card.fragment::<AuraFragment>()
+        .map(|af| &af.bonus)
+        .or_else(|| card.fragment::<EquipmentFragment>().map(|ef| &ef.bonus))
+}
+
+pub fn equip_cost(card: &Card) -> Option<ManaCost>
+{
+    card.fragment::<EquipmentFragment>().map(|ef| ef.equip_cost)
+}
+
+/// Recompute what every creature on a battlefield gets from the attachments on it, on top of
+/// the `shared` bonus they all get (from anthems; see statics.rs).
+pub fn apply_bonuses(battlefield: &mut [Card], shared: &AttachmentBonus)
+{
+    let bonuses: Vec<(CardId, AttachmentBonus)> = battlefield
+        .iter()
+        .filter_map(|card| Some((attached_to(card)?, bonus(card)?.clone())))
+        .collect();
+
+    for card in battlefield
+        .iter_mut()
+        .filter(|c| crate::creature::is_creature(c))
+    {
+        let mut modifier = StatModifier {
+            power: shared.power,
+            toughness: shared.toughness,
+        };
+        let mut keywords = shared.keywords.clone();
+        for (_, bonus) in bonuses.iter().filter(|(host, _)| *host == card.id) {
+            modifier.power += bonus.power;
+            modifier.toughness += bonus.toughness;
+            keywords.extend(bonus.keywords.iter().copied());
+        }
+        crate::creature::set_continuous_effects(card, modifier, keywords);
+    }
+}
+
+/// The creature an attachment goes on: the one hitting hardest, so the bonus is used right away.
+pub fn choose_host(battlefield: &[Card]) -> Option<CardId>
+{
+    battlefield
+        .iter()
+        .filter(|c| crate::creature::is_creature(c))
+        .max_by_key(|c| {
+            (
+                crate::creature::effective_stats(c).map_or(0, |s| s.power),
+                std::cmp::Reverse(c.id),
+            )
+        })
+        .map(|c| c.id)
+}
@@ -0,0 +1,264 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::card::CardType;
+use crate::game::{GameState, PlayerCounter, Zone};
+
+// What a condition measures
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Quantity
+{
+    Turn,
+    Life,
+    Poison,
+    Energy,
+    Experience,
+    Hand,
+    Library,
+    Graveyard,
+    Creatures,
+    Lands,
+    // Total power of the player's creatures
+    Power,
+}
+
+impl Quantity
+{
+    const NAMES: [(&'static str, Quantity); 11] = [
+        ("turn", Quantity::Turn),
+        ("life", Quantity::Life),
+        ("poison", Quantity::Poison),
+        ("energy", Quantity::Energy),
+        ("experience", Quantity::Experience),
+        ("hand", Quantity::Hand),
+        ("library", Quantity::Library),
+        ("graveyard", Quantity::Graveyard),
+        ("creatures", Quantity::Creatures),
+        ("lands", Quantity::Lands),
+        ("power", Quantity::Power),
+    ];
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Comparison
+{
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison
+{
+    fn holds(self, left: i64, right: i64) -> bool
+    {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+// One comparison such as `opponent_life <= 5`
+#[derive(Clone, Debug, PartialEq)]
+struct Condition
+{
+    quantity: Quantity,
+    // Measured for the first opponent instead of the simulated player
+    opponent: bool,
+    comparison: Comparison,
+    value: i64,
+}
+
+impl Condition
+{
+    fn holds(&self, game: &GameState) -> bool
+    {
+        let player = &game.players[if self.opponent { 1 } else { 0 }];
+        let count = |zone: Zone| player.zones.get(&zone).map_or(0, Vec::len) as i64;
+        let battlefield = player
+            .zones
+            .get(&Zone::Battlefield)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let creatures = || {
+            battlefield
+                .iter()
+                .filter(|c| crate::creature::is_creature(c))
+        };
+        let measured = match self.quantity {
+            Quantity::Turn => game.turns as i64,
+            Quantity::Life => player.life as i64,
+            Quantity::Poison => player.counters(PlayerCounter::Poison) as i64,
+            Quantity::Energy => player.counters(PlayerCounter::Energy) as i64,
+            Quantity::Experience => player.counters(PlayerCounter::Experience) as i64,
+            Quantity::Hand => count(Zone::Hand),
+            Quantity::Library => count(Zone::Library),
+            Quantity::Graveyard => count(Zone::Graveyard),
+            Quantity::Creatures => creatures().count() as i64,
+            Quantity::Lands => battlefield
+                .iter()
+                .filter(|c| c.is_type(CardType::Land))
+                .count() as i64,
+            Quantity::Power => creatures()
+                .map(|c| crate::creature::effective_stats(c).map_or(0, |s| s.power as i64))
+                .sum(),
+        };
+        self.comparison.holds(measured, self.value)
+    }
+}
+
+impl FromStr for Condition
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let s = s.trim();
+        let Some(at) = s.find(['<', '>', '=', '!']) else {
+            return Err(format!("'{}' isn't a comparison like life <= 5", s));
+        };
+        let (name, rest) = s.split_at(at);
+        let (comparison, value) = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ]
+        .into_iter()
+        .find_map(|(symbol, comparison)| rest.strip_prefix(symbol).map(|value| (comparison, value)))
+        .ok_or_else(|| format!("'{}' isn't a comparison like life <= 5", s))?;
+
+        let name = name.trim();
+        let (opponent, quantity_name) = match name.strip_prefix("opponent_") {
+            Some(quantity) => (true, quantity),
+            None => (false, name),
+        };
+        let quantity = Quantity::NAMES.iter()
+            .find(|(n, q)| *n == quantity_name && !(opponent && *q == Quantity::Turn))
+            .map(|(_, q)| *q)
+            .ok_or_else(|| format!("unknown quantity '{}' (expected turn, or life, poison, energy, experience, hand, library, graveyard, creatures, lands or power with an optional opponent_ prefix)", name))?;
+        let value = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' isn't a number", value.trim()))?;
+        Ok(Condition {
+            quantity,
+            opponent,
+            comparison,
+            value,
+        })
+    }
+}
+
+/// A condition to run a stepped game until, like `opponent_life <= 5` or `turn == 6 and
+/// creatures > 2`: comparisons of a quantity with a number, joined by `and` and `or` (`and`
+/// binds tighter). Quantities are measured for the simulated player, or for their opponent when
+/// prefixed with `opponent_`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint
+{
+    text: String,
+    // Alternatives, each of which holds when all its conditions do
+    any_of: Vec<Vec<Condition>>,
+}
+
+impl Breakpoint
+{
+    pub fn hit(&self, game: &GameState) -> bool
+    {
+        self.any_of
+            .iter()
+            .any(|all| all.iter().all(|c| c.holds(game)))
+    }
+}
+
+impl FromStr for Breakpoint
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let any_of = s
+            .split(" or ")
+            .map(|alternative| {
+                alternative
+                    .split(" and ")
+                    .map(str::parse)
+                    .collect::<Result<Vec<Condition>, String>>()
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Breakpoint {
+            text: s.trim().to_string(),
+            any_of,
+        })
+    }
+}
+
+impl fmt::Display for Breakpoint
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::Deck;
+
+    #[test]
+    fn breakpoints_parse_and_stop_when_their_condition_holds()
+    {
+        let deck = Deck::example();
+        let mut game = GameState::new_seeded(2, &deck, 3);
+        let turn: Breakpoint = "turn == 6".parse().unwrap();
+        let either: Breakpoint = "opponent_life<5 or lands >= 3 and creatures >= 2"
+            .parse()
+            .unwrap();
+        assert!(!turn.hit(&game));
+        while !turn.hit(&game) {
+            game.step();
+        }
+        assert_eq!(game.turns, 6);
+        let you = &game.players[0].zones[&Zone::Battlefield];
+        let expected = game.players[1].life < 5
+            || (you.iter().filter(|c| c.is_type(CardType::Land)).count() >= 3
+                && you
+                    .iter()
+                    .filter(|c| crate::creature::is_creature(c))
+                    .count()
+                    >= 2);
+        assert_eq!(either.hit(&game), expected);
+
+        assert!(
+            "opponent_turn > 2"
+                .parse::<Breakpoint>()
+                .unwrap_err()
+                .contains("unknown quantity")
+        );
+        assert!(
+            "life 5"
+                .parse::<Breakpoint>()
+                .unwrap_err()
+                .contains("isn't a comparison")
+        );
+        assert!(
+            "life <= five"
+                .parse::<Breakpoint>()
+                .unwrap_err()
+                .contains("isn't a number")
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::result::SimulationResult;
+
+// What a cache file holds; every game's turn count is kept so percentiles survive the round trip
+#[derive(Serialize, Deserialize)]
+struct CachedResult
+{
+    result: SimulationResult,
+    turns: Vec<u32>,
+}
+
+/// Stable 64-bit FNV-1a hash of a scenario key, which names its cache file. Unlike std's hasher
+/// it's the same on every build, so the cache stays valid between runs.
+pub fn key_hash(key: &str) -> u64
+{
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn entry_path(dir: &Path, key: &str) -> PathBuf
+{
+    dir.join(format!("{:016x}.json", key_hash(key)))
+}
+
+/// The result cached in `dir` for the scenario `key` describes; unreadable entries count as missing.
+pub fn load(dir: &Path, key: &str) -> Option<SimulationResult>
+{
+    let text = std::fs::read_to_string(entry_path(dir, key)).ok()?;
+    let cached: CachedResult = serde_json::from_str(&text).ok()?;
+    Some(SimulationResult {
+        turns: cached.turns,
+        ..cached.result
+    })
+}
+
+/// Cache `result` for the scenario `key` describes, creating `dir` if needed.
+pub fn store(dir: &Path, key: &str, result: &SimulationResult) -> io::Result<()>
+{
+    std::fs::create_dir_all(dir)?;
+    let cached = CachedResult {
+        result: result.clone(),
+        turns: result.turns.clone(),
+    };
+    std::fs::write(entry_path(dir, key), serde_json::to_string(&cached)?)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn cached_results_round_trip_by_key()
+    {
+        assert_eq!(key_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(key_hash("a"), 0xaf63_dc4c_8601_ec8c);
+
+        let dir = std::env::temp_dir().join(format!("tcg-sim-cache-{}", std::process::id()));
+        let result = SimulationResult {
+            games: 3,
+            mean_turns: 9.0,
+            turns: vec![8, 9, 10],
+            ..SimulationResult::default()
+        };
+        store(&dir, "24 lands", &result).unwrap();
+        let loaded = load(&dir, "24 lands");
+        let missing = load(&dir, "23 lands");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded, Some(result));
+        assert_eq!(missing, None);
+    }
+}
@@ -0,0 +1,221 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameState;
+use crate::gamelog::{LogRecord, Watcher};
+use crate::logfile;
+use crate::replay::Recorder;
+use crate::sim::{BatchConfig, GameOutcome};
+use crate::{ELoggingVerbosity, GameLogScope};
+
+// Defaults for --capture-slowest and --capture-missed-drops
+pub const SLOWEST_PERCENT: f64 = 1.0;
+pub const MISSED_LAND_DROPS: u32 = 3;
+
+/// Which games of a batch are worth keeping a close look at, and where to save them. Each one is
+/// played again from its seed to save its replay (for `--replay`) and a log of every action.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CaptureRules
+{
+    pub dir: PathBuf,
+    // The longest games, as a percentage of the batch; 0 for none
+    pub slowest_percent: f64,
+    // Games where the player missed at least this many land drops in the turns the curve
+    // report covers; 0 for none
+    pub missed_land_drops: u32,
+}
+
+impl CaptureRules
+{
+    pub fn new(dir: PathBuf) -> Self
+    {
+        CaptureRules {
+            dir,
+            slowest_percent: SLOWEST_PERCENT,
+            missed_land_drops: MISSED_LAND_DROPS,
+        }
+    }
+
+    /// Seeds of the games in `outcomes` the rules pick, in the order they were played, with why
+    /// each was picked.
+    pub fn interesting(&self, outcomes: &[GameOutcome]) -> Vec<(u64, String)>
+    {
+        let slowest = (outcomes.len() as f64 * self.slowest_percent / 100.0).ceil() as usize;
+        let mut by_length: Vec<&GameOutcome> = outcomes.iter().collect();
+        by_length.sort_by_key(|game| std::cmp::Reverse(game.turns));
+        let longest: Vec<u64> = by_length
+            .iter()
+            .take(slowest)
+            .map(|game| game.seed)
+            .collect();
+
+        outcomes
+            .iter()
+            .filter_map(|game| {
+                let missed = game.curve.iter().filter(|(land, _)| !land).count() as u32;
+                let mut reasons = Vec::new();
+                if longest.contains(&game.seed) {
+                    reasons.push(format!(
+                        "among the slowest {}% at {} turns",
+                        self.slowest_percent, game.turns
+                    ));
+                }
+                if self.missed_land_drops > 0 && missed >= self.missed_land_drops {
+                    reasons.push(format!("missed {} land drops", missed));
+                }
+                (!reasons.is_empty()).then(|| (game.seed, reasons.join(", ")))
+            })
+            .collect()
+    }
+
+    /// Play the game with `seed` again, on the play or the draw as it was, to save `game-SEED.json`,
+    /// its replay, and `game-SEED.log`, everything logged while it played along with each action.
+    pub fn capture(
+        &self,
+        config: &BatchConfig,
+        seed: u64,
+        on_play: bool,
+        reason: &str,
+    ) -> io::Result<()>
+    {
+        let _log = GameLogScope::enter(seed);
+        let mut game = config.new_game(seed, on_play);
+        let recorder = Recorder::start(&mut game);
+        game.watcher = Some(Arc::new(ActionLogger));
+        let ((), lines) = logfile::capture(|| {
+            while !game.is_game_over() {
+                game.step();
+            }
+        });
+
+        fs::create_dir_all(&self.dir)?;
+        recorder
+            .finish()
+            .save(&self.dir.join(format!("game-{}.json", seed)))?;
+        let outcome = GameOutcome::from_game(&game, config);
+        let ending = match (outcome.won, outcome.lost) {
+            (true, _) => "won",
+            (_, true) => "lost",
+            _ => "drawn",
+        };
+        let mut log = format!("Game {}: {}\n", seed, reason);
+        for line in lines {
+            log.push_str(&line);
+            log.push('\n');
+        }
+        log.push_str(&format!("Game {} after {} turns\n", ending, outcome.turns));
+        fs::write(log_path(&self.dir, seed), log)
+    }
+
+    /// Capture every game of the batch the rules pick.
+    pub fn capture_batch(&self, config: &BatchConfig, outcomes: &[GameOutcome])
+    {
+        let games = self.interesting(outcomes);
+        for (seed, reason) in &games {
+            let on_play = outcomes
+                .iter()
+                .find(|game| game.seed == *seed)
+                .is_some_and(|game| game.on_play);
+            if let Err(e) = self.capture(config, *seed, on_play, reason) {
+                vlog!(
+                    ELoggingVerbosity::Warning,
+                    "Can't capture game {} in {}: {}",
+                    seed,
+                    self.dir.display(),
+                    e
+                );
+                return;
+            }
+        }
+        if !games.is_empty() {
+            vlog!(
+                ELoggingVerbosity::Normal,
+                "Captured {} game(s) to {}",
+                games.len(),
+                self.dir.display()
+            );
+        }
+    }
+}
+
+fn log_path(dir: &Path, seed: u64) -> PathBuf
+{
+    dir.join(format!("game-{}.log", seed))
+}
+
+// Logs each action of a captured game
+struct ActionLogger;
+
+impl Watcher for ActionLogger
+{
+    fn action(&self, _game: &GameState, record: &LogRecord)
+    {
+        vlog!(
+            ELoggingVerbosity::VeryVerbose,
+            "Turn {}: player {} {}",
+            record.turn,
+            record.player + 1,
+            record.event
+        );
+    }
+
+    fn priority(&self, _game: &GameState, _player: usize) {}
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::Deck;
+    use crate::replay::GameRecord;
+    use crate::sim::run_game;
+
+    #[test]
+    fn the_longest_and_land_light_games_are_saved_with_a_log_and_replay()
+    {
+        let config = BatchConfig::new(Deck::example());
+        let outcomes: Vec<GameOutcome> = (0..50).map(|i| run_game(&config, i)).collect();
+        let dir = std::env::temp_dir().join(format!("tcg-sim-capture-{}", std::process::id()));
+        let rules = CaptureRules {
+            dir: dir.clone(),
+            slowest_percent: 2.0,
+            missed_land_drops: 0,
+        };
+
+        let picked = rules.interesting(&outcomes);
+        assert_eq!(picked.len(), 1);
+        let longest = outcomes.iter().map(|game| game.turns).max().unwrap();
+        let (seed, reason) = &picked[0];
+        assert_eq!(
+            outcomes
+                .iter()
+                .find(|game| game.seed == *seed)
+                .unwrap()
+                .turns,
+            longest
+        );
+
+        rules.capture_batch(&config, &outcomes);
+        let log = fs::read_to_string(log_path(&dir, *seed)).unwrap();
+        assert!(log.starts_with(&format!("Game {}: {}", seed, reason)));
+        assert!(log.contains("Turn 1: player 1"));
+        let record = GameRecord::load(&dir.join(format!("game-{}.json", seed))).unwrap();
+        assert_eq!(record.seed, *seed);
+        let _ = fs::remove_dir_all(&dir);
+
+        let land_light = CaptureRules {
+            slowest_percent: 0.0,
+            missed_land_drops: 1,
+            ..rules
+        };
+        let missed_any = outcomes
+            .iter()
+            .filter(|game| game.curve.iter().any(|(land, _)| !land))
+            .count();
+        assert_eq!(land_light.interesting(&outcomes).len(), missed_any);
+    }
+}
@@ -1,27 +1,81 @@
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 use std::any::Any;
-use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum CardType 
+use crate::attachment::AttachmentBonus;
+use crate::effect::Effect;
+use crate::keyword::Keyword;
+use crate::layers::LayeredEffect;
+use crate::mana::{ManaColor, ManaCost};
+use crate::planeswalker::LoyaltyAbility;
+use crate::replacement::ReplacementEffect;
+use crate::statics::StaticEffect;
+use crate::tappable::EnterTappedRule;
+use crate::trigger::TriggeredAbility;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CardType
 {
     Land,
     Creature,
+    Instant,
+    Sorcery,
+    Artifact,
+    Enchantment,
+    Planeswalker,
+}
+
+/// Identifies one card for the whole game, wherever it moves. Ids are handed out when the game
+/// starts; cards outside a game all have the default id.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct CardId(pub u32);
+
+// Power/toughness changes that come from other cards rather than the creature itself
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatModifier
+{
+    pub power: i32,
+    pub toughness: i32,
 }
 
 // Use composition so only creatures have power/toughness.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CreatureStats
 {
     pub power: u8,
     pub toughness: u8,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CounterKind
+{
+    PlusOne,  // +1/+1
+    MinusOne, // -1/-1
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum CardFragmentKind
 {
     Creature,
     Tappable,
+    ManaSource,
+    Keywords,
+    Trigger,
+    Effect,
+    Aura,
+    Equipment,
+    Token,
+    Script,
+    Commander,
+    Faces,
+    Planeswalker,
+    Enchantment,
+    Artifact,
+    Replacement,
+    SacrificeAbility,
 }
 
 pub trait Fragment: Any + Send + Sync
@@ -29,13 +83,58 @@ pub trait Fragment: Any + Send + Sync
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn box_clone(&self) -> Box<dyn Fragment>;
+    /// The fragment as stored in saved cards and games.
+    fn to_serializable(&self) -> SerializableFragment;
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// A concrete fragment type, and the kind it's stored under on a card. Lets `Card::fragment`
+/// and friends find and downcast it in one step.
+pub trait TypedFragment: Fragment + Sized
+{
+    const KIND: CardFragmentKind;
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CreatureFragment
 {
     pub stats: CreatureStats,
     pub summoning_sickness: bool,
+    // Counters on top of the printed stats; see creature::effective_stats
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub counters: HashMap<CounterKind, u32>,
+    // Damage marked this turn, and whether any of it came from a deathtouch source
+    #[serde(default)]
+    pub damage: u32,
+    #[serde(default)]
+    pub deathtouch_damage: bool,
+    // Continuous effects of other permanents (attachments), recomputed by the game
+    #[serde(default)]
+    pub modifier: StatModifier,
+    // Pump effects that wear off in the cleanup step
+    #[serde(default)]
+    pub until_end_of_turn: StatModifier,
+    #[serde(default)]
+    pub granted_keywords: Vec<Keyword>,
+    // Keywords gained until the cleanup step, e.g. haste from Reckless Charge
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub until_end_of_turn_keywords: Vec<Keyword>,
+    // Regeneration shields until the cleanup step, each replacing one destruction
+    #[serde(default)]
+    pub regeneration_shields: u32,
+    // Layered effects of static abilities, recomputed by the game, and of spells until the
+    // cleanup step; see layers.rs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<LayeredEffect>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub until_end_of_turn_effects: Vec<LayeredEffect>,
+    // Only a creature because a continuous effect made it one
+    #[serde(default)]
+    pub animated: bool,
+}
+
+impl TypedFragment for CreatureFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Creature;
 }
 
 impl Fragment for CreatureFragment
@@ -52,10 +151,20 @@ impl Fragment for CreatureFragment
 
     fn box_clone(&self) -> Box<dyn Fragment>
     {
-        Box::new(CreatureFragment { stats: self.stats, summoning_sickness: self.summoning_sickness })
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Creature(self.clone())
     }
 }
 
+impl TypedFragment for TappableFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Tappable;
+}
+
 impl Fragment for TappableFragment
 {
     fn as_any(&self) -> &dyn Any
@@ -70,15 +179,432 @@ impl Fragment for TappableFragment
 
     fn box_clone(&self) -> Box<dyn Fragment>
     {
-        Box::new(TappableFragment { tapped: self.tapped })
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Tappable(self.clone())
+    }
+}
+
+impl TypedFragment for ManaSourceFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::ManaSource;
+}
+
+impl Fragment for ManaSourceFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::ManaSource(self.clone())
+    }
+}
+
+impl TypedFragment for KeywordsFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Keywords;
+}
+
+impl Fragment for KeywordsFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Keywords(self.clone())
     }
 }
 
-impl Clone for Box<dyn Fragment>
+impl TypedFragment for TriggerFragment
 {
-    fn clone(&self) -> Box<dyn Fragment>
+    const KIND: CardFragmentKind = CardFragmentKind::Trigger;
+}
+
+impl Fragment for TriggerFragment
+{
+    fn as_any(&self) -> &dyn Any
     {
-        self.box_clone()
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Trigger(self.clone())
+    }
+}
+
+impl TypedFragment for EffectFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Effect;
+}
+
+impl Fragment for EffectFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Effect(self.clone())
+    }
+}
+
+impl TypedFragment for AuraFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Aura;
+}
+
+impl Fragment for AuraFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Aura(self.clone())
+    }
+}
+
+impl TypedFragment for EquipmentFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Equipment;
+}
+
+impl Fragment for EquipmentFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Equipment(self.clone())
+    }
+}
+
+impl TypedFragment for TokenFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Token;
+}
+
+impl Fragment for TokenFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Token(self.clone())
+    }
+}
+
+impl TypedFragment for SacrificeAbilityFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::SacrificeAbility;
+}
+
+impl Fragment for SacrificeAbilityFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::SacrificeAbility(self.clone())
+    }
+}
+
+impl TypedFragment for CommanderFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Commander;
+}
+
+impl Fragment for CommanderFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Commander(self.clone())
+    }
+}
+
+impl TypedFragment for ScriptFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Script;
+}
+
+impl Fragment for ScriptFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Script(self.clone())
+    }
+}
+
+impl TypedFragment for FacesFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Faces;
+}
+
+impl Fragment for FacesFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Faces(self.clone())
+    }
+}
+
+impl TypedFragment for PlaneswalkerFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Planeswalker;
+}
+
+impl Fragment for PlaneswalkerFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Planeswalker(self.clone())
+    }
+}
+
+impl TypedFragment for EnchantmentFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Enchantment;
+}
+
+impl Fragment for EnchantmentFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Enchantment(self.clone())
+    }
+}
+
+impl TypedFragment for ArtifactFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Artifact;
+}
+
+impl Fragment for ArtifactFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Artifact(self.clone())
+    }
+}
+
+impl TypedFragment for ReplacementFragment
+{
+    const KIND: CardFragmentKind = CardFragmentKind::Replacement;
+}
+
+impl Fragment for ReplacementFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+
+    fn to_serializable(&self) -> SerializableFragment
+    {
+        SerializableFragment::Replacement(self.clone())
     }
 }
 
@@ -88,72 +614,290 @@ pub enum SerializableFragment
 {
     Creature(CreatureFragment),
     Tappable(TappableFragment),
+    ManaSource(ManaSourceFragment),
+    Keywords(KeywordsFragment),
+    Trigger(TriggerFragment),
+    Effect(EffectFragment),
+    Aura(AuraFragment),
+    Equipment(EquipmentFragment),
+    Token(TokenFragment),
+    Script(ScriptFragment),
+    Commander(CommanderFragment),
+    Faces(FacesFragment),
+    Planeswalker(PlaneswalkerFragment),
+    Enchantment(EnchantmentFragment),
+    Artifact(ArtifactFragment),
+    Replacement(ReplacementFragment),
+    SacrificeAbility(SacrificeAbilityFragment),
 }
 
 impl SerializableFragment
 {
     /// Convert to trait object
-    pub fn to_fragment(&self) -> Box<dyn Fragment>
+    pub fn to_fragment(&self) -> Arc<dyn Fragment>
     {
-        match self
-        {
-            SerializableFragment::Creature(cf) => Box::new(cf.clone()),
-            SerializableFragment::Tappable(tf) => Box::new(tf.clone()),
+        match self {
+            SerializableFragment::Creature(cf) => Arc::new(cf.clone()),
+            SerializableFragment::Tappable(tf) => Arc::new(tf.clone()),
+            SerializableFragment::ManaSource(mf) => Arc::new(mf.clone()),
+            SerializableFragment::Keywords(kf) => Arc::new(kf.clone()),
+            SerializableFragment::Trigger(tf) => Arc::new(tf.clone()),
+            SerializableFragment::Effect(sf) => Arc::new(sf.clone()),
+            SerializableFragment::Aura(af) => Arc::new(af.clone()),
+            SerializableFragment::Equipment(ef) => Arc::new(ef.clone()),
+            SerializableFragment::Token(tf) => Arc::new(tf.clone()),
+            SerializableFragment::Script(sf) => Arc::new(sf.clone()),
+            SerializableFragment::Commander(cf) => Arc::new(cf.clone()),
+            SerializableFragment::Faces(ff) => Arc::new(ff.clone()),
+            SerializableFragment::Planeswalker(pf) => Arc::new(pf.clone()),
+            SerializableFragment::Enchantment(ef) => Arc::new(ef.clone()),
+            SerializableFragment::Artifact(af) => Arc::new(af.clone()),
+            SerializableFragment::Replacement(rf) => Arc::new(rf.clone()),
+            SerializableFragment::SacrificeAbility(sf) => Arc::new(sf.clone()),
         }
     }
-
-    /// Convert from trait object (best effort)
-    pub fn from_fragment(fragment: &dyn Fragment) -> Option<Self>
-    {
-        if let Some(cf) = fragment.as_any().downcast_ref::<CreatureFragment>()
-        {
-            return Some(SerializableFragment::Creature(cf.clone()));
-        }
-        if let Some(tf) = fragment.as_any().downcast_ref::<TappableFragment>()
-        {
-            return Some(SerializableFragment::Tappable(tf.clone()));
-        }
-        None
-    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TappableFragment
 {
     pub tapped: bool,
+    #[serde(default)]
+    pub enters_tapped: EnterTappedRule,
+}
+
+// Permanents that tap for mana: lands, mana rocks and mana creatures
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManaSourceFragment
+{
+    // One of these colors per mana
+    pub colors: Vec<ManaColor>,
+    // Mana added per tap, e.g. 2 for a Sol Ring
+    #[serde(default = "one")]
+    pub amount: u32,
+}
+
+fn one() -> u32
+{
+    1
+}
+
+// Evergreen keyword abilities; see keyword.rs for what each one does
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeywordsFragment
+{
+    pub keywords: Vec<Keyword>,
+}
+
+// "When this enters / dies / attacks" abilities; see trigger.rs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TriggerFragment
+{
+    pub abilities: Vec<TriggeredAbility>,
+}
+
+// What an instant or sorcery does when it resolves; see effect.rs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EffectFragment
+{
+    pub effects: Vec<Effect>,
+    // What was paid for X when the spell was last cast
+    #[serde(default)]
+    pub x: u32,
+    // Creatures sacrificed as an additional cost to cast it
+    #[serde(default)]
+    pub sacrifice: u32,
+    // Optional mana paid on top of the cost for `kicked_effects` instead of `effects`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kicker: Option<ManaCost>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kicked_effects: Vec<Effect>,
+    // Whether the kicker was paid when the spell was last cast
+    #[serde(default)]
+    pub kicked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternative_cost: Option<AlternativeCost>,
+}
+
+/// A cost that can be paid instead of a spell's mana cost, e.g. Force of Will's 1 life and a blue
+/// card exiled from hand.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlternativeCost
+{
+    #[serde(default)]
+    pub mana: ManaCost,
+    #[serde(default)]
+    pub life: u32,
+    // Another card of this color exiled from hand
+    #[serde(default)]
+    pub pitch: Option<ManaColor>,
+}
+
+// Auras pick their creature when cast and are put into the graveyard when it leaves
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuraFragment
+{
+    pub attached_to: Option<CardId>,
+    pub bonus: AttachmentBonus,
+}
+
+// Equipment enters unattached and moves between creatures by paying its equip cost
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EquipmentFragment
+{
+    pub attached_to: Option<CardId>,
+    pub equip_cost: ManaCost,
+    pub bonus: AttachmentBonus,
+}
+
+// Marks cards created by effects; see token.rs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TokenFragment {}
+
+// A deck's commander; see commander.rs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CommanderFragment
+{
+    // Times it has been cast from the command zone, for commander tax
+    pub casts: u32,
+}
+
+/// How the faces of a card with more than one are cast or played.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FaceLayout
+{
+    // A creature or other permanent with an instant or sorcery to cast first; after the
+    // adventure resolves the card waits in exile, castable as the permanent
+    Adventure,
+    // Two spells on one card; either is cast, and it goes to the graveyard as the whole card
+    Split,
+    // Either face is cast or played (a land face as the land drop); it's the front again
+    // once it leaves the battlefield
+    ModalDoubleFaced,
+}
+
+// A card with more than one face; see faces.rs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FacesFragment
+{
+    pub layout: FaceLayout,
+    // Every face, the front first, each a card of its own without this fragment
+    pub faces: Vec<Card>,
+    // Which of `faces` the card is now
+    #[serde(default)]
+    pub showing: usize,
+    // Adventures only: in exile after its adventure, so castable as the permanent
+    #[serde(default)]
+    pub on_adventure: bool,
+}
+
+// Loyalty and abilities of a planeswalker; see planeswalker.rs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PlaneswalkerFragment
+{
+    // Loyalty it enters the battlefield with
+    pub starting_loyalty: u32,
+    pub loyalty: u32,
+    pub abilities: Vec<LoyaltyAbility>,
+    // The turn (`GameState::turns`) one of its abilities was last activated; once per turn
+    #[serde(default)]
+    pub activated_turn: Option<u32>,
+}
+
+// An enchantment other than an Aura, and what it does while on the battlefield; see statics.rs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EnchantmentFragment
+{
+    pub statics: Vec<StaticEffect>,
+    // When it entered the battlefield, to order its effects with others'
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+// An artifact other than an Equipment, likewise
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ArtifactFragment
+{
+    pub statics: Vec<StaticEffect>,
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+// An activated ability that sacrifices the permanent, like a Clue's "{2}, Sacrifice: Draw a
+// card"; see token.rs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SacrificeAbilityFragment
+{
+    #[serde(default)]
+    pub cost: ManaCost,
+    // Tapping the permanent is part of the cost
+    #[serde(default)]
+    pub tap: bool,
+    // What it does; none for a mana ability, which is activated while paying for something
+    // (the permanent is a mana source too, like a Treasure)
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+}
+
+// How the card changes events before they happen; see replacement.rs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReplacementFragment
+{
+    pub replacements: Vec<ReplacementEffect>,
+}
+
+// Rhai source for effects written in card files; only run with the `scripting` feature (script.rs)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ScriptFragment
+{
+    pub on_cast: Option<String>,
+    pub on_etb: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Card
 {
+    #[serde(default)]
+    pub id: CardId,
     pub name: String,
     pub card_types: Vec<CardType>,
-    pub cost: u32,
-    #[serde(serialize_with = "serialize_fragments", deserialize_with = "deserialize_fragments")]
-    pub fragments: HashMap<CardFragmentKind, Box<dyn Fragment>>,
+    pub cost: ManaCost,
+    #[serde(
+        serialize_with = "serialize_fragments",
+        deserialize_with = "deserialize_fragments"
+    )]
+    pub fragments: HashMap<CardFragmentKind, Arc<dyn Fragment>>,
 }
 
-// Custom serialization for fragments
+// Custom serialization for fragments, in kind order so saved cards are byte-stable
 fn serialize_fragments<S>(
-    fragments: &HashMap<CardFragmentKind, Box<dyn Fragment>>,
+    fragments: &HashMap<CardFragmentKind, Arc<dyn Fragment>>,
     serializer: S,
 ) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
-    let serializable: HashMap<CardFragmentKind, SerializableFragment> = fragments
+    let serializable: BTreeMap<CardFragmentKind, SerializableFragment> = fragments
         .iter()
-        .filter_map(|(k, v)| {
-            SerializableFragment::from_fragment(v.as_ref()).map(|sf| (*k, sf))
-        })
+        .map(|(k, v)| (*k, v.to_serializable()))
         .collect();
     serializable.serialize(serializer)
 }
 
+/// Serialize a map in key order rather than the hash map's, so saved games are byte-stable.
+pub fn serialize_sorted<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    S: serde::Serializer,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
 // Custom deserialization for fragments
 fn deserialize_fragments<'de, D>(
     deserializer: D,
-) -> Result<HashMap<CardFragmentKind, Box<dyn Fragment>>, D::Error>
+) -> Result<HashMap<CardFragmentKind, Arc<dyn Fragment>>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -170,6 +914,7 @@ impl std::fmt::Debug for Card
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
     {
         f.debug_struct("Card")
+            .field("id", &self.id)
             .field("name", &self.name)
             .field("card_types", &self.card_types)
             .field("cost", &self.cost)
@@ -179,37 +924,78 @@ impl std::fmt::Debug for Card
 
 impl Card
 {
+    /// The card's fragment of type `T`, if it has one.
+    pub fn fragment<T: TypedFragment>(&self) -> Option<&T>
+    {
+        self.fragments
+            .get(&T::KIND)
+            .and_then(|f| f.as_any().downcast_ref::<T>())
+    }
+
+    /// The card's fragment of type `T`, to change. Fragments are shared between copies of a card
+    /// until one of them changes, so this copies the fragment first if another card still uses it.
+    pub fn fragment_mut<T: TypedFragment>(&mut self) -> Option<&mut T>
+    {
+        let fragment = self.fragments.get_mut(&T::KIND)?;
+        if Arc::get_mut(fragment).is_none() {
+            *fragment = Arc::from(fragment.box_clone());
+        }
+        Arc::get_mut(fragment).and_then(|f| f.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// The card's fragment of type `T`, added with `make` first if it has none.
+    pub fn fragment_or_insert_with<T: TypedFragment>(&mut self, make: impl FnOnce() -> T)
+    -> &mut T
+    {
+        self.fragments
+            .entry(T::KIND)
+            .or_insert_with(|| Arc::new(make()));
+        self.fragment_mut::<T>()
+            .expect("fragment stored under another fragment type's kind")
+    }
+
     pub fn is_type(&self, t: CardType) -> bool
     {
-        self.card_types.iter().any(|ct| *ct == t)
+        self.card_types.contains(&t)
     }
 
     pub fn add_type(&mut self, t: CardType)
     {
-        if !self.card_types.contains(&t)
-        {
+        if !self.card_types.contains(&t) {
             self.card_types.push(t);
         }
     }
 
     pub fn remove_type(&mut self, t: CardType)
     {
-        if let Some(pos) = self.card_types.iter().position(|ct| *ct == t)
-        {
+        if let Some(pos) = self.card_types.iter().position(|ct| *ct == t) {
             self.card_types.remove(pos);
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Deck
 {
     pub cards: Vec<Card>,
+    // Commander decks only: the cards that start in the command zone
+    pub commanders: Vec<Card>,
+    // Cards swapped in between games of a match, following `plans`
+    pub sideboard: Vec<Card>,
+    pub plans: Vec<crate::deck::SideboardPlan>,
 }
 
 impl Deck
 {
-    pub fn count(&self, card_type: CardType) -> usize 
+    pub fn new(cards: Vec<Card>) -> Self
+    {
+        Deck {
+            cards,
+            ..Deck::default()
+        }
+    }
+
+    pub fn count(&self, card_type: CardType) -> usize
     {
         self.cards.iter().filter(|c| c.is_type(card_type)).count()
     }
@@ -225,50 +1011,85 @@ impl Deck
             cards.push(grizzly_bears());
         }
 
-        Deck { cards }
+        Deck::new(cards)
+    }
+
+    /// An opponent that only ever plays lands, for measuring how fast a deck kills on its own.
+    pub fn goldfish() -> Deck
+    {
+        Deck::new((0..60).map(|_| forest()).collect())
     }
 }
 
-pub fn forest() -> Card 
+pub fn forest() -> Card
 {
-    Card
-    {
+    Card {
+        id: CardId::default(),
         name: String::from("Forest"),
         card_types: vec![CardType::Land],
-        cost: 0,
+        cost: ManaCost::default(),
         fragments: {
             let mut m = HashMap::new();
             m.insert(
                 CardFragmentKind::Tappable,
-                Box::new(TappableFragment { tapped: false }) as Box<dyn Fragment>,
+                Arc::new(TappableFragment {
+                    tapped: false,
+                    enters_tapped: EnterTappedRule::Never,
+                }) as Arc<dyn Fragment>,
+            );
+            m.insert(
+                CardFragmentKind::ManaSource,
+                Arc::new(ManaSourceFragment {
+                    colors: vec![ManaColor::Green],
+                    amount: 1,
+                }) as Arc<dyn Fragment>,
             );
             m
         },
     }
 }
 
-pub fn grizzly_bears() -> Card 
+pub fn grizzly_bears() -> Card
 {
-    Card
-    {
-        name: String::from("Grizzly Bears"),
+    vanilla_creature("Grizzly Bears", "1G", 2, 2)
+}
+
+// Vanilla creature with the usual creature + tappable fragments
+fn vanilla_creature(name: &str, cost: &str, power: u8, toughness: u8) -> Card
+{
+    Card {
+        id: CardId::default(),
+        name: String::from(name),
         card_types: vec![CardType::Creature],
-        cost: 2,
+        cost: cost.parse().expect("valid mana cost"),
         fragments: {
             let mut m = HashMap::new();
             m.insert(
                 CardFragmentKind::Creature,
-                Box::new(CreatureFragment { stats: CreatureStats { power: 2, toughness: 2 }, summoning_sickness: false }) as Box<dyn Fragment>,
+                Arc::new(CreatureFragment {
+                    stats: CreatureStats { power, toughness },
+                    summoning_sickness: false,
+                    ..CreatureFragment::default()
+                }) as Arc<dyn Fragment>,
             );
             m.insert(
                 CardFragmentKind::Tappable,
-                Box::new(TappableFragment { tapped: false }) as Box<dyn Fragment>,
+                Arc::new(TappableFragment {
+                    tapped: false,
+                    enters_tapped: EnterTappedRule::Never,
+                }) as Arc<dyn Fragment>,
             );
             m
         },
     }
 }
 
+// Built-in cards that can be referred to by name (decklists, optimizer pools)
+pub fn card_by_name(name: &str) -> Option<Card>
+{
+    crate::carddb::CardDb::builtin().get(name)
+}
+
 #[cfg(test)]
 mod tests
 {
@@ -302,4 +1123,30 @@ mod tests
         assert!(creature::is_creature(&g));
         assert_eq!(creature::creature_stats(&g).unwrap().power, 3);
     }
+
+    #[test]
+    fn fragments_are_found_by_type()
+    {
+        let mut card = grizzly_bears();
+        assert_eq!(
+            card.fragment::<CreatureFragment>().map(|cf| cf.stats.power),
+            Some(2)
+        );
+        assert!(card.fragment::<ManaSourceFragment>().is_none());
+
+        card.fragment_mut::<CreatureFragment>().unwrap().damage = 1;
+        assert_eq!(creature::marked_damage(&card), 1);
+
+        card.fragment_or_insert_with(|| KeywordsFragment {
+            keywords: Vec::new(),
+        })
+        .keywords
+        .push(Keyword::Trample);
+        assert_eq!(
+            card.fragment::<KeywordsFragment>()
+                .map(|kf| kf.keywords.len()),
+            Some(1)
+        );
+        assert!(card.fragments.contains_key(&KeywordsFragment::KIND));
+    }
 }
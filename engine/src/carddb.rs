@@ -0,0 +1,544 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::attachment::AttachmentBonus;
+use crate::card::{
+    AlternativeCost, ArtifactFragment, AuraFragment, Card, CardFragmentKind, CardId, CardType,
+    CreatureFragment, CreatureStats, EffectFragment, EnchantmentFragment, EquipmentFragment,
+    FaceLayout, Fragment, KeywordsFragment, ManaSourceFragment, PlaneswalkerFragment,
+    ReplacementFragment, ScriptFragment, TappableFragment, TriggerFragment,
+};
+use crate::effect::Effect;
+use crate::keyword::Keyword;
+use crate::mana::{self, ManaColor, ManaCost};
+use crate::planeswalker::LoyaltyAbility;
+use crate::replacement::ReplacementEffect;
+use crate::statics::StaticEffect;
+use crate::tappable::EnterTappedRule;
+use crate::trigger::TriggeredAbility;
+
+/// One card as written in a JSON card file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CardDefinition
+{
+    pub name: String,
+    pub types: Vec<CardType>,
+    // "1G", "{2}{U}{U}" or a plain number of generic mana
+    #[serde(default)]
+    pub cost: ManaCost,
+    #[serde(default)]
+    pub power: Option<u8>,
+    #[serde(default)]
+    pub toughness: Option<u8>,
+    // Colors a land, mana rock or mana creature taps for; basic lands default to their own
+    // color, other lands to colorless
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub produces: Vec<ManaColor>,
+    // Mana added per tap when it's more than one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mana_per_tap: Option<u32>,
+    // "Always", "Fastland", { "IfYouControlLands": 3 } or { "Checkland": ["B", "R"] }
+    #[serde(default, skip_serializing_if = "EnterTappedRule::is_never")]
+    pub enters_tapped: EnterTappedRule,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<Keyword>,
+    // e.g. { "condition": "EntersBattlefield", "effect": { "GainLife": 4 } }
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub triggers: Vec<TriggeredAbility>,
+    // What an instant or sorcery does on resolution, e.g. [{ "DealDamage": 3 }] or ["CounterSpell"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<Effect>,
+    // Instants and sorceries only: creatures sacrificed as an additional cost, e.g. Bone Splinters' 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sacrifice: Option<u32>,
+    // Instants and sorceries only: optional mana paid on top of the cost to do `kicked_effects`
+    // instead of `effects`, e.g. Burst Lightning's "4" and [{ "DealDamage": 4 }]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kicker: Option<ManaCost>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kicked_effects: Vec<Effect>,
+    // Instants and sorceries only: a cost paid instead of the mana cost, e.g. { "life": 1, "pitch": "U" }
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternative_cost: Option<AlternativeCost>,
+    // What an Aura or Equipment gives its creature, e.g. { "power": 2, "keywords": ["Trample"] }
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<AttachmentBonus>,
+    // What an enchantment or artifact does while on the battlefield, e.g. [{ "Anthem": { "power": 1, "toughness": 1 } }]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statics: Vec<StaticEffect>,
+    // How a permanent changes events before they happen, e.g. [{ "EntersWithCounters": 2 }] or
+    // [{ "DrawInstead": { "DrawCards": 2 } }]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replacements: Vec<ReplacementEffect>,
+    // Equipment only: the cost to attach it to a creature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equip: Option<ManaCost>,
+    // Rhai scripts run when the spell resolves / the permanent enters, e.g. "deal_damage(2); draw(1);"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_cast: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_etb: Option<String>,
+    // Planeswalkers only: starting loyalty and abilities, e.g.
+    // [{ "loyalty": 1, "effect": { "DamageOpponents": 1 } }, { "loyalty": -3, "effect": { "DealDamage": 3 } }]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loyalty: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub loyalty_abilities: Vec<LoyaltyAbility>,
+    // Cards with more than one face: "Adventure", "Split" or "ModalDoubleFaced", and the faces
+    // after this one, e.g. [{ "name": "Stomp", "types": ["Instant"], "cost": "1R", "effects": [{ "DealDamage": 2 }] }]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<FaceLayout>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub faces: Vec<CardDefinition>,
+}
+
+impl CardDefinition
+{
+    pub fn validate(&self) -> Result<(), String>
+    {
+        if self.name.trim().is_empty() {
+            return Err(String::from("card without a name"));
+        }
+        if self.types.contains(&CardType::Creature)
+            && (self.power.is_none() || self.toughness.is_none())
+        {
+            return Err(format!(
+                "creature '{}' needs power and toughness",
+                self.name
+            ));
+        }
+        if (self.types.contains(&CardType::Instant) || self.types.contains(&CardType::Sorcery))
+            && self.effects.is_empty()
+            && self.on_cast.is_none()
+        {
+            return Err(format!("spell '{}' needs at least one effect", self.name));
+        }
+        if self.types.contains(&CardType::Planeswalker)
+            && (self.loyalty.is_none() || self.loyalty_abilities.is_empty())
+        {
+            return Err(format!(
+                "planeswalker '{}' needs loyalty and at least one loyalty ability",
+                self.name
+            ));
+        }
+        if self.sacrifice.is_some() && self.effects.is_empty() {
+            return Err(format!(
+                "only instants and sorceries have a sacrifice cost, not '{}'",
+                self.name
+            ));
+        }
+        if self.kicker.is_some() == self.kicked_effects.is_empty() {
+            return Err(format!(
+                "'{}' needs both a kicker and kicked effects, or neither",
+                self.name
+            ));
+        }
+        if (self.kicker.is_some() || self.alternative_cost.is_some()) && self.effects.is_empty() {
+            return Err(format!(
+                "only instants and sorceries have kickers and alternative costs, not '{}'",
+                self.name
+            ));
+        }
+        if self.equip.is_some() && !self.types.contains(&CardType::Artifact) {
+            return Err(format!("equipment '{}' must be an artifact", self.name));
+        }
+        if self.types.contains(&CardType::Enchantment)
+            && self.attachment.is_none()
+            && self.statics.is_empty()
+            && self.replacements.is_empty()
+        {
+            return Err(format!(
+                "enchantment '{}' needs an attachment bonus, static or replacement effects",
+                self.name
+            ));
+        }
+        if !self.replacements.is_empty()
+            && self
+                .types
+                .iter()
+                .any(|t| matches!(t, CardType::Instant | CardType::Sorcery))
+        {
+            return Err(format!(
+                "only permanents have replacement effects, not '{}'",
+                self.name
+            ));
+        }
+        let can_have_statics =
+            self.types.contains(&CardType::Enchantment) || self.types.contains(&CardType::Artifact);
+        if !self.statics.is_empty() && !can_have_statics {
+            return Err(format!(
+                "only enchantments and artifacts have static effects, not '{}'",
+                self.name
+            ));
+        }
+        #[cfg(feature = "scripting")]
+        for script in self.on_cast.iter().chain(self.on_etb.iter()) {
+            crate::script::check(script)
+                .map_err(|e| format!("script of '{}' doesn't compile: {}", self.name, e))?;
+        }
+        #[cfg(not(feature = "scripting"))]
+        if self.on_cast.is_some() || self.on_etb.is_some() {
+            return Err(format!(
+                "'{}' has a script, but this build doesn't have the `scripting` feature",
+                self.name
+            ));
+        }
+        if self.layout.is_some() == self.faces.is_empty() {
+            return Err(format!(
+                "'{}' needs both a layout and its other faces, or neither",
+                self.name
+            ));
+        }
+        if self.layout == Some(FaceLayout::Adventure)
+            && (self.faces.len() != 1
+                || !self.faces[0]
+                    .types
+                    .iter()
+                    .any(|t| matches!(t, CardType::Instant | CardType::Sorcery)))
+        {
+            return Err(format!(
+                "the adventure of '{}' must be a single instant or sorcery",
+                self.name
+            ));
+        }
+        for face in self.faces.iter() {
+            if !face.faces.is_empty() {
+                return Err(format!(
+                    "face '{}' of '{}' can't have faces of its own",
+                    face.name, self.name
+                ));
+            }
+            face.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn to_card(&self) -> Card
+    {
+        let mut fragments: HashMap<CardFragmentKind, Arc<dyn Fragment>> = HashMap::new();
+        fragments.insert(
+            CardFragmentKind::Tappable,
+            Arc::new(TappableFragment {
+                tapped: false,
+                enters_tapped: self.enters_tapped.clone(),
+            }),
+        );
+
+        if let (Some(power), Some(toughness)) = (self.power, self.toughness) {
+            fragments.insert(
+                CardFragmentKind::Creature,
+                Arc::new(CreatureFragment {
+                    stats: CreatureStats { power, toughness },
+                    summoning_sickness: false,
+                    ..CreatureFragment::default()
+                }),
+            );
+        }
+
+        if !self.keywords.is_empty() {
+            fragments.insert(
+                CardFragmentKind::Keywords,
+                Arc::new(KeywordsFragment {
+                    keywords: self.keywords.clone(),
+                }),
+            );
+        }
+
+        if !self.triggers.is_empty() {
+            fragments.insert(
+                CardFragmentKind::Trigger,
+                Arc::new(TriggerFragment {
+                    abilities: self.triggers.clone(),
+                }),
+            );
+        }
+
+        if !self.effects.is_empty() {
+            fragments.insert(
+                CardFragmentKind::Effect,
+                Arc::new(EffectFragment {
+                    effects: self.effects.clone(),
+                    x: 0,
+                    sacrifice: self.sacrifice.unwrap_or(0),
+                    kicker: self.kicker,
+                    kicked_effects: self.kicked_effects.clone(),
+                    kicked: false,
+                    alternative_cost: self.alternative_cost.clone(),
+                }),
+            );
+        }
+
+        if !self.replacements.is_empty() {
+            fragments.insert(
+                CardFragmentKind::Replacement,
+                Arc::new(ReplacementFragment {
+                    replacements: self.replacements.clone(),
+                }),
+            );
+        }
+
+        if let Some(loyalty) = self.loyalty {
+            fragments.insert(
+                CardFragmentKind::Planeswalker,
+                Arc::new(PlaneswalkerFragment {
+                    starting_loyalty: loyalty,
+                    loyalty,
+                    abilities: self.loyalty_abilities.clone(),
+                    activated_turn: None,
+                }),
+            );
+        }
+
+        if self.on_cast.is_some() || self.on_etb.is_some() {
+            fragments.insert(
+                CardFragmentKind::Script,
+                Arc::new(ScriptFragment {
+                    on_cast: self.on_cast.clone(),
+                    on_etb: self.on_etb.clone(),
+                }),
+            );
+        }
+
+        let bonus = self.attachment.clone().unwrap_or_default();
+        if let Some(equip_cost) = self.equip {
+            fragments.insert(
+                CardFragmentKind::Equipment,
+                Arc::new(EquipmentFragment {
+                    attached_to: None,
+                    equip_cost,
+                    bonus,
+                }),
+            );
+        } else if self.types.contains(&CardType::Enchantment) && self.attachment.is_some() {
+            fragments.insert(
+                CardFragmentKind::Aura,
+                Arc::new(AuraFragment {
+                    attached_to: None,
+                    bonus,
+                }),
+            );
+        } else if self.types.contains(&CardType::Enchantment) {
+            fragments.insert(
+                CardFragmentKind::Enchantment,
+                Arc::new(EnchantmentFragment {
+                    statics: self.statics.clone(),
+                    timestamp: 0,
+                }),
+            );
+        } else if self.types.contains(&CardType::Artifact) {
+            fragments.insert(
+                CardFragmentKind::Artifact,
+                Arc::new(ArtifactFragment {
+                    statics: self.statics.clone(),
+                    timestamp: 0,
+                }),
+            );
+        }
+
+        if self.types.contains(&CardType::Land) || !self.produces.is_empty() {
+            let colors = if self.produces.is_empty() {
+                vec![mana::basic_land_color(&self.name).unwrap_or(ManaColor::Colorless)]
+            } else {
+                self.produces.clone()
+            };
+            fragments.insert(
+                CardFragmentKind::ManaSource,
+                Arc::new(ManaSourceFragment {
+                    colors,
+                    amount: self.mana_per_tap.unwrap_or(1),
+                }),
+            );
+        }
+
+        let card = Card {
+            id: CardId::default(),
+            name: self.name.clone(),
+            card_types: self.types.clone(),
+            cost: self.cost,
+            fragments,
+        };
+        match self.layout {
+            Some(layout) => crate::faces::with_faces(
+                card,
+                layout,
+                self.faces.iter().map(CardDefinition::to_card).collect(),
+            ),
+            None => card,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CardDbError
+{
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for CardDbError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            CardDbError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            CardDbError::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            CardDbError::Invalid(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for CardDbError {}
+
+/// Card definitions by name. Names are matched case-insensitively; a later definition with the
+/// same name replaces an earlier one, so user files can override the built-in cards.
+#[derive(Clone, Debug, Default)]
+pub struct CardDb
+{
+    cards: HashMap<String, CardDefinition>,
+}
+
+static BUILTIN: OnceLock<CardDb> = OnceLock::new();
+
+impl CardDb
+{
+    pub fn new() -> Self
+    {
+        CardDb::default()
+    }
+
+    /// The cards shipped in `engine/data/cards.json`.
+    pub fn builtin() -> &'static CardDb
+    {
+        BUILTIN.get_or_init(|| {
+            let mut db = CardDb::new();
+            db.load_json(
+                include_str!("../data/cards.json"),
+                Path::new("data/cards.json"),
+            )
+            .expect("built-in card data is valid");
+            db
+        })
+    }
+
+    pub fn insert(&mut self, definition: CardDefinition)
+    {
+        self.cards
+            .insert(definition.name.to_lowercase(), definition);
+    }
+
+    /// Add every card in a JSON array of definitions; `source` is only used in error messages.
+    pub fn load_json(&mut self, text: &str, source: &Path) -> Result<usize, CardDbError>
+    {
+        let definitions: Vec<CardDefinition> =
+            serde_json::from_str(text).map_err(|e| CardDbError::Parse(source.to_path_buf(), e))?;
+
+        for definition in definitions.iter() {
+            definition
+                .validate()
+                .map_err(|e| CardDbError::Invalid(source.to_path_buf(), e))?;
+        }
+
+        let count = definitions.len();
+        for definition in definitions {
+            self.insert(definition);
+        }
+        Ok(count)
+    }
+
+    /// Load a JSON card file, or every `.json` file in a directory (in name order).
+    pub fn load_path(&mut self, path: &Path) -> Result<usize, CardDbError>
+    {
+        if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+                .map_err(|e| CardDbError::Io(path.to_path_buf(), e))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            files.sort();
+
+            let mut count = 0;
+            for file in files {
+                count += self.load_path(&file)?;
+            }
+            return Ok(count);
+        }
+
+        let text =
+            std::fs::read_to_string(path).map_err(|e| CardDbError::Io(path.to_path_buf(), e))?;
+        self.load_json(&text, path)
+    }
+
+    /// The definition named `name`. A card with several faces goes by its front's name, or by
+    /// every face's, like "Fire // Ice".
+    pub fn definition(&self, name: &str) -> Option<&CardDefinition>
+    {
+        let front = name.split(" // ").next().unwrap_or(name);
+        self.cards.get(&front.to_lowercase())
+    }
+
+    pub fn get(&self, name: &str) -> Option<Card>
+    {
+        self.definition(name).map(CardDefinition::to_card)
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.cards.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::creature;
+
+    #[test]
+    fn builtin_cards_match_factories()
+    {
+        let bears = CardDb::builtin().get("grizzly bears").unwrap();
+        assert_eq!(bears.name, "Grizzly Bears");
+        assert_eq!(bears.cost, crate::card::grizzly_bears().cost);
+        assert_eq!(creature::creature_stats(&bears).unwrap().toughness, 2);
+
+        let forest = CardDb::builtin().get("Forest").unwrap();
+        assert!(forest.is_type(CardType::Land));
+        assert!(!creature::is_creature(&forest));
+        assert_eq!(mana::produced_colors(&forest), vec![ManaColor::Green]);
+    }
+
+    #[test]
+    fn user_cards_override_and_validate()
+    {
+        let mut db = CardDb::builtin().clone();
+        db.load_json(r#"[{ "name": "Grizzly Bears", "types": ["Creature"], "cost": 1, "power": 2, "toughness": 2 }]"#, Path::new("test")).unwrap();
+        assert_eq!(db.get("Grizzly Bears").unwrap().cost, ManaCost::generic(1));
+
+        let err = db
+            .load_json(
+                r#"[{ "name": "Broken", "types": ["Creature"] }]"#,
+                Path::new("test"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, CardDbError::Invalid(..)));
+        assert!(db.get("Broken").is_none());
+
+        // Without effects there's no fragment to keep a sacrifice cost in
+        let err = db
+            .load_json(
+                r#"[{ "name": "Altar Bears", "types": ["Creature"], "cost": 1, "power": 2, "toughness": 2, "sacrifice": 1 }]"#,
+                Path::new("test"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, CardDbError::Invalid(..)));
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::ELoggingVerbosity;
+
+/// Terminal colors the board and log use.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Color
+{
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    // Faint, for things that matter less, like tapped permanents
+    Dim,
+    Bold,
+}
+
+impl Color
+{
+    fn code(self) -> &'static str
+    {
+        match self {
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Blue => "34",
+            Color::Magenta => "35",
+            Color::Cyan => "36",
+            Color::Dim => "2",
+            Color::Bold => "1",
+        }
+    }
+}
+
+// Off until the binary has checked the terminal, NO_COLOR and --no-color
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool)
+{
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool
+{
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether the environment asks for plain output: NO_COLOR set to anything but the empty string.
+pub fn disabled_by_env() -> bool
+{
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// `text` in `color`, or as it is when colors are off.
+pub fn paint(text: impl Display, color: Color) -> String
+{
+    paint_with(enabled(), text, color)
+}
+
+fn paint_with(enabled: bool, text: impl Display, color: Color) -> String
+{
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", color.code(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// The color of everything `player` controls, so both sides of the board are told apart at a
+/// glance.
+pub fn player(player: usize) -> Color
+{
+    const PLAYERS: [Color; 4] = [Color::Cyan, Color::Magenta, Color::Blue, Color::Yellow];
+    PLAYERS[player % PLAYERS.len()]
+}
+
+/// A log line colored by how serious it is: errors red, warnings yellow, the rest plain.
+pub fn for_level(level: ELoggingVerbosity, line: String) -> String
+{
+    match level {
+        ELoggingVerbosity::Error => paint(line, Color::Red),
+        ELoggingVerbosity::Warning => paint(line, Color::Yellow),
+        ELoggingVerbosity::Normal | ELoggingVerbosity::Verbose | ELoggingVerbosity::VeryVerbose => {
+            line
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn colors_wrap_text_only_when_enabled()
+    {
+        assert_eq!(
+            paint_with(true, "Forest", Color::Green),
+            "\x1b[32mForest\x1b[0m"
+        );
+        assert_eq!(paint_with(false, "Forest", Color::Green), "Forest");
+        assert_ne!(player(0), player(1));
+    }
+}
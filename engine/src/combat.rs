@@ -0,0 +1,688 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::Card;
+use crate::creature;
+use crate::keyword;
+use crate::planeswalker;
+use crate::tappable;
+
+/// Who attacks and blocks this turn. Indices point into the attacking and defending players'
+/// battlefields; nothing leaves the battlefield before combat damage, so they stay valid until then.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Combat
+{
+    pub defending_player: usize,
+    pub attackers: Vec<usize>,
+    // (blocker, attacker) pairs; an attacker's blockers receive its damage in this order
+    pub blocks: Vec<(usize, usize)>,
+    // (attacker, planeswalker) pairs for attackers going after one of the defending player's
+    // planeswalkers rather than the player
+    #[serde(default)]
+    pub planeswalkers: Vec<(usize, usize)>,
+}
+
+/// Damage dealt to the creature at `index` of a battlefield.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CreatureDamage
+{
+    pub index: usize,
+    pub amount: u32,
+    pub deathtouch: bool,
+}
+
+/// Combat damage to be dealt. Creatures aren't destroyed here; that's up to the state-based
+/// actions that run once the damage is marked.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DamageReport
+{
+    pub to_player: u32,
+    // The same damage by attacker index, for effects that care about the source
+    pub player_damage: Vec<(usize, u32)>,
+    // (attacker, planeswalker, amount) for damage to the defending player's planeswalkers
+    pub to_planeswalkers: Vec<(usize, usize, u32)>,
+    pub to_attackers: Vec<CreatureDamage>,
+    pub to_blockers: Vec<CreatureDamage>,
+    // Life gained by each side's lifelinkers
+    pub attacker_life_gained: u32,
+    pub defender_life_gained: u32,
+}
+
+pub fn can_attack(card: &Card) -> bool
+{
+    creature::is_creature(card)
+        && !creature::has_summoning_sickness(card)
+        && !tappable::is_tapped(card)
+        && keyword::all(card, |h| h.can_attack())
+}
+
+/// Whether attacking taps the creature; it doesn't with vigilance.
+pub fn taps_to_attack(card: &Card) -> bool
+{
+    keyword::all(card, |h| h.taps_to_attack())
+}
+
+/// The fewest creatures that may block `attacker`: two with menace, otherwise one.
+pub fn min_blockers(attacker: &Card) -> usize
+{
+    keyword::keywords(attacker)
+        .iter()
+        .map(|k| k.hooks().min_blockers())
+        .max()
+        .unwrap_or(1)
+}
+
+pub fn can_block(card: &Card) -> bool
+{
+    creature::is_creature(card) && !tappable::is_tapped(card)
+}
+
+/// Whether `blocker` may legally block `attacker` (untapped, and not shut out by evasion).
+pub fn can_block_attacker(blocker: &Card, attacker: &Card) -> bool
+{
+    can_block(blocker) && keyword::all(attacker, |h| h.can_be_blocked_by(blocker))
+}
+
+/// The legal part of a declared attack: each of `attackers` once, if it can attack.
+pub fn legal_attackers(attacking: &[Card], attackers: &[usize]) -> Vec<usize>
+{
+    let mut legal: Vec<usize> = Vec::new();
+    for &attacker in attackers {
+        if attacking.get(attacker).is_some_and(can_attack) && !legal.contains(&attacker) {
+            legal.push(attacker);
+        }
+    }
+    legal
+}
+
+/// The legal part of declared `blocks`: every blocker blocks at most one of the `attackers` it may
+/// block, and attackers with too few blockers for their menace aren't blocked at all.
+pub fn legal_blocks(
+    attacking: &[Card],
+    attackers: &[usize],
+    defending: &[Card],
+    blocks: &[(usize, usize)],
+) -> Vec<(usize, usize)>
+{
+    let mut legal: Vec<(usize, usize)> = Vec::new();
+    for &(blocker, attacker) in blocks {
+        let allowed = attackers.contains(&attacker)
+            && defending
+                .get(blocker)
+                .zip(attacking.get(attacker))
+                .is_some_and(|(b, a)| can_block_attacker(b, a))
+            && !legal.iter().any(|(b, _)| *b == blocker);
+        if allowed {
+            legal.push((blocker, attacker));
+        }
+    }
+    let count = |attacker: usize, legal: &[(usize, usize)]| {
+        legal.iter().filter(|(_, a)| *a == attacker).count()
+    };
+    let snapshot = legal.clone();
+    legal
+        .retain(|(_, attacker)| count(*attacker, &snapshot) >= min_blockers(&attacking[*attacker]));
+    legal
+}
+
+// (power, toughness), zero for anything without creature stats
+fn stats(card: &Card) -> (u32, u32)
+{
+    creature::effective_stats(card)
+        .map(|s| (s.power as u32, s.toughness as u32))
+        .unwrap_or((0, 0))
+}
+
+fn first_strike(card: &Card) -> bool
+{
+    keyword::any(card, |h| h.deals_first_strike_damage())
+}
+
+fn deathtouch(card: &Card) -> bool
+{
+    keyword::any(card, |h| h.damage_is_lethal())
+}
+
+// Combat damage `source` deals to `target`: none if protection prevents it
+fn power_against(source: &Card, target: &Card) -> u32
+{
+    if keyword::protected_from(target, source) {
+        0
+    } else {
+        stats(source).0
+    }
+}
+
+// Whether `source`'s combat damage alone destroys `target`, and it stays destroyed
+fn kills(source: &Card, target: &Card) -> bool
+{
+    let power = power_against(source, target);
+    power > 0
+        && (power >= stats(target).1 || deathtouch(source))
+        && !keyword::is_indestructible(target)
+        && creature::regeneration_shields(target) == 0
+}
+
+// One-on-one fight: (a dies, b dies). A first striker that kills its opponent takes no damage.
+fn fight(a: &Card, b: &Card) -> (bool, bool)
+{
+    let (a_kills, b_kills) = (kills(a, b), kills(b, a));
+    match (first_strike(a), first_strike(b)) {
+        (true, false) => (!a_kills && b_kills, a_kills),
+        (false, true) => (b_kills, !b_kills && a_kills),
+        _ => (b_kills, a_kills),
+    }
+}
+
+/// Attack with everything if the damage that gets through even when every possible blocker blocks
+/// is lethal; otherwise only with creatures that no single blocker can kill and survive.
+pub fn choose_attackers(attacking: &[Card], defending: &[Card], defender_life: i32) -> Vec<usize>
+{
+    let ready: Vec<usize> = (0..attacking.len())
+        .filter(|i| can_attack(&attacking[*i]))
+        .collect();
+    let blockers: Vec<&Card> = defending.iter().filter(|c| can_block(c)).collect();
+
+    // Menace makes an attacker evasive when too few creatures could block it together
+    let able = |attacker: &Card| {
+        blockers
+            .iter()
+            .filter(|b| can_block_attacker(b, attacker))
+            .count()
+    };
+    let (blockable, evasive): (Vec<usize>, Vec<usize>) = ready
+        .iter()
+        .partition(|i| able(&attacking[**i]) >= min_blockers(&attacking[**i]).max(1));
+    let mut powers: Vec<u32> = blockable.iter().map(|i| stats(&attacking[*i]).0).collect();
+    powers.sort_unstable_by(|a, b| b.cmp(a));
+    let unblockable_damage: u32 = powers.iter().skip(blockers.len()).sum::<u32>()
+        + evasive.iter().map(|i| stats(&attacking[*i]).0).sum::<u32>();
+    if unblockable_damage as i32 >= defender_life {
+        return ready;
+    }
+
+    ready
+        .into_iter()
+        .filter(|i| {
+            let attacker = &attacking[*i];
+            able(attacker) < min_blockers(attacker)
+                || !blockers
+                    .iter()
+                    .any(|b| can_block_attacker(b, attacker) && fight(attacker, b) == (true, false))
+        })
+        .collect()
+}
+
+/// Which `attackers` go after the defending player's planeswalkers: none when the attack could
+/// be lethal, otherwise the biggest attackers that can bring each planeswalker down, the most
+/// loyal first, as long as there's enough power for it.
+pub fn choose_planeswalker_attacks(
+    attacking: &[Card],
+    attackers: &[usize],
+    defending: &[Card],
+    defender_life: i32,
+) -> Vec<(usize, usize)>
+{
+    let total: u32 = attackers.iter().map(|i| stats(&attacking[*i]).0).sum();
+    if total as i32 >= defender_life {
+        return Vec::new();
+    }
+
+    let mut planeswalkers: Vec<(usize, u32)> = defending
+        .iter()
+        .enumerate()
+        .filter_map(|(i, card)| planeswalker::loyalty(card).map(|loyalty| (i, loyalty)))
+        .collect();
+    planeswalkers.sort_by_key(|(_, loyalty)| std::cmp::Reverse(*loyalty));
+    let mut free = attackers.to_vec();
+    free.sort_by_key(|i| std::cmp::Reverse(stats(&attacking[*i]).0));
+
+    let mut assigned = Vec::new();
+    for (planeswalker, loyalty) in planeswalkers {
+        let mut power = 0;
+        let mut sent = Vec::new();
+        for &attacker in free.iter() {
+            if power >= loyalty {
+                break;
+            }
+            power += stats(&attacking[attacker]).0;
+            sent.push(attacker);
+        }
+        if power < loyalty {
+            break;
+        }
+        free.retain(|a| !sent.contains(a));
+        assigned.extend(sent.into_iter().map(|attacker| (attacker, planeswalker)));
+    }
+    assigned
+}
+
+/// Block to stay alive first, then take blocks that kill the attacker and survive, then even
+/// trades. Bigger attackers are dealt with first; every blocker blocks at most one attacker.
+pub fn choose_blockers(
+    attacking: &[Card],
+    attackers: &[usize],
+    defending: &[Card],
+    defender_life: i32,
+) -> Vec<(usize, usize)>
+{
+    let mut available: Vec<usize> = (0..defending.len())
+        .filter(|i| can_block(&defending[*i]))
+        .collect();
+    let mut order = attackers.to_vec();
+    order.sort_by_key(|i| std::cmp::Reverse(stats(&attacking[*i]).0));
+
+    let mut incoming: u32 = order.iter().map(|i| stats(&attacking[*i]).0).sum();
+    let mut blocks = Vec::new();
+
+    for attacker_index in order {
+        let attacker = &attacking[attacker_index];
+        let value = attacker.cost.mana_value();
+        let needed = min_blockers(attacker);
+        if needed > 1 {
+            // Menace: gang-block with the cheapest creatures, but only to survive the attack
+            let mut legal: Vec<usize> = available
+                .iter()
+                .copied()
+                .filter(|b| can_block_attacker(&defending[*b], attacker))
+                .collect();
+            legal.sort_by_key(|b| defending[*b].cost.mana_value());
+            if incoming as i32 >= defender_life && legal.len() >= needed {
+                let chosen: Vec<usize> = legal.into_iter().take(needed).collect();
+                let (power, _) = stats(attacker);
+                let toughness: u32 = chosen.iter().map(|b| stats(&defending[*b]).1).sum();
+                let stopped = if keyword::any(attacker, |h| h.tramples()) {
+                    power.min(toughness)
+                } else {
+                    power
+                };
+                available.retain(|b| !chosen.contains(b));
+                blocks.extend(chosen.into_iter().map(|b| (b, attacker_index)));
+                incoming -= stopped;
+            }
+            continue;
+        }
+        let cheapest = |filter: &dyn Fn(&Card) -> bool| {
+            available
+                .iter()
+                .copied()
+                .filter(|b| can_block_attacker(&defending[*b], attacker) && filter(&defending[*b]))
+                .min_by_key(|b| defending[*b].cost.mana_value())
+        };
+
+        let choice = cheapest(&|b| fight(attacker, b) == (true, false))
+            .or_else(|| {
+                cheapest(&|b| fight(attacker, b) == (true, true) && b.cost.mana_value() <= value)
+            })
+            .or_else(|| {
+                (incoming as i32 >= defender_life)
+                    .then(|| cheapest(&|_| true))
+                    .flatten()
+            });
+
+        if let Some(blocker) = choice {
+            // A trampler still pushes its excess damage through a chump blocker
+            let (power, _) = stats(attacker);
+            let stopped = if keyword::any(attacker, |h| h.tramples()) {
+                power.min(stats(&defending[blocker]).1)
+            } else {
+                power
+            };
+            available.retain(|b| *b != blocker);
+            blocks.push((blocker, attacker_index));
+            incoming -= stopped;
+        }
+    }
+
+    blocks
+}
+
+/// Work out combat damage. First strikers deal their damage in a step of their own, before
+/// everyone else. Unblocked attackers hit the defending player; blocked ones assign lethal damage
+/// to each blocker in order and the rest to the last one (or to the player, with trample).
+pub fn assign_damage(attacking: &[Card], defending: &[Card], combat: &Combat) -> DamageReport
+{
+    // Damage already marked this turn counts towards lethal damage
+    let marked =
+        |cards: &[Card]| -> Vec<u32> { cards.iter().map(creature::marked_damage).collect() };
+    let mut attacker_damage = marked(attacking);
+    let mut blocker_damage = marked(defending);
+    // Dealt damage by a deathtouch source in this combat
+    let mut attacker_touched = vec![false; attacking.len()];
+    let mut blocker_touched = vec![false; defending.len()];
+    let mut attacker_dead = vec![false; attacking.len()];
+    let mut blocker_dead = vec![false; defending.len()];
+    let mut report = DamageReport::default();
+
+    let blockers_of = |attacker: usize| -> Vec<usize> {
+        combat
+            .blocks
+            .iter()
+            .filter(|(_, a)| *a == attacker)
+            .map(|(b, _)| *b)
+            .collect()
+    };
+
+    let any_first_strike = combat
+        .attackers
+        .iter()
+        .any(|a| first_strike(&attacking[*a]))
+        || combat
+            .blocks
+            .iter()
+            .any(|(b, _)| first_strike(&defending[*b]));
+    let passes: &[bool] = if any_first_strike {
+        &[true, false]
+    } else {
+        &[false]
+    };
+
+    for &first_strike_pass in passes {
+        for &attacker in combat.attackers.iter() {
+            if attacker_dead[attacker] {
+                continue;
+            }
+
+            let blockers: Vec<usize> = blockers_of(attacker)
+                .into_iter()
+                .filter(|b| !blocker_dead[*b])
+                .collect();
+            let card = &attacking[attacker];
+
+            if first_strike(card) == first_strike_pass {
+                let (power, _) = stats(card);
+                let tramples = keyword::any(card, |h| h.tramples());
+                let was_blocked = !blockers_of(attacker).is_empty();
+
+                let mut remaining = power;
+                let mut prevented = 0;
+                for (n, &blocker) in blockers.iter().enumerate() {
+                    let lethal = if deathtouch(card) {
+                        1
+                    } else {
+                        stats(&defending[blocker])
+                            .1
+                            .saturating_sub(blocker_damage[blocker])
+                            .max(1)
+                    };
+                    let assigned = if n + 1 == blockers.len() && !tramples {
+                        remaining
+                    } else {
+                        remaining.min(lethal)
+                    };
+                    // Damage is still assigned to a blocker with protection, then prevented
+                    let protected = keyword::protected_from(&defending[blocker], card);
+                    if protected {
+                        prevented += assigned;
+                    } else {
+                        blocker_damage[blocker] += assigned;
+                        blocker_touched[blocker] |= assigned > 0 && deathtouch(card);
+                    }
+                    remaining -= assigned;
+                }
+                // A blocked creature whose blockers are all gone deals no damage, unless it tramples
+                let mut dealt = power - remaining - prevented;
+                if !was_blocked || tramples {
+                    let planeswalker = combat
+                        .planeswalkers
+                        .iter()
+                        .find(|(a, _)| *a == attacker)
+                        .map(|(_, p)| *p);
+                    match planeswalker {
+                        Some(planeswalker) if remaining > 0 => {
+                            report
+                                .to_planeswalkers
+                                .push((attacker, planeswalker, remaining))
+                        }
+                        Some(_) => {}
+                        None => {
+                            report.to_player += remaining;
+                            if remaining > 0 {
+                                report.player_damage.push((attacker, remaining));
+                            }
+                        }
+                    }
+                    dealt += remaining;
+                }
+                report.attacker_life_gained += keyword::life_gained_from_damage(card, dealt);
+            }
+
+            for &blocker in blockers
+                .iter()
+                .filter(|b| first_strike(&defending[**b]) == first_strike_pass)
+            {
+                let card = &defending[blocker];
+                let power = power_against(card, &attacking[attacker]);
+                attacker_damage[attacker] += power;
+                attacker_touched[attacker] |= power > 0 && deathtouch(card);
+                report.defender_life_gained += keyword::life_gained_from_damage(card, power);
+            }
+        }
+
+        // Creatures destroyed (or regenerated, leaving combat) by first-strike damage don't deal
+        // regular damage
+        let lethal = |card: &Card, damage: u32, touched: bool| {
+            damage > 0 && (damage >= stats(card).1 || touched) && !keyword::is_indestructible(card)
+        };
+        for i in 0..attacking.len() {
+            attacker_dead[i] |= lethal(&attacking[i], attacker_damage[i], attacker_touched[i]);
+        }
+        for i in 0..defending.len() {
+            blocker_dead[i] |= lethal(&defending[i], blocker_damage[i], blocker_touched[i]);
+        }
+    }
+
+    let dealt = |cards: &[Card], damage: &[u32], touched: &[bool]| -> Vec<CreatureDamage> {
+        (0..cards.len())
+            .map(|i| CreatureDamage {
+                index: i,
+                amount: damage[i] - creature::marked_damage(&cards[i]),
+                deathtouch: touched[i],
+            })
+            .filter(|d| d.amount > 0)
+            .collect()
+    };
+    report.to_attackers = dealt(attacking, &attacker_damage, &attacker_touched);
+    report.to_blockers = dealt(defending, &blocker_damage, &blocker_touched);
+    report
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{card_by_name, grizzly_bears};
+
+    #[test]
+    fn blocks_with_a_creature_that_survives()
+    {
+        let attacking = vec![grizzly_bears()];
+        let defending = vec![grizzly_bears(), card_by_name("Centaur Courser").unwrap()];
+
+        // The Courser eats the Bears, so they shouldn't even attack unless it's lethal
+        assert!(choose_attackers(&attacking, &defending, 20).is_empty());
+        assert_eq!(choose_attackers(&attacking, &defending[..1], 20), vec![0]);
+        assert_eq!(
+            choose_blockers(&attacking, &[0], &defending, 20),
+            vec![(1, 0)]
+        );
+    }
+
+    #[test]
+    fn chump_blocks_only_to_survive()
+    {
+        let attacking = vec![card_by_name("Craw Wurm").unwrap()];
+        let defending = vec![grizzly_bears()];
+
+        assert!(choose_blockers(&attacking, &[0], &defending, 20).is_empty());
+        assert_eq!(
+            choose_blockers(&attacking, &[0], &defending, 6),
+            vec![(0, 0)]
+        );
+        // With one possible blocker at least 4 damage gets through, which is lethal at 4 life
+        assert_eq!(
+            choose_attackers(
+                &[
+                    grizzly_bears(),
+                    card_by_name("Craw Wurm").unwrap(),
+                    grizzly_bears()
+                ],
+                &defending,
+                4
+            ),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn flying_and_first_strike_change_the_fight()
+    {
+        let drake = card_by_name("Wind Drake").unwrap();
+        let knight = card_by_name("Youthful Knight").unwrap();
+
+        // Nothing on the ground can block a flyer, so it attacks into a board of Bears
+        assert!(!can_block_attacker(&grizzly_bears(), &drake));
+        assert_eq!(choose_attackers(&[drake], &[grizzly_bears()], 20), vec![0]);
+
+        // The 2/1 first striker kills a blocking Bears before it can strike back
+        let combat = Combat {
+            defending_player: 1,
+            attackers: vec![0],
+            blocks: vec![(0, 0)],
+            planeswalkers: Vec::new(),
+        };
+        let report = assign_damage(&[knight], &[grizzly_bears()], &combat);
+        assert_eq!(
+            report.to_blockers,
+            vec![CreatureDamage {
+                index: 0,
+                amount: 2,
+                deathtouch: false
+            }]
+        );
+        assert!(report.to_attackers.is_empty());
+    }
+
+    #[test]
+    fn vigilance_menace_reach_and_defender()
+    {
+        let angel = card_by_name("Serra Angel").unwrap();
+        let brute = card_by_name("Boggart Brute").unwrap();
+        let spider = card_by_name("Giant Spider").unwrap();
+        let wall = card_by_name("Wall of Stone").unwrap();
+
+        // Vigilance attacks untapped; defender can't attack at all but still blocks
+        assert!(can_attack(&angel) && !taps_to_attack(&angel));
+        assert!(taps_to_attack(&brute));
+        assert!(!can_attack(&wall) && can_block(&wall));
+        assert_eq!(
+            legal_attackers(&[wall.clone(), brute.clone(), brute.clone()], &[0, 1, 1]),
+            vec![1]
+        );
+
+        // Reach blocks flyers
+        assert!(can_block_attacker(&spider, &angel));
+        assert!(!can_block_attacker(&grizzly_bears(), &angel));
+
+        // A lone blocker can't block menace, so the block is dropped; two can
+        let attacking = vec![brute];
+        let defending = vec![wall, grizzly_bears()];
+        assert_eq!(min_blockers(&attacking[0]), 2);
+        assert!(legal_blocks(&attacking, &[0], &defending, &[(0, 0)]).is_empty());
+        assert_eq!(
+            legal_blocks(&attacking, &[0], &defending, &[(0, 0), (1, 0)]),
+            vec![(0, 0), (1, 0)]
+        );
+        assert_eq!(choose_attackers(&attacking, &defending[..1], 20), vec![0]);
+        assert!(choose_blockers(&attacking, &[0], &defending, 20).is_empty());
+        assert_eq!(
+            choose_blockers(&attacking, &[0], &defending, 3),
+            vec![(1, 0), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn trample_deathtouch_and_lifelink()
+    {
+        let mut attacking = vec![card_by_name("Colossal Dreadmaw").unwrap()];
+        let defending = vec![card_by_name("Vampire Nighthawk").unwrap()];
+        let combat = Combat {
+            defending_player: 1,
+            attackers: vec![0],
+            blocks: vec![(0, 0)],
+            planeswalkers: Vec::new(),
+        };
+
+        // 6 damage tramples over a 2/3: 3 to the blocker, 3 to the player; deathtouch kills the Dreadmaw
+        let report = assign_damage(&attacking, &defending, &combat);
+        assert_eq!(report.to_player, 3);
+        assert_eq!(
+            report.to_blockers,
+            vec![CreatureDamage {
+                index: 0,
+                amount: 3,
+                deathtouch: false
+            }]
+        );
+        assert_eq!(
+            report.to_attackers,
+            vec![CreatureDamage {
+                index: 0,
+                amount: 2,
+                deathtouch: true
+            }]
+        );
+        assert_eq!(report.defender_life_gained, 2);
+
+        // With deathtouch a trampler only needs to assign 1 damage to its blocker
+        crate::keyword::add_keyword(&mut attacking[0], crate::keyword::Keyword::Deathtouch);
+        assert_eq!(assign_damage(&attacking, &defending, &combat).to_player, 5);
+    }
+
+    #[test]
+    fn damage_is_assigned_in_blocker_order()
+    {
+        let attacking = vec![card_by_name("Craw Wurm").unwrap(), grizzly_bears()];
+        let defending = vec![
+            grizzly_bears(),
+            card_by_name("Centaur Courser").unwrap(),
+            grizzly_bears(),
+        ];
+        let combat = Combat {
+            defending_player: 1,
+            attackers: vec![0, 1],
+            blocks: vec![(0, 0), (1, 0)],
+            planeswalkers: Vec::new(),
+        };
+
+        let report = assign_damage(&attacking, &defending, &combat);
+        assert_eq!(report.to_player, 2);
+        // 2 damage kills the Bears and the remaining 4 kill the Courser; 5 back kills the 6/4 Wurm
+        let amounts = |damage: &[CreatureDamage]| {
+            damage
+                .iter()
+                .map(|d| (d.index, d.amount))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(amounts(&report.to_blockers), vec![(0, 2), (1, 4)]);
+        assert_eq!(amounts(&report.to_attackers), vec![(0, 5)]);
+    }
+
+    #[test]
+    fn damage_marked_earlier_counts_towards_lethal()
+    {
+        let attacking = vec![card_by_name("Craw Wurm").unwrap()];
+        let mut defending = vec![card_by_name("Centaur Courser").unwrap(), grizzly_bears()];
+        creature::deal_damage(&mut defending[0], 2, false);
+        let combat = Combat {
+            defending_player: 1,
+            attackers: vec![0],
+            blocks: vec![(0, 0), (1, 0)],
+            planeswalkers: Vec::new(),
+        };
+
+        // The wounded Courser only needs 1 more, leaving 5 for the Bears
+        let report = assign_damage(&attacking, &defending, &combat);
+        assert_eq!(report.to_blockers[0].amount, 1);
+        assert_eq!(report.to_blockers[1].amount, 5);
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use crate::card::{Card, CardFragmentKind, CardType, CommanderFragment, Deck};
+use crate::mana::{self, ManaColor, ManaCost};
+
+/// Mark `card` as its deck's commander.
+pub fn make_commander(mut card: Card) -> Card
+{
+    card.fragments.insert(
+        CardFragmentKind::Commander,
+        Arc::new(CommanderFragment::default()),
+    );
+    card
+}
+
+pub fn is_commander(card: &Card) -> bool
+{
+    card.fragments.contains_key(&CardFragmentKind::Commander)
+}
+
+/// Casting from the command zone costs {2} more for every earlier cast from there.
+pub fn cost_from_command_zone(card: &Card) -> ManaCost
+{
+    let casts = card
+        .fragment::<CommanderFragment>()
+        .map(|cf| cf.casts)
+        .unwrap_or(0);
+    let mut cost = card.cost;
+    cost.generic += 2 * casts;
+    cost
+}
+
+pub fn record_cast(card: &mut Card)
+{
+    if let Some(cf) = card.fragment_mut::<CommanderFragment>() {
+        cf.casts += 1;
+    }
+}
+
+/// Colors in the card's cost, plus the colors a nonbasic land or other mana source taps for
+/// (the engine doesn't keep rules text, so that stands in for its mana symbols).
+pub fn color_identity(card: &Card) -> Vec<ManaColor>
+{
+    let mut colors = card.cost.colors();
+    let basic = card.is_type(CardType::Land) && mana::basic_land_color(&card.name).is_some();
+    if !basic {
+        for color in mana::produced_colors(card) {
+            if color != ManaColor::Colorless && !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+    }
+    colors
+}
+
+/// Cards outside the combined color identity of the deck's commanders.
+pub fn outside_color_identity(deck: &Deck) -> Vec<&Card>
+{
+    let allowed: Vec<ManaColor> = deck.commanders.iter().flat_map(color_identity).collect();
+    deck.cards
+        .iter()
+        .filter(|card| color_identity(card).iter().any(|c| !allowed.contains(c)))
+        .collect()
+}
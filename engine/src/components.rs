@@ -0,0 +1,199 @@
+use crate::card::{Card, CardId};
+use crate::creature;
+use crate::keyword;
+use crate::mana::{self, ManaColor};
+use crate::tappable;
+
+/// Columnar view of one battlefield: an entry per permanent, in battlefield order, for each
+/// component the engine's hot questions need, so "which creatures can attack", "which can block"
+/// and "what can tap for mana" become scans over plain columns instead of a fragment lookup per
+/// card per question. Every player keeps one alongside their battlefield: `GameState::move_card`
+/// adds and removes rows as permanents come and go, tapping updates its row, and the whole view is
+/// refreshed wherever continuous effects are reapplied.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Components
+{
+    // Which card each row is, to tell when the battlefield was changed behind the view's back
+    pub ids: Vec<CardId>,
+    pub creature: Vec<bool>,
+    pub tapped: Vec<bool>,
+    // Arrived this turn without haste, so can't attack or tap for mana
+    pub summoning_sick: Vec<bool>,
+    // Has a keyword that says it can't attack, like defender
+    pub defender: Vec<bool>,
+    // Colors a mana source can make and how much mana it makes; no colors for other permanents
+    pub mana: Vec<(Vec<ManaColor>, u32)>,
+}
+
+impl Components
+{
+    pub fn of(battlefield: &[Card]) -> Self
+    {
+        let mut components = Components::default();
+        for card in battlefield {
+            components.push(card);
+        }
+        components
+    }
+
+    /// Add a row for a permanent put at the end of the battlefield.
+    pub fn push(&mut self, card: &Card)
+    {
+        let is_creature = creature::is_creature(card);
+        self.ids.push(card.id);
+        self.creature.push(is_creature);
+        self.tapped.push(tappable::is_tapped(card));
+        self.summoning_sick
+            .push(is_creature && creature::has_summoning_sickness(card));
+        self.defender.push(!keyword::all(card, |h| h.can_attack()));
+        self.mana.push(if mana::is_mana_source(card) {
+            (mana::produced_colors(card), mana::produced_amount(card))
+        } else {
+            (Vec::new(), 0)
+        });
+    }
+
+    /// Drop the row of the permanent at `index` as it leaves the battlefield.
+    pub fn remove(&mut self, index: usize)
+    {
+        self.ids.remove(index);
+        self.creature.remove(index);
+        self.tapped.remove(index);
+        self.summoning_sick.remove(index);
+        self.defender.remove(index);
+        self.mana.remove(index);
+    }
+
+    /// Look every permanent of `battlefield` up again, after continuous effects may have changed
+    /// any of them.
+    pub fn refresh(&mut self, battlefield: &[Card])
+    {
+        self.ids.clear();
+        self.creature.clear();
+        self.tapped.clear();
+        self.summoning_sick.clear();
+        self.defender.clear();
+        self.mana.clear();
+        for card in battlefield {
+            self.push(card);
+        }
+    }
+
+    /// Look the permanent at `index` up again after it was tapped or untapped, or gained or lost
+    /// summoning sickness. What mana it makes doesn't change that way.
+    pub fn refresh_row(&mut self, index: usize, card: &Card)
+    {
+        let is_creature = creature::is_creature(card);
+        self.ids[index] = card.id;
+        self.creature[index] = is_creature;
+        self.tapped[index] = tappable::is_tapped(card);
+        self.summoning_sick[index] = is_creature && creature::has_summoning_sickness(card);
+        self.defender[index] = !keyword::all(card, |h| h.can_attack());
+    }
+
+    /// Whether the rows are still those of `battlefield`'s permanents, in order.
+    pub fn matches(&self, battlefield: &[Card]) -> bool
+    {
+        self.ids.len() == battlefield.len()
+            && self
+                .ids
+                .iter()
+                .zip(battlefield)
+                .all(|(id, card)| *id == card.id)
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.tapped.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.tapped.is_empty()
+    }
+
+    pub fn is_creature(&self, index: usize) -> bool
+    {
+        self.creature[index]
+    }
+
+    /// Untapped creatures that have been around since the turn began, or don't need to have, and
+    /// are allowed to attack at all.
+    pub fn ready_attackers(&self) -> Vec<usize>
+    {
+        (0..self.len())
+            .filter(|&i| {
+                self.is_creature(i)
+                    && !self.tapped[i]
+                    && !self.summoning_sick[i]
+                    && !self.defender[i]
+            })
+            .collect()
+    }
+
+    pub fn untapped_creatures(&self) -> Vec<usize>
+    {
+        (0..self.len())
+            .filter(|&i| self.is_creature(i) && !self.tapped[i])
+            .collect()
+    }
+
+    /// Untapped mana sources (index) and the colors each can produce, listed once per mana they
+    /// make. Creatures can't tap for mana the turn they arrive, unless they have haste.
+    pub fn untapped_mana(&self) -> Vec<(usize, Vec<ManaColor>)>
+    {
+        (0..self.len())
+            .filter(|&i| !self.mana[i].0.is_empty() && !self.tapped[i] && !self.summoning_sick[i])
+            .flat_map(|i| std::iter::repeat_n((i, self.mana[i].0.clone()), self.mana[i].1 as usize))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{Deck, card_by_name};
+    use crate::combat;
+    use crate::game::{GameState, Zone};
+
+    #[test]
+    fn columns_kept_with_the_battlefield_answer_like_the_cards_do()
+    {
+        let cards = [
+            ("Forest", 12),
+            ("Mountain", 12),
+            ("Llanowar Elves", 8),
+            ("Raging Goblin", 8),
+            ("Grizzly Bears", 8),
+            ("Giant Growth", 6),
+            ("Lightning Bolt", 6),
+        ];
+        let deck = Deck::new(
+            cards
+                .iter()
+                .flat_map(|&(name, count)| (0..count).map(move |_| card_by_name(name).unwrap()))
+                .collect(),
+        );
+        let mut game = GameState::new_seeded(2, &deck, 9);
+        let mut checked = 0;
+        while !game.is_game_over() {
+            game.step();
+            for player in game.players.iter() {
+                let battlefield = player.zones.get(&Zone::Battlefield).unwrap();
+                let components = &player.components;
+                assert_eq!(*components, Components::of(battlefield));
+                let attackers: Vec<usize> = (0..battlefield.len())
+                    .filter(|i| combat::can_attack(&battlefield[*i]))
+                    .collect();
+                let blockers: Vec<usize> = (0..battlefield.len())
+                    .filter(|i| combat::can_block(&battlefield[*i]))
+                    .collect();
+                assert_eq!(components.ready_attackers(), attackers);
+                assert_eq!(components.untapped_creatures(), blockers);
+                checked += attackers.len();
+            }
+        }
+        assert!(checked > 0);
+    }
+}
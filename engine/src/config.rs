@@ -0,0 +1,168 @@
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Turn a TOML simulation config into the command-line arguments it stands for, so every option
+/// can live in either place. Keys are option names (`games = 5000` is `--games 5000`; `_` and `-`
+/// both work), tables only group keys (`[rules]` can hold `format` and `starting_life`), `true`
+/// turns a flag on and arrays repeat the option once per item. Paths are taken as written, so
+/// relative ones are relative to the working directory.
+pub fn args_from_toml(text: &str) -> Result<Vec<String>, String>
+{
+    let document: DocumentMut = text
+        .parse()
+        .map_err(|e: toml_edit::TomlError| e.to_string())?;
+    let mut args = Vec::new();
+    for (key, item) in document.as_table().iter() {
+        push_item(key, item, &mut args)?;
+    }
+    Ok(args)
+}
+
+/// Drop the options in `args` (from `args_from_toml`) that the command line in `given` sets
+/// itself or conflicts with, so its values replace the config's instead of adding to them.
+pub fn without_overridden(args: Vec<String>, command: &Command, given: &ArgMatches) -> Vec<String>
+{
+    let on_command_line =
+        |arg: &Arg| given.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine);
+    let conflict = |a: &Arg, b: &Arg| {
+        command
+            .get_arg_conflicts_with(a)
+            .iter()
+            .any(|c| c.get_id() == b.get_id())
+    };
+    let set: Vec<&Arg> = command
+        .get_arguments()
+        .filter(|arg| on_command_line(arg))
+        .collect();
+    let overridden = |flag: &str| {
+        command
+            .get_arguments()
+            .find(|arg| {
+                arg.get_long()
+                    .is_some_and(|long| flag.strip_prefix("--") == Some(long))
+            })
+            .is_some_and(|arg| {
+                set.iter()
+                    .any(|s| s.get_id() == arg.get_id() || conflict(s, arg) || conflict(arg, s))
+            })
+    };
+
+    let mut kept = Vec::new();
+    let mut keep = true;
+    for arg in args {
+        // An option's values follow it until the next option
+        if arg.starts_with("--") {
+            keep = !overridden(&arg);
+        }
+        if keep {
+            kept.push(arg);
+        }
+    }
+    kept
+}
+
+fn push_item(key: &str, item: &Item, args: &mut Vec<String>) -> Result<(), String>
+{
+    match item {
+        Item::None => Ok(()),
+        Item::Value(value) => push_value(key, value, args),
+        Item::Table(table) => table
+            .iter()
+            .try_for_each(|(key, item)| push_item(key, item, args)),
+        Item::ArrayOfTables(_) => Err(format!("'{}' can't be an array of tables", key)),
+    }
+}
+
+fn push_value(key: &str, value: &Value, args: &mut Vec<String>) -> Result<(), String>
+{
+    if key == "config" {
+        return Err(String::from("a config file can't name another config file"));
+    }
+    let flag = format!("--{}", key.replace('_', "-"));
+    match value {
+        Value::String(s) => args.extend([flag, s.value().clone()]),
+        Value::Integer(i) => args.extend([flag, i.value().to_string()]),
+        Value::Float(f) => args.extend([flag, f.value().to_string()]),
+        Value::Boolean(b) if *b.value() => args.push(flag),
+        Value::Boolean(_) => {}
+        Value::Array(items) => {
+            for item in items.iter() {
+                if matches!(item, Value::Array(_) | Value::InlineTable(_)) {
+                    return Err(format!("'{}' can only list plain values", key));
+                }
+                push_value(key, item, args)?;
+            }
+        }
+        Value::InlineTable(table) => table
+            .iter()
+            .try_for_each(|(key, value)| push_value(key, value, args))?,
+        Value::Datetime(_) => return Err(format!("'{}' can't be a date", key)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn config_keys_become_command_line_options()
+    {
+        let args = args_from_toml("games = 5000\nseed = 7\nsweep = true\nno_interactive = false\npool = [\"Grizzly Bears\", \"Craw Wurm\"]\n\n[rules]\nformat = \"commander\"\nstarting-life = 30\n").unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "--games",
+                "5000",
+                "--seed",
+                "7",
+                "--sweep",
+                "--pool",
+                "Grizzly Bears",
+                "--pool",
+                "Craw Wurm",
+                "--format",
+                "commander",
+                "--starting-life",
+                "30"
+            ]
+        );
+
+        assert!(
+            args_from_toml(include_str!("../data/sim.toml"))
+                .unwrap()
+                .contains(&String::from("--optimizer"))
+        );
+        assert!(args_from_toml("config = \"other.toml\"").is_err());
+        assert!(args_from_toml("games = ").is_err());
+    }
+
+    #[test]
+    fn command_line_options_replace_the_configs()
+    {
+        let command = Command::new("engine")
+            .arg(Arg::new("games").long("games"))
+            .arg(
+                Arg::new("pool")
+                    .long("pool")
+                    .action(clap::ArgAction::Append),
+            )
+            .arg(Arg::new("seed").long("seed"))
+            .arg(Arg::new("resume").long("resume").conflicts_with("seed"));
+        let given = command
+            .clone()
+            .try_get_matches_from(["engine", "--pool", "Craw Wurm", "--resume", "run.json"])
+            .unwrap();
+
+        let args = args_from_toml(
+            "games = 5000\nseed = 42\npool = [\"Grizzly Bears\", \"Centaur Courser\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            without_overridden(args, &command, &given),
+            vec!["--games", "5000"]
+        );
+    }
+}
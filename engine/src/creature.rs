@@ -1,23 +1,31 @@
-use crate::card::{Card, CardType, CardFragmentKind, CreatureFragment, CreatureStats};
+use std::sync::Arc;
+
+use crate::card::{
+    Card, CardFragmentKind, CardType, CounterKind, CreatureFragment, CreatureStats, StatModifier,
+};
+use crate::keyword::Keyword;
+use crate::layers::{self, ContinuousEffect, LayeredEffect};
 
 pub fn is_creature(card: &Card) -> bool
 {
-    card.card_types.iter().any(|ct| *ct == CardType::Creature)
+    card.card_types.contains(&CardType::Creature)
         || card.fragments.contains_key(&CardFragmentKind::Creature)
 }
 
 pub fn creature_stats(card: &Card) -> Option<CreatureStats>
 {
-    card.fragments.get(&CardFragmentKind::Creature).and_then(|f|
-        f.as_any().downcast_ref::<CreatureFragment>().map(|cf| cf.stats)
-    )
+    card.fragment::<CreatureFragment>().map(|cf| cf.stats)
 }
 
 pub fn add_creature_fragment(card: &mut Card, power: u8, toughness: u8)
 {
     card.fragments.insert(
         CardFragmentKind::Creature,
-        Box::new(CreatureFragment { stats: CreatureStats { power, toughness }, summoning_sickness: false }),
+        Arc::new(CreatureFragment {
+            stats: CreatureStats { power, toughness },
+            summoning_sickness: false,
+            ..CreatureFragment::default()
+        }),
     );
 }
 
@@ -28,18 +36,252 @@ pub fn remove_creature_fragment(card: &mut Card)
 
 pub fn set_summoning_sickness(card: &mut Card, value: bool)
 {
-    if let Some(f) = card.fragments.get_mut(&CardFragmentKind::Creature)
-    {
-        if let Some(cf) = f.as_any_mut().downcast_mut::<CreatureFragment>()
-        {
-            cf.summoning_sickness = value;
-        }
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.summoning_sickness = value;
     }
 }
 
-pub fn has_summoning_sickness(card: &Card) -> bool
+/// Whether a creature came under its controller's control this turn, haste or not.
+pub fn arrived_this_turn(card: &Card) -> bool
 {
-    card.fragments.get(&CardFragmentKind::Creature)
-        .and_then(|f| f.as_any().downcast_ref::<CreatureFragment>().map(|cf| cf.summoning_sickness))
+    card.fragment::<CreatureFragment>()
+        .map(|cf| cf.summoning_sickness)
         .unwrap_or(false)
 }
+
+/// Whether summoning sickness stops a creature attacking or tapping for mana right now: it
+/// arrived this turn and has no haste, printed, from another permanent or until end of turn.
+/// Haste gained or lost mid-turn counts from that moment on.
+pub fn has_summoning_sickness(card: &Card) -> bool
+{
+    arrived_this_turn(card) && !crate::keyword::any(card, |h| h.ignores_summoning_sickness())
+}
+
+pub fn counters(card: &Card, kind: CounterKind) -> u32
+{
+    card.fragment::<CreatureFragment>()
+        .and_then(|cf| cf.counters.get(&kind).copied())
+        .unwrap_or(0)
+}
+
+/// Put counters on a creature. +1/+1 and -1/-1 counters cancel each other out in pairs.
+pub fn add_counters(card: &mut Card, kind: CounterKind, amount: u32)
+{
+    let Some(cf) = card.fragment_mut::<CreatureFragment>() else {
+        return;
+    };
+
+    let opposite = match kind {
+        CounterKind::PlusOne => CounterKind::MinusOne,
+        CounterKind::MinusOne => CounterKind::PlusOne,
+    };
+    let existing = cf.counters.remove(&opposite).unwrap_or(0);
+    let cancelled = existing.min(amount);
+    if existing > cancelled {
+        cf.counters.insert(opposite, existing - cancelled);
+    }
+    if amount > cancelled {
+        *cf.counters.entry(kind).or_insert(0) += amount - cancelled;
+    }
+}
+
+/// Printed stats with continuous effects applied in layer order, then counters. Neither value
+/// goes below zero.
+pub fn effective_stats(card: &Card) -> Option<CreatureStats>
+{
+    let cf = card.fragment::<CreatureFragment>()?;
+    let counters =
+        counters(card, CounterKind::PlusOne) as i32 - counters(card, CounterKind::MinusOne) as i32;
+    // Anthems, attachments and pumps all modify power and toughness; being sums, their order doesn't matter
+    let modifier = LayeredEffect {
+        timestamp: 0,
+        effect: ContinuousEffect::ModifyPowerToughness(StatModifier {
+            power: cf.modifier.power + cf.until_end_of_turn.power,
+            toughness: cf.modifier.toughness + cf.until_end_of_turn.toughness,
+        }),
+    };
+    Some(layers::power_toughness(
+        cf.stats,
+        cf.effects
+            .iter()
+            .chain(&cf.until_end_of_turn_effects)
+            .chain([&modifier]),
+        counters,
+    ))
+}
+
+/// Give a creature +power/+toughness and `keywords` until end of turn.
+pub fn pump(card: &mut Card, power: i32, toughness: i32, keywords: &[Keyword])
+{
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.until_end_of_turn.power += power;
+        cf.until_end_of_turn.toughness += toughness;
+        for keyword in keywords {
+            if !cf.until_end_of_turn_keywords.contains(keyword) {
+                cf.until_end_of_turn_keywords.push(*keyword);
+            }
+        }
+    }
+}
+
+/// Replace the continuous effects other permanents have on a creature.
+pub fn set_continuous_effects(
+    card: &mut Card,
+    modifier: StatModifier,
+    granted_keywords: Vec<Keyword>,
+)
+{
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.modifier = modifier;
+        cf.granted_keywords = granted_keywords;
+    }
+}
+
+/// Keywords a creature has from other permanents, then those it gained until end of turn.
+pub fn granted_keywords(card: &Card) -> impl Iterator<Item = &Keyword>
+{
+    card.fragment::<CreatureFragment>()
+        .into_iter()
+        .flat_map(|cf| {
+            cf.granted_keywords
+                .iter()
+                .chain(cf.until_end_of_turn_keywords.iter())
+        })
+}
+
+pub fn marked_damage(card: &Card) -> u32
+{
+    card.fragment::<CreatureFragment>()
+        .map(|cf| cf.damage)
+        .unwrap_or(0)
+}
+
+/// Mark damage on a creature. It stays until the cleanup at the end of the turn.
+pub fn deal_damage(card: &mut Card, amount: u32, deathtouch: bool)
+{
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.damage += amount;
+        cf.deathtouch_damage |= deathtouch && amount > 0;
+    }
+}
+
+pub fn clear_damage(card: &mut Card)
+{
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.damage = 0;
+        cf.deathtouch_damage = false;
+    }
+}
+
+/// Give a creature a regeneration shield until end of turn.
+pub fn add_regeneration_shield(card: &mut Card)
+{
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.regeneration_shields += 1;
+    }
+}
+
+pub fn regeneration_shields(card: &Card) -> u32
+{
+    card.fragment::<CreatureFragment>()
+        .map_or(0, |cf| cf.regeneration_shields)
+}
+
+/// Use up a regeneration shield, if the creature has one, to remove its damage instead of it
+/// being destroyed. The caller taps it and removes it from combat.
+pub fn regenerate(card: &mut Card) -> bool
+{
+    let Some(cf) = card
+        .fragment_mut::<CreatureFragment>()
+        .filter(|cf| cf.regeneration_shields > 0)
+    else {
+        return false;
+    };
+    cf.regeneration_shields -= 1;
+    clear_damage(card);
+    true
+}
+
+/// A creature leaving the battlefield loses its counters, damage and "until end of turn" effects.
+pub fn leave_battlefield(card: &mut Card)
+{
+    clear_damage(card);
+    clear_until_end_of_turn(card);
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.counters.clear();
+    }
+}
+
+/// Set a creature's base power and toughness until end of turn, as of `timestamp`.
+pub fn set_base_stats(card: &mut Card, stats: CreatureStats, timestamp: u64)
+{
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.until_end_of_turn_effects.push(LayeredEffect {
+            timestamp,
+            effect: ContinuousEffect::SetPowerToughness(stats),
+        });
+    }
+}
+
+pub fn clear_until_end_of_turn(card: &mut Card)
+{
+    if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+        cf.until_end_of_turn = StatModifier::default();
+        cf.until_end_of_turn_effects.clear();
+        cf.until_end_of_turn_keywords.clear();
+        cf.regeneration_shields = 0;
+    }
+}
+
+/// Whether state-based actions put this creature into the graveyard: it has no toughness left,
+/// lethal damage marked, or any damage from a deathtouch source.
+pub fn is_lethally_damaged(card: &Card) -> bool
+{
+    let Some(cf) = card.fragment::<CreatureFragment>() else {
+        return false;
+    };
+
+    let toughness = effective_stats(card).map_or(0, |s| s.toughness as u32);
+    toughness == 0 || cf.damage >= toughness || cf.deathtouch_damage
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::grizzly_bears;
+
+    #[test]
+    fn counters_adjust_stats_and_cancel_out()
+    {
+        let mut bears = grizzly_bears();
+        add_counters(&mut bears, CounterKind::PlusOne, 2);
+        assert_eq!(effective_stats(&bears).unwrap().power, 4);
+        assert_eq!(creature_stats(&bears).unwrap().power, 2);
+
+        add_counters(&mut bears, CounterKind::MinusOne, 5);
+        assert_eq!(counters(&bears, CounterKind::PlusOne), 0);
+        assert_eq!(counters(&bears, CounterKind::MinusOne), 3);
+        assert_eq!(effective_stats(&bears).unwrap().toughness, 0);
+
+        let mut forest = crate::card::forest();
+        add_counters(&mut forest, CounterKind::PlusOne, 1);
+        assert!(effective_stats(&forest).is_none());
+        assert!(is_lethally_damaged(&bears));
+    }
+
+    #[test]
+    fn marked_damage_is_lethal_at_toughness()
+    {
+        let mut bears = grizzly_bears();
+        deal_damage(&mut bears, 1, false);
+        assert!(!is_lethally_damaged(&bears));
+        deal_damage(&mut bears, 1, false);
+        assert!(is_lethally_damaged(&bears));
+
+        clear_damage(&mut bears);
+        assert_eq!(marked_damage(&bears), 0);
+        deal_damage(&mut bears, 1, true);
+        assert!(is_lethally_damaged(&bears));
+    }
+}
@@ -0,0 +1,640 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::card::{Card, CardType, Deck};
+use crate::rules::GameRules;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecklistEntry
+{
+    pub count: u32,
+    pub name: String,
+    // Line of the decklist it was read from, for error messages
+    #[serde(default)]
+    pub line: usize,
+}
+
+/// Cards to swap between games against one archetype: the deck or archetype name the plan is
+/// for ("*" for any opponent without a plan of its own), what comes out of the main deck and what
+/// comes in from the sideboard.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SideboardPlan
+{
+    pub versus: String,
+    pub out: Vec<DecklistEntry>,
+    pub bring_in: Vec<DecklistEntry>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Decklist
+{
+    pub main: Vec<DecklistEntry>,
+    pub sideboard: Vec<DecklistEntry>,
+    pub commander: Vec<DecklistEntry>,
+    pub plans: Vec<SideboardPlan>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecklistError
+{
+    InvalidLine
+    {
+        line: usize, text: String
+    },
+    UnknownCard
+    {
+        line: usize, name: String
+    },
+}
+
+impl fmt::Display for DecklistError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            DecklistError::InvalidLine { line, text } => write!(
+                f,
+                "line {}: expected '<count> <card name>', got '{}'",
+                line, text
+            ),
+            DecklistError::UnknownCard { line, name } => {
+                write!(f, "line {}: unknown card '{}'", line, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecklistError {}
+
+/// A reason a deck can't be played in a format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LegalityError
+{
+    TooFewCards
+    {
+        size: u32, min: u32
+    },
+    TooManyCards
+    {
+        size: u32, max: u32
+    },
+    TooManyCopies
+    {
+        name: String, count: u32, max: u32
+    },
+    Banned
+    {
+        name: String
+    },
+    CommanderCount
+    {
+        found: usize
+    },
+    OutsideColorIdentity
+    {
+        name: String
+    },
+}
+
+impl fmt::Display for LegalityError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            LegalityError::TooFewCards { size, min } => {
+                write!(f, "deck has {} cards, needs at least {}", size, min)
+            }
+            LegalityError::TooManyCards { size, max } => {
+                write!(f, "deck has {} cards, at most {} allowed", size, max)
+            }
+            LegalityError::TooManyCopies { name, count, max } => {
+                write!(f, "{} copies of '{}', at most {} allowed", count, name, max)
+            }
+            LegalityError::Banned { name } => write!(f, "'{}' is banned", name),
+            LegalityError::CommanderCount { found } => write!(
+                f,
+                "a commander deck needs one or two commanders, found {}",
+                found
+            ),
+            LegalityError::OutsideColorIdentity { name } => {
+                write!(f, "'{}' is outside the commander's color identity", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LegalityError {}
+
+/// Everything that keeps `deck` from being legal under `format`: deck size, copies per card
+/// (basic lands are exempt), banned cards and, for Commander, the commanders and their colors.
+pub fn validate(deck: &Deck, format: &GameRules) -> Vec<LegalityError>
+{
+    let mut errors = Vec::new();
+    let size = (deck.cards.len() + deck.commanders.len()) as u32;
+    if size < format.min_deck_size {
+        errors.push(LegalityError::TooFewCards {
+            size,
+            min: format.min_deck_size,
+        });
+    }
+    if let Some(max) = format.max_deck_size
+        && size > max
+    {
+        errors.push(LegalityError::TooManyCards { size, max });
+    }
+
+    // Copies per name, in deck order; the sideboard shares the main deck's limit
+    let mut counts: Vec<(&str, u32)> = Vec::new();
+    for card in deck
+        .cards
+        .iter()
+        .chain(deck.commanders.iter())
+        .chain(deck.sideboard.iter())
+    {
+        match counts.iter_mut().find(|(name, _)| *name == card.name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&card.name, 1)),
+        }
+    }
+    for (name, count) in counts {
+        let basic = crate::mana::basic_land_color(name).is_some()
+            && deck
+                .cards
+                .iter()
+                .any(|c| c.name == name && c.is_type(CardType::Land));
+        if let Some(max) = format.max_copies
+            && count > max
+            && !basic
+        {
+            errors.push(LegalityError::TooManyCopies {
+                name: name.to_string(),
+                count,
+                max,
+            });
+        }
+        if format
+            .banned
+            .iter()
+            .any(|banned| banned.eq_ignore_ascii_case(name))
+        {
+            errors.push(LegalityError::Banned {
+                name: name.to_string(),
+            });
+        }
+    }
+
+    if format.commander {
+        if !(1..=2).contains(&deck.commanders.len()) {
+            errors.push(LegalityError::CommanderCount {
+                found: deck.commanders.len(),
+            });
+        }
+        for card in crate::commander::outside_color_identity(deck) {
+            let error = LegalityError::OutsideColorIdentity {
+                name: card.name.clone(),
+            };
+            if !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+    }
+
+    errors
+}
+
+/// What simulations do with a deck that fails `validate`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Legality
+{
+    // Simulate it anyway
+    Ignore,
+    // Simulate it, but log why it isn't legal
+    #[default]
+    Warn,
+    // Don't simulate it; the optimizer then never picks it
+    Refuse,
+}
+
+impl FromStr for Legality
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s {
+            "ignore" => Ok(Legality::Ignore),
+            "warn" => Ok(Legality::Warn),
+            "refuse" => Ok(Legality::Refuse),
+            _ => Err(format!(
+                "unknown legality setting '{}' (expected ignore, warn or refuse)",
+                s
+            )),
+        }
+    }
+}
+
+/// Card names from a banned-list file: one per line, with `//` and `#` comments.
+pub fn parse_banned_list(text: &str) -> Vec<String>
+{
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Section
+{
+    Main,
+    Sideboard,
+    Commander,
+    // Index of the sideboard plan being read
+    Plan(usize),
+}
+
+// "Deck", "Sideboard:", ... → the section it starts, if the line is a header
+fn section_header(line: &str) -> Option<Section>
+{
+    match line.trim_end_matches(':').to_ascii_lowercase().as_str() {
+        "deck" | "main" | "maindeck" => Some(Section::Main),
+        "commander" => Some(Section::Commander),
+        "sideboard" | "companion" => Some(Section::Sideboard),
+        _ => None,
+    }
+}
+
+// "4 Lightning Bolt", "4x Lightning Bolt" or Arena's "4 Lightning Bolt (M10) 146"
+fn parse_entry(line: &str, number: usize) -> Option<DecklistEntry>
+{
+    let (count, rest) = line.split_once(char::is_whitespace)?;
+    let count: u32 = count.trim_end_matches(['x', 'X']).parse().ok()?;
+
+    let mut name = rest.trim();
+    if let Some(open) = name.rfind(" (") {
+        // Drop Arena's "(SET) collector-number" suffix
+        let suffix = &name[open + 2..];
+        if let Some((set, number)) = suffix.split_once(')')
+            && !set.is_empty()
+            && set.chars().all(|c| c.is_ascii_alphanumeric())
+            && number
+                .trim()
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            name = name[..open].trim_end();
+        }
+    }
+
+    (count > 0 && !name.is_empty()).then(|| DecklistEntry {
+        count,
+        name: name.to_string(),
+        line: number,
+    })
+}
+
+/// Parse a plain-text or MTG Arena decklist into card names and counts.
+///
+/// Understands `4 Name` / `4x Name`, Arena set suffixes, `Deck`/`Sideboard`/`Commander` headers, MTGO's
+/// `SB:` prefix and the blank line that separates main deck and sideboard in plain lists.
+/// Sideboard plans start with a `vs <archetype>` line followed by `-2 Name` / `+2 Name` swaps.
+/// Lines starting with `//` or `#` are comments.
+pub fn parse_decklist_entries(text: &str) -> Result<Decklist, DecklistError>
+{
+    let mut list = Decklist::default();
+    let mut section = Section::Main;
+    let mut saw_header = false;
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.starts_with("//") || line.starts_with('#') {
+            continue;
+        }
+
+        if line.is_empty() {
+            // Without headers, a blank line after the main deck starts the sideboard
+            if !saw_header && !list.main.is_empty() {
+                section = Section::Sideboard;
+            }
+            continue;
+        }
+
+        if let Some(header) = section_header(line) {
+            section = header;
+            saw_header = true;
+            continue;
+        }
+
+        if let Some(versus) = line
+            .strip_prefix("vs ")
+            .or_else(|| line.strip_prefix("versus "))
+        {
+            list.plans.push(SideboardPlan {
+                versus: versus.trim().trim_end_matches(':').to_string(),
+                ..SideboardPlan::default()
+            });
+            section = Section::Plan(list.plans.len() - 1);
+            saw_header = true;
+            continue;
+        }
+
+        if let Section::Plan(plan) = section {
+            let invalid = || DecklistError::InvalidLine {
+                line: index + 1,
+                text: line.to_string(),
+            };
+            let (swap, entry) = line.split_at_checked(1).ok_or_else(invalid)?;
+            let entry = parse_entry(entry.trim_start(), index + 1).ok_or_else(invalid)?;
+            match swap {
+                "-" => list.plans[plan].out.push(entry),
+                "+" => list.plans[plan].bring_in.push(entry),
+                _ => return Err(invalid()),
+            }
+            continue;
+        }
+
+        let (target, line) = match line.strip_prefix("SB:") {
+            Some(rest) => (Section::Sideboard, rest.trim()),
+            None => (section, line),
+        };
+
+        let entry = parse_entry(line, index + 1).ok_or_else(|| DecklistError::InvalidLine {
+            line: index + 1,
+            text: line.to_string(),
+        })?;
+        match target {
+            Section::Main => list.main.push(entry),
+            Section::Sideboard => list.sideboard.push(entry),
+            Section::Commander => list.commander.push(entry),
+            Section::Plan(_) => unreachable!(),
+        }
+    }
+
+    Ok(list)
+}
+
+/// Parse a decklist and build the main deck, looking card names up with `lookup`.
+pub fn parse_decklist_with(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<Card>,
+) -> Result<Deck, DecklistError>
+{
+    let list = parse_decklist_entries(text)?;
+    let build = |entries: &[DecklistEntry]| -> Result<Vec<Card>, DecklistError> {
+        let mut cards = Vec::new();
+        for entry in entries.iter() {
+            let card = lookup(&entry.name).ok_or_else(|| DecklistError::UnknownCard {
+                line: entry.line,
+                name: entry.name.clone(),
+            })?;
+            cards.extend(std::iter::repeat_n(card, entry.count as usize));
+        }
+        Ok(cards)
+    };
+
+    Ok(Deck {
+        cards: build(&list.main)?,
+        commanders: build(&list.commander)?,
+        sideboard: build(&list.sideboard)?,
+        plans: list.plans,
+    })
+}
+
+/// `deck` after following its sideboard plan against `opponent`: the plans for exactly that
+/// name, or else those for "*". Swaps for cards that aren't there are skipped.
+pub fn sideboard_against(deck: &Deck, opponent: &str) -> Deck
+{
+    let mut plans: Vec<&SideboardPlan> = deck
+        .plans
+        .iter()
+        .filter(|p| p.versus.eq_ignore_ascii_case(opponent))
+        .collect();
+    if plans.is_empty() {
+        plans = deck.plans.iter().filter(|p| p.versus == "*").collect();
+    }
+
+    let mut sideboarded = deck.clone();
+    for plan in plans {
+        for entry in plan.out.iter() {
+            move_cards(&mut sideboarded.cards, &mut sideboarded.sideboard, entry);
+        }
+        for entry in plan.bring_in.iter() {
+            move_cards(&mut sideboarded.sideboard, &mut sideboarded.cards, entry);
+        }
+    }
+    sideboarded
+}
+
+// Move up to `entry.count` copies of a card from one pile to the other
+fn move_cards(from: &mut Vec<Card>, to: &mut Vec<Card>, entry: &DecklistEntry)
+{
+    for _ in 0..entry.count {
+        let Some(pos) = from
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(&entry.name))
+        else {
+            return;
+        };
+        to.push(from.remove(pos));
+    }
+}
+
+/// Parse a decklist using the built-in cards.
+pub fn parse_decklist(text: &str) -> Result<Deck, DecklistError>
+{
+    parse_decklist_with(text, crate::card::card_by_name)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn plain_text_list_with_sideboard()
+    {
+        let text = "// Stompy\n20 Forest\n4x Grizzly Bears\n\n2 Craw Wurm\n";
+        let list = parse_decklist_entries(text).unwrap();
+
+        assert_eq!(
+            list.main,
+            vec![
+                DecklistEntry {
+                    count: 20,
+                    name: String::from("Forest"),
+                    line: 2
+                },
+                DecklistEntry {
+                    count: 4,
+                    name: String::from("Grizzly Bears"),
+                    line: 3
+                },
+            ]
+        );
+        assert_eq!(
+            list.sideboard,
+            vec![DecklistEntry {
+                count: 2,
+                name: String::from("Craw Wurm"),
+                line: 5
+            }]
+        );
+    }
+
+    #[test]
+    fn arena_export_format()
+    {
+        let text = "Deck\n24 Forest (M21) 274\n\n4 Centaur Courser (M19) 171\nSB: 1 Grizzly Bears\n\nSideboard\n3 Craw Wurm (M20) 169\n";
+        let list = parse_decklist_entries(text).unwrap();
+
+        assert_eq!(list.main.len(), 2);
+        assert_eq!(list.main[1].name, "Centaur Courser");
+        assert_eq!(list.sideboard.iter().map(|e| e.count).sum::<u32>(), 4);
+    }
+
+    #[test]
+    fn commander_section_fills_the_command_zone()
+    {
+        let deck =
+            parse_decklist("Commander\n1 Llanowar Elves\n\nDeck\n98 Forest\n1 Grizzly Bears\n")
+                .unwrap();
+        assert_eq!(deck.commanders.len(), 1);
+        assert_eq!(deck.cards.len(), 99);
+        assert!(validate(&deck, &GameRules::commander()).is_empty());
+    }
+
+    #[test]
+    fn decks_are_validated_against_the_format()
+    {
+        use crate::card::{card_by_name, forest, grizzly_bears};
+
+        // 31 Grizzly Bears are fine in Limited but not in Constructed
+        assert!(validate(&Deck::example(), &GameRules::limited()).is_empty());
+        assert_eq!(
+            validate(&Deck::example(), &GameRules::constructed()),
+            vec![LegalityError::TooManyCopies {
+                name: String::from("Grizzly Bears"),
+                count: 31,
+                max: 4
+            }]
+        );
+        assert_eq!(
+            validate(&Deck::new(vec![forest(); 40]), &GameRules::constructed()),
+            vec![LegalityError::TooFewCards { size: 40, min: 60 }]
+        );
+
+        let banned = GameRules {
+            banned: parse_banned_list("# Banned\nGrizzly Bears\n"),
+            ..GameRules::limited()
+        };
+        assert_eq!(
+            validate(&Deck::example(), &banned),
+            vec![LegalityError::Banned {
+                name: String::from("Grizzly Bears")
+            }]
+        );
+
+        let commander = GameRules::commander();
+        let mut cards = vec![forest(); 98];
+        cards.push(card_by_name("Lightning Bolt").unwrap());
+        cards.push(grizzly_bears());
+        cards.push(grizzly_bears());
+        let deck = Deck::new(cards);
+        assert_eq!(
+            validate(&deck, &commander),
+            vec![
+                LegalityError::TooManyCards {
+                    size: 101,
+                    max: 100
+                },
+                LegalityError::TooManyCopies {
+                    name: String::from("Grizzly Bears"),
+                    count: 2,
+                    max: 1
+                },
+                LegalityError::CommanderCount { found: 0 },
+                LegalityError::OutsideColorIdentity {
+                    name: String::from("Lightning Bolt")
+                },
+                LegalityError::OutsideColorIdentity {
+                    name: String::from("Grizzly Bears")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sideboard_plans_swap_cards_per_opponent()
+    {
+        let text = "20 Forest\n4 Grizzly Bears\n\n3 Craw Wurm\n1 Giant Growth\n\nvs aggro\n-2 Grizzly Bears\n+2 Craw Wurm\n\nvs *\n-1 Grizzly Bears\n+1 Giant Growth\n";
+        let deck = parse_decklist(text).unwrap();
+        assert_eq!(
+            (deck.cards.len(), deck.sideboard.len(), deck.plans.len()),
+            (24, 4, 2)
+        );
+
+        let count = |deck: &Deck, name: &str| deck.cards.iter().filter(|c| c.name == name).count();
+        let against_aggro = sideboard_against(&deck, "Aggro");
+        assert_eq!(
+            (
+                count(&against_aggro, "Grizzly Bears"),
+                count(&against_aggro, "Craw Wurm")
+            ),
+            (2, 2)
+        );
+        assert_eq!(
+            against_aggro.cards.len() + against_aggro.sideboard.len(),
+            28
+        );
+
+        let against_control = sideboard_against(&deck, "control");
+        assert_eq!(
+            (
+                count(&against_control, "Grizzly Bears"),
+                count(&against_control, "Giant Growth")
+            ),
+            (3, 1)
+        );
+
+        assert!(matches!(
+            parse_decklist_entries("20 Forest\nvs aggro\n2 Craw Wurm\n"),
+            Err(DecklistError::InvalidLine { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn builds_deck_from_built_in_cards()
+    {
+        let deck = parse_decklist("24 Forest\n36 Grizzly Bears\n").unwrap();
+        assert_eq!(deck.cards.len(), 60);
+        assert_eq!(deck.count(CardType::Land), 24);
+    }
+
+    #[test]
+    fn reports_bad_lines_and_unknown_cards()
+    {
+        assert_eq!(
+            parse_decklist("20 Forest\nLightning Bolt\n").unwrap_err(),
+            DecklistError::InvalidLine {
+                line: 2,
+                text: String::from("Lightning Bolt")
+            }
+        );
+        assert_eq!(
+            parse_decklist("20 Forest\n4 Black Lotus\n").unwrap_err(),
+            DecklistError::UnknownCard {
+                line: 2,
+                name: String::from("Black Lotus")
+            }
+        );
+        // The line the entry was on, not the first one mentioning its name
+        assert_eq!(
+            parse_decklist("4 Grizzly Bears\n20 Forest\n2 Bears\n").unwrap_err(),
+            DecklistError::UnknownCard {
+                line: 3,
+                name: String::from("Bears")
+            }
+        );
+    }
+}
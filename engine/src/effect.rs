@@ -0,0 +1,1028 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::{AlternativeCost, Card, CardId, CardType, CreatureStats, EffectFragment};
+use crate::creature;
+use crate::game::{GameState, PlayerCounter, Zone};
+use crate::gamelog::{DamageTarget, LogEvent};
+use crate::keyword::Keyword;
+use crate::mana::{ManaColor, ManaCost};
+use crate::token::ArtifactToken;
+
+/// What a resolving ability does, on behalf of the player controlling it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Effect
+{
+    GainLife(u32),
+    DrawCards(u32),
+    // The controller discards cards of their choice
+    Discard(u32),
+    // Draw, then discard: "draw two cards, then discard a card"
+    Loot
+    {
+        draw: u32,
+        discard: u32,
+    },
+    // Damage to each opponent of the controller
+    DamageOpponents(u32),
+    // Each opponent of the controller loses this much life (it isn't damage, so nothing prevents it)
+    LoseLife(u32),
+    // Damage to any target: a creature it kills, or else the opponent's face
+    DealDamage(u32),
+    // Destroy the opponent's best creature
+    DestroyTarget,
+    // The controller sacrifices this many creatures, of their choice
+    Sacrifice(u32),
+    // Each opponent of the controller sacrifices this many creatures, of their choice
+    OpponentsSacrifice(u32),
+    // Each opponent loses this much life and the controller gains the life lost, e.g. Blood
+    // Artist's death trigger { "Drain": 1 }
+    Drain(u32),
+    // The opponent's best creature has base power and toughness this until end of turn, e.g.
+    // Diminish's { "SetBaseStats": { "power": 1, "toughness": 1 } }
+    SetBaseStats
+    {
+        power: u8,
+        toughness: u8,
+    },
+    // +power/+toughness until end of turn on one of the controller's creatures, attackers first
+    // and any `keywords`, e.g. Reckless Charge's { "power": 3, "toughness": 0, "keywords": ["Haste"] }
+    PumpCreature
+    {
+        power: i32,
+        toughness: i32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        keywords: Vec<Keyword>,
+    },
+    // +power/+toughness until end of turn on the creature whose triggered ability this is, e.g.
+    // prowess
+    PumpSelf
+    {
+        power: i32,
+        toughness: i32,
+    },
+    // Counter the topmost spell on the stack that an opponent controls
+    CounterSpell,
+    // Copy the topmost instant or sorcery spell on the stack, whoever controls it, e.g. Twincast
+    CopySpell,
+    // Create a token that's a copy of the controller's best creature, e.g. Cackling Counterpart
+    CreateTokenCopy,
+    // A regeneration shield until end of turn on the controller's creature about to be destroyed,
+    // by an opposing spell on the stack or in the combat being fought
+    Regenerate,
+    // Add mana to the controller's pool, e.g. "{B}{B}{B}"; generic amounts add colorless
+    AddMana(ManaCost),
+    // Put a basic land from the controller's library onto the battlefield
+    FetchBasicLand
+    {
+        tapped: bool,
+    },
+    // Put `count` power/toughness creature tokens onto the controller's battlefield
+    CreateTokens
+    {
+        count: u32,
+        power: u8,
+        toughness: u8,
+        #[serde(default)]
+        keywords: Vec<Keyword>,
+    },
+    // Put `count` Treasure, Clue, Food or Blood tokens onto the controller's battlefield
+    CreateArtifactTokens
+    {
+        kind: ArtifactToken,
+        count: u32,
+    },
+    // The controller gets `count` energy, experience or poison counters
+    GetCounters
+    {
+        kind: PlayerCounter,
+        count: u32,
+    },
+    // Each opponent gets `count` poison counters
+    PoisonOpponents(u32),
+    // The controller pays `count` counters of `kind`, if they have them, for `effect`
+    SpendCounters
+    {
+        kind: PlayerCounter,
+        count: u32,
+        effect: Box<Effect>,
+    },
+    // Exile the top `count` cards of the controller's library; they may play them this turn, or
+    // until the end of their next turn, e.g. Reckless Impulse's
+    // { "ImpulseDraw": { "count": 2, "until_next_turn": true } }
+    ImpulseDraw
+    {
+        count: u32,
+        #[serde(default)]
+        until_next_turn: bool,
+    },
+    // Look at the top cards of the controller's library, putting any of them on the bottom
+    Scry(u32),
+    // ... or into their graveyard
+    Surveil(u32),
+    // Search the controller's library for a card `filter` matches and put it into their hand, or
+    // on top once the library is shuffled
+    Tutor
+    {
+        #[serde(default)]
+        filter: CardFilter,
+        #[serde(default)]
+        to_top: bool,
+    },
+    // "Choose one": the controller's strategy picks the mode as the spell resolves
+    Modal(ModalEffect),
+    // The effect with its amount replaced by the X paid for the spell, e.g. { "WithX": { "DealDamage": 0 } }
+    WithX(Box<Effect>),
+    // ... by the number of spells cast this turn, this one included, e.g. { "WithSpellCount": { "CreateTokens": ... } }
+    WithSpellCount(Box<Effect>),
+}
+
+impl Effect
+{
+    /// The effect with its amount (damage, cards, life, tokens or +X/+X) set to `amount`.
+    /// Effects without an amount stay as they are.
+    pub fn with_amount(&self, amount: u32) -> Effect
+    {
+        match self {
+            Effect::GainLife(_) => Effect::GainLife(amount),
+            Effect::DrawCards(_) => Effect::DrawCards(amount),
+            Effect::Discard(_) => Effect::Discard(amount),
+            Effect::DamageOpponents(_) => Effect::DamageOpponents(amount),
+            Effect::LoseLife(_) => Effect::LoseLife(amount),
+            Effect::Drain(_) => Effect::Drain(amount),
+            Effect::DealDamage(_) => Effect::DealDamage(amount),
+            Effect::Scry(_) => Effect::Scry(amount),
+            Effect::Surveil(_) => Effect::Surveil(amount),
+            Effect::PumpCreature { keywords, .. } => Effect::PumpCreature {
+                power: amount as i32,
+                toughness: amount as i32,
+                keywords: keywords.clone(),
+            },
+            Effect::CreateTokens {
+                power,
+                toughness,
+                keywords,
+                ..
+            } => Effect::CreateTokens {
+                count: amount,
+                power: *power,
+                toughness: *toughness,
+                keywords: keywords.clone(),
+            },
+            Effect::CreateArtifactTokens { kind, .. } => Effect::CreateArtifactTokens {
+                kind: *kind,
+                count: amount,
+            },
+            Effect::GetCounters { kind, .. } => Effect::GetCounters {
+                kind: *kind,
+                count: amount,
+            },
+            Effect::ImpulseDraw {
+                until_next_turn, ..
+            } => Effect::ImpulseDraw {
+                count: amount,
+                until_next_turn: *until_next_turn,
+            },
+            Effect::PoisonOpponents(_) => Effect::PoisonOpponents(amount),
+            other => other.clone(),
+        }
+    }
+
+    /// The effect as it resolves for a spell cast with `x`: every `WithX` filled in, in each mode
+    /// of a modal one too.
+    pub fn with_x(&self, x: u32) -> Effect
+    {
+        match self {
+            Effect::WithX(inner) => inner.with_amount(x),
+            Effect::Modal(modal) => Effect::Modal(ModalEffect {
+                modes: modal
+                    .modes
+                    .iter()
+                    .map(|mode| mode.iter().map(|e| e.with_x(x)).collect())
+                    .collect(),
+            }),
+            other => other.clone(),
+        }
+    }
+}
+
+/// The modes of a "choose one" effect, each a list of effects applied in order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModalEffect
+{
+    pub modes: Vec<Vec<Effect>>,
+}
+
+impl ModalEffect
+{
+    /// Whether every effect of `mode` has something to act on, counters needing an opposing
+    /// spell on the stack to counter.
+    pub fn mode_has_target(&self, game: &GameState, controller: usize, mode: usize) -> bool
+    {
+        self.modes.get(mode).is_some_and(|effects| {
+            effects.iter().all(|effect| match effect {
+                Effect::CounterSpell => game.stack.last().is_some_and(|top| *top != controller),
+                _ => has_target(game, controller, effect),
+            })
+        })
+    }
+}
+
+/// Which cards a tutor can find: every field that's set has to match, so the default finds any.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CardFilter
+{
+    pub card_type: Option<CardType>,
+    pub max_mana_value: Option<u32>,
+}
+
+impl CardFilter
+{
+    pub fn matches(&self, card: &Card) -> bool
+    {
+        self.card_type.is_none_or(|t| card.is_type(t))
+            && self
+                .max_mana_value
+                .is_none_or(|max| card.cost.mana_value() <= max)
+    }
+}
+
+/// Creatures sacrificed as an additional cost to cast `card`.
+pub fn sacrifice_cost(card: &Card) -> u32
+{
+    card.fragment::<EffectFragment>()
+        .map_or(0, |ef| ef.sacrifice)
+}
+
+/// Effects of an instant or sorcery, applied in order when it resolves: its kicked effects
+/// while it's on the stack with its kicker paid.
+pub fn spell_effects(card: &Card) -> &[Effect]
+{
+    card.fragment::<EffectFragment>()
+        .map(|ef| {
+            if ef.kicked {
+                ef.kicked_effects.as_slice()
+            } else {
+                ef.effects.as_slice()
+            }
+        })
+        .unwrap_or(&[])
+}
+
+pub fn kicker(card: &Card) -> Option<ManaCost>
+{
+    card.fragment::<EffectFragment>().and_then(|ef| ef.kicker)
+}
+
+pub fn alternative_cost(card: &Card) -> Option<&AlternativeCost>
+{
+    card.fragment::<EffectFragment>()
+        .and_then(|ef| ef.alternative_cost.as_ref())
+}
+
+/// Record whether the kicker of the spell `card` was paid as it's cast.
+pub fn set_kicked(card: &mut Card, kicked: bool)
+{
+    if let Some(ef) = card.fragment_mut::<EffectFragment>() {
+        ef.kicked = kicked;
+    }
+}
+
+// The player the controller's targeted effects go after: the next one in turn order still playing
+pub(crate) fn opponent(game: &GameState, controller: usize) -> Option<usize>
+{
+    let count = game.players.len();
+    (1..count)
+        .map(|offset| (controller + offset) % count)
+        .find(|p| !game.players[*p].has_lost)
+}
+
+fn battlefield(game: &GameState, player: usize) -> &[Card]
+{
+    game.players[player]
+        .zones
+        .get(&Zone::Battlefield)
+        .map_or(&[], Vec::as_slice)
+}
+
+// The spell whose effects are resolving, if any
+fn resolving(game: &GameState) -> Option<Card>
+{
+    game.resolving.and_then(|id| game.card(id)).cloned()
+}
+
+// A player's permanents that effects of `source` may target: those without protection from it
+fn targets<'a>(
+    game: &'a GameState,
+    player: usize,
+    source: Option<&'a Card>,
+) -> impl Iterator<Item = (usize, &'a Card)> + Clone
+{
+    battlefield(game, player)
+        .iter()
+        .enumerate()
+        .filter(move |(_, card)| {
+            source.is_none_or(|source| !crate::keyword::protected_from(card, source))
+        })
+}
+
+// Biggest threat first: power, then mana value
+fn best_creature<'a>(cards: impl Iterator<Item = (usize, &'a Card)>) -> Option<usize>
+{
+    cards
+        .filter(|(_, card)| creature::is_creature(card))
+        .max_by_key(|(i, card)| {
+            (
+                creature::effective_stats(card).map_or(0, |s| s.power),
+                card.cost.mana_value(),
+                std::cmp::Reverse(*i),
+            )
+        })
+        .map(|(i, _)| i)
+}
+
+// Opposing creature that `amount` more damage kills
+fn damage_target(
+    game: &GameState,
+    opponent: usize,
+    amount: u32,
+    source: Option<&Card>,
+) -> Option<CardId>
+{
+    let creatures = battlefield(game, opponent);
+    best_creature(targets(game, opponent, source).filter(|(_, card)| {
+        let toughness = creature::effective_stats(card).map_or(0, |s| s.toughness as u32);
+        toughness <= creature::marked_damage(card) + amount
+    }))
+    .map(|i| creatures[i].id)
+}
+
+fn pump_target(
+    game: &GameState,
+    controller: usize,
+    keywords: &[Keyword],
+    source: Option<&Card>,
+) -> Option<CardId>
+{
+    let attacking = controller == game.current_player_index;
+    let battlefield = battlefield(game, controller);
+    let creatures = targets(game, controller, source);
+    // Haste is wasted on anything that could attack already
+    let hasty = keywords.contains(&Keyword::Haste);
+    best_creature(
+        creatures
+            .clone()
+            .filter(|(i, _)| attacking && game.combat.attackers.contains(i)),
+    )
+    .or_else(|| {
+        best_creature(
+            creatures
+                .clone()
+                .filter(|(_, card)| hasty && creature::has_summoning_sickness(card)),
+        )
+    })
+    .or_else(|| best_creature(creatures))
+    .map(|i| battlefield[i].id)
+}
+
+// The controller's best creature that the opposing spell on top of the stack or the declared
+// blocks would destroy, and that isn't shielded or indestructible already
+fn regenerate_target(game: &GameState, controller: usize, source: Option<&Card>) -> Option<CardId>
+{
+    let creatures = battlefield(game, controller);
+    let mut doomed: Vec<usize> = Vec::new();
+    if let Some(&caster) = game.stack.last().filter(|caster| **caster != controller)
+        && let Some(spell) = game.players[caster]
+            .zones
+            .get(&Zone::Stack)
+            .and_then(|stack| stack.last())
+    {
+        for effect in spell_effects(spell) {
+            match effect {
+                Effect::DestroyTarget => {
+                    doomed.extend(best_creature(targets(game, controller, Some(spell))))
+                }
+                Effect::DealDamage(amount) => doomed.extend(
+                    damage_target(game, controller, *amount, Some(spell))
+                        .and_then(|id| creatures.iter().position(|c| c.id == id)),
+                ),
+                _ => {}
+            }
+        }
+    }
+    if !game.combat.attackers.is_empty() {
+        let attacking = battlefield(game, game.current_player_index);
+        let report = crate::combat::assign_damage(
+            attacking,
+            battlefield(game, game.combat.defending_player),
+            &game.combat,
+        );
+        let damage = if controller == game.current_player_index {
+            report.to_attackers
+        } else if controller == game.combat.defending_player {
+            report.to_blockers
+        } else {
+            Vec::new()
+        };
+        doomed.extend(
+            damage
+                .iter()
+                .filter(|d| {
+                    d.deathtouch
+                        || d.amount + creature::marked_damage(&creatures[d.index])
+                            >= creature::effective_stats(&creatures[d.index])
+                                .map_or(0, |s| s.toughness as u32)
+                })
+                .map(|d| d.index),
+        );
+    }
+    best_creature(targets(game, controller, source).filter(|(i, card)| {
+        doomed.contains(i)
+            && creature::regeneration_shields(card) == 0
+            && !crate::keyword::is_indestructible(card)
+    }))
+    .map(|i| creatures[i].id)
+}
+
+// The basic land of the color the controller's hand needs most and their mana sources don't
+// make yet
+fn basic_land_to_fetch(game: &GameState, controller: usize) -> Option<usize>
+{
+    let zones = &game.players[controller].zones;
+    let library = zones.get(&Zone::Library)?;
+    let produced: Vec<ManaColor> = zones
+        .get(&Zone::Battlefield)?
+        .iter()
+        .flat_map(crate::mana::produced_colors)
+        .collect();
+    let needed = |color: ManaColor| -> i64 {
+        let pips: u32 = zones
+            .get(&Zone::Hand)
+            .map_or(0, |hand| hand.iter().map(|c| c.cost.pips(color)).sum());
+        pips as i64 - produced.iter().filter(|c| **c == color).count() as i64
+    };
+
+    library
+        .iter()
+        .enumerate()
+        .filter_map(|(i, card)| {
+            crate::mana::basic_land_color(&card.name)
+                .filter(|_| card.is_type(crate::card::CardType::Land))
+                .map(|color| (i, color))
+        })
+        .max_by_key(|(i, color)| (needed(*color), std::cmp::Reverse(*i)))
+        .map(|(i, _)| i)
+}
+
+// Library indices of the cards a tutor with `filter` can find
+fn tutor_candidates(game: &GameState, controller: usize, filter: &CardFilter) -> Vec<usize>
+{
+    let library = game.players[controller]
+        .zones
+        .get(&Zone::Library)
+        .map_or(&[][..], Vec::as_slice);
+    (0..library.len())
+        .filter(|&i| filter.matches(&library[i]))
+        .collect()
+}
+
+// Scry or surveil `count`: the controller's strategy keeps some of the top cards there, in the
+// order it likes, and the rest go to the bottom or, surveilling, to the graveyard
+fn look_at_top(game: &mut GameState, controller: usize, count: u32, surveil: bool)
+{
+    let library = game.players[controller]
+        .zones
+        .entry(Zone::Library)
+        .or_default();
+    let count = (count as usize).min(library.len());
+    if count == 0 {
+        return;
+    }
+    let at = library.len() - count;
+    let mut top = library.split_off(at);
+    top.reverse();
+
+    let mut kept = game.decide(controller, |strategy, game, rng| {
+        strategy.keep_on_top(game, controller, &top, rng)
+    });
+    let mut chosen = vec![false; count];
+    kept.retain(|&i| i < count && !std::mem::replace(&mut chosen[i], true));
+    game.log_event(controller, || match surveil {
+        true => LogEvent::Surveil {
+            looked: count as u32,
+            kept: kept.len() as u32,
+        },
+        false => LogEvent::Scry {
+            looked: count as u32,
+            kept: kept.len() as u32,
+        },
+    });
+
+    let library = game.players[controller]
+        .zones
+        .get_mut(&Zone::Library)
+        .unwrap();
+    let rest: Vec<Card> = (0..count)
+        .filter(|&i| !chosen[i])
+        .map(|i| top[i].clone())
+        .collect();
+    if surveil {
+        // Back on top, then into the graveyard one at a time so each move is seen like any other
+        let moved = rest.len();
+        library.extend(rest);
+        for _ in 0..moved {
+            let index = game.players[controller].zones[&Zone::Library].len() - 1;
+            game.move_card(controller, Zone::Library, index, Zone::Graveyard);
+        }
+    } else {
+        library.splice(0..0, rest);
+    }
+    let library = game.players[controller]
+        .zones
+        .get_mut(&Zone::Library)
+        .unwrap();
+    library.extend(kept.iter().rev().map(|&i| top[i].clone()));
+}
+
+/// Whether `effect` has anything to act on right now, so casting it isn't a waste.
+pub fn has_target(game: &GameState, controller: usize, effect: &Effect) -> bool
+{
+    has_target_from(game, controller, effect, resolving(game).as_ref())
+}
+
+/// Whether `effect` of `source` has anything to act on: protection from the source rules out
+/// a target.
+pub fn has_target_from(
+    game: &GameState,
+    controller: usize,
+    effect: &Effect,
+    source: Option<&Card>,
+) -> bool
+{
+    match effect {
+        Effect::DestroyTarget | Effect::SetBaseStats { .. } => opponent(game, controller)
+            .is_some_and(|o| best_creature(targets(game, o, source)).is_some()),
+        Effect::PumpCreature { keywords, .. } => {
+            pump_target(game, controller, keywords, source).is_some()
+        }
+        Effect::CopySpell => game.spell_to_copy().is_some(),
+        Effect::CreateTokenCopy => best_creature(targets(game, controller, source)).is_some(),
+        Effect::Regenerate => regenerate_target(game, controller, source).is_some(),
+        Effect::OpponentsSacrifice(_) => opponent(game, controller)
+            .is_some_and(|o| battlefield(game, o).iter().any(creature::is_creature)),
+        Effect::FetchBasicLand { .. } => basic_land_to_fetch(game, controller).is_some(),
+        Effect::Tutor { filter, .. } => !tutor_candidates(game, controller, filter).is_empty(),
+        Effect::Modal(modal) => {
+            (0..modal.modes.len()).any(|mode| modal.mode_has_target(game, controller, mode))
+        }
+        Effect::WithX(inner)
+        | Effect::WithSpellCount(inner)
+        | Effect::SpendCounters { effect: inner, .. } => {
+            has_target_from(game, controller, inner, source)
+        }
+        // Only worth it when the mana is spent right away; see GameState::ritual_enables
+        Effect::AddMana(_) => false,
+        _ => true,
+    }
+}
+
+pub fn apply(game: &mut GameState, controller: usize, effect: &Effect)
+{
+    match effect {
+        Effect::GainLife(amount) => {
+            crate::life::gain(game, controller, *amount);
+        }
+        Effect::DrawCards(count) => {
+            for _ in 0..*count {
+                game.draw_card(controller);
+            }
+        }
+        Effect::Discard(count) => game.discard(controller, *count as usize),
+        Effect::Loot { draw, discard } => {
+            apply(game, controller, &Effect::DrawCards(*draw));
+            game.discard(controller, *discard as usize);
+        }
+        Effect::DamageOpponents(amount) => {
+            for target in (0..game.players.len()).filter(|i| *i != controller) {
+                let amount = game.damage_dealt_to(target, *amount);
+                game.log_event(controller, || LogEvent::Damage {
+                    source: None,
+                    target: DamageTarget::Player(target),
+                    amount,
+                });
+                crate::life::lose(game, target, amount);
+            }
+        }
+        Effect::LoseLife(amount) => {
+            for target in (0..game.players.len()).filter(|i| *i != controller) {
+                crate::life::lose(game, target, *amount);
+            }
+        }
+        Effect::Drain(amount) => {
+            let mut drained = 0;
+            let opponents: Vec<usize> = (0..game.players.len())
+                .filter(|i| *i != controller && !game.players[*i].has_lost)
+                .collect();
+            for target in opponents {
+                crate::life::lose(game, target, *amount);
+                drained += *amount;
+            }
+            crate::life::gain(game, controller, drained);
+        }
+        Effect::Sacrifice(count) => game.sacrifice(controller, *count as usize),
+        Effect::OpponentsSacrifice(count) => {
+            let opponents: Vec<usize> = (0..game.players.len())
+                .filter(|i| *i != controller && !game.players[*i].has_lost)
+                .collect();
+            for target in opponents {
+                game.sacrifice(target, *count as usize);
+            }
+        }
+        Effect::DealDamage(amount) => {
+            let Some(opponent) = opponent(game, controller) else {
+                return;
+            };
+            // Go face when that wins the game, otherwise take out a creature if possible
+            let to_face = game.damage_dealt_to(opponent, *amount);
+            let source = resolving(game);
+            let target = (game.players[opponent].life > to_face as i32)
+                .then(|| damage_target(game, opponent, *amount, source.as_ref()))
+                .flatten();
+            let amount = if target.is_some() { *amount } else { to_face };
+            game.log_event(controller, || LogEvent::Damage {
+                source: None,
+                target: match target {
+                    Some(id) => DamageTarget::Creature(game.card(id).unwrap().into()),
+                    None => DamageTarget::Player(opponent),
+                },
+                amount,
+            });
+            match target {
+                Some(id) => creature::deal_damage(game.card_mut(id).unwrap(), amount, false),
+                None => crate::life::lose(game, opponent, amount),
+            }
+        }
+        Effect::DestroyTarget => {
+            let Some(opponent) = opponent(game, controller) else {
+                return;
+            };
+            let source = resolving(game);
+            if let Some(index) = best_creature(targets(game, opponent, source.as_ref())) {
+                game.destroy_permanents(opponent, &[index]);
+            }
+        }
+        Effect::SetBaseStats { power, toughness } => {
+            let Some(opponent) = opponent(game, controller) else {
+                return;
+            };
+            let source = resolving(game);
+            let Some(id) = best_creature(targets(game, opponent, source.as_ref()))
+                .map(|i| battlefield(game, opponent)[i].id)
+            else {
+                return;
+            };
+            let timestamp = game.timestamp();
+            if let Some(card) = game.card_mut(id) {
+                creature::set_base_stats(
+                    card,
+                    CreatureStats {
+                        power: *power,
+                        toughness: *toughness,
+                    },
+                    timestamp,
+                );
+            }
+        }
+        Effect::PumpCreature {
+            power,
+            toughness,
+            keywords,
+        } => {
+            let source = resolving(game);
+            if let Some(card) = pump_target(game, controller, keywords, source.as_ref())
+                .and_then(|id| game.card_mut(id))
+            {
+                creature::pump(card, *power, *toughness, keywords);
+            }
+            // Haste changes what can attack and tap for mana
+            if !keywords.is_empty() {
+                game.refresh_components();
+            }
+        }
+        Effect::PumpSelf { power, toughness } => {
+            if let Some(card) = game.resolving.and_then(|id| game.card_mut(id)) {
+                creature::pump(card, *power, *toughness, &[]);
+            }
+        }
+        Effect::CounterSpell => {
+            game.counter_spell(controller);
+        }
+        Effect::CopySpell => {
+            game.copy_spell(controller);
+        }
+        Effect::CreateTokenCopy => {
+            let source = resolving(game);
+            if let Some(index) = best_creature(targets(game, controller, source.as_ref())) {
+                let copy = crate::token::copy_of(&battlefield(game, controller)[index]);
+                game.create_token(controller, &copy);
+            }
+        }
+        Effect::Regenerate => {
+            let source = resolving(game);
+            if let Some(card) = regenerate_target(game, controller, source.as_ref())
+                .and_then(|id| game.card_mut(id))
+            {
+                creature::add_regeneration_shield(card);
+            }
+        }
+        Effect::AddMana(mana) => {
+            let pool = &mut game.players[controller].mana_pool;
+            pool.add(ManaColor::Colorless, mana.generic);
+            for color in ManaColor::ALL {
+                pool.add(color, mana.pips(color));
+            }
+        }
+        Effect::FetchBasicLand { tapped } => {
+            let Some(index) = basic_land_to_fetch(game, controller) else {
+                return;
+            };
+            if let Some(index) = game.move_card(controller, Zone::Library, index, Zone::Battlefield)
+                && *tapped
+            {
+                game.set_tapped(controller, index, true);
+            }
+            game.shuffle_library(controller);
+        }
+        Effect::CreateTokens {
+            count,
+            power,
+            toughness,
+            keywords,
+        } => {
+            let token = crate::token::creature_token(*power, *toughness, keywords);
+            for _ in 0..*count {
+                game.create_token(controller, &token);
+            }
+        }
+        Effect::CreateArtifactTokens { kind, count } => {
+            let token = crate::token::artifact_token(*kind);
+            for _ in 0..*count {
+                game.create_token(controller, &token);
+            }
+        }
+        Effect::GetCounters { kind, count } => game.players[controller].add_counters(*kind, *count),
+        Effect::ImpulseDraw {
+            count,
+            until_next_turn,
+        } => {
+            let until = game.turns
+                + if *until_next_turn {
+                    game.players.len() as u32
+                } else {
+                    0
+                };
+            for _ in 0..*count {
+                let Some(index) = game.exile_top(controller) else {
+                    break;
+                };
+                let id = game.players[controller].zones[&Zone::Exile][index].id;
+                game.players[controller]
+                    .playable_from_exile
+                    .push((id, until));
+            }
+        }
+        Effect::PoisonOpponents(count) => {
+            for opponent in (0..game.players.len()).filter(|i| *i != controller) {
+                game.players[opponent].add_counters(PlayerCounter::Poison, *count);
+            }
+        }
+        Effect::SpendCounters {
+            kind,
+            count,
+            effect,
+        } => {
+            if game.players[controller].spend_counters(*kind, *count) {
+                apply(game, controller, effect);
+            }
+        }
+        Effect::Scry(count) => look_at_top(game, controller, *count, false),
+        Effect::Surveil(count) => look_at_top(game, controller, *count, true),
+        Effect::Tutor { filter, to_top } => {
+            let candidates = tutor_candidates(game, controller, filter);
+            let found = match candidates.is_empty() {
+                true => None,
+                false => game
+                    .decide(controller, |strategy, game, rng| {
+                        strategy.tutor(game, controller, &candidates, rng)
+                    })
+                    .filter(|i| candidates.contains(i)),
+            };
+            if let Some(index) = found {
+                game.log_event(controller, || LogEvent::Tutor {
+                    card: (&game.players[controller].zones[&Zone::Library][index]).into(),
+                });
+                if *to_top {
+                    let card = game.players[controller]
+                        .zones
+                        .get_mut(&Zone::Library)
+                        .unwrap()
+                        .remove(index);
+                    game.shuffle_library(controller);
+                    game.players[controller]
+                        .zones
+                        .get_mut(&Zone::Library)
+                        .unwrap()
+                        .push(card);
+                    return;
+                }
+                game.move_card(controller, Zone::Library, index, Zone::Hand);
+            }
+            game.shuffle_library(controller);
+        }
+        Effect::Modal(modal) => {
+            let mode = game.decide(controller, |strategy, game, rng| {
+                strategy.choose_mode(game, controller, modal, rng)
+            });
+            for effect in modal.modes.get(mode).into_iter().flatten() {
+                apply(game, controller, effect);
+            }
+        }
+        // Outside a spell, where nothing was paid for X
+        Effect::WithX(inner) => apply(game, controller, &inner.with_amount(0)),
+        Effect::WithSpellCount(inner) => {
+            apply(game, controller, &inner.with_amount(game.spells_this_turn))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{Deck, card_by_name, forest, grizzly_bears};
+
+    #[test]
+    fn targeted_effects_pick_the_best_target()
+    {
+        let deck = Deck::new(Vec::new());
+        let mut game = GameState::with_decks(&[&deck, &deck], 0);
+        game.players[0]
+            .zones
+            .insert(Zone::Battlefield, vec![grizzly_bears()]);
+        game.players[1].zones.insert(
+            Zone::Battlefield,
+            vec![grizzly_bears(), card_by_name("Craw Wurm").unwrap()],
+        );
+        for card in game
+            .players
+            .iter_mut()
+            .flat_map(|p| p.zones.get_mut(&Zone::Battlefield).unwrap())
+        {
+            card.id = CardId(game.next_card_id);
+            game.next_card_id += 1;
+        }
+
+        // 3 damage can't kill the Wurm, so it goes to the Bears
+        apply(&mut game, 0, &Effect::DealDamage(3));
+        game.process_events();
+        assert_eq!(battlefield(&game, 1).len(), 1);
+
+        apply(&mut game, 0, &Effect::DestroyTarget);
+        assert!(battlefield(&game, 1).is_empty());
+        assert!(!has_target(&game, 0, &Effect::DestroyTarget));
+
+        apply(&mut game, 0, &Effect::DealDamage(3));
+        assert_eq!(game.players[1].life, 17);
+
+        apply(
+            &mut game,
+            0,
+            &Effect::PumpCreature {
+                power: 3,
+                toughness: 3,
+                keywords: Vec::new(),
+            },
+        );
+        let bears = &mut game.players[0].zones.get_mut(&Zone::Battlefield).unwrap()[0];
+        assert_eq!(creature::effective_stats(bears).unwrap().power, 5);
+        creature::clear_until_end_of_turn(bears);
+        assert_eq!(creature::effective_stats(bears).unwrap().power, 2);
+    }
+
+    #[test]
+    fn scry_surveil_and_tutors_rearrange_the_library()
+    {
+        let deck = Deck::new(Vec::new());
+        let mut game = GameState::with_decks(&[&deck, &deck], 0);
+        let wurm = || card_by_name("Craw Wurm").unwrap();
+        // Bottom first: the Forest is on top
+        game.players[0]
+            .zones
+            .insert(Zone::Library, vec![grizzly_bears(), wurm(), forest()]);
+        game.players[0]
+            .zones
+            .insert(Zone::Battlefield, vec![forest(), forest()]);
+        game.players[0].zones.insert(Zone::Hand, vec![wurm()]);
+        for card in game.players[0].zones.values_mut().flatten() {
+            card.id = CardId(game.next_card_id);
+            game.next_card_id += 1;
+        }
+        let names = |game: &GameState, zone: Zone| {
+            game.players[0].zones[&zone]
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Still short of lands, and a second Wurm can't be cast any time soon
+        apply(&mut game, 0, &Effect::Scry(2));
+        assert_eq!(
+            names(&game, Zone::Library),
+            ["Craw Wurm", "Grizzly Bears", "Forest"]
+        );
+
+        apply(&mut game, 0, &Effect::Surveil(3));
+        assert_eq!(names(&game, Zone::Library), ["Grizzly Bears", "Forest"]);
+        assert_eq!(names(&game, Zone::Graveyard), ["Craw Wurm"]);
+
+        let creature = CardFilter {
+            card_type: Some(CardType::Creature),
+            ..CardFilter::default()
+        };
+        apply(
+            &mut game,
+            0,
+            &Effect::Tutor {
+                filter: creature.clone(),
+                to_top: false,
+            },
+        );
+        assert_eq!(names(&game, Zone::Library), ["Forest"]);
+        assert_eq!(names(&game, Zone::Hand), ["Craw Wurm", "Grizzly Bears"]);
+        assert!(!has_target(
+            &game,
+            0,
+            &Effect::Tutor {
+                filter: creature,
+                to_top: true
+            }
+        ));
+    }
+
+    #[test]
+    fn looting_draws_then_discards_what_the_hand_can_spare()
+    {
+        let deck = Deck::new(Vec::new());
+        let mut game = GameState::with_decks(&[&deck, &deck], 0);
+        game.players[0]
+            .zones
+            .insert(Zone::Library, vec![grizzly_bears(), forest(), forest()]);
+        game.players[0].zones.insert(
+            Zone::Hand,
+            vec![forest(), forest(), card_by_name("Craw Wurm").unwrap()],
+        );
+
+        apply(
+            &mut game,
+            0,
+            &card_by_name("Catalog")
+                .map(|c| spell_effects(&c)[0].clone())
+                .unwrap(),
+        );
+        let hand = &game.players[0].zones[&Zone::Hand];
+        assert_eq!(hand.len(), 4);
+        assert_eq!(
+            hand.iter().filter(|c| c.is_type(CardType::Land)).count(),
+            3,
+            "one of four lands goes"
+        );
+        assert_eq!(game.players[0].zones[&Zone::Graveyard][0].name, "Forest");
+        assert_eq!(game.players[0].stats.cards_drawn, 2);
+
+        apply(&mut game, 0, &Effect::Discard(9));
+        assert!(game.players[0].zones[&Zone::Hand].is_empty());
+    }
+
+    #[test]
+    fn modal_spells_resolve_with_the_first_mode_that_has_a_target()
+    {
+        let deck = Deck::new(Vec::new());
+        let mut game = GameState::with_decks(&[&deck, &deck], 0);
+        game.players[1]
+            .zones
+            .insert(Zone::Battlefield, vec![grizzly_bears()]);
+        game.players[1].zones.get_mut(&Zone::Battlefield).unwrap()[0].id = CardId(1);
+        let charm = card_by_name("Izzet Charm").unwrap();
+        let Effect::Modal(modal) = &spell_effects(&charm)[0] else {
+            panic!("Izzet Charm is modal")
+        };
+
+        // Nothing on the stack to counter, so the 2 damage goes to the Bears
+        assert!(!modal.mode_has_target(&game, 0, 0));
+        assert!(has_target(&game, 0, &spell_effects(&charm)[0]));
+        apply(&mut game, 0, &spell_effects(&charm)[0]);
+        game.process_events();
+        assert!(battlefield(&game, 1).is_empty());
+        assert_eq!(game.players[1].life, 20);
+    }
+}
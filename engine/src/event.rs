@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::card::CardId;
+use crate::game::Zone;
+
+/// Something that happened in the game that abilities may react to. Cards are named by id, so an
+/// event still means the same card after others have moved; look it up with `GameState::card`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent
+{
+    // A card moved between two of `player`'s zones
+    ZoneChanged
+    {
+        player: usize,
+        from: Zone,
+        to: Zone,
+        card: CardId,
+    },
+    // A permanent entered `player`'s battlefield
+    CardEntered
+    {
+        player: usize, card: CardId
+    },
+    // A creature was put into `player`'s graveyard from the battlefield
+    CreatureDied
+    {
+        player: usize, card: CardId
+    },
+    // `player` attacked with these creatures
+    AttackDeclared
+    {
+        player: usize,
+        attackers: Vec<CardId>,
+    },
+    // `player` cast this spell
+    SpellCast
+    {
+        player: usize, card: CardId
+    },
+    LifeGained
+    {
+        player: usize, amount: u32
+    },
+    LifeLost
+    {
+        player: usize, amount: u32
+    },
+}
+
+/// Events waiting to be processed, oldest first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EventBus
+{
+    pending: VecDeque<GameEvent>,
+}
+
+impl EventBus
+{
+    pub fn emit(&mut self, event: GameEvent)
+    {
+        self.pending.push_back(event);
+    }
+
+    pub fn pop(&mut self) -> Option<GameEvent>
+    {
+        self.pending.pop_front()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.pending.is_empty()
+    }
+}
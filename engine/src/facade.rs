@@ -0,0 +1,115 @@
+// Hands log lines to the `log` crate when the `log` feature is on and the application embedding
+// the engine has installed a logger, so they go through its filtering and output instead of
+// stdout. Without either, everything here is a no-op and `vlog!` prints as usual.
+
+use crate::ELoggingVerbosity;
+
+#[cfg(feature = "log")]
+fn facade_level(level: ELoggingVerbosity) -> log::Level
+{
+    match level {
+        ELoggingVerbosity::Error => log::Level::Error,
+        ELoggingVerbosity::Warning => log::Level::Warn,
+        ELoggingVerbosity::Normal => log::Level::Info,
+        ELoggingVerbosity::Verbose => log::Level::Debug,
+        ELoggingVerbosity::VeryVerbose => log::Level::Trace,
+    }
+}
+
+/// Whether a logger took over from stdout.
+pub fn active() -> bool
+{
+    #[cfg(feature = "log")]
+    {
+        log::max_level() != log::LevelFilter::Off
+    }
+    #[cfg(not(feature = "log"))]
+    {
+        false
+    }
+}
+
+/// Whether the logger wants a line at `level` from the module at `module_path`, which is its
+/// target.
+pub fn wants(level: ELoggingVerbosity, module_path: &str) -> bool
+{
+    #[cfg(feature = "log")]
+    {
+        log::log_enabled!(target: module_path, facade_level(level))
+    }
+    #[cfg(not(feature = "log"))]
+    {
+        let _ = (level, module_path);
+        false
+    }
+}
+
+pub fn write(level: ELoggingVerbosity, module_path: &str, line: &str)
+{
+    #[cfg(feature = "log")]
+    {
+        log::log!(target: module_path, facade_level(level), "{}", line);
+    }
+    #[cfg(not(feature = "log"))]
+    {
+        let _ = (level, module_path, line);
+    }
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests
+{
+    use super::*;
+    use std::sync::Mutex;
+
+    // Keeps what this module's tests log, leaving out every other test's lines
+    struct Capture(Mutex<Vec<(log::Level, String)>>);
+
+    impl log::Log for Capture
+    {
+        fn enabled(&self, metadata: &log::Metadata) -> bool
+        {
+            metadata.target().starts_with(module_path!()) && metadata.level() <= log::Level::Debug
+        }
+
+        fn log(&self, record: &log::Record)
+        {
+            if self.enabled(record.metadata()) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((record.level(), record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+    #[test]
+    fn lines_go_to_an_installed_logger_with_its_filtering()
+    {
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        assert!(active());
+        crate::vlog!(
+            ELoggingVerbosity::Warning,
+            "Can't read {}",
+            "decks/missing.txt"
+        );
+        crate::vlog!(ELoggingVerbosity::Verbose, "Turn {} begins", 2);
+        crate::vlog!(ELoggingVerbosity::VeryVerbose, "Filtered out by the logger");
+        let captured = CAPTURE.0.lock().unwrap();
+        assert_eq!(
+            *captured,
+            [
+                (
+                    log::Level::Warn,
+                    String::from("Can't read decks/missing.txt")
+                ),
+                (log::Level::Debug, String::from("Turn 2 begins"))
+            ]
+        );
+    }
+}
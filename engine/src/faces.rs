@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::card::{Card, CardFragmentKind, CardType, FaceLayout, FacesFragment};
+use crate::game::Zone;
+
+/// `card` as the front of a card laid out as `layout`, with its other `faces` in order.
+pub fn with_faces(mut card: Card, layout: FaceLayout, faces: Vec<Card>) -> Card
+{
+    let mut all = vec![card.clone()];
+    all.extend(faces);
+    card.fragments.insert(
+        CardFragmentKind::Faces,
+        Arc::new(FacesFragment {
+            layout,
+            faces: all,
+            showing: 0,
+            on_adventure: false,
+        }),
+    );
+    card
+}
+
+pub fn layout(card: &Card) -> Option<FaceLayout>
+{
+    card.fragment::<FacesFragment>().map(|f| f.layout)
+}
+
+/// Every face of `card`, the front first; just the card itself if it has only the one.
+pub fn faces(card: &Card) -> &[Card]
+{
+    match card.fragment::<FacesFragment>() {
+        Some(f) => &f.faces,
+        None => std::slice::from_ref(card),
+    }
+}
+
+/// Turn `card` to its face number `face`, keeping its id and whether it's a commander.
+pub fn turn_to(card: &mut Card, face: usize)
+{
+    let Some(fragment) = card.fragment::<FacesFragment>() else {
+        return;
+    };
+    if face == fragment.showing || face >= fragment.faces.len() {
+        return;
+    }
+    let mut fragment = fragment.clone();
+    fragment.showing = face;
+    let shown = &fragment.faces[face];
+    let commander = card.fragments.get(&CardFragmentKind::Commander).cloned();
+    card.name = shown.name.clone();
+    card.card_types = shown.card_types.clone();
+    card.cost = shown.cost;
+    card.fragments = shown.fragments.clone();
+    if let Some(commander) = commander {
+        card.fragments
+            .insert(CardFragmentKind::Commander, commander);
+    }
+    card.fragments
+        .insert(CardFragmentKind::Faces, Arc::new(fragment));
+}
+
+/// The first face of `card` that's a land, if any, so it can be the land drop.
+pub fn land_face(card: &Card) -> Option<usize>
+{
+    faces(card)
+        .iter()
+        .position(|face| face.is_type(CardType::Land))
+}
+
+pub fn can_be_land(card: &Card) -> bool
+{
+    land_face(card).is_some()
+}
+
+/// Whether `card` waits in exile after its adventure, castable as the permanent.
+pub fn on_adventure(card: &Card) -> bool
+{
+    card.fragment::<FacesFragment>()
+        .is_some_and(|f| f.on_adventure)
+}
+
+/// Whether resolving `card` sends it on an adventure rather than to the graveyard: it was cast
+/// as the adventure of an adventurer.
+pub fn goes_on_adventure(card: &Card) -> bool
+{
+    card.fragment::<FacesFragment>()
+        .is_some_and(|f| f.layout == FaceLayout::Adventure && f.showing != 0)
+}
+
+pub fn send_on_adventure(card: &mut Card)
+{
+    if let Some(f) = card.fragment_mut::<FacesFragment>() {
+        f.on_adventure = true;
+    }
+}
+
+/// Update `card` as it moves to `zone`: only on the stack and the battlefield does a card show
+/// anything but its front, and leaving exile ends an adventure.
+pub fn moved_to(card: &mut Card, zone: Zone)
+{
+    if zone != Zone::Stack && zone != Zone::Battlefield {
+        turn_to(card, 0);
+    }
+    if on_adventure(card)
+        && let Some(f) = card.fragment_mut::<FacesFragment>()
+    {
+        f.on_adventure = false;
+    }
+}
@@ -1,25 +1,46 @@
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use std::collections::HashMap;
-use serde::{Serialize, Deserialize};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 
-use crate::card::{Card, Deck};
 use crate::ELoggingVerbosity;
+use crate::attachment;
+use crate::card::{Card, CardId, CardType, CounterKind, Deck};
+use crate::color::{self, Color, paint};
+use crate::combat::{self, Combat};
+use crate::components::Components;
+use crate::deck::Legality;
+use crate::event::{EventBus, GameEvent};
+use crate::gamelog::{DamageTarget, GameLog, LogEvent, LogRecord, Watcher};
+use crate::mana::{self, ManaColor, ManaCost, ManaPool};
+use crate::mulligan::{KeepAll, LandRange, MulliganStrategy, OpeningHand};
+use crate::replacement::{self, Outcome, PendingEvent};
+use crate::rules::GameRules;
+use crate::strategy::{Cast, CurveOut, Pilot, PlayerStrategy};
+use crate::wincondition::WinCondition;
 
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub enum GameStep 
+pub enum GameStep
 {
     StartTurn,
     Untap,
     Upkeep,
     Draw,
     Main,
-    Combat,
+    DeclareAttackers,
+    DeclareBlockers,
+    CombatDamage,
     EndTurn,
     GameOver,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Zone
 {
     Library,
@@ -27,59 +48,338 @@ pub enum Zone
     Battlefield,
     Graveyard,
     Exile,
+    // Spells being cast wait here until they resolve
+    Stack,
+    // Commanders start here and return here instead of going to the graveyard or exile
+    Command,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+impl Zone
+{
+    pub const ALL: [Zone; 7] = [
+        Zone::Library,
+        Zone::Hand,
+        Zone::Battlefield,
+        Zone::Graveyard,
+        Zone::Exile,
+        Zone::Stack,
+        Zone::Command,
+    ];
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StepCommand
 {
-    StepPhase,       // "s"
-    StepTurn,        // "t"
-    RunGame,         // "g"
-    RunDeck,         // "d"
-    RunAll,          // "r"
-    Quit,            // "q"
-    Invalid,         // anything else
+    StepPhase,         // "s"
+    StepAction,        // "a": play until the next action (a draw, land, spell, attack, damage...)
+    StepPriority,      // "p": play until a player is about to get priority
+    StepTurn,          // "t"
+    RunGame,           // "g"
+    RunToTurn(u32),    // "g N": play on to the start of turn N
+    RunDeck,           // "d"
+    RunAll,            // "r"
+    Quit,              // "q"
+    OpeningHands(u32), // "h" or "h N": deal sample opening hands without playing
+    Board,             // "b": show both players' side of the table
+    Undo,              // "u": go back to before the last step
+    RunToBreakpoint,   // "break when EXPR": play on until the breakpoint's condition holds
+    Invalid,           // anything else
+}
+
+/// Whether the first player (the deck being simulated) starts the game.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayDraw
+{
+    Play,
+    Draw,
+    // Every other game, by the game's index in the run
+    Alternate,
+}
+
+impl PlayDraw
+{
+    pub fn on_play(self, game_index: u64) -> bool
+    {
+        match self {
+            PlayDraw::Play => true,
+            PlayDraw::Draw => false,
+            PlayDraw::Alternate => game_index.is_multiple_of(2),
+        }
+    }
+}
+
+impl FromStr for PlayDraw
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s {
+            "play" => Ok(PlayDraw::Play),
+            "draw" => Ok(PlayDraw::Draw),
+            "alternate" | "both" => Ok(PlayDraw::Alternate),
+            _ => Err(format!(
+                "unknown play/draw setting '{}' (expected play, draw or alternate)",
+                s
+            )),
+        }
+    }
 }
 
-pub struct ProgramState 
+#[derive(Serialize, Deserialize)]
+pub struct ProgramState
 {
     pub step_mode: StepCommand,
+    pub games_per_scenario: u32,
+    pub interactive: bool,
+    // Deck the second player plays; a copy of the deck being tuned when unset
+    pub opponent_deck: Option<Deck>,
+    // Play against a goldfish that only plays lands when there's no opponent deck
+    #[serde(default)]
+    pub goldfish: bool,
+    // How every player decides whether to keep their opening hand
+    #[serde(with = "crate::mulligan::by_name")]
+    pub mulligan: Arc<dyn MulliganStrategy>,
+    // Whether the deck being tuned goes first
+    pub play_draw: PlayDraw,
+    // Who pilots the deck being tuned; opponents always curve out
+    pub pilot: Pilot,
+    // Life, hand and deck sizes of the format being simulated
+    pub rules: GameRules,
+    // What to do with decks that break the format's deck-building rules
+    pub legality: Legality,
+    // Print how each card drawn early affected the games
+    pub card_report: bool,
+    // Print how often the deck made its land drops and cast spells on curve early on
+    pub curve_report: bool,
+    // When a game counts as mana screwed or flooded
+    pub mana_thresholds: crate::sim::ManaThresholds,
+    // CSV file every simulated game is appended to
+    pub game_export: Option<PathBuf>,
+    // JSON Lines file every action of every simulated game is appended to
+    #[serde(skip)]
+    pub game_log: Option<Arc<GameLog>>,
+    // Seed of a game to record for replaying, and the file to save the recording to
+    pub record_game: Option<(u64, PathBuf)>,
+    // Which games of each batch to play again and save a replay and full log of
+    pub capture: Option<crate::capture::CaptureRules>,
+    // CSV file every scenario's kill-turn histogram is appended to
+    pub histogram_export: Option<PathBuf>,
+    // Directory scenario results are cached in, so rerunning one with the same settings and seed
+    // doesn't simulate it again
+    pub result_cache: Option<PathBuf>,
+    // Cache keys of the scenarios run so far; running one again in the same run plays new games
+    #[serde(skip)]
+    pub cache_keys_seen: HashSet<String>,
+    // Keep playing batches until the 95% confidence interval on the mean is within this of it,
+    // up to `max_games`; a single batch when unset
+    pub epsilon: Option<f64>,
+    pub max_games: u32,
+    // Play best-of-three matches against the opponent deck instead of single games
+    pub best_of_three: bool,
+    // Show games done, games per second and time left on stderr while a batch runs
+    pub progress: bool,
+    // Print how many games per second every scenario was simulated at, never using cached results
+    pub bench_mode: bool,
+    // Names the decks' sideboard plans refer to each other by
+    pub deck_name: String,
+    pub opponent_name: String,
+    // Directory the `profile` commands save settings to and load them from
+    pub profile_dir: PathBuf,
+    // Decks already warned about for breaking the format's rules, by their card names
+    #[serde(skip)]
+    pub legality_warned: HashSet<Vec<String>>,
 }
 
 impl ProgramState
 {
     pub fn new() -> Self
     {
-        ProgramState
-        {
+        ProgramState {
             step_mode: StepCommand::StepPhase,
+            games_per_scenario: 3000,
+            interactive: true,
+            opponent_deck: None,
+            goldfish: false,
+            mulligan: Arc::new(LandRange::default()),
+            play_draw: PlayDraw::Play,
+            pilot: Pilot::default(),
+            rules: GameRules::default(),
+            legality: Legality::default(),
+            card_report: false,
+            curve_report: false,
+            mana_thresholds: crate::sim::ManaThresholds::default(),
+            game_export: None,
+            game_log: None,
+            record_game: None,
+            capture: None,
+            histogram_export: None,
+            result_cache: None,
+            cache_keys_seen: HashSet::new(),
+            epsilon: None,
+            max_games: 30000,
+            best_of_three: false,
+            progress: false,
+            bench_mode: false,
+            deck_name: String::new(),
+            opponent_name: String::new(),
+            profile_dir: PathBuf::from("profiles"),
+            legality_warned: HashSet::new(),
         }
     }
+
+    /// Whether `deck` hasn't been warned about breaking the format's rules yet; a deck's every
+    /// scenario would otherwise repeat the warning.
+    pub fn first_legality_warning(&mut self, deck: &Deck) -> bool
+    {
+        self.legality_warned.insert(
+            deck.cards
+                .iter()
+                .chain(deck.commanders.iter())
+                .map(|c| c.name.clone())
+                .collect(),
+        )
+    }
+}
+
+impl Default for ProgramState
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+// Per-game bookkeeping used for diagnostics; doesn't affect play
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PlayerStats
+{
+    pub turns_taken: u32,
+    pub mulligans: u32,
+    // One entry per turn taken: whether a land was played that turn
+    pub land_drops: Vec<bool>,
+    // One entry per turn taken: lands seen so far (hand + battlefield) at the start of the main phase
+    pub lands_seen: Vec<u32>,
+    // Turns that ended with a spell in hand that enough lands could have paid for, but not the right colors
+    #[serde(default)]
+    pub color_screwed_turns: u32,
+    // Took the first turn of the game
+    #[serde(default)]
+    pub went_first: bool,
+    // One entry per turn taken: whether the player cast a spell whose mana value equals the turn number
+    #[serde(default)]
+    pub on_curve: Vec<bool>,
+    // Names of the cards in the opening hand or drawn during the player's first
+    // `sim::EARLY_CARD_TURNS` turns, without repeats
+    #[serde(default)]
+    pub early_cards: Vec<String>,
+    // Cards drawn after the opening hand, by draw steps and effects alike
+    #[serde(default)]
+    pub cards_drawn: u32,
+    // Spells the player cast, on anyone's turn, and on each of their own turns
+    #[serde(default)]
+    pub spells_cast: u32,
+    #[serde(default)]
+    pub spells_per_turn: Vec<u32>,
+}
+
+/// Counters a player (rather than a permanent) can have.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PlayerCounter
+{
+    // Ten or more and the player loses; see wincondition.rs
+    Poison,
+    // Spent to pay for effects
+    Energy,
+    Experience,
+}
+
+impl PlayerCounter
+{
+    pub const ALL: [PlayerCounter; 3] = [
+        PlayerCounter::Poison,
+        PlayerCounter::Energy,
+        PlayerCounter::Experience,
+    ];
+}
+
+impl std::fmt::Display for PlayerCounter
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        let name = match self {
+            PlayerCounter::Poison => "poison",
+            PlayerCounter::Energy => "energy",
+            PlayerCounter::Experience => "experience",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Player
 {
     pub life: i32,
+    #[serde(serialize_with = "crate::card::serialize_sorted")]
     pub zones: HashMap<Zone, Vec<Card>>,
+    #[serde(default)]
+    pub stats: PlayerStats,
+    #[serde(default)]
+    pub mana_pool: ManaPool,
+    #[serde(default)]
+    pub has_lost: bool,
+    // Tried to draw from an empty library; loses at the next state-based action check
+    #[serde(default)]
+    pub drew_from_empty_library: bool,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "crate::card::serialize_sorted"
+    )]
+    pub counters: HashMap<PlayerCounter, u32>,
+    // Combat damage taken from each commander
+    #[serde(default)]
+    pub commander_damage: Vec<(CardId, u32)>,
+    // Why the player lost
+    #[serde(default)]
+    pub lost_to: Option<WinCondition>,
+    // Cards in exile the player may play, e.g. after impulse draw, and the last turn (counted
+    // like `GameState::turns`) they may
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub playable_from_exile: Vec<(CardId, u32)>,
+    // `GameState::next_timestamp` when the player's latest turn began; permanents stamped since
+    // haven't been under their control since then
+    #[serde(default)]
+    pub turn_started_at: u64,
+    // The battlefield's permanents as columns for the engine's hot queries, kept in step with
+    // the battlefield; see `GameState::components`
+    #[serde(skip)]
+    pub components: Components,
 }
 
 impl Player
 {
-    pub fn new(deck: &Deck) -> Self
+    pub fn new<R: Rng>(deck: &Deck, rng: &mut R) -> Self
     {
-        let mut rng = thread_rng();
-        let mut library = deck.cards.clone();
-        library.shuffle(&mut rng);
+        Self::with_rules(deck, rng, &GameRules::default(), &KeepAll)
+    }
 
-        let mut hand = Vec::new();
-        for _ in 0..7
-        {
-            if let Some(card) = library.pop()
-            {
-                hand.push(card);
-            }
-        }
+    // Starting life and hand size come from `rules`; the opening hand uses the London mulligan
+    pub fn with_rules<R: Rng>(
+        deck: &Deck,
+        rng: &mut R,
+        rules: &GameRules,
+        strategy: &dyn MulliganStrategy,
+    ) -> Self
+    {
+        let OpeningHand {
+            library,
+            hand,
+            mulligans,
+        } = crate::mulligan::london(deck.cards.clone(), rules.starting_hand_size, rng, strategy);
+        let mut early_cards: Vec<String> = hand.iter().map(|c| c.name.clone()).collect();
+        early_cards.sort();
+        early_cards.dedup();
 
         let mut zones = HashMap::new();
         zones.insert(Zone::Library, library);
@@ -87,404 +387,2458 @@ impl Player
         zones.insert(Zone::Battlefield, Vec::new());
         zones.insert(Zone::Graveyard, Vec::new());
         zones.insert(Zone::Exile, Vec::new());
+        zones.insert(Zone::Stack, Vec::new());
+        zones.insert(
+            Zone::Command,
+            deck.commanders
+                .iter()
+                .cloned()
+                .map(crate::commander::make_commander)
+                .collect(),
+        );
 
-        Player
-        {
-            life: 20,
+        Player {
+            life: rules.starting_life,
             zones,
+            stats: PlayerStats {
+                mulligans,
+                early_cards,
+                ..PlayerStats::default()
+            },
+            mana_pool: ManaPool::default(),
+            has_lost: false,
+            drew_from_empty_library: false,
+            counters: HashMap::new(),
+            commander_damage: Vec::new(),
+            playable_from_exile: Vec::new(),
+            turn_started_at: 0,
+            lost_to: None,
+            components: Components::default(),
+        }
+    }
+
+    pub fn counters(&self, kind: PlayerCounter) -> u32
+    {
+        self.counters.get(&kind).copied().unwrap_or(0)
+    }
+
+    pub fn add_counters(&mut self, kind: PlayerCounter, amount: u32)
+    {
+        if amount > 0 {
+            *self.counters.entry(kind).or_insert(0) += amount;
+        }
+    }
+
+    /// Remove `amount` counters of `kind` if the player has that many. Returns false, removing
+    /// none, if they don't.
+    pub fn spend_counters(&mut self, kind: PlayerCounter, amount: u32) -> bool
+    {
+        let have = self.counters(kind);
+        if have < amount {
+            return false;
         }
+        self.counters.insert(kind, have - amount);
+        true
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct GameState 
+pub struct GameState
 {
     pub players: Vec<Player>,
     pub current_player_index: usize,
     pub turns: u32,
     pub step: GameStep,
+    // Seed the game was created from, and the RNG driving every random choice in it
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+    #[serde(default)]
+    pub combat: Combat,
+    #[serde(default)]
+    pub events: EventBus,
+    // Controllers of the spells on the stack, bottom first. Each player's own Stack zone holds
+    // their spells in the same order, so the top spell is the last card of its controller's zone.
+    #[serde(default)]
+    pub stack: Vec<usize>,
+    // Id the next card put into the game gets
+    #[serde(default)]
+    pub next_card_id: u32,
+    // Timestamp the next permanent or continuous effect gets, to order effects in the same layer
+    #[serde(default)]
+    pub next_timestamp: u64,
+    // Set while a draw replacement's effect happens, so the draws it makes aren't replaced again
+    #[serde(skip)]
+    pub replacing_draw: bool,
+    // Spells cast by anyone this turn, for storm
+    #[serde(default)]
+    pub spells_this_turn: u32,
+    // The spell or the source of the triggered ability whose effects are resolving, for
+    // protection to keep it from targeting creatures
+    #[serde(skip)]
+    pub resolving: Option<CardId>,
+    #[serde(default)]
+    pub rules: GameRules,
+    // Who makes each player's decisions; players without one curve out
+    #[serde(skip)]
+    pub strategies: Vec<Arc<dyn PlayerStrategy>>,
+    // Where the game's actions are logged, if anywhere
+    #[serde(skip)]
+    pub log: Option<Arc<GameLog>>,
+    // Who is shown every action and priority pass as it happens, if anyone
+    #[serde(skip)]
+    pub watcher: Option<Arc<dyn Watcher>>,
 }
 
-impl GameState 
+impl GameState
 {
-    pub fn new(player_count: usize, deck: &Deck) -> Self 
+    pub fn new(player_count: usize, deck: &Deck) -> Self
     {
-        let mut players = Vec::new();
-        for _ in 0..player_count.max(2) // Minimum 2 players
-        {
-            players.push(Player::new(deck));
-        }
+        Self::new_seeded(player_count, deck, crate::sim::next_game_seed())
+    }
 
-        GameState
-        {
+    pub fn new_seeded(player_count: usize, deck: &Deck, seed: u64) -> Self
+    {
+        let decks = vec![deck; player_count.max(2)]; // Minimum 2 players
+        Self::with_decks(&decks, seed)
+    }
+
+    /// One player per deck, in turn order.
+    pub fn with_decks(decks: &[&Deck], seed: u64) -> Self
+    {
+        Self::with_rules(decks, seed, &GameRules::default(), &[])
+    }
+
+    /// A game played by `rules` where each player is piloted by their entry of `strategies`,
+    /// mulligans included.
+    pub fn with_rules(
+        decks: &[&Deck],
+        seed: u64,
+        rules: &GameRules,
+        strategies: &[Arc<dyn PlayerStrategy>],
+    ) -> Self
+    {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let players = decks
+            .iter()
+            .enumerate()
+            .map(|(i, deck)| {
+                Player::with_rules(
+                    deck,
+                    &mut rng,
+                    rules,
+                    strategies
+                        .get(i)
+                        .map_or(&KeepAll as &dyn MulliganStrategy, |s| s.mulligan()),
+                )
+            })
+            .collect();
+
+        let mut game = GameState {
             players,
             current_player_index: 0,
             turns: 0,
             step: GameStep::StartTurn,
+            seed,
+            rng,
+            combat: Combat::default(),
+            events: EventBus::default(),
+            stack: Vec::new(),
+            next_card_id: 0,
+            next_timestamp: 0,
+            replacing_draw: false,
+            spells_this_turn: 0,
+            resolving: None,
+            rules: rules.clone(),
+            strategies: strategies.to_vec(),
+            log: None,
+            watcher: None,
+        };
+        game.assign_card_ids();
+        game
+    }
+
+    // Number every card in the game, in a fixed zone order so ids only depend on the seed
+    fn assign_card_ids(&mut self)
+    {
+        for player in self.players.iter_mut() {
+            for zone in Zone::ALL.iter() {
+                for card in player.zones.get_mut(zone).into_iter().flatten() {
+                    card.id = CardId(self.next_card_id);
+                    self.next_card_id += 1;
+                }
+            }
+        }
+    }
+
+    /// A fresh id for a card put into the game after it started.
+    pub fn new_card_id(&mut self) -> CardId
+    {
+        let id = CardId(self.next_card_id);
+        self.next_card_id += 1;
+        id
+    }
+
+    /// Where the card with `id` is: its owner, zone, and index in that zone. Indices shift as
+    /// cards move, so hold on to the id and look the card up again when needed.
+    pub fn locate(&self, id: CardId) -> Option<(usize, Zone, usize)>
+    {
+        self.players.iter().enumerate().find_map(|(player, p)| {
+            Zone::ALL.iter().find_map(|zone| {
+                let index = p.zones.get(zone)?.iter().position(|c| c.id == id)?;
+                Some((player, *zone, index))
+            })
+        })
+    }
+
+    pub fn card(&self, id: CardId) -> Option<&Card>
+    {
+        let (player, zone, index) = self.locate(id)?;
+        self.players[player].zones.get(&zone)?.get(index)
+    }
+
+    pub fn card_mut(&mut self, id: CardId) -> Option<&mut Card>
+    {
+        let (player, zone, index) = self.locate(id)?;
+        self.players[player].zones.get_mut(&zone)?.get_mut(index)
+    }
+
+    /// A copy of the game to play ahead in, for search. Cards share their fragments with this
+    /// game until either side changes one, so branching copies only the cards' names and zone
+    /// lists; and what happens in the copy isn't written to the game log.
+    pub fn clone_for_rollout(&self) -> GameState
+    {
+        let mut copy = self.clone();
+        copy.log = None;
+        copy.watcher = None;
+        copy
+    }
+
+    /// Write the game as JSON, to pick it up later with `load`. Strategies and the event log
+    /// aren't saved; whoever loads the game sets them again.
+    pub fn save(&self, path: &Path) -> io::Result<()>
+    {
+        let json = serde_json::to_string(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self>
+    {
+        let text = std::fs::read_to_string(path)?;
+        let mut game: GameState = serde_json::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        game.refresh_components();
+        Ok(game)
+    }
+
+    /// Columns of `player`'s battlefield. They're kept in step with every move through
+    /// `move_card`; a battlefield changed behind their back, as tests and scripts do, is looked up
+    /// afresh.
+    pub fn components(&self, player: usize) -> Cow<'_, Components>
+    {
+        let (battlefield, components) = (
+            self.players[player].zones.get(&Zone::Battlefield).unwrap(),
+            &self.players[player].components,
+        );
+        match components.matches(battlefield) {
+            true => Cow::Borrowed(components),
+            false => Cow::Owned(Components::of(battlefield)),
+        }
+    }
+
+    /// Look every player's permanents up again for their columns, after continuous effects were
+    /// reapplied.
+    pub fn refresh_components(&mut self)
+    {
+        for player in self.players.iter_mut() {
+            player
+                .components
+                .refresh(player.zones.get(&Zone::Battlefield).unwrap());
+        }
+    }
+
+    /// Tap or untap the permanent at `index` of `player`'s battlefield.
+    pub fn set_tapped(&mut self, player: usize, index: usize, tapped: bool)
+    {
+        let Player {
+            zones, components, ..
+        } = &mut self.players[player];
+        let card = &mut zones.get_mut(&Zone::Battlefield).unwrap()[index];
+        crate::tappable::set_tapped(card, tapped);
+        if components.matches(zones.get(&Zone::Battlefield).unwrap()) {
+            components.refresh_row(index, &zones[&Zone::Battlefield][index]);
         }
     }
 
-    pub fn new_default() -> Self {
+    pub fn new_default() -> Self
+    {
         let deck = Deck::example();
         Self::new(2, &deck) // Default 2 players
     }
 
-    pub fn current_player(&self) -> &Player {
+    pub fn current_player(&self) -> &Player
+    {
         &self.players[self.current_player_index]
     }
 
-    pub fn current_player_mut(&mut self) -> &mut Player {
+    pub fn current_player_mut(&mut self) -> &mut Player
+    {
         &mut self.players[self.current_player_index]
     }
 
-    pub fn other_players(&self) -> Vec<&Player> {
-        self.players.iter().enumerate()
+    pub fn other_players(&self) -> Vec<&Player>
+    {
+        self.players
+            .iter()
+            .enumerate()
             .filter(|(i, _)| *i != self.current_player_index)
             .map(|(_, p)| p)
             .collect()
     }
 
-    pub fn other_players_mut(&mut self) -> Vec<&mut Player> {
+    pub fn other_players_mut(&mut self) -> Vec<&mut Player>
+    {
         let current_idx = self.current_player_index;
-        self.players.iter_mut().enumerate()
+        self.players
+            .iter_mut()
+            .enumerate()
             .filter(|(i, _)| *i != current_idx)
             .map(|(_, p)| p)
             .collect()
     }
 
     // Backward compatibility: access current player's zones
-    pub fn zones(&self) -> &HashMap<Zone, Vec<Card>> {
+    pub fn zones(&self) -> &HashMap<Zone, Vec<Card>>
+    {
         &self.current_player().zones
     }
 
-    pub fn zones_mut(&mut self) -> &mut HashMap<Zone, Vec<Card>> {
+    pub fn zones_mut(&mut self) -> &mut HashMap<Zone, Vec<Card>>
+    {
         &mut self.current_player_mut().zones
     }
 
-    pub fn life(&self) -> i32 {
+    pub fn life(&self) -> i32
+    {
         self.current_player().life
     }
 
-    pub fn set_life(&mut self, life: i32) {
+    pub fn set_life(&mut self, life: i32)
+    {
         self.current_player_mut().life = life;
     }
 }
 
-impl GameState 
+impl GameState
 {
     pub fn step(&mut self)
     {
-        match self.step
-        {
-            GameStep::StartTurn =>
-            {
+        match self.step {
+            GameStep::StartTurn => {
                 self.turns += 1;
+                let first_turn = self.turns == 1;
+                let next_timestamp = self.next_timestamp;
+                self.current_player_mut().turn_started_at = next_timestamp;
+                let stats = &mut self.current_player_mut().stats;
+                stats.turns_taken += 1;
+                stats.went_first |= first_turn;
+                stats.on_curve.push(false);
+                stats.spells_per_turn.push(0);
+                self.spells_this_turn = 0;
+                let turn = self.turns;
+                for player in self.players.iter_mut() {
+                    player
+                        .playable_from_exile
+                        .retain(|&(_, until)| until >= turn);
+                }
                 self.step = GameStep::Untap;
             }
 
-            GameStep::Untap =>
-            {
+            GameStep::Untap => {
                 // Untap all tappable cards
                 {
                     let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                    for card in battlefield.iter_mut()
-                    {
-                        if crate::tappable::is_tapped(card)
-                        {
+                    for card in battlefield.iter_mut() {
+                        if crate::tappable::is_tapped(card) {
                             crate::tappable::set_tapped(card, false);
                         }
                     }
                 }
+                let current = self.current_player_mut();
+                current
+                    .components
+                    .refresh(current.zones.get(&Zone::Battlefield).unwrap());
 
                 self.step = GameStep::Upkeep;
             }
 
-            GameStep::Upkeep =>
-            {
+            GameStep::Upkeep => {
                 // Remove summoning sickness from creatures that have it
                 let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                for card in battlefield.iter_mut()
-                {
+                for card in battlefield.iter_mut() {
                     crate::creature::set_summoning_sickness(card, false);
                 }
+                let current = self.current_player_mut();
+                current
+                    .components
+                    .refresh(current.zones.get(&Zone::Battlefield).unwrap());
 
+                self.run_priority();
                 self.step = GameStep::Draw;
             }
 
-            GameStep::Draw =>
-            {
-                let card = 
-                {
-                    let library = self.zones_mut().get_mut(&Zone::Library).unwrap();
-                    library.pop()
-                };
-
-                if let Some(card) = card 
-                {
-                    let hand = self.zones_mut().get_mut(&Zone::Hand).unwrap();
-                    hand.push(card);
-                    self.step = GameStep::Main;
-                } 
-                else 
-                {
-                    self.step = GameStep::GameOver;
+            GameStep::Draw => {
+                // The player who went first skips the draw on their first turn
+                let current = self.current_player_index;
+                let stats = &self.current_player().stats;
+                if !(stats.went_first && stats.turns_taken == 1) {
+                    self.draw_card(current);
                 }
+                self.run_priority();
+                self.step = GameStep::Main;
             }
 
-            GameStep::Main =>
-            {
+            GameStep::Main => {
+                let lands_seen = [Zone::Hand, Zone::Battlefield]
+                    .iter()
+                    .flat_map(|zone| self.zones().get(zone).unwrap().iter())
+                    .filter(|c| c.is_type(crate::card::CardType::Land))
+                    .count() as u32;
+                self.current_player_mut().stats.lands_seen.push(lands_seen);
+
                 // Play up to one land
                 {
-                    let card_option =
-                    {
-                        let hand = self.zones_mut().get_mut(&Zone::Hand).unwrap();
-                        if let Some(pos) = hand.iter().position(|c| c.is_type(crate::card::CardType::Land))
-                        {
-                            Some(hand.remove(pos))  // hand borrow ends here
-                        }
-                        else
-                        {
-                            None
-                        }
+                    let current = self.current_player_index;
+                    // A land exiled with impulse draw is only asked about while it can be played
+                    let exiled = self.playable_lands_in_exile(current);
+                    let from_exile = match exiled.is_empty() {
+                        true => None,
+                        false => self
+                            .decide(current, |strategy, game, rng| {
+                                strategy.exiled_land_drop(game, current, rng)
+                            })
+                            .filter(|index| exiled.contains(index)),
+                    };
+                    let land = match from_exile {
+                        Some(index) => Some((Zone::Exile, index)),
+                        None => self
+                            .decide(current, |strategy, game, rng| {
+                                strategy.land_drop(game, current, rng)
+                            })
+                            .map(|index| (Zone::Hand, index)),
                     };
 
-                    if let Some(card) = card_option
-                    {
-                        let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                        battlefield.push(card);
+                    self.current_player_mut()
+                        .stats
+                        .land_drops
+                        .push(land.is_some());
+
+                    if let Some((zone, pos)) = land {
+                        // A card with a land face is played as that face
+                        let card = &mut self.zones_mut().get_mut(&zone).unwrap()[pos];
+                        if let Some(face) = crate::faces::land_face(card) {
+                            crate::faces::turn_to(card, face);
+                        }
+                        self.log_event(current, || LogEvent::LandDrop {
+                            card: (&self.zones().get(&zone).unwrap()[pos]).into(),
+                        });
+                        self.move_card(current, zone, pos, Zone::Battlefield);
+                        self.process_events();
                     }
                 }
 
-                // Cast as many creatures as possible until there is no more mana
-                loop
-                {
-                    // Count available untapped lands as available mana
-                    let available_mana = self.zones().get(&Zone::Battlefield).unwrap().iter().filter(|card| 
-                        card.is_type(crate::card::CardType::Land) && !crate::tappable::is_tapped(card)).count() as u32;
-
-                    // Find first castable creature in hand
-                    let cast_pos = 
-                    {
-                        let hand = self.zones().get(&Zone::Hand).unwrap();
-                        hand.iter().position(|card| crate::creature::is_creature(card) && card.cost <= available_mana)
-                    };
-
-                    if let Some(pos) = cast_pos
-                    {
-                        // Remove card first
-                        let mut card = 
-                        {
-                            let hand = self.zones_mut().get_mut(&Zone::Hand).unwrap();
-                            hand.remove(pos)
-                        };
+                // Cast spells until the player's strategy stops or there is no more mana
+                loop {
+                    let current = self.current_player_index;
+                    let host =
+                        attachment::choose_host(self.zones().get(&Zone::Battlefield).unwrap());
+                    let cast = self.decide(current, |strategy, game, rng| {
+                        strategy.main_phase_cast(game, current, rng)
+                    });
 
-                        vlog!(ELoggingVerbosity::Verbose, "Cast {}", card.name);
-
-                        // Newly cast creatures have summoning sickness
-                        crate::creature::set_summoning_sickness(&mut card, true);
-
-                        // Tap lands to pay for the creature's cost
-                        let mut need = card.cost;
-                        {
-                            let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                            for b in battlefield.iter_mut().filter(|c| c.is_type(crate::card::CardType::Land) && !crate::tappable::is_tapped(c)) 
-                            {
-                                if need == 0 
-                                { 
-                                    break; 
-                                }
-                                crate::tappable::set_tapped(b, true);
-                                need -= 1;
+                    if let Some(cast) = cast {
+                        if let Some(stack_index) = self.cast(current, &cast) {
+                            let spell =
+                                &mut self.zones_mut().get_mut(&Zone::Stack).unwrap()[stack_index];
+                            if attachment::is_aura(spell) {
+                                attachment::attach(spell, host);
                             }
                         }
-
-                        // Put the card onto the battlefield
-                        let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                        battlefield.push(card);
-                    }
-                    else
-                    {
+                        // Opponents get a chance to respond before the spell resolves
+                        self.run_priority();
+                    } else {
                         // Nothing more can be cast
                         break;
                     }
                 }
 
-                self.step = GameStep::Combat;
-            }
+                self.equip_unattached();
+                self.activate_loyalty_abilities();
+                self.activate_sacrifice_abilities();
 
-            GameStep::Combat =>
-            {
-                let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                let mut damage = 0;
-                for card in battlefield.iter_mut().filter(|card| card.is_type(crate::card::CardType::Creature) && !crate::creature::has_summoning_sickness(card) && !crate::tappable::is_tapped(card))
-                {
-                    damage += crate::creature::creature_stats(card).map(|stat| stat.power as u32).unwrap_or(0);
-                    crate::tappable::set_tapped(card, true);
+                if self.color_screwed(self.current_player_index) {
+                    self.current_player_mut().stats.color_screwed_turns += 1;
                 }
 
-                // Apply damage to all other players
-                for other_player in self.other_players_mut() {
-                    other_player.life -= damage as i32;
+                self.run_priority();
+                self.step = GameStep::DeclareAttackers;
+            }
+
+            GameStep::DeclareAttackers => {
+                // The next player in turn order defends
+                let current = self.current_player_index;
+                let defending_player = (current + 1) % self.players.len();
+                let attackers = self.decide(current, |strategy, game, rng| {
+                    strategy.attackers(game, current, defending_player, rng)
+                });
+                let attackers = combat::legal_attackers(
+                    self.zones().get(&Zone::Battlefield).unwrap(),
+                    &attackers,
+                );
+
+                // Vigilance attacks without tapping
+                for &attacker in attackers.iter() {
+                    if combat::taps_to_attack(
+                        &self.zones().get(&Zone::Battlefield).unwrap()[attacker],
+                    ) {
+                        self.set_tapped(current, attacker, true);
+                    }
                 }
 
-                // Check if any player has lost
-                let anyone_dead = self.players.iter().any(|p| p.life <= 0);
-                if anyone_dead {
-                    self.step = GameStep::GameOver;
-                } else {
+                if attackers.is_empty() {
                     self.step = GameStep::EndTurn;
+                } else {
+                    vlog!(
+                        ELoggingVerbosity::Verbose,
+                        "Attack with {} creature(s)",
+                        attackers.len()
+                    );
+                    self.log_event(current, || {
+                        let battlefield = self.zones().get(&Zone::Battlefield).unwrap();
+                        LogEvent::Attack {
+                            defender: defending_player,
+                            attackers: attackers
+                                .iter()
+                                .map(|&a| (&battlefield[a]).into())
+                                .collect(),
+                        }
+                    });
+                    let ids = attackers
+                        .iter()
+                        .map(|&a| self.zones().get(&Zone::Battlefield).unwrap()[a].id)
+                        .collect();
+                    self.events.emit(GameEvent::AttackDeclared {
+                        player: self.current_player_index,
+                        attackers: ids,
+                    });
+                    let planeswalkers = self.attack_planeswalkers(defending_player, &attackers);
+                    self.combat = Combat {
+                        defending_player,
+                        attackers,
+                        blocks: Vec::new(),
+                        planeswalkers,
+                    };
+                    self.process_events();
+                    self.run_priority();
+                    self.step = GameStep::DeclareBlockers;
                 }
             }
 
-            GameStep::EndTurn =>
-            {
+            GameStep::DeclareBlockers => {
+                let defender = self.combat.defending_player;
+                let blocks = self.decide(defender, |strategy, game, rng| {
+                    strategy.blockers(game, defender, rng)
+                });
+                self.combat.blocks = combat::legal_blocks(
+                    self.zones().get(&Zone::Battlefield).unwrap(),
+                    &self.combat.attackers,
+                    self.players[defender]
+                        .zones
+                        .get(&Zone::Battlefield)
+                        .unwrap(),
+                    &blocks,
+                );
+
+                vlog!(
+                    ELoggingVerbosity::Verbose,
+                    "Block with {} creature(s)",
+                    self.combat.blocks.len()
+                );
+                self.run_priority();
+                self.step = GameStep::CombatDamage;
+            }
+
+            GameStep::CombatDamage => {
+                let combat = std::mem::take(&mut self.combat);
+                let report = combat::assign_damage(
+                    self.zones().get(&Zone::Battlefield).unwrap(),
+                    self.players[combat.defending_player]
+                        .zones
+                        .get(&Zone::Battlefield)
+                        .unwrap(),
+                    &combat,
+                );
+
+                let current = self.current_player_index;
+                // Lifelink gains as much life as the damage actually dealt, after prevention
+                let mut attacker_life_gained = report.attacker_life_gained;
+                for &(attacker, assigned) in report.player_damage.iter() {
+                    let amount = self.damage_dealt_to(combat.defending_player, assigned);
+                    let source =
+                        &self.players[current].zones.get(&Zone::Battlefield).unwrap()[attacker];
+                    attacker_life_gained -=
+                        crate::keyword::life_gained_from_damage(source, assigned)
+                            - crate::keyword::life_gained_from_damage(source, amount);
+                    if amount == 0 {
+                        continue;
+                    }
+                    let source =
+                        &self.players[current].zones.get(&Zone::Battlefield).unwrap()[attacker];
+                    self.log_event(current, || LogEvent::Damage {
+                        source: Some(source.into()),
+                        target: DamageTarget::Player(combat.defending_player),
+                        amount,
+                    });
+                    let (infect, commander) = (
+                        crate::keyword::any(source, |h| h.damages_players_with_poison()),
+                        crate::commander::is_commander(source).then_some(source.id),
+                    );
+                    if infect {
+                        self.players[combat.defending_player]
+                            .add_counters(PlayerCounter::Poison, amount);
+                    } else {
+                        crate::life::lose(self, combat.defending_player, amount);
+                    }
+                    let defender = &mut self.players[combat.defending_player];
+                    if let Some(id) = commander {
+                        match defender.commander_damage.iter_mut().find(|(c, _)| *c == id) {
+                            Some((_, total)) => *total += amount,
+                            None => defender.commander_damage.push((id, amount)),
+                        }
+                    }
+                }
+                for &(attacker, planeswalker, amount) in report.to_planeswalkers.iter() {
+                    let source =
+                        &self.players[current].zones.get(&Zone::Battlefield).unwrap()[attacker];
+                    let target = &self.players[combat.defending_player]
+                        .zones
+                        .get(&Zone::Battlefield)
+                        .unwrap()[planeswalker];
+                    self.log_event(current, || LogEvent::Damage {
+                        source: Some(source.into()),
+                        target: DamageTarget::Planeswalker(target.into()),
+                        amount,
+                    });
+                    crate::planeswalker::deal_damage(
+                        &mut self.players[combat.defending_player]
+                            .zones
+                            .get_mut(&Zone::Battlefield)
+                            .unwrap()[planeswalker],
+                        amount,
+                    );
+                }
+                crate::life::gain(self, combat.defending_player, report.defender_life_gained);
+                crate::life::gain(self, current, attacker_life_gained);
+                for (player, damage) in [
+                    (current, &report.to_attackers),
+                    (combat.defending_player, &report.to_blockers),
+                ] {
+                    let source_player = if player == current {
+                        combat.defending_player
+                    } else {
+                        current
+                    };
+                    for d in damage.iter() {
+                        self.log_event(source_player, || LogEvent::Damage {
+                            source: None,
+                            target: DamageTarget::Creature(
+                                (&self.players[player].zones.get(&Zone::Battlefield).unwrap()
+                                    [d.index])
+                                    .into(),
+                            ),
+                            amount: d.amount,
+                        });
+                    }
+                    let battlefield = self.players[player]
+                        .zones
+                        .get_mut(&Zone::Battlefield)
+                        .unwrap();
+                    for d in damage.iter() {
+                        crate::creature::deal_damage(
+                            &mut battlefield[d.index],
+                            d.amount,
+                            d.deathtouch,
+                        );
+                    }
+                }
+                self.process_events();
+                self.run_priority();
+                self.step = GameStep::EndTurn;
+            }
+
+            GameStep::EndTurn => {
+                self.run_priority();
+
+                // Cleanup: damage and "until end of turn" effects wear off
+                for player in self.players.iter_mut() {
+                    for card in player.zones.get_mut(&Zone::Battlefield).unwrap().iter_mut() {
+                        crate::creature::clear_damage(card);
+                        crate::creature::clear_until_end_of_turn(card);
+                    }
+                }
+                self.refresh_components();
+                self.discard_to_hand_size();
+
                 // Advance to next player
                 self.current_player_index = (self.current_player_index + 1) % self.players.len();
                 self.step = GameStep::StartTurn;
             }
 
-            GameStep::GameOver =>
-            {
+            GameStep::GameOver => {
                 // Do nothing
             }
         }
+
+        self.process_events();
+
+        // Unspent mana empties between steps
+        for player in self.players.iter_mut() {
+            player.mana_pool.clear();
+        }
+
+        if self.players.iter().any(|p| p.has_lost) {
+            self.step = GameStep::GameOver;
+        }
     }
 
-    pub fn is_game_over(&self) -> bool
+    /// Move the top card of a player's library to their hand. Drawing from an empty library
+    /// loses the game at the next state-based action check.
+    pub fn draw_card(&mut self, player: usize) -> bool
     {
-        self.step == GameStep::GameOver
+        if let Outcome::Instead { controller, effect } =
+            replacement::replace(self, PendingEvent::Draw { player })
+        {
+            self.replacing_draw = true;
+            crate::effect::apply(self, controller, &effect);
+            self.replacing_draw = false;
+            return true;
+        }
+
+        let library_size = self.players[player]
+            .zones
+            .get(&Zone::Library)
+            .map_or(0, Vec::len);
+        if library_size == 0 {
+            self.players[player].drew_from_empty_library = true;
+            return false;
+        }
+
+        let Some(index) = self.move_card(player, Zone::Library, library_size - 1, Zone::Hand)
+        else {
+            return false;
+        };
+        self.players[player].stats.cards_drawn += 1;
+        self.log_event(player, || LogEvent::Draw {
+            card: (&self.players[player].zones.get(&Zone::Hand).unwrap()[index]).into(),
+        });
+        let player = &mut self.players[player];
+        let name = &player.zones.get(&Zone::Hand).unwrap()[index].name;
+        if player.stats.turns_taken <= crate::sim::EARLY_CARD_TURNS
+            && !player.stats.early_cards.contains(name)
+        {
+            player.stats.early_cards.push(name.clone());
+        }
+        true
     }
 
-    pub fn describe(&self, verbose: bool)
+    /// Write an event concerning `player` to the game's log and show it to the game's watcher.
+    /// The event is only built when someone is looking.
+    pub fn log_event(&self, player: usize, event: impl FnOnce() -> LogEvent)
     {
-        println!("Turn: {}", self.turns);
-        println!("Step: {:?}", self.step);
-        println!("Life: {}", self.life());
+        if self.log.is_none() && self.watcher.is_none() {
+            return;
+        }
+        let record = LogRecord {
+            game: self.seed,
+            turn: self.turns,
+            player,
+            event: event(),
+        };
+        if let Some(log) = &self.log {
+            log.record(&record);
+        }
+        if let Some(watcher) = &self.watcher {
+            watcher.action(self, &record);
+        }
+    }
 
-        if verbose 
-        {
-            self.describe_verbose();
-        } 
-        else 
-        {
-            self.describe_summary();
+    /// How much of `amount` damage is dealt to `player` once replacement effects prevent theirs.
+    pub fn damage_dealt_to(&self, player: usize, amount: u32) -> u32
+    {
+        match replacement::replace(self, PendingEvent::DamagePlayer { player, amount }) {
+            Outcome::Happens(PendingEvent::DamagePlayer { amount, .. }) => amount,
+            _ => 0,
         }
     }
 
-    pub fn describe_summary(&self)
+    /// Put `amount` +1/+1 counters on the creature at `index` on `player`'s battlefield, as many
+    /// more as replacement effects add.
+    pub fn put_counters(&mut self, player: usize, index: usize, amount: u32)
     {
-        // Print only zone counts
-        for zone in &[Zone::Hand, Zone::Battlefield, Zone::Library, Zone::Graveyard, Zone::Exile]
-        {
-            let cards = self.zones().get(zone).unwrap();
-            println!("{:?}: {} cards", zone, cards.len());
+        let Some(card) = self.players[player]
+            .zones
+            .get(&Zone::Battlefield)
+            .and_then(|cards| cards.get(index))
+        else {
+            return;
+        };
+        if let Outcome::Happens(PendingEvent::PutCounters { amount, .. }) = replacement::replace(
+            self,
+            PendingEvent::PutCounters {
+                player,
+                card: card.id,
+                amount,
+            },
+        ) {
+            crate::creature::add_counters(
+                &mut self.players[player]
+                    .zones
+                    .get_mut(&Zone::Battlefield)
+                    .unwrap()[index],
+                CounterKind::PlusOne,
+                amount,
+            );
         }
     }
 
-    pub fn describe_verbose(&self)
+    /// A fresh timestamp for a continuous effect starting now.
+    pub fn timestamp(&mut self) -> u64
+    {
+        self.next_timestamp += 1;
+        self.next_timestamp - 1
+    }
+
+    /// Move the card at `index` in one of `player`'s zones to the end of another zone and emit
+    /// the matching events. Returns the card's index in its new zone.
+    pub fn move_card(&mut self, player: usize, from: Zone, index: usize, to: Zone)
+    -> Option<usize>
     {
-        for zone in &[Zone::Hand, Zone::Battlefield, Zone::Library, Zone::Graveyard]
+        if self.players[player]
+            .zones
+            .get(&from)
+            .is_none_or(|cards| index >= cards.len())
         {
-            let cards = self.zones().get(zone).unwrap();
-            if cards.is_empty() && (*zone == Zone::Battlefield || *zone == Zone::Graveyard)
-            {
-                continue;
-            }
+            return None;
+        }
+        let timestamp = (to == Zone::Battlefield).then(|| self.timestamp());
+        let Player {
+            zones, components, ..
+        } = &mut self.players[player];
+        // A battlefield changed behind the columns' back is looked up afresh, not patched
+        let in_step = (from == Zone::Battlefield || to == Zone::Battlefield)
+            && components.matches(zones.get(&Zone::Battlefield).unwrap());
+        let source = zones.get_mut(&from).filter(|cards| index < cards.len())?;
+        let mut card = source.remove(index);
+        if from == Zone::Battlefield && in_step {
+            components.remove(index);
+        }
+        let is_creature = crate::creature::is_creature(&card);
+        crate::faces::moved_to(&mut card, to);
 
-            println!("{:?}: ({} cards)", zone, cards.len());
+        let destination = zones.entry(to).or_default();
+        if to == Zone::Battlefield && crate::tappable::enters_tapped(&card, destination) {
+            crate::tappable::set_tapped(&mut card, true);
+        }
+        if let Some(timestamp) = timestamp {
+            crate::planeswalker::enter(&mut card);
+            crate::statics::enter(&mut card, timestamp);
+        }
+        if from == Zone::Stack {
+            crate::effect::set_kicked(&mut card, false);
+        }
+        if from == Zone::Battlefield {
+            crate::creature::leave_battlefield(&mut card);
+            crate::layers::leave_battlefield(&mut card);
+        }
+        let (id, counters) = (card.id, replacement::enters_with_counters(&card));
+        if to == Zone::Battlefield && in_step {
+            components.push(&card);
+        }
+        destination.push(card);
+        let new_index = destination.len() - 1;
+        if from == Zone::Exile {
+            self.players[player]
+                .playable_from_exile
+                .retain(|&(exiled, _)| exiled != id);
+        }
+        if to == Zone::Battlefield && counters > 0 {
+            self.put_counters(player, new_index, counters);
+        }
 
-            match zone
-            {
-                Zone::Library =>
-                {
-                    // Show library cards grouped by count
-                    let mut card_groups: HashMap<String, u32> = HashMap::new();
-                    for card in cards.iter()
-                    {
-                        *card_groups.entry(card.name.clone()).or_insert(0) += 1;
-                    }
+        self.events.emit(GameEvent::ZoneChanged {
+            player,
+            from,
+            to,
+            card: id,
+        });
+        if to == Zone::Battlefield {
+            self.events
+                .emit(GameEvent::CardEntered { player, card: id });
+        }
+        if from == Zone::Battlefield && to == Zone::Graveyard && is_creature {
+            self.log_event(player, || LogEvent::Death {
+                card: (&self.players[player].zones.get(&Zone::Graveyard).unwrap()[new_index])
+                    .into(),
+            });
+            self.events
+                .emit(GameEvent::CreatureDied { player, card: id });
+        }
 
-                    for (name, count) in card_groups.iter()
-                    {
-                        println!("  {} x{}", name, count);
-                    }
-                }
-                Zone::Hand =>
-                {
-                    // Print hand cards grouped by count in an inline list
-                    let mut groups: HashMap<String, u32> = HashMap::new();
-                    for card in cards.iter()
-                    {
-                        *groups.entry(card.name.clone()).or_insert(0) += 1;
-                    }
+        Some(new_index)
+    }
+
+    // Untapped mana sources on a player's battlefield (index) and the colors each can produce;
+    // see `Components::untapped_mana`
+    fn untapped_sources(&self, player: usize) -> Vec<(usize, Vec<ManaColor>)>
+    {
+        self.components(player).untapped_mana()
+    }
+
+    /// How `player` could pay `cost` with mana already in their pool plus untapped sources:
+    /// (battlefield index, color) pairs for the sources to tap.
+    pub fn plan_cost(&self, player: usize, cost: &ManaCost) -> Option<Vec<(usize, ManaColor)>>
+    {
+        self.plan_with(player, cost, &self.untapped_sources(player))
+    }
+
+    // `plan_cost` with the untapped sources already looked up, for checking several spells
+    fn plan_with(
+        &self,
+        player: usize,
+        cost: &ManaCost,
+        sources: &[(usize, Vec<ManaColor>)],
+    ) -> Option<Vec<(usize, ManaColor)>>
+    {
+        let remaining = self.players[player].mana_pool.remaining_cost(cost);
+        let colors: Vec<Vec<ManaColor>> = sources.iter().map(|(_, c)| c.clone()).collect();
+        let plan = mana::plan_payment(&remaining, &colors)?;
+        Some(
+            plan.into_iter()
+                .map(|(source, color)| (sources[source].0, color))
+                .collect(),
+        )
+    }
+
+    pub fn plan_cast(&self, player: usize, card: &Card) -> Option<Vec<(usize, ManaColor)>>
+    {
+        self.plan_cost(player, &card.cost)
+    }
+
+    // Whether some spell in `player`'s hand can't be paid for only because the untapped sources
+    // make the wrong colors: it would be if every source made any color
+    fn color_screwed(&self, player: usize) -> bool
+    {
+        let sources = self.untapped_sources(player);
+        self.players[player].zones[&Zone::Hand]
+            .iter()
+            .filter(|card| !card.is_type(CardType::Land))
+            .any(|card| {
+                let colorless = ManaCost::generic(card.cost.mana_value());
+                self.plan_with(player, &card.cost, &sources).is_none()
+                    && self.plan_with(player, &colorless, &sources).is_some()
+            })
+    }
+
+    // Tap the sources in `plan` for mana, then pay `cost` from the pool. Mana a source makes
+    // beyond what the plan uses stays in the pool. Sources sacrificed for their mana, like
+    // Treasures, leave the battlefield afterwards, so battlefield indices can shift.
+    fn pay_cost(&mut self, player: usize, cost: &ManaCost, plan: &[(usize, ManaColor)])
+    {
+        let mut tapped: Vec<usize> = Vec::new();
+        let mut sacrificed: Vec<usize> = Vec::new();
+        for &(source, color) in plan.iter() {
+            self.players[player].mana_pool.add(color, 1);
+            if tapped.contains(&source) {
+                continue;
+            }
+            tapped.push(source);
+
+            self.set_tapped(player, source, true);
+            let card = &self.players[player].zones.get(&Zone::Battlefield).unwrap()[source];
+            let unused = mana::produced_amount(card)
+                - plan.iter().filter(|(s, _)| *s == source).count() as u32;
+            let spare_color = mana::produced_colors(card)[0];
+            if crate::token::sacrificed_for_mana(card) {
+                sacrificed.push(source);
+            }
+            self.players[player].mana_pool.add(spare_color, unused);
+        }
+        let paid = self.players[player].mana_pool.pay(cost);
+        debug_assert!(paid, "payment plan didn't cover {}", cost);
+        self.put_into_graveyard(player, &sacrificed);
+    }
+
+    /// Put the card at `hand_index` on top of the stack, tapping the sources in `plan` to pay for
+    /// it. Returns the spell's index in its controller's Stack zone.
+    pub fn cast_spell(
+        &mut self,
+        player: usize,
+        hand_index: usize,
+        plan: &[(usize, ManaColor)],
+    ) -> Option<usize>
+    {
+        self.cast_from(player, Zone::Hand, hand_index, plan, false)
+    }
+
+    /// Cast a spell as `cast` says, turning the card to the face it's cast as first.
+    pub fn cast(&mut self, player: usize, cast: &Cast) -> Option<usize>
+    {
+        let card = self.players[player]
+            .zones
+            .get_mut(&cast.zone)?
+            .get_mut(cast.index)?;
+        crate::faces::turn_to(card, cast.face);
+        self.cast_from(player, cast.zone, cast.index, &cast.plan, cast.alternative)
+    }
+
+    /// The card `cast` would put on the stack: the face it's cast as.
+    pub fn spell(&self, player: usize, cast: &Cast) -> &Card
+    {
+        &crate::faces::faces(&self.players[player].zones[&cast.zone][cast.index])[cast.face]
+    }
+
+    /// `cost` for casting `card` with the taxes of the static effects in force added.
+    pub fn taxed(&self, card: &Card, cost: ManaCost) -> ManaCost
+    {
+        crate::statics::taxed(&crate::statics::in_force(self), card, cost)
+    }
+
+    /// Cast the card at `index` of `zone` (the hand, or the command zone with commander tax),
+    /// for its alternative cost if `alternative`.
+    pub fn cast_from(
+        &mut self,
+        player: usize,
+        zone: Zone,
+        index: usize,
+        plan: &[(usize, ManaColor)],
+        alternative: bool,
+    ) -> Option<usize>
+    {
+        let stack_index = self.move_card(player, zone, index, Zone::Stack)?;
+        let spell = &mut self.players[player].zones.get_mut(&Zone::Stack).unwrap()[stack_index];
+        let cost = if zone == Zone::Command {
+            let cost = crate::commander::cost_from_command_zone(spell);
+            crate::commander::record_cast(spell);
+            cost
+        } else if alternative && let Some(alternative) = crate::effect::alternative_cost(spell) {
+            alternative.mana
+        } else {
+            spell.cost
+        };
+        vlog!(ELoggingVerbosity::Verbose, "Cast {}", spell.name);
+        let cost = self.taxed(
+            &self.players[player].zones.get(&Zone::Stack).unwrap()[stack_index],
+            cost,
+        );
+
+        let stats = &mut self.players[player].stats;
+        if player == self.current_player_index
+            && cost.mana_value() == stats.turns_taken
+            && let Some(on_curve) = stats.on_curve.last_mut()
+        {
+            *on_curve = true;
+        }
+        self.pay_cost(player, &cost, plan);
+        let sacrifice =
+            crate::effect::sacrifice_cost(&self.players[player].zones[&Zone::Stack][stack_index]);
+        self.sacrifice(player, sacrifice as usize);
+        if alternative {
+            self.pay_alternative(player, stack_index);
+        }
+        self.pay_kicker(player, stack_index);
+        if cost.x > 0 {
+            self.pay_x(player, stack_index, cost.x);
+        }
+        self.finish_cast(player, stack_index);
+        Some(stack_index)
+    }
+
+    /// Cast the card at `index` of `zone` without paying its mana cost, as cascade does, with X
+    /// as 0. Additional costs are still paid.
+    pub fn cast_without_paying(&mut self, player: usize, zone: Zone, index: usize)
+    -> Option<usize>
+    {
+        let stack_index = self.move_card(player, zone, index, Zone::Stack)?;
+        let spell = &mut self.players[player].zones.get_mut(&Zone::Stack).unwrap()[stack_index];
+        vlog!(
+            ELoggingVerbosity::Verbose,
+            "Cast {} without paying its mana cost",
+            spell.name
+        );
+        if let Some(effects) = spell.fragment_mut::<crate::card::EffectFragment>() {
+            effects.x = 0;
+        }
+        let sacrifice =
+            crate::effect::sacrifice_cost(&self.players[player].zones[&Zone::Stack][stack_index]);
+        self.sacrifice(player, sacrifice as usize);
+        self.pay_kicker(player, stack_index);
+        self.finish_cast(player, stack_index);
+        Some(stack_index)
+    }
+
+    // Put the paid-for spell at `stack_index` on the stack and let everything that watches for
+    // spells being cast see it
+    fn finish_cast(&mut self, player: usize, stack_index: usize)
+    {
+        self.stack.push(player);
+        self.log_event(player, || LogEvent::Cast {
+            card: (&self.players[player].zones.get(&Zone::Stack).unwrap()[stack_index]).into(),
+        });
+        self.spell_cast(player, stack_index);
+        self.process_events();
+    }
+
+    // Count the spell `player` just cast at `stack_index`, copy it once per spell cast before it
+    // this turn if it has storm, and cascade if it has cascade
+    fn spell_cast(&mut self, player: usize, stack_index: usize)
+    {
+        let storm_count = self.spells_this_turn;
+        self.spells_this_turn += 1;
+        let stats = &mut self.players[player].stats;
+        stats.spells_cast += 1;
+        if player == self.current_player_index
+            && let Some(spells) = stats.spells_per_turn.last_mut()
+        {
+            *spells += 1;
+        }
+
+        let spell = &self.players[player].zones[&Zone::Stack][stack_index];
+        let (id, storm, mana_value) = (
+            spell.id,
+            crate::keyword::any(spell, |h| h.copied_for_earlier_spells()),
+            spell.cost.mana_value(),
+        );
+        let cascade = crate::keyword::any(spell, |h| h.cascades());
+        if storm {
+            for _ in 0..storm_count {
+                self.copy_spell(player);
+            }
+        }
+        self.events.emit(GameEvent::SpellCast { player, card: id });
+        if cascade {
+            self.cascade(player, mana_value);
+        }
+    }
+
+    // Exile cards from the top of `player`'s library until a nonland card cheaper than
+    // `mana_value`, which their strategy may cast for free if it could be cast at all, then put
+    // the rest on the bottom in a random order
+    fn cascade(&mut self, player: usize, mana_value: u32)
+    {
+        let mut exiled: Vec<CardId> = Vec::new();
+        let mut hit = None;
+        while let Some(index) = self.exile_top(player) {
+            let card = &self.players[player].zones[&Zone::Exile][index];
+            exiled.push(card.id);
+            if !card.is_type(CardType::Land) && card.cost.mana_value() < mana_value {
+                hit = Some(index);
+                break;
+            }
+        }
+
+        if let Some(index) = hit {
+            let card = &self.players[player].zones[&Zone::Exile][index];
+            let spell = !(card.is_type(CardType::Instant) || card.is_type(CardType::Sorcery));
+            let castable = (spell || self.has_targets(player, card))
+                && self.can_pay_sacrifice(player, card)
+                && (!attachment::is_aura(card)
+                    || attachment::choose_host(&self.players[player].zones[&Zone::Battlefield])
+                        .is_some());
+            if castable
+                && self.decide(player, |strategy, game, rng| {
+                    strategy.cascade_into(
+                        game,
+                        player,
+                        &game.players[player].zones[&Zone::Exile][index],
+                        rng,
+                    )
+                })
+            {
+                let host = attachment::choose_host(&self.players[player].zones[&Zone::Battlefield]);
+                if let Some(stack_index) = self.cast_without_paying(player, Zone::Exile, index) {
+                    let spell =
+                        &mut self.players[player].zones.get_mut(&Zone::Stack).unwrap()[stack_index];
+                    if attachment::is_aura(spell) {
+                        attachment::attach(spell, host);
+                    }
+                }
+            }
+        }
+
+        let exile = self.players[player].zones.get_mut(&Zone::Exile).unwrap();
+        let (mut rest, kept): (Vec<Card>, Vec<Card>) = std::mem::take(exile)
+            .into_iter()
+            .partition(|card| exiled.contains(&card.id));
+        *exile = kept;
+        rest.shuffle(&mut self.rng);
+        self.players[player]
+            .zones
+            .get_mut(&Zone::Library)
+            .unwrap()
+            .splice(0..0, rest);
+    }
+
+    // The life and pitched card of the alternative cost of the spell at `stack_index`; the card
+    // exiled is the cheapest one of the color
+    fn pay_alternative(&mut self, player: usize, stack_index: usize)
+    {
+        let Some(alternative) =
+            crate::effect::alternative_cost(&self.players[player].zones[&Zone::Stack][stack_index])
+                .cloned()
+        else {
+            return;
+        };
+        crate::life::lose(self, player, alternative.life);
+        if let Some(color) = alternative.pitch {
+            let pitched = self.players[player].zones[&Zone::Hand]
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| card.cost.colors().contains(&color))
+                .min_by_key(|(_, card)| card.cost.mana_value())
+                .map(|(index, _)| index);
+            if let Some(index) = pitched {
+                vlog!(
+                    ELoggingVerbosity::Verbose,
+                    "Exile {}",
+                    self.players[player].zones[&Zone::Hand][index].name
+                );
+                self.move_card(player, Zone::Hand, index, Zone::Exile);
+            }
+        }
+    }
+
+    // Pay the kicker of the spell at `stack_index` if `player` can and their strategy wants to
+    fn pay_kicker(&mut self, player: usize, stack_index: usize)
+    {
+        let spell = &self.players[player].zones[&Zone::Stack][stack_index];
+        let Some(kicker) = crate::effect::kicker(spell) else {
+            return;
+        };
+        let kicked = self.plan_cost(player, &kicker).filter(|_| {
+            self.decide(player, |strategy, game, rng| {
+                strategy.kick(
+                    game,
+                    player,
+                    &game.players[player].zones[&Zone::Stack][stack_index],
+                    rng,
+                )
+            })
+        });
+        if let Some(plan) = kicked.as_ref() {
+            self.pay_cost(player, &kicker, plan);
+        }
+        crate::effect::set_kicked(
+            &mut self.players[player].zones.get_mut(&Zone::Stack).unwrap()[stack_index],
+            kicked.is_some(),
+        );
+    }
+
+    // Let `player`'s strategy pick X for the spell at `stack_index`, as much as their untapped
+    // sources and floating mana can pay for each of its `symbols` X symbols, and pay it
+    fn pay_x(&mut self, player: usize, stack_index: usize, symbols: u32)
+    {
+        let sources = self.untapped_sources(player);
+        let max_x = (sources.len() as u32 + self.players[player].mana_pool.total()) / symbols;
+        let mut x = self
+            .decide(player, |strategy, game, rng| {
+                strategy.choose_x(
+                    game,
+                    player,
+                    &game.players[player].zones[&Zone::Stack][stack_index],
+                    max_x,
+                    rng,
+                )
+            })
+            .min(max_x);
+        // Sources making more than one mana are counted once, so settle for less if need be
+        while x > 0 {
+            let cost = ManaCost::generic(x * symbols);
+            if let Some(plan) = self.plan_with(player, &cost, &sources) {
+                self.pay_cost(player, &cost, &plan);
+                break;
+            }
+            x -= 1;
+        }
+        let spell = &mut self.players[player].zones.get_mut(&Zone::Stack).unwrap()[stack_index];
+        if let Some(effects) = spell.fragment_mut::<crate::card::EffectFragment>() {
+            effects.x = x;
+        }
+    }
+
+    // Pay equip costs to put the current player's unattached Equipment on their best creature.
+    // Equipping resolves right away; nothing responds to activated abilities yet.
+    fn equip_unattached(&mut self)
+    {
+        let current = self.current_player_index;
+        loop {
+            let battlefield = self.zones().get(&Zone::Battlefield).unwrap();
+            let Some(host) = attachment::choose_host(battlefield) else {
+                return;
+            };
+            let equip = battlefield
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| attachment::attached_to(card).is_none())
+                .filter_map(|(i, card)| attachment::equip_cost(card).map(|cost| (i, cost)))
+                .find_map(|(i, cost)| self.plan_cost(current, &cost).map(|plan| (i, cost, plan)));
+
+            let Some((index, cost, plan)) = equip else {
+                return;
+            };
+            let id = battlefield[index].id;
+            self.pay_cost(current, &cost, &plan);
+            let Some(index) = self
+                .zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .position(|card| card.id == id)
+            else {
+                return;
+            };
+            let equipment = &mut self.zones_mut().get_mut(&Zone::Battlefield).unwrap()[index];
+            vlog!(ELoggingVerbosity::Verbose, "Equip {}", equipment.name);
+            attachment::attach(equipment, Some(host));
+            self.process_events();
+        }
+    }
+
+    // Let the current player's strategy activate one loyalty ability of each of their
+    // planeswalkers. Abilities resolve right away, like equipping.
+    fn activate_loyalty_abilities(&mut self)
+    {
+        let current = self.current_player_index;
+        let planeswalkers: Vec<CardId> = self
+            .zones()
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .filter(|card| crate::planeswalker::is_planeswalker(card))
+            .map(|card| card.id)
+            .collect();
+        for id in planeswalkers {
+            let turn = self.turns;
+            let Some(index) = self
+                .zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .position(|card| card.id == id)
+            else {
+                continue;
+            };
+            if !crate::planeswalker::can_activate(
+                &self.zones().get(&Zone::Battlefield).unwrap()[index],
+                turn,
+            ) {
+                continue;
+            }
+            let usable = crate::planeswalker::usable_abilities(
+                self,
+                current,
+                &self.zones().get(&Zone::Battlefield).unwrap()[index],
+            );
+            let Some(choice) = self
+                .decide(current, |strategy, game, rng| {
+                    strategy.loyalty_ability(game, current, index, rng)
+                })
+                .filter(|choice| usable.contains(choice))
+            else {
+                continue;
+            };
+
+            let card = &mut self.zones_mut().get_mut(&Zone::Battlefield).unwrap()[index];
+            let Some(ability) = crate::planeswalker::activate(card, choice, turn) else {
+                continue;
+            };
+            vlog!(
+                ELoggingVerbosity::Verbose,
+                "Activate {}'s {:+} ability",
+                card.name,
+                ability.loyalty
+            );
+            self.log_event(current, || LogEvent::Loyalty {
+                card: (&self.zones().get(&Zone::Battlefield).unwrap()[index]).into(),
+                change: ability.loyalty,
+            });
+            crate::effect::apply(self, current, &ability.effect);
+            self.process_events();
+        }
+    }
+
+    // Let the current player's strategy sacrifice their Clues, Food and Blood for their
+    // abilities. Like equipping, each resolves right away.
+    fn activate_sacrifice_abilities(&mut self)
+    {
+        let current = self.current_player_index;
+        let tokens: Vec<CardId> = self
+            .zones()
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .filter(|card| {
+                crate::token::sacrifice_ability(card).is_some_and(|a| !a.effects.is_empty())
+            })
+            .map(|card| card.id)
+            .collect();
+        for id in tokens {
+            let Some(index) = self
+                .zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .position(|card| card.id == id)
+            else {
+                continue;
+            };
+            let card = &self.zones().get(&Zone::Battlefield).unwrap()[index];
+            let ability = crate::token::sacrifice_ability(card).unwrap().clone();
+            if ability.tap && crate::tappable::is_tapped(card) {
+                continue;
+            }
+            // Blood's discard is part of its cost
+            if ability.effects.contains(&crate::effect::Effect::Discard(1))
+                && self.zones().get(&Zone::Hand).unwrap().is_empty()
+            {
+                continue;
+            }
+            let Some(plan) = self.plan_cost(current, &ability.cost) else {
+                continue;
+            };
+            // The token can't tap to pay for itself
+            if plan.iter().any(|&(source, _)| source == index) {
+                continue;
+            }
+            if !self.decide(current, |strategy, game, rng| {
+                strategy.activate_sacrifice(game, current, index, rng)
+            }) {
+                continue;
+            }
+
+            self.pay_cost(current, &ability.cost, &plan);
+            let Some(index) = self
+                .zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .position(|card| card.id == id)
+            else {
+                continue;
+            };
+            vlog!(
+                ELoggingVerbosity::Verbose,
+                "Sacrifice {}",
+                self.zones().get(&Zone::Battlefield).unwrap()[index].name
+            );
+            self.put_into_graveyard(current, &[index]);
+            for effect in ability.effects.iter() {
+                crate::effect::apply(self, current, effect);
+            }
+            self.process_events();
+        }
+    }
+
+    // Which of `attackers` the current player sends at the defender's planeswalkers, keeping
+    // only pairs of a real attacker and planeswalker, each attacker once
+    fn attack_planeswalkers(
+        &mut self,
+        defending_player: usize,
+        attackers: &[usize],
+    ) -> Vec<(usize, usize)>
+    {
+        let current = self.current_player_index;
+        let defending = self.players[defending_player]
+            .zones
+            .get(&Zone::Battlefield)
+            .unwrap();
+        if !defending.iter().any(crate::planeswalker::is_planeswalker) {
+            return Vec::new();
+        }
+        let chosen = self.decide(current, |strategy, game, rng| {
+            strategy.attack_planeswalkers(game, current, defending_player, attackers, rng)
+        });
+        let defending = self.players[defending_player]
+            .zones
+            .get(&Zone::Battlefield)
+            .unwrap();
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for (attacker, planeswalker) in chosen {
+            if attackers.contains(&attacker)
+                && defending
+                    .get(planeswalker)
+                    .is_some_and(crate::planeswalker::is_planeswalker)
+                && !pairs.iter().any(|(a, _)| *a == attacker)
+            {
+                pairs.push((attacker, planeswalker));
+            }
+        }
+        pairs
+    }
+
+    /// Players take turns getting priority, starting with the active player. Whenever they all
+    /// pass in a row the top of the stack resolves; the window closes once they all pass with
+    /// the stack empty.
+    pub fn run_priority(&mut self)
+    {
+        loop {
+            let mut player = self.current_player_index;
+            let mut passes = 0;
+            while passes < self.players.len() {
+                if let Some(watcher) = self
+                    .watcher
+                    .as_ref()
+                    .filter(|_| !self.players[player].has_lost)
+                {
+                    watcher.priority(self, player);
+                }
+                let response = if self.players[player].has_lost {
+                    None
+                } else {
+                    self.decide(player, |strategy, game, rng| {
+                        strategy.respond(game, player, rng)
+                    })
+                };
+                match response {
+                    // The caster keeps priority
+                    Some(cast) => {
+                        self.cast(player, &cast);
+                        passes = 0;
+                    }
+                    None => {
+                        passes += 1;
+                        player = (player + 1) % self.players.len();
+                    }
+                }
+            }
+
+            if self.stack.is_empty() {
+                break;
+            }
+            self.resolve_top();
+        }
+    }
+
+    /// Whether every effect of a spell has something to act on.
+    pub fn has_targets(&self, player: usize, card: &Card) -> bool
+    {
+        crate::effect::spell_effects(card)
+            .iter()
+            .all(|effect| crate::effect::has_target_from(self, player, effect, Some(card)))
+    }
+
+    // Faces `player` might cast from hand, any face of each card, and from exile, adventurers
+    // as their permanent: (zone, index, face, the face as a card)
+    fn spell_faces(&self, player: usize) -> impl Iterator<Item = (Zone, usize, usize, &Card)>
+    {
+        let zones = &self.players[player].zones;
+        let hand = zones
+            .get(&Zone::Hand)
+            .unwrap()
+            .iter()
+            .enumerate()
+            .flat_map(|(index, card)| {
+                crate::faces::faces(card)
+                    .iter()
+                    .enumerate()
+                    .map(move |(face, spell)| (Zone::Hand, index, face, spell))
+            });
+        let adventurers = zones
+            .get(&Zone::Exile)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, card)| crate::faces::on_adventure(card))
+            .map(|(index, card)| (Zone::Exile, index, 0, card));
+        let exiled = zones
+            .get(&Zone::Exile)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(move |(_, card)| {
+                !crate::faces::on_adventure(card) && self.may_play_from_exile(player, card)
+            })
+            .flat_map(|(index, card)| {
+                crate::faces::faces(card)
+                    .iter()
+                    .enumerate()
+                    .map(move |(face, spell)| (Zone::Exile, index, face, spell))
+            });
+        hand.chain(adventurers).chain(exiled)
+    }
+
+    /// Whether `player` may still play `card` from exile this turn.
+    pub fn may_play_from_exile(&self, player: usize, card: &Card) -> bool
+    {
+        self.players[player]
+            .playable_from_exile
+            .iter()
+            .any(|&(id, until)| id == card.id && until >= self.turns)
+    }
+
+    /// Exile indices of the lands `player` may play from exile this turn.
+    pub fn playable_lands_in_exile(&self, player: usize) -> Vec<usize>
+    {
+        self.players[player]
+            .zones
+            .get(&Zone::Exile)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, card)| {
+                crate::faces::can_be_land(card) && self.may_play_from_exile(player, card)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Exile the top card of `player`'s library. Returns its index in their Exile zone.
+    pub fn exile_top(&mut self, player: usize) -> Option<usize>
+    {
+        let top = self.players[player]
+            .zones
+            .get(&Zone::Library)?
+            .len()
+            .checked_sub(1)?;
+        vlog!(
+            ELoggingVerbosity::Verbose,
+            "Exile {}",
+            self.players[player].zones[&Zone::Library][top].name
+        );
+        self.move_card(player, Zone::Library, top, Zone::Exile)
+    }
+
+    /// Spells `player` could cast at sorcery speed, and how they'd pay, taxes included:
+    /// commanders in the command zone (commander tax too), then nonland cards from hand, as any
+    /// of their faces, and adventurers from exile. Auras need a creature to enchant, instants and
+    /// sorceries something to affect, rituals a spell they make affordable, and X spells enough
+    /// mana for X = 1.
+    pub fn castable_spells(&self, player: usize) -> Vec<Cast>
+    {
+        let zones = &self.players[player].zones;
+        let statics = crate::statics::in_force(self);
+        let host = attachment::choose_host(zones.get(&Zone::Battlefield).unwrap());
+        let sources = self.untapped_sources(player);
+        let commanders = zones
+            .get(&Zone::Command)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, card)| {
+                self.plan_with(
+                    player,
+                    &crate::statics::taxed(
+                        &statics,
+                        card,
+                        crate::commander::cost_from_command_zone(card),
+                    ),
+                    &sources,
+                )
+                .map(|plan| Cast {
+                    zone: Zone::Command,
+                    index,
+                    face: 0,
+                    plan,
+                    alternative: false,
+                })
+            });
+        let spells = self
+            .spell_faces(player)
+            .filter(|(_, _, _, card)| {
+                !card.is_type(CardType::Land) && (host.is_some() || !attachment::is_aura(card))
+            })
+            .filter(|(_, _, _, card)| self.can_pay_sacrifice(player, card))
+            .filter(|(_, _, _, card)| {
+                !(card.is_type(CardType::Instant) || card.is_type(CardType::Sorcery))
+                    || self.has_targets(player, card)
+                    || self.ritual_enables(player, card)
+            })
+            .flat_map(|spell| self.casts_of(player, &statics, &sources, spell));
+        commanders.chain(spells).collect()
+    }
+
+    /// Instants and spells with flash in `player`'s hand they could cast now, and how they'd pay.
+    pub fn castable_instants(&self, player: usize) -> Vec<Cast>
+    {
+        let statics = crate::statics::in_force(self);
+        let sources = self.untapped_sources(player);
+        let host =
+            attachment::choose_host(self.players[player].zones.get(&Zone::Battlefield).unwrap());
+        self.spell_faces(player)
+            .filter(|(_, _, _, card)| {
+                crate::keyword::instant_speed(card)
+                    && self.has_targets(player, card)
+                    && self.can_pay_sacrifice(player, card)
+                    && (host.is_some() || !attachment::is_aura(card))
+            })
+            .flat_map(|spell| self.casts_of(player, &statics, &sources, spell))
+            .collect()
+    }
+
+    // The ways `player` could pay for one of their `spell_faces`: its taxed mana cost (with
+    // X = 1), then its alternative cost
+    fn casts_of(
+        &self,
+        player: usize,
+        statics: &[(usize, &crate::statics::StaticEffect)],
+        sources: &[(usize, Vec<ManaColor>)],
+        (zone, index, face, card): (Zone, usize, usize, &Card),
+    ) -> Vec<Cast>
+    {
+        let mut casts: Vec<Cast> = self
+            .plan_with(
+                player,
+                &crate::statics::taxed(statics, card, card.cost.with_x(1)),
+                sources,
+            )
+            .map(|plan| Cast {
+                zone,
+                index,
+                face,
+                plan,
+                alternative: false,
+            })
+            .into_iter()
+            .collect();
+        if let Some(alternative) = crate::effect::alternative_cost(card)
+            && self.players[player].life >= alternative.life as i32
+            && alternative.pitch.is_none_or(|color| {
+                self.players[player].zones[&Zone::Hand]
+                    .iter()
+                    .enumerate()
+                    .any(|(i, other)| {
+                        (zone, i) != (Zone::Hand, index) && other.cost.colors().contains(&color)
+                    })
+            })
+            && let Some(plan) = self.plan_with(
+                player,
+                &crate::statics::taxed(statics, card, alternative.mana),
+                sources,
+            )
+        {
+            casts.push(Cast {
+                zone,
+                index,
+                face,
+                plan,
+                alternative: true,
+            });
+        }
+        casts
+    }
+
+    /// Mana `player` could spend right now: their pool plus what untapped sources make.
+    pub fn available_mana(&self, player: usize) -> u32
+    {
+        self.untapped_sources(player).len() as u32 + self.players[player].mana_pool.total()
+    }
+
+    /// The strategy making `player`'s decisions.
+    pub fn strategy(&self, player: usize) -> &dyn PlayerStrategy
+    {
+        static FALLBACK: OnceLock<CurveOut> = OnceLock::new();
+        match self.strategies.get(player) {
+            Some(strategy) => strategy.as_ref(),
+            None => FALLBACK.get_or_init(CurveOut::default),
+        }
+    }
+
+    // Ask `player`'s strategy for a decision, lending it the game's RNG
+    pub(crate) fn decide<T>(
+        &mut self,
+        player: usize,
+        decision: impl FnOnce(&dyn PlayerStrategy, &GameState, &mut ChaCha8Rng) -> T,
+    ) -> T
+    {
+        let mut rng = self.rng.clone();
+        let choice = decision(self.strategy(player), self, &mut rng);
+        self.rng = rng;
+        choice
+    }
+
+    // Resolve the spell on top of the stack: instants and sorceries apply their effects and go
+    // to the graveyard, everything else enters the battlefield. Targets are picked on resolution.
+    fn resolve_top(&mut self)
+    {
+        let Some(controller) = self.stack.pop() else {
+            return;
+        };
+        let index = self.players[controller]
+            .zones
+            .get(&Zone::Stack)
+            .unwrap()
+            .len()
+            - 1;
+        let card = self.players[controller].zones.get(&Zone::Stack).unwrap()[index].clone();
+        vlog!(ELoggingVerbosity::Verbose, "{} resolves", card.name);
+
+        // An Aura whose creature is gone has nothing to enchant
+        let host_missing = attachment::is_aura(&card)
+            && !attachment::attached_to(&card).is_some_and(|host| {
+                self.players[controller]
+                    .zones
+                    .get(&Zone::Battlefield)
+                    .unwrap()
+                    .iter()
+                    .any(|c| c.id == host)
+            });
+
+        if card.is_type(CardType::Instant) || card.is_type(CardType::Sorcery) {
+            let x = card
+                .fragment::<crate::card::EffectFragment>()
+                .map_or(0, |effects| effects.x);
+            self.resolving = Some(card.id);
+            for effect in crate::effect::spell_effects(&card) {
+                crate::effect::apply(self, controller, &effect.with_x(x));
+            }
+            #[cfg(feature = "scripting")]
+            if let Some(script) = crate::script::on_cast(&card) {
+                for effect in crate::script::run(self, controller, script) {
+                    crate::effect::apply(self, controller, &effect);
+                }
+            }
+            self.resolving = None;
+            if crate::faces::goes_on_adventure(&card) {
+                if let Some(index) = self.move_card(controller, Zone::Stack, index, Zone::Exile) {
+                    crate::faces::send_on_adventure(
+                        &mut self.players[controller]
+                            .zones
+                            .get_mut(&Zone::Exile)
+                            .unwrap()[index],
+                    );
+                }
+            } else {
+                self.move_card(controller, Zone::Stack, index, Zone::Graveyard);
+            }
+        } else if host_missing {
+            self.move_card(controller, Zone::Stack, index, Zone::Graveyard);
+        } else if let Some(index) =
+            self.move_card(controller, Zone::Stack, index, Zone::Battlefield)
+        {
+            // Newly cast creatures have summoning sickness
+            let Player {
+                zones, components, ..
+            } = &mut self.players[controller];
+            let battlefield = zones.get_mut(&Zone::Battlefield).unwrap();
+            crate::creature::set_summoning_sickness(&mut battlefield[index], true);
+            if components.matches(battlefield) {
+                components.refresh_row(index, &battlefield[index]);
+            }
+        }
+        self.process_events();
+    }
+
+    /// Counter the topmost spell on the stack that isn't `controller`'s. Returns whether there was one.
+    pub fn counter_spell(&mut self, controller: usize) -> bool
+    {
+        let Some(position) = self.stack.iter().rposition(|player| *player != controller) else {
+            return false;
+        };
+        let player = self.stack.remove(position);
+        let index = self.stack[..position]
+            .iter()
+            .filter(|p| **p == player)
+            .count();
+
+        if let Some(card) = self.players[player]
+            .zones
+            .get(&Zone::Stack)
+            .and_then(|cards| cards.get(index))
+        {
+            vlog!(ELoggingVerbosity::Verbose, "{} is countered", card.name);
+        }
+        self.move_card(player, Zone::Stack, index, Zone::Graveyard)
+            .is_some()
+    }
+
+    /// The topmost instant or sorcery spell on the stack: its controller, and its index in their
+    /// Stack zone.
+    pub fn spell_to_copy(&self) -> Option<(usize, usize)>
+    {
+        self.stack
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(position, &player)| {
+                (
+                    player,
+                    self.stack[..position]
+                        .iter()
+                        .filter(|p| **p == player)
+                        .count(),
+                )
+            })
+            .find(|&(player, index)| {
+                self.players[player]
+                    .zones
+                    .get(&Zone::Stack)
+                    .and_then(|cards| cards.get(index))
+                    .is_some_and(|card| {
+                        card.is_type(CardType::Instant) || card.is_type(CardType::Sorcery)
+                    })
+            })
+    }
+
+    /// Put a copy of the topmost instant or sorcery spell on the stack, with the same X, on top of
+    /// it as `controller`'s. Returns whether there was one to copy.
+    pub fn copy_spell(&mut self, controller: usize) -> bool
+    {
+        let Some((player, index)) = self.spell_to_copy() else {
+            return false;
+        };
+        let mut copy = crate::token::copy_of(&self.players[player].zones[&Zone::Stack][index]);
+        copy.id = self.new_card_id();
+        vlog!(ELoggingVerbosity::Verbose, "{} is copied", copy.name);
+        self.players[controller]
+            .zones
+            .get_mut(&Zone::Stack)
+            .unwrap()
+            .push(copy);
+        self.stack.push(controller);
+        true
+    }
+
+    // Cleanup: the active player discards down to the maximum hand size
+    fn discard_to_hand_size(&mut self)
+    {
+        let Some(max) = self.rules.max_hand_size else {
+            return;
+        };
+        let current = self.current_player_index;
+        let excess = self
+            .zones()
+            .get(&Zone::Hand)
+            .unwrap()
+            .len()
+            .saturating_sub(max as usize);
+        if excess == 0 {
+            return;
+        }
+        self.discard(current, excess);
+    }
+
+    /// `player` discards `count` cards of their strategy's choice, or their whole hand if it's
+    /// smaller.
+    pub fn discard(&mut self, player: usize, count: usize)
+    {
+        let hand_size = self.players[player]
+            .zones
+            .get(&Zone::Hand)
+            .map_or(0, Vec::len);
+        let count = count.min(hand_size);
+        if count == 0 {
+            return;
+        }
+        let mut discards = self.decide(player, |strategy, game, rng| {
+            strategy.discards(
+                game.players[player].zones.get(&Zone::Hand).unwrap(),
+                count,
+                rng,
+            )
+        });
+        discards.retain(|&index| index < hand_size);
+        discards.sort_unstable_by(|a, b| b.cmp(a));
+        discards.dedup();
+        for index in discards {
+            self.log_event(player, || LogEvent::Discard {
+                card: (&self.players[player].zones.get(&Zone::Hand).unwrap()[index]).into(),
+            });
+            self.move_card(player, Zone::Hand, index, Zone::Graveyard);
+        }
+    }
+
+    pub fn shuffle_library(&mut self, player: usize)
+    {
+        if let Some(library) = self.players[player].zones.get_mut(&Zone::Library) {
+            library.shuffle(&mut self.rng);
+        }
+    }
+
+    /// Whether casting a ritual (a spell that only adds mana) lets `player` cast another card
+    /// from hand that they can't afford otherwise.
+    pub fn ritual_enables(&self, player: usize, ritual: &Card) -> bool
+    {
+        use crate::effect::Effect;
+
+        let effects = crate::effect::spell_effects(ritual);
+        if effects.is_empty() || !effects.iter().all(|e| matches!(e, Effect::AddMana(_))) {
+            return false;
+        }
+
+        let added: u32 = effects
+            .iter()
+            .map(|e| {
+                if let Effect::AddMana(mana) = e {
+                    mana.mana_value()
+                } else {
+                    0
+                }
+            })
+            .sum();
+        let available = self.available_mana(player);
+        let after = (available + added).saturating_sub(ritual.cost.mana_value());
+        self.players[player]
+            .zones
+            .get(&Zone::Hand)
+            .unwrap()
+            .iter()
+            .filter(|card| !card.is_type(CardType::Land) && !std::ptr::eq(*card, ritual))
+            .any(|card| {
+                card.cost.mana_value() > available.saturating_sub(ritual.cost.mana_value())
+                    && card.cost.mana_value() <= after
+                    && self.plan_cast(player, card).is_none()
+            })
+    }
+
+    /// Put a copy of `token` onto `player`'s battlefield as a new card. Returns its battlefield index.
+    pub fn create_token(&mut self, player: usize, token: &Card) -> usize
+    {
+        let mut card = token.clone();
+        card.id = self.new_card_id();
+        crate::creature::set_summoning_sickness(&mut card, true);
+        crate::planeswalker::enter(&mut card);
+        let timestamp = self.timestamp();
+        crate::statics::enter(&mut card, timestamp);
+
+        let (id, counters) = (card.id, replacement::enters_with_counters(&card));
+        let Player {
+            zones, components, ..
+        } = &mut self.players[player];
+        let battlefield = zones.get_mut(&Zone::Battlefield).unwrap();
+        if components.matches(battlefield) {
+            components.push(&card);
+        }
+        battlefield.push(card);
+        let index = battlefield.len() - 1;
+        if counters > 0 {
+            self.put_counters(player, index, counters);
+        }
+        self.events
+            .emit(GameEvent::CardEntered { player, card: id });
+        index
+    }
+
+    /// Resolve the triggered abilities of every pending event, including events those abilities
+    /// cause, in the order they happened, and check state-based actions until the game settles.
+    pub fn process_events(&mut self)
+    {
+        loop {
+            while let Some(event) = self.events.pop() {
+                for (controller, source, name, effect) in crate::trigger::collect(self, &event) {
+                    vlog!(
+                        ELoggingVerbosity::Verbose,
+                        "{} triggers: {:?}",
+                        name,
+                        effect
+                    );
+                    let resolving = self.resolving.replace(source);
+                    crate::effect::apply(self, controller, &effect);
+                    self.resolving = resolving;
+                }
+            }
+
+            crate::layers::apply_statics(self);
+            let statics = crate::statics::in_force(self);
+            let anthems: Vec<attachment::AttachmentBonus> = (0..self.players.len())
+                .map(|player| crate::statics::creature_bonus(&statics, player))
+                .collect();
+            for (player, shared) in self.players.iter_mut().zip(anthems.iter()) {
+                attachment::apply_bonuses(
+                    player.zones.get_mut(&Zone::Battlefield).unwrap(),
+                    shared,
+                );
+            }
+
+            // Creatures dying here may trigger more abilities
+            self.check_state_based_actions();
+            self.refresh_components();
+            if self.events.is_empty() {
+                break;
+            }
+        }
+    }
+
+    /// One pass of state-based actions: players at 0 life or who drew from an empty library lose,
+    /// tokens that left the battlefield cease to exist, creatures with lethal damage or no
+    /// toughness die, planeswalkers without loyalty go to the graveyard, and attachments whose
+    /// creature is gone fall off (Auras go to the graveyard).
+    pub fn check_state_based_actions(&mut self)
+    {
+        for index in 0..self.players.len() {
+            let player = &mut self.players[index];
+            if player.has_lost {
+                continue;
+            }
+            if let Some(condition) =
+                crate::wincondition::loss_condition(player, &self.rules.win_conditions)
+            {
+                vlog!(
+                    ELoggingVerbosity::Verbose,
+                    "A player loses to {}",
+                    condition
+                );
+                player.has_lost = true;
+                player.lost_to = Some(condition);
+                self.log_event(index, || LogEvent::Loss {
+                    condition: condition.to_string(),
+                });
+            }
+        }
+
+        // Commanders that died or were exiled (by an earlier pass) go back to the command zone
+        for player in 0..self.players.len() {
+            for zone in [Zone::Graveyard, Zone::Exile] {
+                while let Some(index) = self.players[player]
+                    .zones
+                    .get(&zone)
+                    .and_then(|cards| cards.iter().position(crate::commander::is_commander))
+                {
+                    self.move_card(player, zone, index, Zone::Command);
+                }
+            }
+        }
+
+        // Before anything dies in this pass, so a token's dies trigger still finds it
+        for player in self.players.iter_mut() {
+            for (zone, cards) in player
+                .zones
+                .iter_mut()
+                .filter(|(zone, _)| **zone != Zone::Battlefield && **zone != Zone::Stack)
+            {
+                if cards.iter().any(crate::token::is_token) {
+                    vlog!(
+                        ELoggingVerbosity::Verbose,
+                        "Tokens in {:?} cease to exist",
+                        zone
+                    );
+                    cards.retain(|card| !crate::token::is_token(card));
+                }
+            }
+        }
+
+        for player in 0..self.players.len() {
+            let battlefield = self.players[player]
+                .zones
+                .get_mut(&Zone::Battlefield)
+                .unwrap();
+            let creatures: Vec<CardId> = battlefield
+                .iter()
+                .filter(|c| crate::creature::is_creature(c))
+                .map(|c| c.id)
+                .collect();
+            for card in battlefield.iter_mut() {
+                if attachment::is_equipment(card)
+                    && attachment::attached_to(card).is_some_and(|host| !creatures.contains(&host))
+                {
+                    attachment::attach(card, None);
+                }
+            }
+
+            // Lethal damage destroys a creature, which regeneration and indestructible replace;
+            // no toughness, no loyalty or nothing to enchant just puts a permanent into the graveyard
+            let no_toughness = |card: &Card| {
+                crate::creature::effective_stats(card).is_some_and(|s| s.toughness == 0)
+            };
+            let (mut destroyed, dying): (Vec<usize>, Vec<usize>) = battlefield
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| {
+                    (crate::creature::is_creature(card)
+                        && crate::creature::is_lethally_damaged(card))
+                        || crate::planeswalker::is_out_of_loyalty(card)
+                        || (attachment::is_aura(card)
+                            && !attachment::attached_to(card)
+                                .is_some_and(|host| creatures.contains(&host)))
+                })
+                .map(|(i, _)| i)
+                .partition(|i| {
+                    crate::creature::is_creature(&battlefield[*i])
+                        && !no_toughness(&battlefield[*i])
+                });
+            destroyed.retain(|&index| !self.survives_destruction(player, index));
+            self.put_into_graveyard(player, &[destroyed, dying].concat());
+        }
+    }
+
+    // Whether the permanent at `index` of a player's battlefield is indestructible, or uses a
+    // regeneration shield to be tapped, have its damage removed and leave combat instead
+    fn survives_destruction(&mut self, player: usize, index: usize) -> bool
+    {
+        let card = &mut self.players[player]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()[index];
+        if crate::keyword::is_indestructible(card) {
+            return true;
+        }
+        if !crate::creature::regenerate(card) {
+            return false;
+        }
+        vlog!(ELoggingVerbosity::Verbose, "{} regenerates", card.name);
+        self.set_tapped(player, index, true);
+        let combat = &mut self.combat;
+        if player == self.current_player_index {
+            combat.attackers.retain(|a| *a != index);
+            combat.blocks.retain(|(_, a)| *a != index);
+            combat.planeswalkers.retain(|(a, _)| *a != index);
+        } else if player == combat.defending_player {
+            combat.blocks.retain(|(b, _)| *b != index);
+        }
+        true
+    }
+
+    /// Destroy the permanents at `indices` of a player's battlefield: they go to their graveyard
+    /// unless indestructible or regenerated.
+    pub fn destroy_permanents(&mut self, player: usize, indices: &[usize])
+    {
+        let indices: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|&index| !self.survives_destruction(player, index))
+            .collect();
+        self.put_into_graveyard(player, &indices);
+    }
+
+    /// `player` sacrifices `count` creatures of their strategy's choice, or every one they have if
+    /// that's fewer. Sacrificing isn't destroying: indestructible and regeneration don't help.
+    pub fn sacrifice(&mut self, player: usize, count: usize)
+    {
+        let battlefield = self.players[player]
+            .zones
+            .get(&Zone::Battlefield)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let creatures: Vec<usize> = (0..battlefield.len())
+            .filter(|&i| crate::creature::is_creature(&battlefield[i]))
+            .collect();
+        let count = count.min(creatures.len());
+        if count == 0 {
+            return;
+        }
+        let mut chosen = Vec::new();
+        for index in self.decide(player, |strategy, game, rng| {
+            strategy.sacrifice(game, player, count, rng)
+        }) {
+            if creatures.contains(&index) && !chosen.contains(&index) && chosen.len() < count {
+                chosen.push(index);
+            }
+        }
+        // Make up for picks that weren't the player's creatures
+        let battlefield = &self.players[player].zones[&Zone::Battlefield];
+        for index in crate::strategy::sacrifice_fodder(battlefield, count) {
+            if chosen.len() < count && !chosen.contains(&index) {
+                chosen.push(index);
+            }
+        }
+        for &index in chosen.iter() {
+            vlog!(
+                ELoggingVerbosity::Verbose,
+                "{} is sacrificed",
+                battlefield[index].name
+            );
+        }
+        self.put_into_graveyard(player, &chosen);
+    }
+
+    /// Whether `player` has the creatures to sacrifice for `card`'s additional cost.
+    pub fn can_pay_sacrifice(&self, player: usize, card: &Card) -> bool
+    {
+        let creatures = self.players[player]
+            .zones
+            .get(&Zone::Battlefield)
+            .into_iter()
+            .flatten()
+            .filter(|c| crate::creature::is_creature(c))
+            .count();
+        crate::effect::sacrifice_cost(card) as usize <= creatures
+    }
+
+    /// Move the permanents at `indices` of a player's battlefield to their graveyard.
+    pub fn put_into_graveyard(&mut self, player: usize, indices: &[usize])
+    {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+
+        for index in indices {
+            vlog!(
+                ELoggingVerbosity::Verbose,
+                "{} dies",
+                self.players[player].zones.get(&Zone::Battlefield).unwrap()[index].name
+            );
+            self.move_card(player, Zone::Battlefield, index, Zone::Graveyard);
+        }
+    }
+
+    pub fn is_game_over(&self) -> bool
+    {
+        self.step == GameStep::GameOver
+    }
+
+    /// The only player left standing once the game is over.
+    pub fn winner(&self) -> Option<usize>
+    {
+        if !self.is_game_over() {
+            return None;
+        }
+
+        let mut remaining = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.has_lost)
+            .map(|(i, _)| i);
+        match (remaining.next(), remaining.next()) {
+            (Some(winner), None) => Some(winner),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self, verbose: bool)
+    {
+        println!("Turn: {}", self.turns);
+        println!("Step: {:?}", self.step);
+        println!("Life: {}", self.life());
+
+        if verbose {
+            self.describe_verbose();
+        } else {
+            self.describe_summary();
+        }
+    }
+
+    pub fn describe_summary(&self)
+    {
+        // Print only zone counts
+        for zone in &[
+            Zone::Hand,
+            Zone::Battlefield,
+            Zone::Library,
+            Zone::Graveyard,
+            Zone::Exile,
+        ] {
+            let cards = self.zones().get(zone).unwrap();
+            println!("{:?}: {} cards", zone, cards.len());
+        }
+    }
+
+    pub fn describe_verbose(&self)
+    {
+        for zone in &[
+            Zone::Hand,
+            Zone::Battlefield,
+            Zone::Library,
+            Zone::Graveyard,
+        ] {
+            let cards = self.zones().get(zone).unwrap();
+            if cards.is_empty() && (*zone == Zone::Battlefield || *zone == Zone::Graveyard) {
+                continue;
+            }
+
+            println!("{:?}: ({} cards)", zone, cards.len());
+
+            match zone {
+                Zone::Library => {
+                    // Show library cards grouped by count
+                    let mut card_groups: HashMap<String, u32> = HashMap::new();
+                    for card in cards.iter() {
+                        *card_groups.entry(card.name.clone()).or_insert(0) += 1;
+                    }
+
+                    for (name, count) in card_groups.iter() {
+                        println!("  {} x{}", name, count);
+                    }
+                }
+                Zone::Hand => {
+                    // Print hand cards grouped by count in an inline list
+                    let mut groups: HashMap<String, u32> = HashMap::new();
+                    for card in cards.iter() {
+                        *groups.entry(card.name.clone()).or_insert(0) += 1;
+                    }
 
                     let mut items: Vec<(String, u32)> = groups.into_iter().collect();
                     items.sort_by(|a, b| a.0.cmp(&b.0));
 
                     let mut parts: Vec<String> = Vec::new();
-                    for (name, count) in items.iter()
-                    {
-                        if *count > 1
-                        {
+                    for (name, count) in items.iter() {
+                        if *count > 1 {
                             parts.push(format!("{} x{}", name, count));
-                        }
-                        else
-                        {
+                        } else {
                             parts.push(name.clone());
                         }
                     }
 
-                    if !parts.is_empty()
-                    {
+                    if !parts.is_empty() {
                         println!("  {}", parts.join(", "));
                     }
                 }
-                Zone::Battlefield =>
-                {
+                Zone::Battlefield => {
                     // Group identical cards together with counts (use owned String keys)
-                    let mut card_groups: HashMap<String, (String, u8, u8, bool, bool, u32)> = HashMap::new();
-                    for card in cards.iter()
-                    {
-                        let power = crate::creature::creature_stats(card).map(|s| s.power).unwrap_or(0);
-                        let toughness = crate::creature::creature_stats(card).map(|s| s.toughness).unwrap_or(0);
+                    let mut card_groups: HashMap<String, (String, u8, u8, bool, bool, u32)> =
+                        HashMap::new();
+                    for card in cards.iter() {
+                        let stats = crate::creature::effective_stats(card);
+                        let power = stats.map(|s| s.power).unwrap_or(0);
+                        let toughness = stats.map(|s| s.toughness).unwrap_or(0);
                         let is_creature = crate::creature::is_creature(card);
                         let is_sick = crate::creature::has_summoning_sickness(card);
 
-                        let uniquename = if is_creature && is_sick
-                        {
-                            format!("{} (sick)", card.name)
-                        }
-                        else
-                        {
-                            card.name.clone()
+                        // Only permanents with creature stats group by power/toughness
+                        let uniquename = match stats {
+                            Some(_) if is_creature && is_sick => {
+                                format!("{} {}/{} (sick)", card.name, power, toughness)
+                            }
+                            Some(_) => format!("{} {}/{}", card.name, power, toughness),
+                            None => card.name.clone(),
                         };
 
-                        card_groups.entry(uniquename)
+                        card_groups
+                            .entry(uniquename)
                             .and_modify(|(_, _, _, _, _, count)| *count += 1)
-                            .or_insert((card.name.clone(), power, toughness, is_creature, is_sick, 1));
+                            .or_insert((
+                                self.paint_permanent(self.current_player_index, card),
+                                power,
+                                toughness,
+                                is_creature,
+                                is_sick,
+                                1,
+                            ));
                     }
 
-                    for (_uniquename, (name, power, toughness, is_creature, is_sick, count)) in card_groups.iter()
+                    for (_uniquename, (name, power, toughness, is_creature, is_sick, count)) in
+                        card_groups.iter()
                     {
-                        if *is_creature
-                        {
-                            if *count > 1
-                            {
-                                println!("  {}: {}/{} x{} ({})", name, power, toughness, count, is_sick.then(|| "sick").unwrap_or("ready"));
+                        if *is_creature {
+                            if *count > 1 {
+                                println!(
+                                    "  {}: {}/{} x{} ({})",
+                                    name,
+                                    power,
+                                    toughness,
+                                    count,
+                                    is_sick.then(|| "sick").unwrap_or("ready")
+                                );
+                            } else {
+                                println!(
+                                    "  {}: {}/{} ({})",
+                                    name,
+                                    power,
+                                    toughness,
+                                    is_sick.then(|| "sick").unwrap_or("ready")
+                                );
                             }
-                            else
-                            {
-                                println!("  {}: {}/{} ({})", name, power, toughness, is_sick.then(|| "sick").unwrap_or("ready"));
-                            }
-                        }
-                        else
-                        {
-                            if *count > 1
-                            {
+                        } else {
+                            if *count > 1 {
                                 println!("  {} x{}", name, count);
-                            }
-                            else
-                            {
+                            } else {
                                 println!("  {}", name);
                             }
                         }
@@ -494,52 +2848,734 @@ impl GameState
             }
         }
     }
-}
 
-#[cfg(test)]
-mod tests
-{
-    use super::*;
-    use crate::card::{grizzly_bears, forest};
-    use crate::creature;
+    /// Both players' side of the table: life, how many cards they hold, the mana they have left
+    /// and every permanent, with creatures' current power/toughness and whether they're tapped or
+    /// summoning sick.
+    pub fn board(&self) -> String
+    {
+        let mut lines = vec![format!("Turn {}, {:?}", self.turns, self.step)];
+        for (index, player) in self.players.iter().enumerate() {
+            let count = |zone: Zone| player.zones.get(&zone).map_or(0, Vec::len);
+            let battlefield = player
+                .zones
+                .get(&Zone::Battlefield)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let lands: Vec<&Card> = battlefield
+                .iter()
+                .filter(|c| c.is_type(CardType::Land))
+                .collect();
+            let untapped_lands = lands
+                .iter()
+                .filter(|c| !crate::tappable::is_tapped(c))
+                .count();
+
+            let name = paint(format!("Player {}", index + 1), color::player(index));
+            let mut header = format!(
+                "{}{}: {} life",
+                name,
+                if index == self.current_player_index {
+                    " (active)"
+                } else {
+                    ""
+                },
+                player.life
+            );
+            for kind in PlayerCounter::ALL
+                .into_iter()
+                .filter(|&kind| player.counters(kind) > 0)
+            {
+                header += &format!(", {} {}", player.counters(kind), kind);
+            }
+            header += &format!(
+                ", {} in hand, {} in library, {} in graveyard, {} of {} lands untapped, {} mana available",
+                count(Zone::Hand),
+                count(Zone::Library),
+                count(Zone::Graveyard),
+                untapped_lands,
+                lands.len(),
+                self.available_mana(index)
+            );
+            if player.has_lost {
+                header += " (lost)";
+            }
+            lines.push(header);
+
+            for card in battlefield.iter() {
+                let mut line = format!("  {}", self.paint_permanent(index, card));
+                if let Some(stats) = crate::creature::effective_stats(card) {
+                    line += &format!(" {}/{}", stats.power, stats.toughness);
+                    let damage = crate::creature::marked_damage(card);
+                    if damage > 0 {
+                        line += &format!(" {}", paint(format!("({} damage)", damage), Color::Red));
+                    }
+                }
+                let mut status = Vec::new();
+                if crate::tappable::is_tapped(card) {
+                    status.push("tapped");
+                }
+                if crate::creature::is_creature(card)
+                    && crate::creature::has_summoning_sickness(card)
+                {
+                    status.push("sick");
+                }
+                if !status.is_empty() {
+                    line += &format!(" {}", paint(format!("[{}]", status.join(", ")), Color::Dim));
+                }
+                lines.push(line);
+            }
+            if battlefield.is_empty() {
+                lines.push(String::from("  (no permanents)"));
+            }
+        }
+        lines.join("\n")
+    }
+
+    // A permanent's name colored for the board: lands green, creatures in their controller's color
+    fn paint_permanent(&self, player: usize, card: &Card) -> String
+    {
+        if crate::creature::is_creature(card) {
+            paint(&card.name, color::player(player))
+        } else if card.is_type(CardType::Land) {
+            paint(&card.name, Color::Green)
+        } else {
+            card.name.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{CardFragmentKind, forest, grizzly_bears};
+    use crate::creature;
+
+    // Build a two-player game where player 0 owns the given zones and player 1 is an idle opponent
+    fn game_with_zones(mut zones: HashMap<Zone, Vec<Card>>, step: GameStep) -> GameState
+    {
+        for zone in Zone::ALL {
+            zones.entry(zone).or_default();
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut opponent = Player::new(&Deck::new(Vec::new()), &mut rng);
+        opponent
+            .zones
+            .get_mut(&Zone::Library)
+            .unwrap()
+            .extend((0..10).map(|_| forest()));
+
+        let mut gs = GameState {
+            players: vec![
+                Player {
+                    life: 20,
+                    zones,
+                    stats: PlayerStats::default(),
+                    mana_pool: ManaPool::default(),
+                    has_lost: false,
+                    drew_from_empty_library: false,
+                    counters: HashMap::new(),
+                    commander_damage: Vec::new(),
+                    playable_from_exile: Vec::new(),
+                    turn_started_at: 0,
+                    lost_to: None,
+                    components: Components::default(),
+                },
+                opponent,
+            ],
+            current_player_index: 0,
+            turns: 0,
+            step,
+            seed: 0,
+            rng,
+            combat: Combat::default(),
+            events: EventBus::default(),
+            stack: Vec::new(),
+            next_card_id: 0,
+            next_timestamp: 0,
+            replacing_draw: false,
+            spells_this_turn: 0,
+            resolving: None,
+            rules: GameRules::default(),
+            strategies: Vec::new(),
+            log: None,
+            watcher: None,
+        };
+        gs.assign_card_ids();
+        gs
+    }
+
+    // A card from the built-in card list
+    fn card(name: &str) -> Card
+    {
+        crate::card::card_by_name(name).unwrap()
+    }
+
+    // Step through the combat steps of the current turn
+    fn run_combat(gs: &mut GameState)
+    {
+        while matches!(
+            gs.step,
+            GameStep::DeclareAttackers | GameStep::DeclareBlockers | GameStep::CombatDamage
+        ) {
+            gs.step();
+        }
+    }
+
+    #[test]
+    fn creature_without_sickness_deals_damage()
+    {
+        let mut battlefield = Vec::new();
+        let mut g = grizzly_bears();
+        creature::add_creature_fragment(&mut g, 2, 2);
+        creature::set_summoning_sickness(&mut g, false);
+        battlefield.push(g);
+
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, battlefield);
+        zones.insert(Zone::Hand, Vec::new());
+        zones.insert(Zone::Library, Vec::new());
+        zones.insert(Zone::Graveyard, Vec::new());
+
+        let mut gs = game_with_zones(zones, GameStep::DeclareAttackers);
+        run_combat(&mut gs);
+        assert_eq!(gs.players[1].life, 18);
+        assert_eq!(gs.winner(), None);
+    }
+
+    #[test]
+    fn creature_with_sickness_does_not_deal_damage()
+    {
+        let mut battlefield = Vec::new();
+        let mut g = grizzly_bears();
+        creature::set_summoning_sickness(&mut g, true);
+        battlefield.push(g);
+
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, battlefield);
+        zones.insert(Zone::Hand, Vec::new());
+        zones.insert(Zone::Library, Vec::new());
+        zones.insert(Zone::Graveyard, Vec::new());
+
+        let mut gs = game_with_zones(zones, GameStep::DeclareAttackers);
+        run_combat(&mut gs);
+        assert_eq!(gs.players[1].life, 20);
+    }
+
+    #[test]
+    fn blocked_attackers_trade_with_blockers()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![grizzly_bears(), card("Craw Wurm")]);
+
+        let mut gs = game_with_zones(zones, GameStep::DeclareAttackers);
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(grizzly_bears());
+        gs.players[1].life = 8;
+        run_combat(&mut gs);
+
+        // Facing lethal, the opponent chumps the Wurm and takes 2 from the Bears
+        assert_eq!(gs.players[1].life, 6);
+        assert_eq!(gs.players[1].zones.get(&Zone::Graveyard).unwrap().len(), 1);
+        assert_eq!(gs.zones().get(&Zone::Battlefield).unwrap().len(), 2);
+        assert_eq!(gs.step, GameStep::EndTurn);
+    }
+
+    #[test]
+    fn enter_attack_and_dies_triggers_resolve()
+    {
+        use crate::trigger::{TriggerCondition, TriggeredAbility};
+
+        let mut raider = grizzly_bears();
+        crate::trigger::add_triggered_ability(
+            &mut raider,
+            TriggeredAbility {
+                condition: TriggerCondition::Attacks,
+                effect: crate::effect::Effect::DamageOpponents(1),
+            },
+        );
+
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Lone Missionary")]);
+        zones.insert(
+            Zone::Battlefield,
+            vec![
+                card("Plains"),
+                card("Plains"),
+                raider,
+                card("Goblin Arsonist"),
+            ],
+        );
+
+        // Casting the Missionary gains 4 life, attacking with the raider deals 1 on top of combat damage
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        assert_eq!(gs.players[0].life, 24);
+        run_combat(&mut gs);
+        assert_eq!(gs.players[1].life, 20 - 1 - 2 - 1);
+
+        // The Arsonist pings the opponent on its way to the graveyard
+        let arsonist = gs
+            .zones()
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .position(|c| c.name == "Goblin Arsonist")
+            .unwrap();
+        gs.destroy_permanents(0, &[arsonist]);
+        gs.process_events();
+        assert_eq!(gs.players[1].life, 15);
+        assert!(gs.events.is_empty());
+    }
+
+    #[test]
+    fn instants_respond_with_priority()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![grizzly_bears()]);
+        zones.insert(Zone::Battlefield, vec![forest(), forest()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        let opponent = &mut gs.players[1].zones;
+        opponent.insert(
+            Zone::Hand,
+            vec![card("Counterspell"), card("Lightning Bolt")],
+        );
+        opponent.insert(
+            Zone::Battlefield,
+            ["Island", "Island", "Mountain"]
+                .into_iter()
+                .map(card)
+                .collect(),
+        );
+
+        // The Bears are countered while on the stack
+        gs.step();
+        assert!(
+            gs.zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .all(|c| !crate::creature::is_creature(c))
+        );
+        assert_eq!(
+            gs.zones().get(&Zone::Graveyard).unwrap()[0].name,
+            "Grizzly Bears"
+        );
+        assert!(gs.stack.is_empty());
+
+        // The Bolt waits for the end of the turn
+        run_combat(&mut gs);
+        assert_eq!(gs.players[0].life, 20);
+        assert_eq!(gs.step, GameStep::EndTurn);
+        gs.step();
+        assert_eq!(gs.players[0].life, 17);
+        assert_eq!(gs.players[1].zones.get(&Zone::Graveyard).unwrap().len(), 2);
+        assert!(gs.players[1].zones.get(&Zone::Stack).unwrap().is_empty());
+    }
+
+    #[test]
+    fn state_based_actions_follow_marked_damage()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![grizzly_bears(), grizzly_bears()]);
+        let mut gs = game_with_zones(zones, GameStep::EndTurn);
+
+        // Damage short of lethal wears off in the cleanup
+        let battlefield = gs.zones_mut().get_mut(&Zone::Battlefield).unwrap();
+        creature::deal_damage(&mut battlefield[0], 2, false);
+        creature::deal_damage(&mut battlefield[1], 1, false);
+        gs.process_events();
+        assert_eq!(gs.zones().get(&Zone::Battlefield).unwrap().len(), 1);
+        gs.step();
+        assert_eq!(
+            creature::marked_damage(&gs.players[0].zones.get(&Zone::Battlefield).unwrap()[0]),
+            0
+        );
+
+        // Drawing from an empty library loses once state-based actions are checked
+        assert!(!gs.draw_card(0));
+        assert!(!gs.players[0].has_lost);
+        gs.step();
+        assert_eq!(gs.winner(), Some(1));
+    }
+
+    #[test]
+    fn attachments_boost_their_creature()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Bonesplitter"), card("Rancor")]);
+        zones.insert(
+            Zone::Battlefield,
+            vec![grizzly_bears(), forest(), forest(), forest()],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+
+        // Cast both, then equip the Bonesplitter with the last land
+        gs.step();
+        let bears = &gs.zones().get(&Zone::Battlefield).unwrap()[0];
+        let stats = creature::effective_stats(bears).unwrap();
+        assert_eq!((stats.power, stats.toughness), (6, 2));
+        assert!(crate::keyword::has_keyword(
+            bears,
+            crate::keyword::Keyword::Trample
+        ));
+
+        run_combat(&mut gs);
+        assert_eq!(gs.players[1].life, 14);
+
+        // Once the Bears are gone the Aura follows them and the Equipment stays behind
+        gs.destroy_permanents(0, &[0]);
+        gs.process_events();
+        let battlefield = gs.zones().get(&Zone::Battlefield).unwrap();
+        assert!(battlefield.iter().all(|c| !attachment::is_aura(c)));
+        assert!(
+            battlefield
+                .iter()
+                .any(|c| attachment::is_equipment(c) && attachment::attached_to(c).is_none())
+        );
+        assert_eq!(gs.zones().get(&Zone::Graveyard).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn tokens_enter_and_vanish_after_dying()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Raise the Alarm")]);
+        zones.insert(Zone::Battlefield, vec![card("Plains"), card("Plains")]);
+        let mut gs = game_with_zones(zones, GameStep::EndTurn);
+        gs.current_player_index = 1;
+
+        // Cast at the end of the opponent's turn
+        gs.step();
+        let tokens: Vec<&Card> = gs.players[0]
+            .zones
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .filter(|c| crate::token::is_token(c))
+            .collect();
+        assert_eq!(tokens.len(), 2);
+        assert_ne!(tokens[0].id, tokens[1].id);
+        assert_eq!(creature::effective_stats(tokens[0]).unwrap().power, 1);
+
+        gs.destroy_permanents(0, &[2, 3]);
+        gs.process_events();
+        assert_eq!(
+            gs.players[0].zones.get(&Zone::Battlefield).unwrap().len(),
+            2
+        );
+        assert_eq!(gs.players[0].zones.get(&Zone::Graveyard).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ramp_is_cast_first_and_pays_for_more()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![grizzly_bears(), card("Sol Ring")]);
+        zones.insert(Zone::Battlefield, vec![card("Forest"), card("Forest")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+
+        // Two lands cast both: Sol Ring first, then its two colorless help pay for the bears
+        gs.step();
+        assert!(gs.zones().get(&Zone::Hand).unwrap().is_empty());
+        assert_eq!(gs.zones().get(&Zone::Battlefield).unwrap().len(), 4);
+
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Rampant Growth")]);
+        zones.insert(Zone::Battlefield, vec![card("Forest"), card("Forest")]);
+        zones.insert(Zone::Library, vec![grizzly_bears(), card("Island")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+
+        gs.step();
+        let battlefield = gs.zones().get(&Zone::Battlefield).unwrap();
+        assert_eq!(battlefield[2].name, "Island");
+        assert!(crate::tappable::is_tapped(&battlefield[2]));
+        assert_eq!(gs.zones().get(&Zone::Library).unwrap().len(), 1);
+
+        // A ritual is only worth casting for something it makes affordable
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Hand,
+            vec![card("Dark Ritual"), card("Vampire Nighthawk")],
+        );
+        zones.insert(Zone::Battlefield, vec![card("Swamp")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        assert!(gs.ritual_enables(0, &gs.zones().get(&Zone::Hand).unwrap()[0]));
+
+        gs.step();
+        assert_eq!(
+            gs.zones().get(&Zone::Battlefield).unwrap()[1].name,
+            "Vampire Nighthawk"
+        );
+        assert_eq!(
+            gs.zones().get(&Zone::Graveyard).unwrap()[0].name,
+            "Dark Ritual"
+        );
+    }
+
+    #[test]
+    fn tapped_lands_are_played_when_the_mana_isnt_needed()
+    {
+        // Nothing costs two, so the guildgate goes down now and the forest is saved for later
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Hand,
+            vec![
+                card("Forest"),
+                card("Gruul Guildgate"),
+                card("Centaur Courser"),
+            ],
+        );
+        zones.insert(Zone::Battlefield, vec![card("Forest")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        let battlefield = gs.zones().get(&Zone::Battlefield).unwrap();
+        assert_eq!(battlefield[1].name, "Gruul Guildgate");
+        assert!(crate::tappable::is_tapped(&battlefield[1]));
+
+        // With a two-drop in hand the untapped forest is worth more
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Hand,
+            vec![card("Gruul Guildgate"), card("Forest"), grizzly_bears()],
+        );
+        zones.insert(Zone::Battlefield, vec![card("Forest")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        let battlefield = gs.zones().get(&Zone::Battlefield).unwrap();
+        assert_eq!(battlefield[1].name, "Forest");
+        assert_eq!(battlefield[2].name, "Grizzly Bears");
+    }
+
+    #[test]
+    fn only_the_player_who_went_first_skips_their_first_draw()
+    {
+        let deck = Deck::example();
+        let mut gs = GameState::with_rules(&[&deck, &deck], 1, &GameRules::default(), &[]);
+        gs.current_player_index = 1;
+        while !(gs.current_player_index == 0 && gs.step == GameStep::Main) {
+            gs.step();
+        }
+        assert_eq!(
+            (
+                gs.players[0].stats.cards_drawn,
+                gs.players[1].stats.cards_drawn
+            ),
+            (1, 0)
+        );
+        assert!(gs.players[1].stats.went_first && !gs.players[0].stats.went_first);
+    }
+
+    #[test]
+    fn rules_set_life_and_hand_size()
+    {
+        let deck = Deck::example();
+        let gs = GameState::with_rules(&[&deck, &deck], 1, &GameRules::commander(), &[]);
+        assert_eq!(gs.players[0].life, 40);
+        assert_eq!(gs.players[0].zones.get(&Zone::Hand).unwrap().len(), 7);
+
+        // Without a maximum nothing is discarded
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![forest(); 9]);
+        let mut gs = game_with_zones(zones.clone(), GameStep::EndTurn);
+        gs.step();
+        assert_eq!(gs.players[0].zones.get(&Zone::Hand).unwrap().len(), 9);
+
+        // Cleanup discards down to the maximum, lands first from a land-heavy hand
+        let mut gs = game_with_zones(zones, GameStep::EndTurn);
+        gs.rules.max_hand_size = Some(7);
+        gs.step();
+        assert_eq!(gs.players[0].zones.get(&Zone::Hand).unwrap().len(), 7);
+        assert_eq!(gs.players[0].zones.get(&Zone::Graveyard).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn commanders_recast_from_the_command_zone_with_tax()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Command,
+            vec![crate::commander::make_commander(grizzly_bears())],
+        );
+        zones.insert(
+            Zone::Battlefield,
+            vec![forest(), forest(), forest(), forest()],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+
+        gs.step();
+        assert_eq!(
+            gs.zones().get(&Zone::Battlefield).unwrap()[4].name,
+            "Grizzly Bears"
+        );
+
+        // Dying sends it back to the command zone, and the next cast costs {2} more
+        gs.destroy_permanents(0, &[4]);
+        gs.process_events();
+        let commander = &gs.zones().get(&Zone::Command).unwrap()[0];
+        assert_eq!(
+            crate::commander::cost_from_command_zone(commander),
+            "3G".parse().unwrap()
+        );
+        assert!(gs.zones().get(&Zone::Graveyard).unwrap().is_empty());
+
+        for land in 0..4 {
+            gs.set_tapped(0, land, false);
+        }
+        gs.step = GameStep::Main;
+        gs.step();
+        assert!(gs.zones().get(&Zone::Command).unwrap().is_empty());
+    }
+
+    #[test]
+    fn poison_and_commander_damage_end_the_game()
+    {
+        let mut elf = crate::commander::make_commander(card("Glistener Elf"));
+        crate::creature::set_summoning_sickness(&mut elf, false);
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![elf]);
+        let mut gs = game_with_zones(zones, GameStep::DeclareAttackers);
+        gs.players[1].add_counters(PlayerCounter::Poison, 9);
+
+        while gs.step != GameStep::EndTurn && !gs.is_game_over() {
+            gs.step();
+        }
+        assert_eq!(gs.players[1].life, 20);
+        assert_eq!(
+            gs.players[1].commander_damage,
+            vec![(gs.zones().get(&Zone::Battlefield).unwrap()[0].id, 1)]
+        );
+        assert_eq!(gs.players[1].lost_to, Some(WinCondition::Poison));
+        assert!(gs.is_game_over());
+
+        // Only the conditions in the rules count
+        let mut gs = game_with_zones(std::collections::HashMap::new(), GameStep::Main);
+        gs.rules.win_conditions = vec![WinCondition::LifeTotal];
+        gs.players[1].commander_damage.push((CardId(7), 21));
+        gs.check_state_based_actions();
+        assert!(!gs.players[1].has_lost);
+        gs.rules.win_conditions.push(WinCondition::CommanderDamage);
+        gs.check_state_based_actions();
+        assert_eq!(gs.players[1].lost_to, Some(WinCondition::CommanderDamage));
+    }
 
     #[test]
-    fn creature_without_sickness_deals_damage()
+    fn cards_move_between_zones_with_events()
     {
-        let mut battlefield = Vec::new();
-        let mut g = grizzly_bears();
-        creature::add_creature_fragment(&mut g, 2, 2);
-        creature::set_summoning_sickness(&mut g, false);
-        battlefield.push(g);
+        use crate::event::GameEvent;
 
         let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Hand, Vec::new());
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Graveyard, Vec::new());
+        zones.insert(Zone::Battlefield, vec![grizzly_bears()]);
+        zones.insert(Zone::Graveyard, vec![grizzly_bears()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Combat };
-        gs.step();
-        assert_eq!(gs.life, 18);
+        // Recursion from the graveyard, then exiling what died
+        assert_eq!(gs.move_card(0, Zone::Graveyard, 0, Zone::Hand), Some(0));
+        let recurred = gs.zones().get(&Zone::Hand).unwrap()[0].id;
+        assert_eq!(
+            gs.events.pop(),
+            Some(GameEvent::ZoneChanged {
+                player: 0,
+                from: Zone::Graveyard,
+                to: Zone::Hand,
+                card: recurred
+            })
+        );
+        assert!(gs.events.is_empty());
+
+        let destroyed = gs.zones().get(&Zone::Battlefield).unwrap()[0].id;
+        assert_ne!(destroyed, recurred);
+        gs.destroy_permanents(0, &[0]);
+        assert_eq!(
+            gs.events.pop(),
+            Some(GameEvent::ZoneChanged {
+                player: 0,
+                from: Zone::Battlefield,
+                to: Zone::Graveyard,
+                card: destroyed
+            })
+        );
+        assert_eq!(
+            gs.events.pop(),
+            Some(GameEvent::CreatureDied {
+                player: 0,
+                card: destroyed
+            })
+        );
+
+        assert_eq!(gs.move_card(0, Zone::Graveyard, 0, Zone::Exile), Some(0));
+        assert_eq!(gs.move_card(0, Zone::Graveyard, 0, Zone::Exile), None);
+        assert_eq!(gs.zones().get(&Zone::Exile).unwrap().len(), 1);
+        assert_eq!(gs.zones().get(&Zone::Hand).unwrap().len(), 1);
     }
 
     #[test]
-    fn creature_with_sickness_does_not_deal_damage()
+    fn board_shows_each_permanent_and_what_is_left_to_tap()
     {
-        let mut battlefield = Vec::new();
-        let mut g = grizzly_bears();
-        creature::set_summoning_sickness(&mut g, true);
-        battlefield.push(g);
+        let mut bears = grizzly_bears();
+        creature::deal_damage(&mut bears, 1, false);
+        crate::tappable::set_tapped(&mut bears, true);
+        let mut sick = grizzly_bears();
+        creature::set_summoning_sickness(&mut sick, true);
+        let mut tapped_forest = forest();
+        crate::tappable::set_tapped(&mut tapped_forest, true);
 
         let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Hand, Vec::new());
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Graveyard, Vec::new());
+        zones.insert(
+            Zone::Battlefield,
+            vec![bears, sick, forest(), tapped_forest],
+        );
+        zones.insert(Zone::Hand, vec![grizzly_bears(), forest()]);
+        let gs = game_with_zones(zones, GameStep::Main);
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Combat };
-        gs.step();
-        assert_eq!(gs.life, 20);
+        let board = gs.board();
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines[0], "Turn 0, Main");
+        assert_eq!(
+            lines[1],
+            "Player 1 (active): 20 life, 2 in hand, 0 in library, 0 in graveyard, 1 of 2 lands untapped, 1 mana available"
+        );
+        assert_eq!(
+            &lines[2..6],
+            [
+                "  Grizzly Bears 2/2 (1 damage) [tapped]",
+                "  Grizzly Bears 2/2 [sick]",
+                "  Forest",
+                "  Forest [tapped]"
+            ]
+        );
+        assert!(lines[6].starts_with("Player 2: 20 life, 0 in hand, 10 in library"));
+        assert_eq!(lines[7], "  (no permanents)");
+    }
+
+    #[test]
+    fn cards_are_found_by_id_wherever_they_move()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![grizzly_bears(), grizzly_bears()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        let second = gs.zones().get(&Zone::Battlefield).unwrap()[1].id;
+        assert_eq!(gs.locate(second), Some((0, Zone::Battlefield, 1)));
+
+        // Destroying the first bear shifts the second one's index but not its id
+        gs.destroy_permanents(0, &[0]);
+        assert_eq!(gs.locate(second), Some((0, Zone::Battlefield, 0)));
+        crate::creature::pump(gs.card_mut(second).unwrap(), 1, 1, &[]);
+        assert_eq!(
+            crate::creature::effective_stats(gs.card(second).unwrap())
+                .map(|s| (s.power, s.toughness)),
+            Some((3, 3))
+        );
+
+        let token = gs.create_token(1, &grizzly_bears());
+        let id = gs.players[1].zones.get(&Zone::Battlefield).unwrap()[token].id;
+        assert_eq!(gs.locate(id), Some((1, Zone::Battlefield, token)));
+        assert_eq!(gs.locate(CardId(gs.next_card_id)), None);
     }
 
     #[test]
@@ -556,9 +3592,9 @@ mod tests
         zones.insert(Zone::Library, Vec::new());
         zones.insert(Zone::Graveyard, Vec::new());
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Upkeep };
+        let mut gs = game_with_zones(zones, GameStep::Upkeep);
         gs.step();
-        let bf = gs.zones.get(&Zone::Battlefield).unwrap();
+        let bf = gs.zones().get(&Zone::Battlefield).unwrap();
         assert!(!crate::creature::has_summoning_sickness(&bf[0]));
     }
 
@@ -566,8 +3602,7 @@ mod tests
     fn play_one_land_if_available()
     {
         let library = Vec::new();
-        let mut hand = Vec::new();
-        hand.push(forest());
+        let hand = vec![forest()];
 
         let mut zones = std::collections::HashMap::new();
         zones.insert(Zone::Library, library);
@@ -575,23 +3610,20 @@ mod tests
         zones.insert(Zone::Battlefield, Vec::new());
         zones.insert(Zone::Graveyard, Vec::new());
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Main };
+        let mut gs = game_with_zones(zones, GameStep::Main);
         gs.step();
 
-        assert_eq!(gs.zones.get(&Zone::Battlefield).unwrap().len(), 1);
+        assert_eq!(gs.zones().get(&Zone::Battlefield).unwrap().len(), 1);
     }
 
     #[test]
     fn play_as_many_creatures_as_possible()
     {
         // Start with 4 lands available and two creatures in hand (cost 2 each)
-        let mut hand = Vec::new();
-        hand.push(grizzly_bears());
-        hand.push(grizzly_bears());
+        let hand = vec![grizzly_bears(), grizzly_bears()];
 
         let mut battlefield = Vec::new();
-        for _ in 0..4 
-        {
+        for _ in 0..4 {
             battlefield.push(forest());
         }
 
@@ -601,15 +3633,23 @@ mod tests
         zones.insert(Zone::Battlefield, battlefield);
         zones.insert(Zone::Graveyard, Vec::new());
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Main };
+        let mut gs = game_with_zones(zones, GameStep::Main);
         gs.step();
 
-        // Only ONE creature should be cast per main phase (4 lands available, but can only cast 1 creature)
-        assert_eq!(gs.zones.get(&Zone::Battlefield).unwrap().len(), 5); // 4 lands + 1 creature
+        // Both creatures should be cast (4 lands pay for two 2-drops)
+        assert_eq!(gs.zones().get(&Zone::Battlefield).unwrap().len(), 6); // 4 lands + 2 creatures
         // Verify we have the 4 lands still on battlefield
-        assert_eq!(gs.zones.get(&Zone::Battlefield).unwrap().iter().filter(|c| c.is_type(crate::card::CardType::Land)).count(), 4);
-        // One creature should be in hand still
-        assert_eq!(gs.zones.get(&Zone::Hand).unwrap().len(), 1);
+        assert_eq!(
+            gs.zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .filter(|c| c.is_type(crate::card::CardType::Land))
+                .count(),
+            4
+        );
+        // Hand should be empty
+        assert!(gs.zones().get(&Zone::Hand).unwrap().is_empty());
     }
 
     #[test]
@@ -618,17 +3658,11 @@ mod tests
         // Hand: 2x Forest + Grizzly, Battlefield: 1x Forest (to give us 2 mana for grizzly)
         // Library: 2x Forest (for subsequent draws)
         // This ensures we can play another land and cast the grizzly in the first main phase
-        let mut hand = Vec::new();
-        hand.push(forest());
-        hand.push(forest());
-        hand.push(grizzly_bears());
+        let hand = vec![forest(), forest(), grizzly_bears()];
 
-        let mut battlefield = Vec::new();
-        battlefield.push(forest());
+        let battlefield = vec![forest()];
 
-        let mut library = Vec::new();
-        library.push(forest());
-        library.push(forest());
+        let library = vec![forest(), forest()];
 
         let mut zones = std::collections::HashMap::new();
         zones.insert(Zone::Library, library);
@@ -636,41 +3670,39 @@ mod tests
         zones.insert(Zone::Battlefield, battlefield);
         zones.insert(Zone::Graveyard, Vec::new());
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::StartTurn };
+        let mut gs = game_with_zones(zones, GameStep::StartTurn);
 
-        // Turn 1: StartTurn -> Untap -> Upkeep -> Draw -> Main -> Combat
+        // Turn 1: StartTurn -> Untap -> Upkeep -> Draw -> Main -> DeclareAttackers
         gs.step(); // StartTurn -> Untap
         gs.step(); // Untap -> Upkeep
         gs.step(); // Upkeep -> Draw (draws a forest)
         gs.step(); // Draw -> Main
-        gs.step(); // Main -> Combat (plays 1 land, casts grizzly with 2 mana total, gives it summoning sickness)
-        gs.step(); // Combat should NOT deal damage because creature is sick
-        assert_eq!(gs.life, 20, "Creature with summoning sickness should not deal damage on the turn it was cast");
+        gs.step(); // Main -> DeclareAttackers (plays 1 land, casts grizzly with 2 mana total, gives it summoning sickness)
+        run_combat(&mut gs); // should NOT deal damage because creature is sick
+        assert_eq!(
+            gs.players[1].life, 20,
+            "Creature with summoning sickness should not deal damage on the turn it was cast"
+        );
 
-        // Continue to EndTurn -> StartTurn -> Untap -> Upkeep (for turn 2)
-        gs.step(); // Combat -> EndTurn
+        // Let the opponent take their turn, then advance to our second combat
         gs.step(); // EndTurn -> StartTurn
-        gs.step(); // StartTurn -> Untap
-        gs.step(); // Untap -> Upkeep (clears sickness)
-
-        // Advance to Combat of second turn
-        gs.step(); // Upkeep -> Draw (draws another forest)
-        gs.step(); // Draw -> Main
-        gs.step(); // Main -> Combat
-        gs.step(); // Combat should now deal damage
-        assert!(gs.life < 20, "Creature should deal damage after sickness cleared on upkeep");
+        while gs.current_player_index != 0 || gs.step != GameStep::DeclareAttackers {
+            gs.step();
+        }
+        run_combat(&mut gs); // should now deal damage
+        assert!(
+            gs.players[1].life < 20,
+            "Creature should deal damage after sickness cleared on upkeep"
+        );
     }
 
     #[test]
     fn casting_taps_forests_used_for_payment()
     {
         // Battlefield: 2x Forest (untapped). Hand: Grizzly Bears (cost 2). Main phase.
-        let mut hand = Vec::new();
-        hand.push(grizzly_bears());
+        let hand = vec![grizzly_bears()];
 
-        let mut battlefield = Vec::new();
-        battlefield.push(forest());
-        battlefield.push(forest());
+        let battlefield = vec![forest(), forest()];
 
         let mut zones = std::collections::HashMap::new();
         zones.insert(Zone::Library, Vec::new());
@@ -678,17 +3710,1135 @@ mod tests
         zones.insert(Zone::Battlefield, battlefield);
         zones.insert(Zone::Graveyard, Vec::new());
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Main };
+        let mut gs = game_with_zones(zones, GameStep::Main);
         gs.step();
 
         // After casting, a grizzly should be on the battlefield and two forests should be tapped
-        let bf = gs.zones.get(&Zone::Battlefield).unwrap();
-        assert_eq!(bf.iter().filter(|c| c.is_type(crate::card::CardType::Land)).count(), 2);
-        assert_eq!(bf.iter().filter(|c| c.is_type(crate::card::CardType::Creature)).count(), 1);
-        let tapped_lands = bf.iter().filter(|c| c.is_type(crate::card::CardType::Land) && crate::tappable::is_tapped(c)).count();
+        let bf = gs.zones().get(&Zone::Battlefield).unwrap();
+        assert_eq!(
+            bf.iter()
+                .filter(|c| c.is_type(crate::card::CardType::Land))
+                .count(),
+            2
+        );
+        assert_eq!(
+            bf.iter()
+                .filter(|c| c.is_type(crate::card::CardType::Creature))
+                .count(),
+            1
+        );
+        let tapped_lands = bf
+            .iter()
+            .filter(|c| c.is_type(crate::card::CardType::Land) && crate::tappable::is_tapped(c))
+            .count();
         assert_eq!(tapped_lands, 2, "Both forests used to pay should be tapped");
     }
 
+    #[test]
+    fn colored_costs_need_matching_lands()
+    {
+        let hill_giant = card("Hill Giant");
+        let hand = vec![hill_giant, grizzly_bears()];
+        let battlefield = vec![forest(), forest(), forest(), card("Mountain")];
+
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, hand);
+        zones.insert(Zone::Battlefield, battlefield);
+
+        // Four lands pay for Hill Giant's {3}{R} only if the Mountain is kept for the red pip
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        let bf = gs.zones().get(&Zone::Battlefield).unwrap();
+        assert!(bf.iter().any(|c| c.name == "Hill Giant"));
+        assert_eq!(gs.zones().get(&Zone::Hand).unwrap().len(), 1);
+        assert_eq!(gs.current_player().mana_pool.total(), 0);
+
+        // Without red mana it stays in hand, while the Bears get cast
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Hill Giant"), grizzly_bears()]);
+        zones.insert(
+            Zone::Battlefield,
+            vec![forest(), forest(), forest(), forest(), forest(), forest()],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        assert_eq!(gs.zones().get(&Zone::Hand).unwrap()[0].name, "Hill Giant");
+        assert_eq!(gs.current_player().stats.color_screwed_turns, 1);
+    }
+
+    #[test]
+    fn only_spells_the_colors_block_count_as_color_screw()
+    {
+        // Forests cast the Bears, but leave the Shock in hand with a source to spare
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Shock"), grizzly_bears()]);
+        zones.insert(Zone::Battlefield, vec![forest(), forest(), forest()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        assert!(
+            gs.zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .any(|c| c.name == "Grizzly Bears")
+        );
+        assert_eq!(gs.current_player().stats.color_screwed_turns, 1);
+
+        // Two Forests couldn't pay for Hill Giant in any colors, so that's not color screw
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Hill Giant")]);
+        zones.insert(Zone::Battlefield, vec![forest(), forest()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        assert_eq!(gs.current_player().stats.color_screwed_turns, 0);
+    }
+
+    #[test]
+    fn fireball_pays_just_enough_x_for_lethal()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Fireball")]);
+        zones.insert(
+            Zone::Battlefield,
+            (0..6).map(|_| card("Mountain")).collect(),
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.players[1].life = 4;
+        gs.step();
+
+        // X = 4 plus the {R}, leaving a Mountain untapped
+        assert_eq!(gs.players[1].life, 0);
+        let untapped = gs
+            .zones()
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .filter(|c| !crate::tappable::is_tapped(c))
+            .count();
+        assert_eq!(untapped, 1);
+        let fireball = &gs.zones().get(&Zone::Graveyard).unwrap()[0];
+        assert_eq!(
+            fireball
+                .fragment::<crate::card::EffectFragment>()
+                .unwrap()
+                .x,
+            4
+        );
+    }
+
+    #[test]
+    fn adventures_and_land_faces_are_cast_and_played_as_either_face()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Bonecrusher Giant")]);
+        zones.insert(
+            Zone::Battlefield,
+            (0..5).map(|_| card("Mountain")).collect(),
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        let mut bears = grizzly_bears();
+        bears.id = CardId(1000);
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(bears);
+
+        // Stomp kills the Bears and sends the Giant on its adventure...
+        let stomp = gs
+            .castable_spells(0)
+            .into_iter()
+            .find(|cast| cast.face == 1)
+            .unwrap();
+        assert_eq!(gs.spell(0, &stomp).name, "Stomp");
+        gs.cast(0, &stomp);
+        gs.resolve_top();
+        assert!(
+            gs.players[1]
+                .zones
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .is_empty()
+        );
+        let exiled = &gs.zones().get(&Zone::Exile).unwrap()[0];
+        assert_eq!(exiled.name, "Bonecrusher Giant");
+        assert!(crate::faces::on_adventure(exiled));
+
+        // ...to be cast from exile as the creature
+        let giant = gs
+            .castable_spells(0)
+            .into_iter()
+            .find(|cast| cast.zone == Zone::Exile)
+            .unwrap();
+        gs.cast(0, &giant);
+        gs.resolve_top();
+        assert!(gs.zones().get(&Zone::Exile).unwrap().is_empty());
+        assert!(
+            gs.zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .any(|c| c.name == "Bonecrusher Giant" && creature::is_creature(c))
+        );
+
+        // Without a land in hand, a modal double-faced card is the land drop
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Kazandu Mammoth")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        let valley = &gs.zones().get(&Zone::Battlefield).unwrap()[0];
+        assert_eq!(valley.name, "Kazandu Valley");
+        assert!(valley.is_type(CardType::Land) && crate::tappable::is_tapped(valley));
+        gs.move_card(0, Zone::Battlefield, 0, Zone::Graveyard);
+        assert_eq!(
+            gs.zones().get(&Zone::Graveyard).unwrap()[0].name,
+            "Kazandu Mammoth"
+        );
+    }
+
+    #[test]
+    fn planeswalkers_use_their_abilities_and_can_be_attacked()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Chandra Nalaar")]);
+        zones.insert(
+            Zone::Battlefield,
+            (0..5).map(|_| card("Mountain")).collect(),
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+
+        // Cast, then +1 right away: one damage to the opponent
+        let chandra = gs
+            .zones()
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .find(|c| c.name == "Chandra Nalaar")
+            .unwrap();
+        assert_eq!(crate::planeswalker::loyalty(chandra), Some(7));
+        assert_eq!(gs.players[1].life, 19);
+
+        // Short of lethal on the player, the Wurm goes after the opponent's Gideon instead
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![card("Craw Wurm")]);
+        let mut gs = game_with_zones(zones, GameStep::DeclareAttackers);
+        let mut gideon = card("Gideon, Ally of Zendikar");
+        gideon.id = CardId(1000);
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(gideon);
+        run_combat(&mut gs);
+        assert_eq!(gs.players[1].life, 20);
+        assert!(
+            gs.players[1]
+                .zones
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            gs.players[1].zones.get(&Zone::Graveyard).unwrap()[0].name,
+            "Gideon, Ally of Zendikar"
+        );
+    }
+
+    #[test]
+    fn anthems_pump_creatures_and_taxes_raise_costs()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![grizzly_bears()]);
+        zones.insert(
+            Zone::Battlefield,
+            vec![forest(), forest(), card("Glorious Anthem")],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.step();
+        let bears = gs
+            .zones()
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .find(|c| c.name == "Grizzly Bears")
+            .unwrap();
+        assert_eq!(
+            creature::effective_stats(bears).map(|s| (s.power, s.toughness)),
+            Some((3, 3))
+        );
+        // Only its controller's creatures get the bonus
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(grizzly_bears());
+        gs.process_events();
+        assert_eq!(
+            creature::effective_stats(&gs.players[1].zones.get(&Zone::Battlefield).unwrap()[0])
+                .unwrap()
+                .power,
+            2
+        );
+
+        // An opponent's Sphere of Resistance puts the Bears out of reach of two lands
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![grizzly_bears()]);
+        zones.insert(Zone::Battlefield, vec![forest(), forest()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(card("Sphere of Resistance"));
+        assert!(gs.castable_spells(0).is_empty());
+        assert_eq!(
+            gs.taxed(&grizzly_bears(), grizzly_bears().cost)
+                .mana_value(),
+            3
+        );
+    }
+
+    #[test]
+    fn layers_set_base_stats_before_anthems_and_counters()
+    {
+        let mut bears = grizzly_bears();
+        creature::add_counters(&mut bears, crate::card::CounterKind::MinusOne, 1);
+        let mut gs = game_with_zones(std::collections::HashMap::new(), GameStep::Main);
+        gs.players[1]
+            .zones
+            .insert(Zone::Battlefield, vec![bears, card("Glorious Anthem")]);
+        gs.assign_card_ids();
+        gs.process_events();
+        let stats = |gs: &GameState, player: usize, index: usize| {
+            creature::effective_stats(
+                &gs.players[player].zones.get(&Zone::Battlefield).unwrap()[index],
+            )
+            .map(|s| (s.power, s.toughness))
+        };
+        assert_eq!(stats(&gs, 1, 0), Some((2, 2)));
+
+        // Diminish makes the Bears 1/1 before the anthem and the counter apply
+        crate::effect::apply(
+            &mut gs,
+            0,
+            &crate::effect::Effect::SetBaseStats {
+                power: 1,
+                toughness: 1,
+            },
+        );
+        gs.process_events();
+        assert_eq!(stats(&gs, 1, 0), Some((1, 1)));
+
+        // March of the Machines makes the opponent's Sphere a 2/2 (3/3 with the anthem) until it leaves
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .remove(0);
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(card("Sphere of Resistance"));
+        gs.players[0]
+            .zones
+            .entry(Zone::Hand)
+            .or_default()
+            .push(card("March of the Machines"));
+        gs.assign_card_ids();
+        gs.move_card(0, Zone::Hand, 0, Zone::Battlefield);
+        gs.process_events();
+        assert_eq!(stats(&gs, 1, 1), Some((3, 3)));
+        // A setter with a later timestamp wins over March's
+        crate::effect::apply(
+            &mut gs,
+            0,
+            &crate::effect::Effect::SetBaseStats {
+                power: 1,
+                toughness: 1,
+            },
+        );
+        gs.process_events();
+        assert_eq!(stats(&gs, 1, 1), Some((2, 2)));
+
+        gs.move_card(0, Zone::Battlefield, 0, Zone::Graveyard);
+        gs.process_events();
+        let sphere = &gs.players[1].zones.get(&Zone::Battlefield).unwrap()[1];
+        assert!(!creature::is_creature(sphere) && !sphere.is_type(CardType::Creature));
+    }
+
+    #[test]
+    fn animated_artifacts_are_sick_only_if_they_entered_this_turn()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Hand,
+            vec![card("Sphere of Resistance"), card("March of the Machines")],
+        );
+        zones.insert(Zone::Battlefield, vec![card("Sphere of Resistance")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.assign_card_ids();
+        // The first Sphere was there before this turn began
+        gs.next_timestamp = 5;
+        gs.players[0].turn_started_at = 5;
+
+        gs.move_card(0, Zone::Hand, 0, Zone::Battlefield);
+        gs.move_card(0, Zone::Hand, 0, Zone::Battlefield);
+        gs.process_events();
+        let sick: Vec<Option<bool>> = gs
+            .zones()
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .map(|c| {
+                c.fragment::<crate::card::CreatureFragment>()
+                    .map(|cf| cf.summoning_sickness)
+            })
+            .collect();
+        assert_eq!(sick, vec![Some(false), Some(true), None]);
+    }
+
+    #[test]
+    fn replacement_effects_change_draws_counters_and_damage()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Servant of the Scale")]);
+        zones.insert(Zone::Library, vec![forest(), forest(), forest()]);
+        zones.insert(
+            Zone::Battlefield,
+            vec![
+                card("Hardened Scales"),
+                card("Thought Reflection"),
+                card("Urza's Armor"),
+            ],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+
+        // Enters with its own counter and one more from Hardened Scales
+        let index = gs.move_card(0, Zone::Hand, 0, Zone::Battlefield).unwrap();
+        assert_eq!(
+            creature::counters(
+                &gs.zones().get(&Zone::Battlefield).unwrap()[index],
+                CounterKind::PlusOne
+            ),
+            2
+        );
+
+        // One draw becomes two, without those two being doubled again
+        assert!(gs.draw_card(0));
+        assert_eq!(gs.zones().get(&Zone::Hand).unwrap().len(), 2);
+
+        // Urza's Armor prevents 1 of each damage
+        crate::effect::apply(&mut gs, 1, &crate::effect::Effect::DamageOpponents(3));
+        assert_eq!(gs.players[0].life, 18);
+    }
+
+    #[test]
+    fn copies_take_base_characteristics_but_not_counters_or_attachments()
+    {
+        let mut bears = grizzly_bears();
+        creature::add_counters(&mut bears, CounterKind::PlusOne, 2);
+        creature::deal_damage(&mut bears, 1, false);
+        crate::tappable::set_tapped(&mut bears, true);
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![bears, card("Bonesplitter")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        let original = gs.zones()[&Zone::Battlefield][0].id;
+        crate::attachment::attach(
+            &mut gs.zones_mut().get_mut(&Zone::Battlefield).unwrap()[1],
+            Some(original),
+        );
+
+        crate::effect::apply(&mut gs, 0, &crate::effect::Effect::CreateTokenCopy);
+        gs.process_events();
+        let copy = &gs.zones()[&Zone::Battlefield][2];
+        assert!(
+            crate::token::is_token(copy) && copy.name == "Grizzly Bears" && copy.id != original
+        );
+        assert_eq!(
+            creature::effective_stats(copy).map(|s| (s.power, s.toughness)),
+            Some((2, 2))
+        );
+        assert!(
+            !crate::tappable::is_tapped(copy)
+                && creature::marked_damage(copy) == 0
+                && creature::has_summoning_sickness(copy)
+        );
+
+        // A copy of the opponent's Lightning Bolt is ours, aimed at them, and gone once it resolves
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Stack)
+            .unwrap()
+            .push(card("Lightning Bolt"));
+        gs.stack.push(1);
+        crate::effect::apply(&mut gs, 0, &crate::effect::Effect::CopySpell);
+        assert_eq!(gs.stack, vec![1, 0]);
+        gs.resolve_top();
+        assert_eq!(gs.players[1].life, 17);
+        assert!(gs.zones()[&Zone::Stack].is_empty() && gs.zones()[&Zone::Graveyard].is_empty());
+    }
+
+    #[test]
+    fn flash_spells_wait_for_the_end_step_and_counterspells_are_held_up()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![card("Ambush Viper")]);
+        zones.insert(Zone::Battlefield, vec![forest(), forest()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(
+            gs.castable_spells(0).len() == 1
+                && CurveOut::default()
+                    .main_phase_cast(&gs, 0, &mut rng)
+                    .is_none()
+        );
+
+        // Cast at the opponent's end step instead
+        gs.current_player_index = 1;
+        gs.step = GameStep::EndTurn;
+        gs.run_priority();
+        assert_eq!(
+            gs.players[0].zones[&Zone::Battlefield].last().unwrap().name,
+            "Ambush Viper"
+        );
+
+        // Three lands don't pay for both the Bears and a Counterspell, so the Bears wait
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Hand, vec![grizzly_bears(), card("Counterspell")]);
+        zones.insert(
+            Zone::Battlefield,
+            vec![card("Island"), card("Island"), forest()],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.players[1]
+            .zones
+            .get_mut(&Zone::Hand)
+            .unwrap()
+            .push(grizzly_bears());
+        assert!(
+            CurveOut::default()
+                .main_phase_cast(&gs, 0, &mut rng)
+                .is_none()
+        );
+        gs.zones_mut()
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(forest());
+        assert!(
+            CurveOut::default()
+                .main_phase_cast(&gs, 0, &mut rng)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn haste_gained_and_lost_mid_turn_decides_what_can_attack_and_tap()
+    {
+        let mut bears = grizzly_bears();
+        creature::set_summoning_sickness(&mut bears, true);
+        let mut elves = card("Llanowar Elves");
+        creature::set_summoning_sickness(&mut elves, true);
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![bears, elves]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        let ready = |gs: &GameState| gs.components(0).ready_attackers();
+        assert!(ready(&gs).is_empty() && gs.available_mana(0) == 0);
+
+        // Fervor's haste lets both attack and the Elves tap, until it leaves
+        gs.zones_mut()
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(card("Fervor"));
+        gs.process_events();
+        assert!(ready(&gs) == vec![0, 1] && gs.available_mana(0) == 1);
+        gs.move_card(0, Zone::Battlefield, 2, Zone::Graveyard);
+        gs.process_events();
+        assert!(
+            ready(&gs).is_empty()
+                && creature::arrived_this_turn(&gs.zones()[&Zone::Battlefield][0])
+        );
+
+        // Reckless Charge's haste picks the sick Bears and wears off in the cleanup
+        crate::effect::apply(
+            &mut gs,
+            0,
+            &crate::effect::Effect::PumpCreature {
+                power: 3,
+                toughness: 0,
+                keywords: vec![crate::keyword::Keyword::Haste],
+            },
+        );
+        assert_eq!(ready(&gs), vec![0]);
+        assert_eq!(
+            creature::effective_stats(&gs.zones()[&Zone::Battlefield][0])
+                .unwrap()
+                .power,
+            5
+        );
+        creature::clear_until_end_of_turn(
+            &mut gs.zones_mut().get_mut(&Zone::Battlefield).unwrap()[0],
+        );
+        gs.refresh_components();
+        assert!(ready(&gs).is_empty());
+    }
+
+    #[test]
+    fn indestructible_regeneration_and_protection_change_what_dies()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Battlefield,
+            vec![card("Darksteel Myr"), card("White Knight"), grizzly_bears()],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.players[1]
+            .zones
+            .insert(Zone::Stack, vec![card("Doom Blade")]);
+        gs.assign_card_ids();
+        let names = |gs: &GameState| {
+            gs.players[0].zones[&Zone::Battlefield]
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Indestructible shrugs off lethal damage and destroy effects
+        creature::deal_damage(
+            &mut gs.players[0].zones.get_mut(&Zone::Battlefield).unwrap()[0],
+            5,
+            false,
+        );
+        gs.process_events();
+        gs.destroy_permanents(0, &[0]);
+        assert_eq!(
+            names(&gs),
+            ["Darksteel Myr", "White Knight", "Grizzly Bears"]
+        );
+
+        // Doom Blade can't target the Knight, protected from black, so the Bears are the target.
+        // Death Ward shields them: they're tapped instead of destroyed, but only once
+        gs.stack.push(1);
+        assert!(crate::effect::has_target(
+            &gs,
+            0,
+            &crate::effect::Effect::Regenerate
+        ));
+        crate::effect::apply(&mut gs, 0, &crate::effect::Effect::Regenerate);
+        gs.resolving = Some(gs.players[1].zones[&Zone::Stack][0].id);
+        crate::effect::apply(&mut gs, 1, &crate::effect::Effect::DestroyTarget);
+        assert!(crate::tappable::is_tapped(
+            &gs.players[0].zones[&Zone::Battlefield][2]
+        ));
+        crate::effect::apply(&mut gs, 1, &crate::effect::Effect::DestroyTarget);
+        assert_eq!(names(&gs), ["Darksteel Myr", "White Knight"]);
+
+        // Black creatures can't block the Knight, and their damage to it is prevented
+        let (knight, rats) = (card("White Knight"), card("Typhoid Rats"));
+        assert!(!combat::can_block_attacker(&rats, &knight));
+        let report = combat::assign_damage(
+            &[rats],
+            &[knight],
+            &Combat {
+                defending_player: 1,
+                attackers: vec![0],
+                blocks: vec![(0, 0)],
+                planeswalkers: Vec::new(),
+            },
+        );
+        assert!(report.to_blockers.is_empty() && report.to_attackers.len() == 1);
+    }
+
+    #[test]
+    fn lifelink_gains_the_damage_dealt_and_life_gain_triggers()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Battlefield,
+            vec![card("Vampire Nighthawk"), card("Epicure of Blood")],
+        );
+        let mut gs = game_with_zones(zones, GameStep::DeclareAttackers);
+        gs.players[1]
+            .zones
+            .insert(Zone::Battlefield, vec![card("Urza's Armor")]);
+        gs.assign_card_ids();
+
+        // Urza's Armor prevents 1 of the Nighthawk's 2 damage, so lifelink gains 1; the Epicure
+        // turns that life gain into another point of life loss
+        run_combat(&mut gs);
+        assert_eq!(gs.players[0].life, 21);
+        assert_eq!(gs.players[1].life, 20 - 1 - 3 - 1);
+    }
+
+    #[test]
+    fn sacrifices_pay_costs_and_feed_death_triggers()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Battlefield,
+            vec![
+                card("Swamp"),
+                card("Blood Artist"),
+                card("Doomed Traveler"),
+                grizzly_bears(),
+            ],
+        );
+        zones.insert(Zone::Hand, vec![card("Bone Splinters")]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.players[1]
+            .zones
+            .insert(Zone::Battlefield, vec![card("Craw Wurm")]);
+        gs.assign_card_ids();
+        let names = |gs: &GameState, player: usize| {
+            gs.players[player].zones[&Zone::Battlefield]
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Bone Splinters sacrifices the cheapest creature, the Traveler, which leaves a token;
+        // its death and the Wurm's each drain 1 through Blood Artist
+        let cast = gs.castable_spells(0).pop().unwrap();
+        gs.cast(0, &cast);
+        gs.run_priority();
+        assert_eq!(
+            names(&gs, 0),
+            ["Swamp", "Blood Artist", "Grizzly Bears", "1/1 Token"]
+        );
+        assert!(names(&gs, 1).is_empty());
+        assert_eq!((gs.players[0].life, gs.players[1].life), (22, 18));
+
+        // An edict takes the token first, and without creatures there's nothing to pay with
+        crate::effect::apply(&mut gs, 1, &crate::effect::Effect::OpponentsSacrifice(1));
+        gs.process_events();
+        assert_eq!(names(&gs, 0), ["Swamp", "Blood Artist", "Grizzly Bears"]);
+        assert_eq!((gs.players[0].life, gs.players[1].life), (23, 17));
+        assert!(!gs.can_pay_sacrifice(1, &card("Bone Splinters")));
+    }
+
+    #[test]
+    fn permanent_spells_need_their_sacrifice_paid_too()
+    {
+        let mut relic = card("Bone Splinters");
+        relic.card_types = vec![CardType::Artifact];
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![card("Swamp")]);
+        zones.insert(Zone::Hand, vec![relic]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        assert!(gs.castable_spells(0).is_empty());
+
+        gs.zones_mut()
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(grizzly_bears());
+        assert_eq!(gs.castable_spells(0).len(), 1);
+    }
+
+    #[test]
+    fn treasure_pays_for_spells_and_clues_and_food_are_cashed_in()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![forest(), forest(), forest()]);
+        zones.insert(
+            Zone::Hand,
+            vec![card("Jewel Thief"), card("Thraben Inspector")],
+        );
+        zones.insert(Zone::Library, vec![grizzly_bears()]);
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.assign_card_ids();
+        let names = |gs: &GameState| {
+            gs.players[0].zones[&Zone::Battlefield]
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let cast = |gs: &mut GameState, spell: &str| {
+            let cast = gs
+                .castable_spells(0)
+                .into_iter()
+                .find(|c| gs.spell(0, c).name == spell)
+                .unwrap();
+            gs.cast(0, &cast);
+            gs.run_priority();
+        };
+
+        // The Thief's Treasure is the only white source, and it's gone once it pays for the Inspector
+        cast(&mut gs, "Jewel Thief");
+        assert_eq!(
+            names(&gs),
+            ["Forest", "Forest", "Forest", "Jewel Thief", "Treasure"]
+        );
+        cast(&mut gs, "Thraben Inspector");
+        assert_eq!(
+            names(&gs),
+            [
+                "Forest",
+                "Forest",
+                "Forest",
+                "Jewel Thief",
+                "Thraben Inspector",
+                "Clue"
+            ]
+        );
+
+        // With the lands untapped, the Clue is cracked for a card; the Food waits for low life
+        for index in 0..gs.players[0].zones[&Zone::Battlefield].len() {
+            gs.set_tapped(0, index, false);
+        }
+        crate::effect::apply(
+            &mut gs,
+            0,
+            &crate::effect::Effect::CreateArtifactTokens {
+                kind: crate::token::ArtifactToken::Food,
+                count: 1,
+            },
+        );
+        gs.activate_sacrifice_abilities();
+        assert_eq!(gs.players[0].zones[&Zone::Hand].len(), 1);
+        assert_eq!(names(&gs).last().unwrap(), "Food");
+
+        gs.players[0].life = 8;
+        gs.set_tapped(0, 0, false);
+        gs.set_tapped(0, 1, false);
+        gs.activate_sacrifice_abilities();
+        assert_eq!(gs.players[0].life, 11);
+        assert!(!names(&gs).contains(&"Food".to_string()));
+    }
+
+    #[test]
+    fn energy_is_spent_on_effects_and_poison_counters_kill()
+    {
+        use crate::effect::{Effect, apply};
+        let mut gs = game_with_zones(std::collections::HashMap::new(), GameStep::Main);
+        gs.players[1]
+            .zones
+            .insert(Zone::Battlefield, vec![grizzly_bears(), card("Craw Wurm")]);
+        gs.assign_card_ids();
+
+        // Harnessed Lightning's energy pays for its damage, and is gone afterwards
+        for effect in crate::effect::spell_effects(&card("Harnessed Lightning")) {
+            apply(&mut gs, 0, effect);
+        }
+        gs.check_state_based_actions();
+        assert_eq!(gs.players[1].zones[&Zone::Battlefield].len(), 1);
+        assert_eq!(gs.players[0].counters(PlayerCounter::Energy), 0);
+
+        // Without the energy nothing happens
+        gs.players[0].add_counters(PlayerCounter::Energy, 2);
+        apply(
+            &mut gs,
+            0,
+            &Effect::SpendCounters {
+                kind: PlayerCounter::Energy,
+                count: 3,
+                effect: Box::new(Effect::DamageOpponents(5)),
+            },
+        );
+        assert_eq!(
+            (
+                gs.players[0].counters(PlayerCounter::Energy),
+                gs.players[1].life
+            ),
+            (2, 20)
+        );
+
+        // The tenth poison counter ends the game
+        gs.players[1].add_counters(PlayerCounter::Poison, 9);
+        apply(&mut gs, 0, &Effect::PoisonOpponents(1));
+        gs.check_state_based_actions();
+        assert_eq!(gs.players[1].lost_to, Some(WinCondition::Poison));
+    }
+
+    #[test]
+    fn kickers_and_alternative_costs_change_what_spells_cost_and_do()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(Zone::Battlefield, vec![card("Mountain")]);
+        zones.insert(
+            Zone::Hand,
+            vec![card("Burst Lightning"), card("Burst Lightning")],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.players[1]
+            .zones
+            .insert(Zone::Battlefield, vec![card("Craw Wurm")]);
+        gs.assign_card_ids();
+
+        // Without the mana for the kicker, 2 damage can only go to the face
+        let cast = gs.castable_instants(0).pop().unwrap();
+        gs.cast(0, &cast);
+        gs.run_priority();
+        assert_eq!(
+            (
+                gs.players[1].life,
+                gs.players[1].zones[&Zone::Battlefield].len()
+            ),
+            (18, 1)
+        );
+
+        // Kicked, it's 4 damage and the Wurm dies; the card in the graveyard isn't kicked anymore
+        gs.players[0].zones.insert(
+            Zone::Battlefield,
+            (0..5).map(|_| card("Mountain")).collect(),
+        );
+        gs.assign_card_ids();
+        let cast = gs.castable_instants(0).pop().unwrap();
+        gs.cast(0, &cast);
+        gs.run_priority();
+        assert!(gs.players[1].zones[&Zone::Battlefield].is_empty());
+        assert!(gs.players[0].zones[&Zone::Graveyard].iter().all(
+            |card| crate::effect::spell_effects(card) == [crate::effect::Effect::DealDamage(2)]
+        ));
+
+        // Force of Will costs 1 life and the cheapest other blue card instead of its mana
+        gs.players[0].zones.insert(
+            Zone::Hand,
+            vec![
+                card("Force of Will"),
+                card("Counterspell"),
+                card("Wind Drake"),
+            ],
+        );
+        gs.assign_card_ids();
+        let pitched = gs.casts_of(0, &[], &[], (Zone::Hand, 0, 0, &card("Force of Will")));
+        assert_eq!(
+            pitched,
+            [Cast {
+                zone: Zone::Hand,
+                index: 0,
+                face: 0,
+                plan: Vec::new(),
+                alternative: true
+            }]
+        );
+        gs.cast(0, &pitched[0]);
+        assert_eq!(gs.players[0].life, 19);
+        assert_eq!(
+            gs.players[0].zones[&Zone::Exile]
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Counterspell"]
+        );
+        assert_eq!(gs.players[0].zones[&Zone::Stack][0].name, "Force of Will");
+    }
+
+    #[test]
+    fn storm_copies_and_prowess_count_the_spells_cast_this_turn()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Battlefield,
+            vec![card("Monastery Mentor"), card("Monastery Swiftspear")]
+                .into_iter()
+                .chain((0..8).map(|_| card("Mountain")))
+                .collect(),
+        );
+        zones.insert(
+            Zone::Hand,
+            vec![
+                card("Lightning Bolt"),
+                card("Lightning Bolt"),
+                card("Empty the Warrens"),
+            ],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.players[0].stats.spells_per_turn.push(0);
+        gs.assign_card_ids();
+        let tokens = |gs: &GameState| {
+            gs.players[0].zones[&Zone::Battlefield]
+                .iter()
+                .filter(|c| crate::token::is_token(c))
+                .count()
+        };
+
+        // Two Bolts, then Empty the Warrens with two copies: six goblins and a monk per spell
+        for _ in 0..3 {
+            let cast = gs
+                .castable_spells(0)
+                .into_iter()
+                .find(|c| c.zone == Zone::Hand)
+                .unwrap();
+            gs.cast(0, &cast);
+            gs.run_priority();
+        }
+        assert_eq!(gs.spells_this_turn, 3);
+        assert_eq!(
+            (
+                gs.players[0].stats.spells_cast,
+                gs.players[0].stats.spells_per_turn.clone()
+            ),
+            (3, vec![3])
+        );
+        assert_eq!(tokens(&gs), 6 + 3);
+        let swiftspear = gs.players[0].zones[&Zone::Battlefield]
+            .iter()
+            .find(|c| c.name == "Monastery Swiftspear")
+            .unwrap();
+        assert_eq!(creature::effective_stats(swiftspear).unwrap().power, 4);
+
+        // Amounts can count the spells too
+        crate::effect::apply(
+            &mut gs,
+            0,
+            &crate::effect::Effect::WithSpellCount(Box::new(crate::effect::Effect::GainLife(0))),
+        );
+        assert_eq!(gs.players[0].life, 23);
+    }
+
+    #[test]
+    fn noncreature_spells_trigger_prowess_and_cast_abilities()
+    {
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Battlefield,
+            vec![
+                card("Young Pyromancer"),
+                card("Guttersnipe"),
+                card("Monastery Swiftspear"),
+                card("Mountain"),
+                card("Mountain"),
+            ],
+        );
+        zones.insert(
+            Zone::Hand,
+            vec![card("Lightning Bolt"), card("Raging Goblin")],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.assign_card_ids();
+        let battlefield = |gs: &GameState| {
+            gs.players[0].zones[&Zone::Battlefield]
+                .iter()
+                .map(|c| {
+                    (
+                        c.name.clone(),
+                        creature::effective_stats(c).map_or(0, |s| s.power),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The Bolt goes face with Guttersnipe's 2, makes a token and pumps the Swiftspear; the
+        // Goblin, a creature spell, triggers nothing
+        for _ in 0..2 {
+            let cast = gs
+                .castable_spells(0)
+                .into_iter()
+                .find(|c| c.zone == Zone::Hand)
+                .unwrap();
+            gs.cast(0, &cast);
+            gs.run_priority();
+        }
+        assert_eq!(gs.players[1].life, 15);
+        assert_eq!(
+            battlefield(&gs),
+            [
+                ("Young Pyromancer".to_string(), 2),
+                ("Guttersnipe".to_string(), 2),
+                ("Monastery Swiftspear".to_string(), 2),
+                ("Mountain".to_string(), 0),
+                ("Mountain".to_string(), 0),
+                ("1/1 Token".to_string(), 1),
+                ("Raging Goblin".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn impulse_draw_and_cascade_play_cards_from_exile()
+    {
+        let names = |cards: &[Card]| cards.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Battlefield,
+            vec![
+                card("Mountain"),
+                card("Mountain"),
+                card("Mountain"),
+                card("Mountain"),
+            ],
+        );
+        zones.insert(Zone::Hand, vec![card("Act on Impulse")]);
+        zones.insert(
+            Zone::Library,
+            vec![card("Forest"), card("Raging Goblin"), card("Mountain")],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.assign_card_ids();
+
+        // The top three are exiled, the Goblin is cast from exile and a land played from there
+        let cast = gs
+            .castable_spells(0)
+            .into_iter()
+            .find(|c| c.zone == Zone::Hand)
+            .unwrap();
+        gs.cast(0, &cast);
+        gs.run_priority();
+        assert_eq!(
+            names(&gs.players[0].zones[&Zone::Exile]),
+            ["Mountain", "Raging Goblin", "Forest"]
+        );
+        let cast = gs
+            .castable_spells(0)
+            .into_iter()
+            .find(|c| c.zone == Zone::Exile)
+            .unwrap();
+        gs.cast(0, &cast);
+        gs.run_priority();
+        gs.step();
+        assert_eq!(names(&gs.players[0].zones[&Zone::Exile]), ["Forest"]);
+        assert!(
+            gs.players[0].zones[&Zone::Battlefield]
+                .iter()
+                .any(|c| c.name == "Raging Goblin")
+        );
+        let forest = gs.players[0].zones[&Zone::Exile][0].clone();
+        assert!(gs.may_play_from_exile(0, &forest));
+        gs.turns += 1;
+        assert!(!gs.may_play_from_exile(0, &forest));
+
+        // Bloodbraid Elf skips the Forest and cascades into the Goblin, cast for free
+        let mut zones = std::collections::HashMap::new();
+        zones.insert(
+            Zone::Battlefield,
+            vec![
+                card("Mountain"),
+                card("Mountain"),
+                card("Forest"),
+                card("Forest"),
+            ],
+        );
+        zones.insert(Zone::Hand, vec![card("Bloodbraid Elf")]);
+        zones.insert(
+            Zone::Library,
+            vec![
+                card("Lightning Bolt"),
+                card("Raging Goblin"),
+                card("Forest"),
+            ],
+        );
+        let mut gs = game_with_zones(zones, GameStep::Main);
+        gs.assign_card_ids();
+        let cast = gs
+            .castable_spells(0)
+            .into_iter()
+            .find(|c| c.zone == Zone::Hand)
+            .unwrap();
+        gs.cast(0, &cast);
+        gs.run_priority();
+        assert!(["Bloodbraid Elf", "Raging Goblin"].iter().all(|n| {
+            gs.players[0].zones[&Zone::Battlefield]
+                .iter()
+                .any(|c| c.name == *n)
+        }));
+        assert_eq!(
+            names(&gs.players[0].zones[&Zone::Library]),
+            ["Forest", "Lightning Bolt"]
+        );
+        assert!(gs.players[0].zones[&Zone::Exile].is_empty());
+    }
+
     #[test]
     fn untap_phase_clears_tapped_state()
     {
@@ -703,10 +4853,130 @@ mod tests
         zones.insert(Zone::Library, Vec::new());
         zones.insert(Zone::Graveyard, Vec::new());
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Untap };
+        let mut gs = game_with_zones(zones, GameStep::Untap);
         gs.step();
 
-        let bf = gs.zones.get(&Zone::Battlefield).unwrap();
+        let bf = gs.zones().get(&Zone::Battlefield).unwrap();
         assert!(!crate::tappable::is_tapped(&bf[0]));
     }
+
+    #[test]
+    fn same_seed_produces_identical_games()
+    {
+        let deck = Deck::example();
+        let mut a = GameState::new_seeded(2, &deck, 42);
+        let mut b = GameState::new_seeded(2, &deck, 42);
+
+        let names = |gs: &GameState| {
+            gs.zones()
+                .get(&Zone::Library)
+                .unwrap()
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&a), names(&b));
+
+        while !a.is_game_over() {
+            a.step();
+        }
+        while !b.is_game_over() {
+            b.step();
+        }
+        assert_eq!(a.turns, b.turns);
+        assert_eq!(a.players[0].life, b.players[0].life);
+        assert_eq!(a.players[1].life, b.players[1].life);
+    }
+
+    #[test]
+    fn saved_game_continues_where_it_left_off()
+    {
+        let deck = Deck::example();
+        let mut game = GameState::new_seeded(2, &deck, 3);
+        while game.turns < 8 {
+            game.step();
+        }
+        let creatures = |gs: &GameState| {
+            gs.players
+                .iter()
+                .flat_map(|p| p.zones.get(&Zone::Battlefield).unwrap().iter())
+                .filter_map(|c| creature::effective_stats(c).map(|s| (s.power, s.toughness)))
+                .collect::<Vec<_>>()
+        };
+        assert!(!creatures(&game).is_empty());
+
+        let path =
+            std::env::temp_dir().join(format!("tcg-sim-saved-game-{}.json", std::process::id()));
+        game.save(&path).unwrap();
+        let mut loaded = GameState::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(creatures(&loaded), creatures(&game));
+
+        while !game.is_game_over() {
+            game.step();
+        }
+        while !loaded.is_game_over() {
+            loaded.step();
+        }
+        assert_eq!(loaded.turns, game.turns);
+        assert_eq!(loaded.winner(), game.winner());
+
+        let state = ProgramState {
+            games_per_scenario: 500,
+            mulligan: Arc::new(LandRange { min: 3, max: 4 }),
+            opponent_deck: Some(Deck::goldfish()),
+            ..ProgramState::new()
+        };
+        let restored: ProgramState =
+            serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(restored.games_per_scenario, 500);
+        assert_eq!(restored.mulligan.name(), state.mulligan.name());
+        assert_eq!(restored.opponent_deck.map(|d| d.cards.len()), Some(60));
+    }
+
+    #[test]
+    fn rollout_copies_share_fragments_until_written()
+    {
+        let deck = Deck::example();
+        let mut game = GameState::new_seeded(2, &deck, 5);
+        let creature_index = |game: &GameState| {
+            game.zones()
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .position(creature::is_creature)
+        };
+        while creature_index(&game).is_none() {
+            game.step();
+        }
+        let index = creature_index(&game).unwrap();
+
+        let mut copy = game.clone_for_rollout();
+        let shared = |a: &Card, b: &Card| {
+            Arc::ptr_eq(
+                &a.fragments[&CardFragmentKind::Creature],
+                &b.fragments[&CardFragmentKind::Creature],
+            )
+        };
+        assert!(shared(
+            &game.zones().get(&Zone::Battlefield).unwrap()[index],
+            &copy.zones().get(&Zone::Battlefield).unwrap()[index]
+        ));
+
+        creature::deal_damage(
+            &mut copy.zones_mut().get_mut(&Zone::Battlefield).unwrap()[index],
+            1,
+            false,
+        );
+        let original = &game.zones().get(&Zone::Battlefield).unwrap()[index];
+        let changed = &copy.zones().get(&Zone::Battlefield).unwrap()[index];
+        assert!(!shared(original, changed));
+        assert_eq!(
+            (
+                creature::marked_damage(original),
+                creature::marked_damage(changed)
+            ),
+            (0, 1)
+        );
+    }
 }
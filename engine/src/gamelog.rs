@@ -0,0 +1,364 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, CardId};
+use crate::game::GameState;
+
+/// Something a player did or had happen to them, as written to the game log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LogEvent
+{
+    Draw
+    {
+        card: LoggedCard
+    },
+    Discard
+    {
+        card: LoggedCard
+    },
+    LandDrop
+    {
+        card: LoggedCard
+    },
+    Cast
+    {
+        card: LoggedCard
+    },
+    // The player attacked `defender` with these creatures
+    Attack
+    {
+        defender: usize,
+        attackers: Vec<LoggedCard>,
+    },
+    // Damage the player's `source` dealt, or damage from a spell when unset
+    Damage
+    {
+        source: Option<LoggedCard>,
+        target: DamageTarget,
+        amount: u32,
+    },
+    // One of the player's creatures died
+    Death
+    {
+        card: LoggedCard
+    },
+    Loss
+    {
+        condition: String
+    },
+    // The player looked at this many cards on top of their library and kept some there
+    Scry
+    {
+        looked: u32, kept: u32
+    },
+    // ... putting the rest into their graveyard
+    Surveil
+    {
+        looked: u32, kept: u32
+    },
+    // The player searched their library for a card
+    Tutor
+    {
+        card: LoggedCard
+    },
+    // The player activated a loyalty ability of this planeswalker, changing its loyalty
+    Loyalty
+    {
+        card: LoggedCard, change: i32
+    },
+    // The player's life total changed by `change`, to `total`
+    Life
+    {
+        change: i32, total: i32
+    },
+}
+
+/// A card as the log names it: its id, which tells apart copies of the same card, and its name
+/// for whoever reads the log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoggedCard
+{
+    pub id: CardId,
+    pub name: String,
+}
+
+impl From<&Card> for LoggedCard
+{
+    fn from(card: &Card) -> Self
+    {
+        LoggedCard {
+            id: card.id,
+            name: card.name.clone(),
+        }
+    }
+}
+
+impl fmt::Display for LogEvent
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            LogEvent::Draw { card } => write!(f, "draws {}", card.name),
+            LogEvent::Discard { card } => write!(f, "discards {}", card.name),
+            LogEvent::LandDrop { card } => write!(f, "plays {}", card.name),
+            LogEvent::Cast { card } => write!(f, "casts {}", card.name),
+            LogEvent::Attack {
+                defender,
+                attackers,
+            } => write!(
+                f,
+                "attacks player {} with {}",
+                defender + 1,
+                attackers
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            LogEvent::Damage {
+                source,
+                target,
+                amount,
+            } => {
+                let target = match target {
+                    DamageTarget::Player(player) => format!("player {}", player + 1),
+                    DamageTarget::Creature(card) | DamageTarget::Planeswalker(card) => {
+                        card.name.clone()
+                    }
+                };
+                match source {
+                    Some(source) => write!(
+                        f,
+                        "deals {} damage to {} with {}",
+                        amount, target, source.name
+                    ),
+                    None => write!(f, "deals {} damage to {}", amount, target),
+                }
+            }
+            LogEvent::Death { card } => write!(f, "loses {} to the graveyard", card.name),
+            LogEvent::Loss { condition } => write!(f, "loses the game to {}", condition),
+            LogEvent::Scry { looked, kept } => {
+                write!(f, "scries {}, keeping {} on top", looked, kept)
+            }
+            LogEvent::Surveil { looked, kept } => {
+                write!(f, "surveils {}, keeping {} on top", looked, kept)
+            }
+            LogEvent::Tutor { card } => write!(f, "searches their library for {}", card.name),
+            LogEvent::Loyalty { card, change } => {
+                write!(f, "activates {}'s {:+} ability", card.name, change)
+            }
+            LogEvent::Life { change, total } if *change >= 0 => {
+                write!(f, "gains {} life, to {}", change, total)
+            }
+            LogEvent::Life { change, total } => write!(f, "loses {} life, to {}", -change, total),
+        }
+    }
+}
+
+/// What took damage: a player by index, a creature or a planeswalker.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DamageTarget
+{
+    Player(usize),
+    Creature(LoggedCard),
+    Planeswalker(LoggedCard),
+}
+
+/// One line of the log: the event, the game it happened in (by seed), whose turn number it was
+/// and which player it concerns.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord
+{
+    pub game: u64,
+    pub turn: u32,
+    pub player: usize,
+    #[serde(flatten)]
+    pub event: LogEvent,
+}
+
+/// A player's life total over one game, from the log: (turn, total) after each change.
+pub fn life_trajectory(records: &[LogRecord], game: u64, player: usize) -> Vec<(u32, i32)>
+{
+    records
+        .iter()
+        .filter(|r| r.game == game && r.player == player)
+        .filter_map(|r| {
+            if let LogEvent::Life { total, .. } = r.event {
+                Some((r.turn, total))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// JSON Lines file every logged game appends its events to. Games running on other threads
+/// share it, so their lines interleave; the `game` field tells them apart.
+#[derive(Debug)]
+pub struct GameLog
+{
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl GameLog
+{
+    /// Append to the file at `path`, creating it if needed.
+    pub fn open(path: &Path) -> io::Result<Self>
+    {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(GameLog {
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    pub fn record(&self, record: &LogRecord)
+    {
+        let Ok(line) = serde_json::to_string(record) else {
+            return;
+        };
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writeln!(writer, "{}", line);
+        }
+    }
+
+    pub fn flush(&self) -> io::Result<()>
+    {
+        match self.writer.lock() {
+            Ok(mut writer) => writer.flush(),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// Looks on while a step is under way, so an interactive session can pause in the middle of a
+/// phase: after every action the game logs, and whenever a player is about to get priority.
+pub trait Watcher: Send + Sync
+{
+    fn action(&self, game: &GameState, record: &LogRecord);
+
+    fn priority(&self, game: &GameState, player: usize);
+}
+
+impl fmt::Debug for dyn Watcher
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "watcher")
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::card::Deck;
+    use crate::game::GameState;
+
+    #[test]
+    fn logged_game_writes_one_json_event_per_line()
+    {
+        let path =
+            std::env::temp_dir().join(format!("tcg-sim-game-log-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = Arc::new(GameLog::open(&path).unwrap());
+
+        let deck = Deck::example();
+        let mut game = GameState::with_decks(&[&deck, &deck], 7);
+        game.log = Some(log.clone());
+        while !game.is_game_over() {
+            game.step();
+        }
+        log.flush().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let records: Vec<LogRecord> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(records.iter().all(|r| r.game == 7 && r.turn >= 1));
+        for kind in ["draw", "land_drop", "cast", "attack", "damage", "death"] {
+            assert!(
+                text.contains(&format!("\"event\":\"{}\"", kind)),
+                "no {} event",
+                kind
+            );
+        }
+        assert!(records.iter().any(|r| matches!(
+            r.event,
+            LogEvent::Damage {
+                target: DamageTarget::Player(_),
+                ..
+            }
+        )));
+        // Every change to a life total is logged, so the trajectory ends where the game did
+        let loser = (0..2)
+            .find(|p| game.players[*p].life <= 0)
+            .expect("nobody died");
+        assert_eq!(
+            life_trajectory(&records, 7, loser)
+                .last()
+                .map(|(_, total)| *total),
+            Some(game.players[loser].life)
+        );
+    }
+
+    // Writes down what it's shown, as lines like the ones the step loop prints
+    #[derive(Default)]
+    struct Transcript(std::sync::Mutex<Vec<String>>);
+
+    impl Watcher for Transcript
+    {
+        fn action(&self, _game: &GameState, record: &LogRecord)
+        {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("player {} {}", record.player + 1, record.event));
+        }
+
+        fn priority(&self, game: &GameState, player: usize)
+        {
+            self.0.lock().unwrap().push(format!(
+                "priority {} with {} on the stack",
+                player + 1,
+                game.stack.len()
+            ));
+        }
+    }
+
+    #[test]
+    fn watcher_sees_every_action_and_priority_pass_in_order()
+    {
+        let deck = Deck::example();
+        let mut game = GameState::with_decks(&[&deck, &deck], 7);
+        let transcript = Arc::new(Transcript::default());
+        game.watcher = Some(transcript.clone());
+        while game.turns < 6 {
+            game.step();
+        }
+
+        let lines = transcript.0.lock().unwrap();
+        let cast = lines
+            .iter()
+            .position(|l| l.ends_with("casts Grizzly Bears"))
+            .expect("no spell cast");
+        // Everyone gets priority with the spell on the stack before it resolves
+        assert!(
+            lines[cast + 1].starts_with("priority")
+                && lines[cast + 1].ends_with("with 1 on the stack")
+        );
+        assert!(lines.iter().any(|l| l.ends_with("plays Forest")));
+        assert!(lines.iter().any(|l| l.starts_with("priority 2")));
+        assert!(game.clone_for_rollout().watcher.is_none());
+    }
+}
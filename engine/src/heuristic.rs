@@ -0,0 +1,399 @@
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::card::{Card, CardType};
+use crate::combat::{self, Combat, CreatureDamage};
+use crate::creature;
+use crate::effect::{self, Effect};
+use crate::game::{GameState, Zone};
+use crate::mana;
+use crate::mulligan::{KeepAll, MulliganStrategy};
+use crate::strategy::{Cast, CurveOut, PlayerStrategy};
+
+/// How much `Heuristic` values each feature of a play. Loaded from JSON files like
+/// `data/weights/aggro.json`; missing fields keep their default of 1.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeuristicWeights
+{
+    // Per point of mana spent, or added every turn by a mana source
+    pub mana_efficiency: f64,
+    // Per point of damage a play deals now or adds to every later attack
+    pub damage_per_turn: f64,
+    // Per card drawn, token made or opposing creature removed, net of cards lost in combat
+    pub card_advantage: f64,
+}
+
+impl Default for HeuristicWeights
+{
+    fn default() -> Self
+    {
+        HeuristicWeights {
+            mana_efficiency: 1.0,
+            damage_per_turn: 1.0,
+            card_advantage: 1.0,
+        }
+    }
+}
+
+impl HeuristicWeights
+{
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error>
+    {
+        serde_json::from_str(text)
+    }
+}
+
+/// Greedy pilot: plays the spell whose features score highest under `weights` for as long as
+/// one scores above zero, and picks attacks and blocks by the damage and cards they're expected
+/// to trade (assuming the opponent answers like `CurveOut`). Land drops and instants follow
+/// `CurveOut`.
+#[derive(Clone, Debug)]
+pub struct Heuristic
+{
+    pub weights: HeuristicWeights,
+    pub mulligan: Arc<dyn MulliganStrategy>,
+}
+
+impl Default for Heuristic
+{
+    fn default() -> Self
+    {
+        Heuristic {
+            weights: HeuristicWeights::default(),
+            mulligan: Arc::new(KeepAll),
+        }
+    }
+}
+
+// Damage and cards a spell is worth to `player` once it resolves
+fn effect_features(game: &GameState, player: usize, effect: &Effect) -> (f64, f64)
+{
+    let opponent = (player + 1) % game.players.len();
+    match effect {
+        Effect::DamageOpponents(amount) | Effect::LoseLife(amount) | Effect::Drain(amount) => {
+            (*amount as f64, 0.0)
+        }
+        Effect::OpponentsSacrifice(count) => (0.0, *count as f64),
+        Effect::Sacrifice(count) => (0.0, -(*count as f64)),
+        Effect::DealDamage(amount) => {
+            // A creature it kills is a card; otherwise it goes to the face
+            let kills = game.players[opponent]
+                .zones
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .any(|c| {
+                    creature::effective_stats(c).is_some_and(|s| (s.toughness as u32) <= *amount)
+                });
+            if kills {
+                (0.0, 1.0)
+            } else {
+                (*amount as f64, 0.0)
+            }
+        }
+        Effect::DestroyTarget => (0.0, 1.0),
+        Effect::CopySpell | Effect::Regenerate => (0.0, 1.0),
+        // Another of the player's best creature
+        Effect::CreateTokenCopy => game.players[player]
+            .zones
+            .get(&Zone::Battlefield)
+            .unwrap()
+            .iter()
+            .filter_map(creature::effective_stats)
+            .map(|s| (s.power as f64, 1.0))
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap_or((0.0, 0.0)),
+        // The power it takes off the opponent's biggest creature
+        Effect::SetBaseStats { power, .. } => {
+            let shrunk = game.players[opponent]
+                .zones
+                .get(&Zone::Battlefield)
+                .unwrap()
+                .iter()
+                .filter_map(creature::effective_stats)
+                .map(|s| s.power.saturating_sub(*power) as f64)
+                .fold(0.0, f64::max);
+            (shrunk, 0.0)
+        }
+        Effect::DrawCards(count) | Effect::ImpulseDraw { count, .. } => (0.0, *count as f64),
+        Effect::PumpCreature { power, .. } | Effect::PumpSelf { power, .. } => (*power as f64, 0.0),
+        Effect::CreateTokens { count, power, .. } => {
+            ((*count * *power as u32) as f64, *count as f64)
+        }
+        // Ten poison counters kill like twenty damage
+        Effect::PoisonOpponents(count) => ((*count * 2) as f64, 0.0),
+        // Counters are worth what they're spent on
+        Effect::GetCounters { .. } => (0.0, 0.0),
+        Effect::SpendCounters { effect, .. } => effect_features(game, player, effect),
+        // Each is worth about half a card once it's cashed in
+        Effect::CreateArtifactTokens { count, .. } => (0.0, *count as f64 / 2.0),
+        // Worth as much as its best mode
+        Effect::Modal(modal) => modal
+            .modes
+            .iter()
+            .map(|mode| {
+                mode.iter()
+                    .map(|e| effect_features(game, player, e))
+                    .fold((0.0, 0.0), |(d, c), (md, mc)| (d + md, c + mc))
+            })
+            .max_by(|a, b| (a.0 + a.1).total_cmp(&(b.0 + b.1)))
+            .unwrap_or((0.0, 0.0)),
+        // Counting the spell itself
+        Effect::WithSpellCount(inner) => {
+            effect_features(game, player, &inner.with_amount(game.spells_this_turn + 1))
+        }
+        // X is about the untapped mana beyond a colored symbol
+        Effect::WithX(inner) => {
+            let mana = game.components(player).untapped_mana().len() as u32;
+            effect_features(game, player, &inner.with_amount(mana.saturating_sub(1)))
+        }
+        _ => (0.0, 0.0),
+    }
+}
+
+impl Heuristic
+{
+    // Weighted value of casting `card` for `player`
+    fn score(&self, game: &GameState, player: usize, card: &Card) -> f64
+    {
+        let mut mana_efficiency = card.cost.mana_value() as f64;
+        if mana::is_mana_source(card) && !card.is_type(CardType::Land) {
+            mana_efficiency += mana::produced_amount(card) as f64;
+        }
+        for effect in effect::spell_effects(card) {
+            if let Effect::AddMana(added) = effect {
+                mana_efficiency += added.mana_value() as f64 - card.cost.mana_value() as f64;
+            }
+        }
+
+        let mut damage = creature::effective_stats(card).map_or(0.0, |s| s.power as f64)
+            + crate::attachment::bonus(card).map_or(0.0, |b| b.power as f64);
+        let mut cards = -(effect::sacrifice_cost(card) as f64);
+        let effects = effect::spell_effects(card).iter().chain(
+            crate::trigger::triggered_abilities(card)
+                .iter()
+                .map(|a| &a.effect),
+        );
+        for effect in effects {
+            let (d, c) = effect_features(game, player, effect);
+            damage += d;
+            cards += c;
+        }
+
+        self.weights.mana_efficiency * mana_efficiency
+            + self.weights.damage_per_turn * damage
+            + self.weights.card_advantage * cards
+    }
+
+    // Weighted value of a combat for the attacking side: damage to the defender, and creatures
+    // each side loses
+    fn combat_score(&self, game: &GameState, combat: &Combat) -> f64
+    {
+        let attacking = game.players[game.current_player_index]
+            .zones
+            .get(&Zone::Battlefield)
+            .unwrap();
+        let defending = game.players[combat.defending_player]
+            .zones
+            .get(&Zone::Battlefield)
+            .unwrap();
+        let report = combat::assign_damage(attacking, defending, combat);
+        let trade = deaths(defending, &report.to_blockers) as f64
+            - deaths(attacking, &report.to_attackers) as f64;
+        self.weights.damage_per_turn * report.to_player as f64 + self.weights.card_advantage * trade
+    }
+}
+
+// Creatures the damage destroys
+fn deaths(cards: &[Card], damage: &[CreatureDamage]) -> u32
+{
+    damage
+        .iter()
+        .filter(|d| {
+            let toughness =
+                creature::effective_stats(&cards[d.index]).map_or(0, |s| s.toughness as u32);
+            let survives = crate::keyword::is_indestructible(&cards[d.index])
+                || creature::regeneration_shields(&cards[d.index]) > 0;
+            !survives
+                && (d.deathtouch
+                    || d.amount + creature::marked_damage(&cards[d.index]) >= toughness)
+        })
+        .count() as u32
+}
+
+impl PlayerStrategy for Heuristic
+{
+    fn name(&self) -> String
+    {
+        String::from("heuristic")
+    }
+
+    fn mulligan(&self) -> &dyn MulliganStrategy
+    {
+        self.mulligan.as_ref()
+    }
+
+    fn land_drop(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<usize>
+    {
+        CurveOut::default().land_drop(game, player, rng)
+    }
+
+    // Instants wait for `respond`, unless they're rituals that make something else affordable
+    fn main_phase_cast(
+        &self,
+        game: &GameState,
+        player: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> Option<Cast>
+    {
+        game.castable_spells(player)
+            .into_iter()
+            .filter_map(|cast| {
+                let card = game.spell(player, &cast);
+                let held = cast.zone == Zone::Hand
+                    && card.is_type(CardType::Instant)
+                    && !game.ritual_enables(player, card);
+                (!held).then(|| (self.score(game, player, card), cast))
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, cast)| cast)
+    }
+
+    fn respond(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<Cast>
+    {
+        CurveOut::default().respond(game, player, rng)
+    }
+
+    // Best of attacking with what `CurveOut` would, with everything, or not at all
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        let attacking = game.players[player].zones.get(&Zone::Battlefield).unwrap();
+        let defender = &game.players[defending_player];
+        let defending = defender.zones.get(&Zone::Battlefield).unwrap();
+        let everything = game.components(player).ready_attackers();
+
+        [
+            CurveOut::default().attackers(game, player, defending_player, rng),
+            everything,
+        ]
+        .into_iter()
+        .map(|attackers| {
+            let blocks = combat::choose_blockers(attacking, &attackers, defending, defender.life);
+            let score = self.combat_score(
+                game,
+                &Combat {
+                    defending_player,
+                    attackers: attackers.clone(),
+                    blocks,
+                    planeswalkers: Vec::new(),
+                },
+            );
+            (score, attackers)
+        })
+        .filter(|(score, _)| *score > 0.0)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, attackers)| attackers)
+        .unwrap_or_default()
+    }
+
+    // `CurveOut`'s blocks or none, whichever costs less; never takes lethal damage if blocking helps
+    fn blockers(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Vec<(usize, usize)>
+    {
+        let life = game.players[player].life as f64;
+        [CurveOut::default().blockers(game, player, rng), Vec::new()]
+            .into_iter()
+            .map(|blocks| {
+                let combat = Combat {
+                    blocks: blocks.clone(),
+                    ..game.combat.clone()
+                };
+                let attacking = game.players[game.current_player_index]
+                    .zones
+                    .get(&Zone::Battlefield)
+                    .unwrap();
+                let lethal = combat::assign_damage(
+                    attacking,
+                    game.players[player].zones.get(&Zone::Battlefield).unwrap(),
+                    &combat,
+                )
+                .to_player as f64
+                    >= life;
+                let cost = if lethal {
+                    f64::INFINITY
+                } else {
+                    self.combat_score(game, &combat)
+                };
+                (cost, blocks)
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, blocks)| blocks)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{Deck, card_by_name, forest};
+    use rand::SeedableRng;
+
+    #[test]
+    fn weights_decide_between_plays()
+    {
+        let deck = Deck::new(vec![
+            card_by_name("Grizzly Bears").unwrap(),
+            card_by_name("Elvish Visionary").unwrap(),
+        ]);
+        let mut game = GameState::with_decks(&[&deck, &Deck::goldfish()], 0);
+        *game.players[0].zones.get_mut(&Zone::Battlefield).unwrap() = vec![forest(), forest()];
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let pick = |weights: HeuristicWeights, game: &GameState, rng: &mut ChaCha8Rng| {
+            let cast = Heuristic {
+                weights,
+                ..Heuristic::default()
+            }
+            .main_phase_cast(game, 0, rng)
+            .unwrap();
+            game.players[0].zones.get(&Zone::Hand).unwrap()[cast.index]
+                .name
+                .clone()
+        };
+
+        let aggro =
+            HeuristicWeights::from_json(include_str!("../data/weights/aggro.json")).unwrap();
+        assert_eq!(pick(aggro, &game, &mut rng), "Grizzly Bears");
+        let control =
+            HeuristicWeights::from_json(include_str!("../data/weights/control.json")).unwrap();
+        assert_eq!(pick(control, &game, &mut rng), "Elvish Visionary");
+
+        // Nothing worth casting: pass
+        let nothing = HeuristicWeights {
+            mana_efficiency: -1.0,
+            damage_per_turn: 0.0,
+            card_advantage: 0.0,
+        };
+        assert!(
+            Heuristic {
+                weights: nothing,
+                ..Heuristic::default()
+            }
+            .main_phase_cast(&game, 0, &mut rng)
+            .is_none()
+        );
+        assert_eq!(
+            HeuristicWeights::from_json("{}").unwrap(),
+            HeuristicWeights::default()
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Context, Editor, Helper};
+
+/// Commands of the step loop: how each is typed and what it does.
+pub const STEP_COMMANDS: [(&str, &str); 16] = [
+    ("s", "step one phase"),
+    (
+        "a",
+        "step to the next action: a draw, land, spell, attack, damage or death",
+    ),
+    (
+        "p",
+        "step to the next time a player gets priority, showing the stack",
+    ),
+    ("t", "step one whole turn"),
+    ("g", "run the current game to completion"),
+    (
+        "g N",
+        "run the current game to the start of turn N, then keep stepping",
+    ),
+    ("d", "run the simulation to completion for the current deck"),
+    ("r", "run the whole simulation to completion (all decks)"),
+    (
+        "h [N]",
+        "deal sample opening hands for the current deck (N of them)",
+    ),
+    (
+        "b",
+        "show the board: life, hand sizes, permanents and untapped lands",
+    ),
+    ("u", "undo the last step; repeat to go further back"),
+    (
+        "break when EXPR",
+        "play on until e.g. opponent_life <= 5, turn == 6 or creatures > 2 and lands >= 4",
+    ),
+    ("save FILE", "save the game being stepped through"),
+    ("load FILE", "continue from a saved game instead"),
+    ("q", "quit"),
+    ("help", "list these commands"),
+];
+
+/// Commands taken between decks, as well as at the first prompt.
+pub const SESSION_COMMANDS: [(&str, &str); 5] = [
+    (
+        "profile save NAME",
+        "save the current settings as a profile",
+    ),
+    ("profile load NAME", "switch to a saved profile's settings"),
+    ("profile list", "list saved profiles"),
+    ("replay FILE", "step through a game saved with --record"),
+    (
+        "opponent FILE",
+        "play the next decks against the decklist in FILE",
+    ),
+];
+
+/// Every command with its description, for the `help` command.
+pub fn help() -> String
+{
+    let width = STEP_COMMANDS
+        .iter()
+        .chain(SESSION_COMMANDS.iter())
+        .map(|(syntax, _)| syntax.len())
+        .max()
+        .unwrap_or(0);
+    let list = |commands: &[(&str, &str)]| {
+        commands
+            .iter()
+            .map(|(syntax, what)| format!("  {:<width$} -> {}", syntax, what, width = width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    format!(
+        "Commands:\n{}\nBetween decks:\n{}",
+        list(&STEP_COMMANDS),
+        list(&SESSION_COMMANDS)
+    )
+}
+
+// Where commands come from
+enum Source
+{
+    // A --script file's commands still to run
+    Script(VecDeque<String>),
+    // A terminal, with line editing, history and completion
+    Terminal(Box<Editor<CommandHelper, DefaultHistory>>),
+    // Anything else piped in
+    Stdin,
+}
+
+impl Source
+{
+    fn detect() -> Self
+    {
+        if !io::stdin().is_terminal() {
+            return Source::Stdin;
+        }
+        let config = Config::builder()
+            .completion_type(CompletionType::List)
+            .build();
+        match Editor::with_config(config) {
+            Ok(mut editor) => {
+                editor.set_helper(Some(CommandHelper {
+                    files: FilenameCompleter::new(),
+                }));
+                Source::Terminal(Box::new(editor))
+            }
+            Err(_) => Source::Stdin,
+        }
+    }
+}
+
+static SOURCE: Mutex<Option<Source>> = Mutex::new(None);
+// Where saved profiles are, to complete their names
+static PROFILE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+// Decklists named on the command line, to complete `opponent` with
+static DECK_FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Take interactive commands from the file at `path`, one per line, instead of stdin. Blank
+/// lines and lines starting with `#` are skipped. Returns how many commands it holds.
+pub fn use_script(path: &Path) -> io::Result<usize>
+{
+    let text = fs::read_to_string(path)?;
+    let commands = parse_script(&text);
+    let count = commands.len();
+    *SOURCE.lock().unwrap() = Some(Source::Script(commands));
+    Ok(count)
+}
+
+fn parse_script(text: &str) -> VecDeque<String>
+{
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Complete `profile load` and `profile save` with the names of the profiles saved in `dir`.
+pub fn set_profile_dir(dir: &Path)
+{
+    *PROFILE_DIR.lock().unwrap() = Some(dir.to_path_buf());
+}
+
+/// Complete `opponent` with these decklists, e.g. the ones named on the command line.
+pub fn set_deck_files(paths: &[&Path])
+{
+    *DECK_FILES.lock().unwrap() = paths.iter().map(|p| p.display().to_string()).collect();
+}
+
+/// Prompt for the next command and read it, trimmed; `help` is answered here. Script commands
+/// are echoed after the prompt so the output reads like a session typed by hand. `None` once
+/// the input is used up: the end of the script, stdin closing, or Ctrl-C/Ctrl-D at a terminal.
+pub fn prompt() -> Option<String>
+{
+    loop {
+        let line = read()?;
+        if line == "help" || line == "?" {
+            println!("{}", help());
+            continue;
+        }
+        return Some(line);
+    }
+}
+
+fn read() -> Option<String>
+{
+    let mut source = SOURCE.lock().unwrap();
+    match source.get_or_insert_with(Source::detect) {
+        Source::Script(commands) => {
+            let command = commands.pop_front();
+            println!("> {}", command.as_deref().unwrap_or(""));
+            command
+        }
+        Source::Terminal(editor) => match editor.readline("> ") {
+            Ok(line) => {
+                let line = line.trim().to_string();
+                if !line.is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                }
+                Some(line)
+            }
+            Err(ReadlineError::Eof | ReadlineError::Interrupted) => None,
+            Err(e) => {
+                vlog!(
+                    crate::ELoggingVerbosity::Warning,
+                    "Can't read the command: {}",
+                    e
+                );
+                None
+            }
+        },
+        Source::Stdin => {
+            print!("> ");
+            io::stdout().flush().unwrap();
+            let mut input = String::new();
+            match io::stdin().lock().read_line(&mut input) {
+                Ok(0) | Err(_) => {
+                    println!();
+                    None
+                }
+                Ok(_) => Some(input.trim().to_string()),
+            }
+        }
+    }
+}
+
+// Completions for the word before the cursor that don't need the file system: where the word
+// starts, and the candidates
+fn complete_word(line: &str, profiles: &[String], decks: &[String]) -> (usize, Vec<String>)
+{
+    let start = line.rfind(' ').map_or(0, |space| space + 1);
+    let (before, word) = line.split_at(start);
+    let candidates: Vec<String> = match before.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [] => {
+            let mut names: Vec<String> = STEP_COMMANDS
+                .iter()
+                .chain(SESSION_COMMANDS.iter())
+                .filter_map(|(syntax, _)| syntax.split(' ').next())
+                .map(String::from)
+                .collect();
+            names.dedup();
+            names
+        }
+        ["break"] => vec![String::from("when")],
+        ["profile"] => vec![
+            String::from("save"),
+            String::from("load"),
+            String::from("list"),
+        ],
+        ["profile", "load" | "save"] => profiles.to_vec(),
+        ["opponent"] => decks.to_vec(),
+        _ => Vec::new(),
+    };
+    (
+        start,
+        candidates
+            .into_iter()
+            .filter(|c| c.starts_with(word))
+            .collect(),
+    )
+}
+
+// Completes command names and their arguments at the terminal
+struct CommandHelper
+{
+    files: FilenameCompleter,
+}
+
+impl Completer for CommandHelper
+{
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)>
+    {
+        let line = &line[..pos];
+        // Commands that take a file: decks, saved games and recordings are all files
+        if ["save ", "load ", "replay "]
+            .iter()
+            .any(|command| line.starts_with(command))
+        {
+            return self.files.complete_path(line, pos);
+        }
+
+        let profiles = PROFILE_DIR
+            .lock()
+            .unwrap()
+            .as_deref()
+            .map(crate::profile::list)
+            .unwrap_or_default();
+        let decks = DECK_FILES.lock().unwrap().clone();
+        let (start, candidates) = complete_word(line, &profiles, &decks);
+        // Any other decklist file will do too
+        if candidates.is_empty() && line.starts_with("opponent ") {
+            return self.files.complete_path(line, pos);
+        }
+        Ok((
+            start,
+            candidates
+                .into_iter()
+                .map(|c| Pair {
+                    display: c.clone(),
+                    replacement: c,
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl Hinter for CommandHelper
+{
+    type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn scripts_skip_blank_lines_and_comments()
+    {
+        let script = parse_script("# Step into the game\ns\n\n  t  \n# then finish it\ng\nq\n");
+        assert_eq!(script, ["s", "t", "g", "q"]);
+    }
+
+    #[test]
+    fn command_names_and_profiles_complete()
+    {
+        let profiles = vec![String::from("commander"), String::from("limited")];
+        assert_eq!(
+            complete_word("br", &profiles, &[]),
+            (0, vec![String::from("break")])
+        );
+        assert_eq!(
+            complete_word("pro", &profiles, &[]),
+            (0, vec![String::from("profile")])
+        );
+        assert_eq!(
+            complete_word("profile l", &profiles, &[]),
+            (8, vec![String::from("load"), String::from("list")])
+        );
+        assert_eq!(
+            complete_word("profile load co", &profiles, &[]),
+            (13, vec![String::from("commander")])
+        );
+        assert_eq!(
+            complete_word("break w", &profiles, &[]),
+            (6, vec![String::from("when")])
+        );
+        assert!(complete_word("g ", &profiles, &[]).1.is_empty());
+        assert!(help().contains("break when EXPR"));
+    }
+
+    #[test]
+    fn opponent_completes_deck_files()
+    {
+        let decks = vec![
+            String::from("decks/burn.txt"),
+            String::from("decks/elves.txt"),
+        ];
+        assert_eq!(
+            complete_word("opp", &[], &decks),
+            (0, vec![String::from("opponent")])
+        );
+        assert_eq!(complete_word("opponent ", &[], &decks), (9, decks.clone()));
+        assert_eq!(
+            complete_word("opponent decks/e", &[], &decks),
+            (9, vec![String::from("decks/elves.txt")])
+        );
+    }
+}
@@ -0,0 +1,498 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::card::{Card, CardType, KeywordsFragment};
+use crate::mana::ManaColor;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Keyword
+{
+    Flying,
+    Haste,
+    Trample,
+    Lifelink,
+    Deathtouch,
+    FirstStrike,
+    // Damage to players is dealt as poison counters (damage to creatures is still regular damage)
+    Infect,
+    // Can be cast any time its controller could cast an instant
+    Flash,
+    Vigilance,
+    // Can't be blocked except by two or more creatures
+    Menace,
+    // Can block creatures with flying
+    Reach,
+    Defender,
+    // Isn't destroyed by lethal damage or destroy effects
+    Indestructible,
+    // When cast, the spell is copied once for each spell cast before it this turn
+    Storm,
+    // Gets +1/+1 until end of turn whenever its controller casts a noncreature spell
+    Prowess,
+    // When cast, exiles cards from the top of the library until a cheaper nonland card, which
+    // may be cast for free
+    Cascade,
+    // Can't be blocked, targeted or dealt damage by anything with the quality, e.g.
+    // { "ProtectionFrom": { "Color": "B" } }
+    ProtectionFrom(Quality),
+}
+
+/// What a protection keyword protects from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Quality
+{
+    Color(ManaColor),
+    Type(CardType),
+}
+
+impl Quality
+{
+    /// Whether `card` has the quality. A card's colors are those of its mana cost.
+    pub fn matches(&self, card: &Card) -> bool
+    {
+        match self {
+            Quality::Color(color) => card.cost.colors().contains(color),
+            Quality::Type(card_type) => card.is_type(*card_type),
+        }
+    }
+}
+
+/// Rules hooks a keyword can override. Every rule asks all of a card's keywords, so adding a
+/// keyword only means adding a variant and an implementation of the hooks it changes.
+pub trait KeywordHooks: Sync
+{
+    // Can attack the turn it comes under its controller's control
+    fn ignores_summoning_sickness(&self) -> bool
+    {
+        false
+    }
+
+    // Evasion: whether `blocker` may block the creature with this keyword
+    fn can_be_blocked_by(&self, _blocker: &Card) -> bool
+    {
+        true
+    }
+
+    // Blocking side of flying: may block creatures with flying
+    fn blocks_fliers(&self) -> bool
+    {
+        false
+    }
+
+    // Fewest creatures that may block the creature with this keyword
+    fn min_blockers(&self) -> usize
+    {
+        1
+    }
+
+    fn can_attack(&self) -> bool
+    {
+        true
+    }
+
+    fn taps_to_attack(&self) -> bool
+    {
+        true
+    }
+
+    fn deals_first_strike_damage(&self) -> bool
+    {
+        false
+    }
+
+    // Any amount of damage from this creature is lethal
+    fn damage_is_lethal(&self) -> bool
+    {
+        false
+    }
+
+    // Damage beyond lethal to blockers is dealt to the defending player
+    fn tramples(&self) -> bool
+    {
+        false
+    }
+
+    // Damage to players gives poison counters instead of costing life
+    fn damages_players_with_poison(&self) -> bool
+    {
+        false
+    }
+
+    // Life its controller gains when it deals `damage`
+    fn life_gained_from_damage(&self, _damage: u32) -> u32
+    {
+        0
+    }
+
+    // The spell may be cast at instant speed
+    fn has_flash(&self) -> bool
+    {
+        false
+    }
+
+    // Survives lethal damage and destroy effects
+    fn indestructible(&self) -> bool
+    {
+        false
+    }
+
+    // The spell is copied for each spell cast before it this turn
+    fn copied_for_earlier_spells(&self) -> bool
+    {
+        false
+    }
+
+    // +1/+1 until end of turn whenever its controller casts a noncreature spell
+    fn pumped_by_noncreature_spells(&self) -> bool
+    {
+        false
+    }
+
+    // Casting the spell cascades into a cheaper spell from the top of the library
+    fn cascades(&self) -> bool
+    {
+        false
+    }
+
+    // Can't be targeted or dealt damage by `source`
+    fn protects_from(&self, _source: &Card) -> bool
+    {
+        false
+    }
+}
+
+struct Flying;
+struct Haste;
+struct Trample;
+struct Lifelink;
+struct Deathtouch;
+struct FirstStrike;
+struct Infect;
+struct Flash;
+struct Vigilance;
+struct Menace;
+struct Reach;
+struct Defender;
+struct Indestructible;
+struct Storm;
+struct Prowess;
+struct Cascade;
+
+impl KeywordHooks for Flying
+{
+    fn can_be_blocked_by(&self, blocker: &Card) -> bool
+    {
+        any(blocker, |h| h.blocks_fliers())
+    }
+
+    fn blocks_fliers(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Haste
+{
+    fn ignores_summoning_sickness(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Trample
+{
+    fn tramples(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Lifelink
+{
+    fn life_gained_from_damage(&self, damage: u32) -> u32
+    {
+        damage
+    }
+}
+
+impl KeywordHooks for Deathtouch
+{
+    fn damage_is_lethal(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for FirstStrike
+{
+    fn deals_first_strike_damage(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Infect
+{
+    fn damages_players_with_poison(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Flash
+{
+    fn has_flash(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Vigilance
+{
+    fn taps_to_attack(&self) -> bool
+    {
+        false
+    }
+}
+
+impl KeywordHooks for Menace
+{
+    fn min_blockers(&self) -> usize
+    {
+        2
+    }
+}
+
+impl KeywordHooks for Reach
+{
+    fn blocks_fliers(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Defender
+{
+    fn can_attack(&self) -> bool
+    {
+        false
+    }
+}
+
+impl KeywordHooks for Indestructible
+{
+    fn indestructible(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Storm
+{
+    fn copied_for_earlier_spells(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Prowess
+{
+    fn pumped_by_noncreature_spells(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Cascade
+{
+    fn cascades(&self) -> bool
+    {
+        true
+    }
+}
+
+impl KeywordHooks for Quality
+{
+    fn can_be_blocked_by(&self, blocker: &Card) -> bool
+    {
+        !self.matches(blocker)
+    }
+
+    fn protects_from(&self, source: &Card) -> bool
+    {
+        self.matches(source)
+    }
+}
+
+impl Keyword
+{
+    pub fn hooks(&self) -> &dyn KeywordHooks
+    {
+        match self {
+            Keyword::Flying => &Flying,
+            Keyword::Haste => &Haste,
+            Keyword::Trample => &Trample,
+            Keyword::Lifelink => &Lifelink,
+            Keyword::Deathtouch => &Deathtouch,
+            Keyword::FirstStrike => &FirstStrike,
+            Keyword::Infect => &Infect,
+            Keyword::Flash => &Flash,
+            Keyword::Vigilance => &Vigilance,
+            Keyword::Menace => &Menace,
+            Keyword::Reach => &Reach,
+            Keyword::Defender => &Defender,
+            Keyword::Indestructible => &Indestructible,
+            Keyword::Storm => &Storm,
+            Keyword::Prowess => &Prowess,
+            Keyword::Cascade => &Cascade,
+            Keyword::ProtectionFrom(quality) => quality,
+        }
+    }
+}
+
+impl FromStr for Keyword
+{
+    type Err = String;
+
+    // Accepts "First strike", "first_strike", "FirstStrike", "Protection from red", ...
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let normalized: String = s
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "flying" => Ok(Keyword::Flying),
+            "haste" => Ok(Keyword::Haste),
+            "trample" => Ok(Keyword::Trample),
+            "lifelink" => Ok(Keyword::Lifelink),
+            "deathtouch" => Ok(Keyword::Deathtouch),
+            "firststrike" => Ok(Keyword::FirstStrike),
+            "infect" => Ok(Keyword::Infect),
+            "flash" => Ok(Keyword::Flash),
+            "vigilance" => Ok(Keyword::Vigilance),
+            "menace" => Ok(Keyword::Menace),
+            "reach" => Ok(Keyword::Reach),
+            "defender" => Ok(Keyword::Defender),
+            "indestructible" => Ok(Keyword::Indestructible),
+            "storm" => Ok(Keyword::Storm),
+            "prowess" => Ok(Keyword::Prowess),
+            "cascade" => Ok(Keyword::Cascade),
+            _ => {
+                let quality = match normalized.strip_prefix("protectionfrom") {
+                    Some("white") => Quality::Color(ManaColor::White),
+                    Some("blue") => Quality::Color(ManaColor::Blue),
+                    Some("black") => Quality::Color(ManaColor::Black),
+                    Some("red") => Quality::Color(ManaColor::Red),
+                    Some("green") => Quality::Color(ManaColor::Green),
+                    Some("creatures") => Quality::Type(CardType::Creature),
+                    Some("artifacts") => Quality::Type(CardType::Artifact),
+                    Some("enchantments") => Quality::Type(CardType::Enchantment),
+                    Some("instants") => Quality::Type(CardType::Instant),
+                    Some("sorceries") => Quality::Type(CardType::Sorcery),
+                    Some("planeswalkers") => Quality::Type(CardType::Planeswalker),
+                    _ => return Err(format!("unsupported keyword '{}'", s)),
+                };
+                Ok(Keyword::ProtectionFrom(quality))
+            }
+        }
+    }
+}
+
+// Printed keywords, then any granted by other permanents or until end of turn (without duplicates)
+pub fn keywords(card: &Card) -> Vec<Keyword>
+{
+    let mut keywords: Vec<Keyword> = card
+        .fragment::<KeywordsFragment>()
+        .map(|kf| kf.keywords.clone())
+        .unwrap_or_default();
+    for keyword in crate::creature::granted_keywords(card) {
+        if !keywords.contains(keyword) {
+            keywords.push(*keyword);
+        }
+    }
+    keywords
+}
+
+pub fn has_keyword(card: &Card, keyword: Keyword) -> bool
+{
+    keywords(card).contains(&keyword)
+}
+
+pub fn add_keyword(card: &mut Card, keyword: Keyword)
+{
+    let kf = card.fragment_or_insert_with(|| KeywordsFragment {
+        keywords: Vec::new(),
+    });
+    if !kf.keywords.contains(&keyword) {
+        kf.keywords.push(keyword);
+    }
+}
+
+/// Whether a spell can be cast whenever its controller has priority: it's an instant or has flash.
+pub fn instant_speed(card: &Card) -> bool
+{
+    card.is_type(CardType::Instant) || any(card, |h| h.has_flash())
+}
+
+/// Whether any of the card's keywords answers yes.
+pub fn any(card: &Card, hook: impl Fn(&dyn KeywordHooks) -> bool) -> bool
+{
+    keywords(card).iter().any(|k| hook(k.hooks()))
+}
+
+/// Whether all of the card's keywords answer yes (true for a card without keywords).
+pub fn all(card: &Card, hook: impl Fn(&dyn KeywordHooks) -> bool) -> bool
+{
+    keywords(card).iter().all(|k| hook(k.hooks()))
+}
+
+/// Whether `card` can't be targeted or dealt damage by `source`.
+pub fn protected_from(card: &Card, source: &Card) -> bool
+{
+    any(card, |h| h.protects_from(source))
+}
+
+pub fn is_indestructible(card: &Card) -> bool
+{
+    any(card, |h| h.indestructible())
+}
+
+// Redundant instances don't stack, so the most generous keyword wins
+pub fn life_gained_from_damage(card: &Card, damage: u32) -> u32
+{
+    keywords(card)
+        .iter()
+        .map(|k| k.hooks().life_gained_from_damage(damage))
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::grizzly_bears;
+
+    #[test]
+    fn keywords_parse_and_drive_hooks()
+    {
+        assert_eq!("First strike".parse::<Keyword>(), Ok(Keyword::FirstStrike));
+        assert!("Banding".parse::<Keyword>().is_err());
+        assert_eq!(
+            "Protection from black".parse::<Keyword>(),
+            Ok(Keyword::ProtectionFrom(Quality::Color(ManaColor::Black)))
+        );
+
+        let mut drake = grizzly_bears();
+        add_keyword(&mut drake, Keyword::Flying);
+        add_keyword(&mut drake, Keyword::Flying);
+        assert_eq!(keywords(&drake), &[Keyword::Flying]);
+
+        let bears = grizzly_bears();
+        assert!(!all(&drake, |h| h.can_be_blocked_by(&bears)));
+        assert!(all(&bears, |h| h.can_be_blocked_by(&drake)));
+        assert!(!any(&drake, |h| h.ignores_summoning_sickness()));
+    }
+}
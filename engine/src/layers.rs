@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::card::{
+    ArtifactFragment, Card, CardFragmentKind, CardId, CardType, CreatureFragment, CreatureStats,
+    StatModifier,
+};
+use crate::game::{GameState, Zone};
+use crate::statics::StaticEffect;
+
+/// The order continuous effects on a permanent apply in, a simplified take on the comprehensive
+/// rules' layers: its types change first, then its base power and toughness are set, then
+/// modified. Counters come last. Effects in the same layer apply in timestamp order, so of two
+/// P/T setters the later one wins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Layer
+{
+    TypeChanging,
+    SetPowerToughness,
+    ModifyPowerToughness,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ContinuousEffect
+{
+    // The permanent is a creature in addition to its other types
+    BecomeCreature,
+    // Its base power and toughness become these, e.g. Diminish's 1/1
+    SetPowerToughness(CreatureStats),
+    ModifyPowerToughness(StatModifier),
+}
+
+impl ContinuousEffect
+{
+    pub fn layer(&self) -> Layer
+    {
+        match self {
+            ContinuousEffect::BecomeCreature => Layer::TypeChanging,
+            ContinuousEffect::SetPowerToughness(_) => Layer::SetPowerToughness,
+            ContinuousEffect::ModifyPowerToughness(_) => Layer::ModifyPowerToughness,
+        }
+    }
+}
+
+/// A continuous effect on one permanent, with the timestamp of what created it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayeredEffect
+{
+    pub timestamp: u64,
+    pub effect: ContinuousEffect,
+}
+
+/// `printed` stats with `effects` applied layer by layer, then `counters` (+1/+1 counters less
+/// -1/-1 ones). Neither value goes below zero.
+pub fn power_toughness<'a>(
+    printed: CreatureStats,
+    effects: impl IntoIterator<Item = &'a LayeredEffect>,
+    counters: i32,
+) -> CreatureStats
+{
+    let mut effects: Vec<&LayeredEffect> = effects.into_iter().collect();
+    effects.sort_by_key(|e| (e.effect.layer(), e.timestamp));
+
+    let (mut power, mut toughness) = (printed.power as i32, printed.toughness as i32);
+    for layered in effects {
+        match layered.effect {
+            ContinuousEffect::BecomeCreature => {}
+            ContinuousEffect::SetPowerToughness(stats) => {
+                (power, toughness) = (stats.power as i32, stats.toughness as i32)
+            }
+            ContinuousEffect::ModifyPowerToughness(modifier) => {
+                power += modifier.power;
+                toughness += modifier.toughness;
+            }
+        }
+    }
+
+    let clamp = |value: i32| (value + counters).clamp(0, u8::MAX as i32) as u8;
+    CreatureStats {
+        power: clamp(power),
+        toughness: clamp(toughness),
+    }
+}
+
+// Whether `card` is a creature only because a continuous effect made it one
+fn is_animated(card: &Card) -> bool
+{
+    card.fragment::<CreatureFragment>()
+        .is_some_and(|cf| cf.animated)
+}
+
+// The effects a static ability has on each permanent it applies to
+fn static_effects(
+    battlefields: &[&[Card]],
+    effect: &StaticEffect,
+    timestamp: u64,
+) -> Vec<(CardId, LayeredEffect)>
+{
+    match effect {
+        StaticEffect::AnimateArtifacts => battlefields
+            .iter()
+            .copied()
+            .flatten()
+            .filter(|card| {
+                card.is_type(CardType::Artifact)
+                    && (!crate::creature::is_creature(card) || is_animated(card))
+            })
+            .flat_map(|card| {
+                let value = card.cost.mana_value().min(u8::MAX as u32) as u8;
+                [
+                    ContinuousEffect::BecomeCreature,
+                    ContinuousEffect::SetPowerToughness(CreatureStats {
+                        power: value,
+                        toughness: value,
+                    }),
+                ]
+                .map(|effect| (card.id, LayeredEffect { timestamp, effect }))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Recompute the continuous effects static abilities have on every permanent: noncreatures
+/// some effect makes creatures become them (summoning sick, to be safe), those no longer
+/// animated stop being creatures, and every creature's P/T effects are replaced.
+pub fn apply_statics(game: &mut GameState)
+{
+    let mut effects: HashMap<CardId, Vec<LayeredEffect>> = HashMap::new();
+    {
+        let battlefields: Vec<&[Card]> = game
+            .players
+            .iter()
+            .map(|p| {
+                p.zones
+                    .get(&Zone::Battlefield)
+                    .map_or(&[][..], Vec::as_slice)
+            })
+            .collect();
+        for card in battlefields.iter().copied().flatten() {
+            let timestamp = crate::statics::timestamp(card);
+            for effect in crate::statics::statics(card) {
+                for (target, layered) in static_effects(&battlefields, effect, timestamp) {
+                    effects.entry(target).or_default().push(layered);
+                }
+            }
+        }
+    }
+
+    for player in game.players.iter_mut() {
+        let turn_started_at = player.turn_started_at;
+        for card in player.zones.get_mut(&Zone::Battlefield).unwrap().iter_mut() {
+            let effects = effects.remove(&card.id).unwrap_or_default();
+            let animate = effects
+                .iter()
+                .any(|e| e.effect == ContinuousEffect::BecomeCreature);
+            if animate && !crate::creature::is_creature(card) {
+                // Sick only if it came under its controller's control after their turn began
+                let summoning_sickness = card
+                    .fragment::<ArtifactFragment>()
+                    .is_none_or(|f| f.timestamp >= turn_started_at);
+                card.card_types.push(CardType::Creature);
+                card.fragments.insert(
+                    CardFragmentKind::Creature,
+                    Arc::new(CreatureFragment {
+                        summoning_sickness,
+                        animated: true,
+                        ..CreatureFragment::default()
+                    }),
+                );
+            } else if !animate && is_animated(card) {
+                leave_battlefield(card);
+            }
+            if let Some(cf) = card.fragment_mut::<CreatureFragment>() {
+                cf.effects = effects;
+            }
+        }
+    }
+}
+
+/// A permanent leaving the battlefield stops being a creature if only an effect made it one.
+pub fn leave_battlefield(card: &mut Card)
+{
+    if is_animated(card) {
+        card.card_types.retain(|t| *t != CardType::Creature);
+        card.fragments.remove(&CardFragmentKind::Creature);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn setters_apply_in_timestamp_order_before_modifiers_and_counters()
+    {
+        let printed = CreatureStats {
+            power: 4,
+            toughness: 4,
+        };
+        let effects = [
+            LayeredEffect {
+                timestamp: 1,
+                effect: ContinuousEffect::ModifyPowerToughness(StatModifier {
+                    power: 1,
+                    toughness: 1,
+                }),
+            },
+            LayeredEffect {
+                timestamp: 3,
+                effect: ContinuousEffect::SetPowerToughness(CreatureStats {
+                    power: 0,
+                    toughness: 2,
+                }),
+            },
+            LayeredEffect {
+                timestamp: 2,
+                effect: ContinuousEffect::SetPowerToughness(CreatureStats {
+                    power: 1,
+                    toughness: 1,
+                }),
+            },
+        ];
+
+        let stats = power_toughness(printed, &effects, -1);
+        assert_eq!((stats.power, stats.toughness), (0, 2));
+    }
+}
@@ -1,8 +1,10 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::cell::Cell;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 #[repr(u8)]
 #[derive(Debug, Copy, Eq, Ord, Clone, PartialEq, PartialOrd)]
-pub enum ELoggingVerbosity 
+pub enum ELoggingVerbosity
 {
     Error = 0,
     Warning = 1,
@@ -11,17 +13,63 @@ pub enum ELoggingVerbosity
     VeryVerbose = 4,
 }
 
+impl std::str::FromStr for ELoggingVerbosity
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(ELoggingVerbosity::Error),
+            "warning" | "warn" => Ok(ELoggingVerbosity::Warning),
+            "normal" => Ok(ELoggingVerbosity::Normal),
+            "verbose" => Ok(ELoggingVerbosity::Verbose),
+            "very-verbose" | "veryverbose" => Ok(ELoggingVerbosity::VeryVerbose),
+            _ => Err(format!(
+                "unknown verbosity '{}' (expected silent, error, warning, normal, verbose or very-verbose)",
+                s
+            )),
+        }
+    }
+}
+
+// A level as given to --log and --log-game, where `silent` hides even errors
+fn parse_level(s: &str) -> Result<Option<ELoggingVerbosity>, String>
+{
+    if s.trim().eq_ignore_ascii_case("silent") {
+        Ok(None)
+    } else {
+        s.parse().map(Some)
+    }
+}
+
+// How many levels a filter shows: none when silent, then one per level up to the one it allows
+fn shown(level: Option<ELoggingVerbosity>) -> usize
+{
+    level.map_or(0, |level| level as usize + 1)
+}
+
 static GLOBAL_VERBOSITY: AtomicUsize = AtomicUsize::new(ELoggingVerbosity::Normal as usize);
+// Levels set for single modules, by the module's name
+static MODULE_VERBOSITY: RwLock<Vec<(String, Option<ELoggingVerbosity>)>> = RwLock::new(Vec::new());
+// Levels set for single games, by their seed
+static GAME_VERBOSITY: RwLock<Vec<(u64, Option<ELoggingVerbosity>)>> = RwLock::new(Vec::new());
+// Whether either table has anything in it, so lines are only checked against them when it does
+static FILTERED: AtomicBool = AtomicBool::new(false);
 
-pub fn set_global_verbosity(level: ELoggingVerbosity) 
+thread_local! {
+    // Seed of the game this thread is playing
+    static CURRENT_GAME: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+pub fn set_global_verbosity(level: ELoggingVerbosity)
 {
     GLOBAL_VERBOSITY.store(level as usize, Ordering::Relaxed);
 }
 
-pub fn global_verbosity() -> ELoggingVerbosity 
+pub fn global_verbosity() -> ELoggingVerbosity
 {
-    match GLOBAL_VERBOSITY.load(Ordering::Relaxed) 
-    {
+    match GLOBAL_VERBOSITY.load(Ordering::Relaxed) {
         0 => ELoggingVerbosity::Error,
         1 => ELoggingVerbosity::Warning,
         2 => ELoggingVerbosity::Normal,
@@ -30,26 +78,274 @@ pub fn global_verbosity() -> ELoggingVerbosity
     }
 }
 
+/// One `--log` setting: the level for everything, like `verbose`, or for one module, like
+/// `combat=very-verbose` or `mana=silent`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFilter
+{
+    // None for the global level
+    pub module: Option<String>,
+    // None for silent
+    pub level: Option<ELoggingVerbosity>,
+}
+
+impl std::str::FromStr for LogFilter
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.split_once('=') {
+            Some((module, level)) => Ok(LogFilter {
+                module: Some(module.trim().to_string()),
+                level: parse_level(level)?,
+            }),
+            None => match parse_level(s)? {
+                Some(level) => Ok(LogFilter {
+                    module: None,
+                    level: Some(level),
+                }),
+                None => Err(String::from(
+                    "only a module can be silent, like mana=silent",
+                )),
+            },
+        }
+    }
+}
+
+/// One `--log-game` setting: the level for everything logged while the game with `seed` plays,
+/// in place of the global and module levels, like `1234=very-verbose`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameLogFilter
+{
+    pub seed: u64,
+    // None for silent
+    pub level: Option<ELoggingVerbosity>,
+}
+
+impl std::str::FromStr for GameLogFilter
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let Some((seed, level)) = s.split_once('=') else {
+            return Err(format!(
+                "'{}' isn't a game's seed and level like 1234=verbose",
+                s
+            ));
+        };
+        let seed = seed
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' isn't a seed", seed.trim()))?;
+        Ok(GameLogFilter {
+            seed,
+            level: parse_level(level)?,
+        })
+    }
+}
+
+/// Apply `--log` and `--log-game` settings, replacing any earlier module and game levels.
+/// Module names match any part of a module's path, so `combat` is `engine::combat`.
+pub fn set_log_filters(filters: &[LogFilter], games: &[GameLogFilter])
+{
+    let mut modules = MODULE_VERBOSITY.write().unwrap();
+    modules.clear();
+    for filter in filters {
+        match &filter.module {
+            Some(module) => modules.push((module.clone(), filter.level)),
+            None => set_global_verbosity(filter.level.unwrap_or(ELoggingVerbosity::Error)),
+        }
+    }
+    let mut game_levels = GAME_VERBOSITY.write().unwrap();
+    *game_levels = games.iter().map(|game| (game.seed, game.level)).collect();
+    FILTERED.store(
+        !modules.is_empty() || !game_levels.is_empty(),
+        Ordering::Relaxed,
+    );
+}
+
+/// Whether a line at `level` from the module at `module_path` is shown: the playing game's level
+/// decides if it has one, then the module's, then the global level.
+pub fn log_enabled(level: ELoggingVerbosity, module_path: &str) -> bool
+{
+    let allowed = if FILTERED.load(Ordering::Relaxed) {
+        CURRENT_GAME
+            .with(Cell::get)
+            .and_then(|seed| {
+                GAME_VERBOSITY
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .find(|(s, _)| *s == seed)
+                    .map(|(_, level)| shown(*level))
+            })
+            .or_else(|| {
+                MODULE_VERBOSITY
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .rev()
+                    .find(|(module, _)| module_path.split("::").any(|part| part == module))
+                    .map(|(_, level)| shown(*level))
+            })
+            .unwrap_or_else(|| shown(Some(global_verbosity())))
+    } else {
+        shown(Some(global_verbosity()))
+    };
+    (level as usize) < allowed
+}
+
+/// Marks this thread as playing the game with `seed` until the scope is dropped, so that game's
+/// `--log-game` level applies to what it logs.
+pub struct GameLogScope
+{
+    previous: Option<u64>,
+}
+
+impl GameLogScope
+{
+    pub fn enter(seed: u64) -> Self
+    {
+        GameLogScope {
+            previous: CURRENT_GAME.with(|game| game.replace(Some(seed))),
+        }
+    }
+}
+
+impl Drop for GameLogScope
+{
+    fn drop(&mut self)
+    {
+        CURRENT_GAME.with(|game| game.set(self.previous));
+    }
+}
+
 #[macro_export]
 macro_rules! vlog
 {
-    ($level:expr, $fmt:expr $(, $args:expr)* $(,)?) => 
+    ($level:expr, $fmt:expr $(, $args:expr)* $(,)?) =>
     {{
-        if ($level as usize) <= $crate::global_verbosity() as usize
+        let level = $level;
+        let module = module_path!();
+        let to_facade = $crate::facade::wants(level, module);
+        let shown = !$crate::facade::active() && $crate::log_enabled(level, module);
+        let to_file = $crate::logfile::wants(level);
+        if shown || to_file || to_facade
         {
-            println!($fmt $(, $args)*);
+            let line = format!($fmt $(, $args)*);
+            if to_facade
+            {
+                $crate::facade::write(level, module, &line);
+            }
+            if to_file
+            {
+                $crate::logfile::write(level, &line);
+            }
+            if shown
+            {
+                println!("{}", $crate::color::for_level(level, line));
+            }
         }
     }};
 }
 
+pub mod attachment;
+pub mod breakpoint;
+pub mod cache;
+pub mod capture;
 pub mod card;
+pub mod carddb;
+pub mod color;
+pub mod combat;
+pub mod commander;
+pub mod components;
+pub mod config;
 pub mod creature;
+pub mod deck;
+pub mod effect;
+pub mod event;
+pub mod facade;
+pub mod faces;
 pub mod game;
-pub mod tappable;
-pub mod sim;
+pub mod gamelog;
+pub mod heuristic;
+pub mod input;
+pub mod keyword;
+pub mod layers;
+pub mod life;
+pub mod logfile;
+pub mod mana;
+pub mod mcts;
+pub mod mulligan;
 pub mod music;
+pub mod optimizer;
+pub mod planeswalker;
+pub mod profile;
+pub mod progress;
+pub mod replacement;
+pub mod replay;
+pub mod report;
+pub mod result;
+pub mod rules;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "scryfall")]
+pub mod scryfall;
+pub mod sim;
+pub mod statics;
+pub mod stats;
+pub mod strategy;
+pub mod tappable;
+pub mod token;
+pub mod trigger;
+pub mod wincondition;
 
 pub use crate::card::*;
 pub use crate::creature::*;
 pub use crate::game::*;
+pub use crate::mana::{ManaColor, ManaCost, ManaPool};
+pub use crate::result::*;
 pub use crate::sim::*;
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn module_and_game_levels_override_the_global_one()
+    {
+        let filters: Vec<LogFilter> = ["normal", "breakpoint=very-verbose", "music=silent"]
+            .iter()
+            .map(|f| f.parse().unwrap())
+            .collect();
+        set_log_filters(&filters, &["77=verbose".parse().unwrap()]);
+        assert!(log_enabled(
+            ELoggingVerbosity::VeryVerbose,
+            "engine::breakpoint"
+        ));
+        assert!(!log_enabled(ELoggingVerbosity::Error, "engine::music"));
+        assert!(!log_enabled(ELoggingVerbosity::Verbose, "engine::sim"));
+        {
+            let _game = GameLogScope::enter(77);
+            assert!(log_enabled(ELoggingVerbosity::Verbose, "engine::music"));
+            assert!(!log_enabled(
+                ELoggingVerbosity::VeryVerbose,
+                "engine::breakpoint"
+            ));
+        }
+        assert!(!log_enabled(ELoggingVerbosity::Verbose, "engine::music"));
+        set_log_filters(&[], &[]);
+
+        assert!("silent".parse::<LogFilter>().is_err());
+        assert!(
+            "combat=loud"
+                .parse::<LogFilter>()
+                .unwrap_err()
+                .contains("unknown verbosity")
+        );
+        assert!("verbose".parse::<GameLogFilter>().is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::event::GameEvent;
+use crate::game::GameState;
+use crate::gamelog::LogEvent;
+
+/// `player` gains `amount` life. Like every change to a life total, it's logged with the new
+/// total and emits an event for "whenever you gain life" triggers.
+pub fn gain(game: &mut GameState, player: usize, amount: u32)
+{
+    if amount == 0 {
+        return;
+    }
+    game.players[player].life += amount as i32;
+    game.log_event(player, || LogEvent::Life {
+        change: amount as i32,
+        total: game.players[player].life,
+    });
+    game.events.emit(GameEvent::LifeGained { player, amount });
+}
+
+/// `player` loses `amount` life, from damage or otherwise.
+pub fn lose(game: &mut GameState, player: usize, amount: u32)
+{
+    if amount == 0 {
+        return;
+    }
+    game.players[player].life -= amount as i32;
+    game.log_event(player, || LogEvent::Life {
+        change: -(amount as i32),
+        total: game.players[player].life,
+    });
+    game.events.emit(GameEvent::LifeLost { player, amount });
+}
@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::ELoggingVerbosity;
+
+/// Rotated logs kept next to the current one, `sim.log.1` being the newest.
+pub const ROTATED_LOGS: usize = 5;
+
+/// A log file that's moved aside to `PATH.1` once it grows past `max_bytes`, shifting older
+/// ones along and dropping the oldest beyond `ROTATED_LOGS`.
+pub struct LogFile
+{
+    path: PathBuf,
+    file: LineWriter<File>,
+    // Bytes in the current file
+    written: u64,
+    // None to let it grow
+    max_bytes: Option<u64>,
+}
+
+impl LogFile
+{
+    /// Append to the log at `path`, creating it if needed.
+    pub fn open(path: &Path, max_bytes: Option<u64>) -> io::Result<Self>
+    {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            file: LineWriter::new(file),
+            written,
+            max_bytes,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()>
+    {
+        let length = line.len() as u64 + 1;
+        if self
+            .max_bytes
+            .is_some_and(|max| self.written > 0 && self.written + length > max)
+        {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += length;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()>
+    {
+        self.file.flush()?;
+        for n in (1..ROTATED_LOGS).rev() {
+            let older = rotated(&self.path, n);
+            if older.exists() {
+                fs::rename(&older, rotated(&self.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(&self.path, 1))?;
+        self.file = LineWriter::new(File::create(&self.path)?);
+        self.written = 0;
+        Ok(())
+    }
+}
+
+// `sim.log` rotated `n` times: `sim.log.n`
+fn rotated(path: &Path, n: usize) -> PathBuf
+{
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+// The --log-file everything goes to, if any
+static SINK: Mutex<Option<LogFile>> = Mutex::new(None);
+// How many levels go to it, counted like the stdout filters; 0 while there's no file
+static SHOWN: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Lines logged on this thread while `capture` runs
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, also collecting every line logged on this thread meanwhile, at any level.
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<String>)
+{
+    let outer = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    let result = f();
+    let lines = CAPTURED
+        .with(|captured| captured.replace(outer))
+        .unwrap_or_default();
+    (result, lines)
+}
+
+/// Also log everything up to `level` to `file`, whatever is shown on stdout.
+pub fn set_sink(file: LogFile, level: ELoggingVerbosity)
+{
+    *SINK.lock().unwrap() = Some(file);
+    SHOWN.store(crate::shown(Some(level)), Ordering::Relaxed);
+}
+
+/// Whether a line at `level` goes to the log file, or is being captured.
+pub fn wants(level: ELoggingVerbosity) -> bool
+{
+    (level as usize) < SHOWN.load(Ordering::Relaxed)
+        || CAPTURED.with(|captured| captured.borrow().is_some())
+}
+
+/// Add a line at `level` to the log file if it takes that level, and to any capture. If the
+/// file can't be written, say so once on stderr and stop logging to it.
+pub fn write(level: ELoggingVerbosity, line: &str)
+{
+    CAPTURED.with(|captured| {
+        if let Some(lines) = captured.borrow_mut().as_mut() {
+            lines.push(line.to_string());
+        }
+    });
+    if (level as usize) >= SHOWN.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut sink = SINK.lock().unwrap();
+    if let Some(file) = sink.as_mut()
+        && let Err(e) = file.write_line(line)
+    {
+        eprintln!("Can't write the log file {}: {}", file.path.display(), e);
+        *sink = None;
+        SHOWN.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn logs_rotate_once_they_outgrow_their_size()
+    {
+        let dir = std::env::temp_dir().join(format!("tcg-sim-log-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sim.log");
+        let mut log = LogFile::open(&path, Some(20)).unwrap();
+        for turn in 1..=8 {
+            log.write_line(&format!("Turn {} begins", turn)).unwrap();
+        }
+        drop(log);
+
+        let read = |n: usize| {
+            fs::read_to_string(if n == 0 {
+                path.clone()
+            } else {
+                rotated(&path, n)
+            })
+            .ok()
+        };
+        assert_eq!(read(0).as_deref(), Some("Turn 8 begins\n"));
+        assert_eq!(read(1).as_deref(), Some("Turn 7 begins\n"));
+        assert_eq!(read(ROTATED_LOGS).as_deref(), Some("Turn 3 begins\n"));
+        assert_eq!(read(ROTATED_LOGS + 1), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
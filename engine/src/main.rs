@@ -1,188 +1,1164 @@
-use engine::{set_global_verbosity, ELoggingVerbosity, game::ProgramState, game::StepCommand, sim, music::{MusicPlayer, MusicConfig, music_dir_path}};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use engine::capture::CaptureRules;
+use engine::carddb::CardDb;
+use engine::config;
+use engine::gamelog::GameLog;
+use engine::heuristic::HeuristicWeights;
+use engine::input;
+use engine::logfile::LogFile;
+use engine::mulligan::{self, MulliganStrategy};
+use engine::optimizer::{
+    Bayesian, Checkpoint, Checkpointing, DeckOptimizer, DeckVector, Genetic, GridSearch, HillClimb,
+    Objective, ObjectiveWeights, OptimizationReport, OptimizerKind, ScenarioEvaluator,
+    SimulatedAnnealing,
+};
+use engine::profile::{self, Profile};
+use engine::replay::{self, GameRecord};
+use engine::report;
+use engine::rules::GameRules;
+use engine::stats;
+use engine::strategy::Pilot;
 use engine::vlog;
-use std::collections::HashMap;
+use engine::{CardType, SimulationResult, deck};
+use engine::{
+    ELoggingVerbosity,
+    game::PlayDraw,
+    game::ProgramState,
+    game::StepCommand,
+    music::{MusicConfig, MusicPlayer, music_dir_path},
+    set_global_verbosity, sim,
+};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// TCG simulator: searches for the best land/nonland split of a deck.
+///
+/// With no arguments the simulator runs interactively and asks for step commands on stdin.
+#[derive(Parser, Debug)]
+#[command(version, args_override_self = true)]
+struct Cli
+{
+    /// Number of lands the optimizer starts from
+    #[arg(long, default_value_t = 28)]
+    lands: u32,
+
+    /// Number of nonlands the optimizer starts from
+    #[arg(long, default_value_t = 32)]
+    nonlands: u32,
+
+    /// Games simulated for every tested configuration (per batch with --epsilon)
+    #[arg(long, default_value_t = 3000)]
+    games: u32,
+
+    /// A game counts as mana screwed if the deck missed a land drop in this many first turns
+    #[arg(long, default_value_t = sim::SCREW_CHECK_TURNS)]
+    screw_turns: usize,
+
+    /// A game counts as mana flooded if the deck had seen this many lands by --flood-turn
+    #[arg(long, default_value_t = sim::FLOOD_LAND_COUNT)]
+    flood_lands: u32,
+
+    /// Turn by which --flood-lands lands make a game flooded
+    #[arg(long, default_value_t = sim::FLOOD_CHECK_TURN)]
+    flood_turn: usize,
+
+    /// After every configuration, list its cards by how much slower games were when they were
+    /// drawn in the first turns, as candidates to cut
+    #[arg(long)]
+    card_report: bool,
+
+    /// After every configuration, show how often the deck made its land drop and cast a spell of
+    /// mana value equal to the turn number on each of turns 1-5
+    #[arg(long)]
+    curve_report: bool,
+
+    /// Don't show the progress line while batches of games run (it only shows on a terminal)
+    #[arg(long)]
+    no_progress: bool,
+
+    /// How much to log, for everything (error, warning, normal, verbose or very-verbose) or for
+    /// one module (e.g. combat=verbose or mana=silent), comma-separated or repeated
+    #[arg(long, value_delimiter = ',')]
+    log: Vec<engine::LogFilter>,
+
+    /// How much to log while the game with this seed plays, whatever --log says, e.g.
+    /// 1234=very-verbose
+    #[arg(long)]
+    log_game: Vec<engine::GameLogFilter>,
+
+    /// Also write the log to this file, at --log-file-level whatever --log shows on stdout
+    #[arg(long)]
+    log_file: Option<PathBuf>,
+
+    /// How much goes to --log-file: error, warning, normal, verbose or very-verbose
+    #[arg(long, default_value = "verbose", requires = "log_file")]
+    log_file_level: ELoggingVerbosity,
+
+    /// Move --log-file aside to FILE.1 once it's this many megabytes, keeping a few older ones
+    #[arg(long, requires = "log_file")]
+    log_file_max_mb: Option<u64>,
+
+    /// Print without colors (they're also off when NO_COLOR is set or output isn't a terminal)
+    #[arg(long)]
+    no_color: bool,
+
+    /// After every configuration, report how many games per second it was simulated at; results
+    /// are always simulated, never taken from --cache
+    #[arg(long)]
+    bench_mode: bool,
+
+    /// Write every tested configuration, its results and the final suggestion to this JSON file
+    /// once the optimizer finishes
+    #[arg(long)]
+    report_json: Option<PathBuf>,
+
+    /// Render the optimizer run as a standalone HTML page with charts, for sharing
+    #[arg(long)]
+    report_html: Option<PathBuf>,
+
+    /// Append one row per simulated game (configuration, seed, turns, mulligans, lands seen by
+    /// turn 5 and more) to this CSV file
+    #[arg(long)]
+    export_csv: Option<PathBuf>,
+
+    /// Append every action of every simulated game (draws, land drops, casts, attacks, damage,
+    /// deaths) to this file as JSON Lines, one event per line
+    #[arg(long)]
+    event_log: Option<PathBuf>,
+
+    /// Record the game with this seed (e.g. one found in --export-csv) to the --record file
+    #[arg(long, requires = "record")]
+    record_seed: Option<u64>,
+
+    /// File to save the game picked by --record-seed to, for --replay
+    #[arg(long, requires = "record_seed")]
+    record: Option<PathBuf>,
+
+    /// Directory to save a replay and a full log of each batch's outliers to: its slowest games
+    /// and those that missed land drops (best-of-three matches aren't captured)
+    #[arg(long)]
+    capture_dir: Option<PathBuf>,
+
+    /// Percentage of each batch's longest games to capture; 0 for none
+    #[arg(long, default_value_t = engine::capture::SLOWEST_PERCENT, requires = "capture_dir")]
+    capture_slowest: f64,
+
+    /// Capture games that missed at least this many land drops in their first turns; 0 for none
+    #[arg(long, default_value_t = engine::capture::MISSED_LAND_DROPS, requires = "capture_dir")]
+    capture_missed_drops: u32,
+
+    /// Step through a game saved with --record, then exit
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Append every configuration's kill-turn histogram to this CSV file
+    #[arg(long)]
+    histogram: Option<PathBuf>,
+
+    /// Keep simulating each configuration until the 95% confidence interval on its mean is within
+    /// this many turns either side (default: play exactly --games)
+    #[arg(long)]
+    epsilon: Option<f64>,
+
+    /// Most games --epsilon may run per configuration
+    #[arg(long, default_value_t = 30000, requires = "epsilon")]
+    max_games: u32,
+
+    /// Comma-separated nonland cards whose copy counts the optimizer may change
+    /// (default: only the land/nonland split of Forest vs Grizzly Bears)
+    #[arg(long, value_delimiter = ',')]
+    pool: Vec<String>,
+
+    /// Start from a decklist file (plain "4 Card Name" lines or an MTG Arena export)
+    #[arg(long, conflicts_with_all = ["pool", "lands", "nonlands"])]
+    deck: Option<PathBuf>,
+
+    /// Decklist for the opposing player; the optimizer then maximizes win rate against it
+    /// (default: a copy of the deck itself, and turns to kill are minimized)
+    #[arg(long)]
+    opponent: Option<PathBuf>,
+
+    /// Play against a goldfish that only plays lands instead of a copy of the deck, to measure
+    /// how fast the deck kills on its own
+    #[arg(long, conflicts_with = "opponent")]
+    goldfish: bool,
+
+    /// Play best-of-three matches against --opponent, sideboarding after game 1, and report the
+    /// match win rate too. Sideboard plans name decks by file stem
+    #[arg(long, requires = "opponent")]
+    best_of_three: bool,
+
+    /// Instead of optimizing, play best-of-three matches between these decklists and print their
+    /// win rates (repeatable, at least two). Sideboard plans name opponents by file stem
+    #[arg(long, num_args = 1.., conflicts_with_all = ["deck", "pool", "opponent", "manabase"])]
+    gauntlet: Vec<PathBuf>,
+
+    /// Extra card definitions: a JSON file or a directory of them (repeatable)
+    #[arg(long = "card-db")]
+    card_db: Vec<PathBuf>,
+
+    /// Import supported cards from a Scryfall bulk-data JSON dump
+    #[cfg(feature = "scryfall")]
+    #[arg(long)]
+    scryfall: Option<PathBuf>,
+
+    /// Only vary the mix of lands (nonland counts stay fixed) and minimize color-screwed games
+    #[arg(long)]
+    manabase: bool,
+
+    /// Comma-separated lands the manabase search may add, e.g. duals not in the starting deck
+    #[arg(long, value_delimiter = ',', requires = "manabase")]
+    land_pool: Vec<String>,
+
+    /// Maximum copies of each nonland card in --pool or --deck the optimizer may try; decks over
+    /// the format's limit are handled by --legality
+    #[arg(long, default_value_t = 4)]
+    max_copies: u32,
+
+    /// Search strategy: hill-climb, grid, annealing, genetic or bayesian
+    #[arg(long, default_value = "hill-climb")]
+    optimizer: OptimizerKind,
+
+    /// Decks in each generation of the genetic optimizer
+    #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u32).range(2..))]
+    population: u32,
+
+    /// Stop the optimizer after this many iterations (generations for the genetic optimizer,
+    /// simulation batches for the bayesian one) even if it's still improving
+    #[arg(long)]
+    iterations: Option<u32>,
+
+    /// Minimize the turn count this percentage of games finish within (e.g. 90 for the worst case
+    /// short of outliers) instead of the average
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..100), conflicts_with_all = ["manabase", "opponent"])]
+    percentile: Option<u32>,
+
+    /// Minimize a weighted sum of average turns, 90th-percentile turns and mana-screw rate
+    /// instead, e.g. 1,0.5,10 to trade a little speed for consistency
+    #[arg(long, conflicts_with_all = ["manabase", "opponent", "percentile"])]
+    objective_weights: Option<ObjectiveWeights>,
+
+    /// After the search, list every configuration no other one beats on average turns,
+    /// 90th-percentile turns and mana-screw rate at once
+    #[arg(long)]
+    pareto: bool,
+
+    /// Family-wise p-value at which the hill-climber accepts a mutation as better than the current
+    /// deck; each step divides it by the number of mutations tested
+    #[arg(long, default_value_t = 0.05)]
+    significance: f64,
+
+    /// Instead of optimizing, simulate every land count from --min-lands to --max-lands (deck size
+    /// --lands plus --nonlands) and print a table and kill-turn heatmap of the whole curve
+    #[arg(long, conflicts_with_all = ["pool", "deck"])]
+    sweep: bool,
+
+    /// Lowest land count tried by the grid optimizer and --sweep (default: --lands minus 4)
+    #[arg(long)]
+    min_lands: Option<u32>,
+
+    /// Highest land count tried by the grid optimizer and --sweep (default: --lands plus 4)
+    #[arg(long)]
+    max_lands: Option<u32>,
+
+    /// Mulligan strategy for every player: keep-all or keep-if-MIN-to-MAX-lands
+    #[arg(long, default_value = "keep-if-2-to-5-lands", value_parser = mulligan::parse_strategy)]
+    mulligan: Arc<dyn MulliganStrategy>,
+
+    /// Who pilots the simulated deck: curve-out, random as a baseline, heuristic, or mcts searching
+    /// each decision (mcts-ROLLOUTS or mcts-MILLISms to set its budget)
+    #[arg(long, default_value = "curve-out")]
+    pilot: Pilot,
+
+    /// JSON file of feature weights for the heuristic pilot, e.g. data/weights/aggro.json
+    #[arg(long)]
+    weights: Option<PathBuf>,
+
+    /// Whether the simulated deck is on the play, on the draw or alternates between the two
+    #[arg(long, default_value = "play")]
+    play_draw: PlayDraw,
+
+    /// Format whose life total, hand size and deck size rules apply: constructed, limited or commander
+    #[arg(long, default_value = "constructed")]
+    format: GameRules,
+
+    /// Override the format's starting life total
+    #[arg(long)]
+    starting_life: Option<i32>,
+
+    /// Override the format's opening hand size
+    #[arg(long)]
+    hand_size: Option<u32>,
+
+    /// Discard down to this many cards at cleanup (no limit by default)
+    #[arg(long)]
+    max_hand_size: Option<u32>,
+
+    /// Override the format's minimum deck size
+    #[arg(long)]
+    min_deck_size: Option<u32>,
+
+    /// File of banned card names, one per line
+    #[arg(long)]
+    banned_list: Option<PathBuf>,
+
+    /// What to do with decks that aren't legal in the format: ignore, warn or refuse to simulate them
+    #[arg(long, default_value = "warn")]
+    legality: deck::Legality,
+
+    /// Seed for all simulated games (random if omitted; the chosen seed is printed)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Cache every configuration's result in this directory, so configurations simulated before
+    /// with the same settings and --seed aren't simulated again
+    #[arg(long)]
+    cache: Option<PathBuf>,
+
+    /// Save the optimizer's progress to this file after every evaluation, for --resume
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Continue an interrupted optimizer run from its checkpoint file (and keep saving to it).
+    /// Use the same deck and options as the original run; the seed is taken from the checkpoint
+    #[arg(long, conflicts_with = "seed")]
+    resume: Option<PathBuf>,
+
+    /// Run the whole optimization without prompting for commands on stdin
+    #[arg(long)]
+    no_interactive: bool,
+
+    /// Take the interactive commands from this file, one per line (lines starting with # are
+    /// comments), instead of stdin; the session quits when the script runs out
+    #[arg(long, conflicts_with = "no_interactive")]
+    script: Option<PathBuf>,
+
+    /// Directory of saved profiles, for --profile and the `profile` commands
+    #[arg(long, default_value = "profiles")]
+    profiles: PathBuf,
+
+    /// Start with the settings of this saved profile (games, mulligans, pilot, format...)
+    /// instead of the options given
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Read options from a TOML file whose keys are option names, e.g. `games = 5000` or
+    /// `[rules]` with `format = "commander"`; options on the command line win
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command
+{
+    /// Work out draw odds analytically instead of simulating, e.g. the chance of at least 3 lands
+    /// in an opening 7 with 24 lands in 60: calc --hits 24 --at-least 3
+    Calc
+    {
+        /// Cards in the deck
+        #[arg(long, default_value_t = 60)]
+        deck_size: u32,
+
+        /// Cards in the deck that count as hits, e.g. lands
+        #[arg(long)]
+        hits: u32,
+
+        /// Cards drawn
+        #[arg(long, default_value_t = 7)]
+        draws: u32,
+
+        /// Hits needed (default: show the whole distribution only)
+        #[arg(long)]
+        at_least: Option<u32>,
+    },
+}
+
+// Print the hypergeometric distribution of hits in a draw, and the odds of at least `at_least`
+fn run_calc(deck_size: u32, hits: u32, draws: u32, at_least: Option<u32>)
+{
+    if hits > deck_size || draws > deck_size {
+        vlog!(
+            ELoggingVerbosity::Error,
+            "A {}-card deck can't have {} hits or draw {} cards",
+            deck_size,
+            hits,
+            draws
+        );
+        return;
+    }
+
+    println!(
+        "{} hits in a {}-card deck, {} cards drawn:",
+        hits, deck_size, draws
+    );
+    for k in 0..=draws.min(hits) {
+        println!(
+            "  {:>2}: exactly {:>6.2}%, at least {:>6.2}%",
+            k,
+            stats::hypergeometric(deck_size, hits, draws, k) * 100.0,
+            stats::hypergeometric_at_least(deck_size, hits, draws, k) * 100.0
+        );
+    }
+    if let Some(k) = at_least {
+        println!(
+            "P(at least {}) = {:.4}%",
+            k,
+            stats::hypergeometric_at_least(deck_size, hits, draws, k) * 100.0
+        );
+    }
+}
 
 fn main()
 {
+    let given = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&given).unwrap_or_else(|e| e.exit());
+    if let Some(path) = &cli.config {
+        match std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| config::args_from_toml(&text))
+        {
+            // Options the command line gives (or rules out) replace the config's
+            Ok(args) => {
+                let mut argv: Vec<std::ffi::OsString> = std::env::args_os().collect();
+                let args = config::without_overridden(args, &Cli::command(), &given);
+                argv.splice(1..1, args.into_iter().map(std::ffi::OsString::from));
+                cli = Cli::parse_from(argv);
+            }
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't read the config {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        }
+    }
+    if let Some(Command::Calc {
+        deck_size,
+        hits,
+        draws,
+        at_least,
+    }) = cli.command
+    {
+        run_calc(deck_size, hits, draws, at_least);
+        return;
+    }
+
     set_global_verbosity(ELoggingVerbosity::Normal);
+    engine::set_log_filters(&cli.log, &cli.log_game);
+    if let Some(path) = &cli.log_file {
+        match LogFile::open(path, cli.log_file_max_mb.map(|mb| mb * 1024 * 1024)) {
+            Ok(file) => engine::logfile::set_sink(file, cli.log_file_level),
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't open the log file {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        }
+    }
+    engine::color::set_enabled(
+        !cli.no_color && !engine::color::disabled_by_env() && std::io::stdout().is_terminal(),
+    );
+
+    let resume = match &cli.resume {
+        Some(path) => match Checkpoint::load(path) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't read the checkpoint {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let seed = resume
+        .as_ref()
+        .map(|checkpoint| checkpoint.seed)
+        .or(cli.seed)
+        .unwrap_or_else(rand::random);
+    sim::set_seed(seed);
+    if let Some(checkpoint) = &resume {
+        sim::set_next_game_index(checkpoint.next_game);
+    }
+
+    let mut program_state = ProgramState::new();
+    program_state.games_per_scenario = cli.games;
+    program_state.interactive = !cli.no_interactive && cli.gauntlet.is_empty();
+    if let Some(path) = &cli.script {
+        match input::use_script(path) {
+            Ok(count) => vlog!(
+                ELoggingVerbosity::Verbose,
+                "Running {} command(s) from {}",
+                count,
+                path.display()
+            ),
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't read the script {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        }
+    }
+    program_state.mulligan = cli.mulligan.clone();
+    program_state.play_draw = cli.play_draw;
+    program_state.pilot = cli.pilot;
+    if let Some(path) = &cli.weights {
+        let weights = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| HeuristicWeights::from_json(&text).map_err(|e| e.to_string()));
+        match (weights, cli.pilot) {
+            (Ok(weights), Pilot::Heuristic(_)) => program_state.pilot = Pilot::Heuristic(weights),
+            (Ok(_), _) => vlog!(
+                ELoggingVerbosity::Warning,
+                "--weights only applies to --pilot heuristic"
+            ),
+            (Err(e), _) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't read weights {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        }
+    }
+    program_state.rules = GameRules {
+        starting_life: cli.starting_life.unwrap_or(cli.format.starting_life),
+        starting_hand_size: cli.hand_size.unwrap_or(cli.format.starting_hand_size),
+        max_hand_size: cli.max_hand_size.or(cli.format.max_hand_size),
+        min_deck_size: cli.min_deck_size.unwrap_or(cli.format.min_deck_size),
+        ..cli.format.clone()
+    };
+    program_state.legality = cli.legality;
+    program_state.best_of_three = cli.best_of_three;
+    program_state.goldfish = cli.goldfish;
+    program_state.progress = !cli.no_progress && std::io::stderr().is_terminal();
+    program_state.epsilon = cli.epsilon;
+    program_state.histogram_export = cli.histogram.clone();
+    program_state.game_export = cli.export_csv.clone();
+    program_state.record_game = cli.record_seed.zip(cli.record.clone());
+    program_state.capture = cli.capture_dir.clone().map(|dir| CaptureRules {
+        slowest_percent: cli.capture_slowest,
+        missed_land_drops: cli.capture_missed_drops,
+        ..CaptureRules::new(dir)
+    });
+    if let Some(path) = &cli.replay {
+        replay(path);
+        return;
+    }
+    if let Some(path) = &cli.event_log {
+        match GameLog::open(path) {
+            Ok(log) => program_state.game_log = Some(Arc::new(log)),
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't open the event log {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        }
+    }
+    program_state.result_cache = cli.cache.clone();
+    program_state.card_report = cli.card_report;
+    program_state.curve_report = cli.curve_report;
+    program_state.bench_mode = cli.bench_mode;
+    program_state.mana_thresholds = sim::ManaThresholds {
+        screw_turns: cli.screw_turns,
+        flood_lands: cli.flood_lands,
+        flood_turn: cli.flood_turn,
+    };
+    program_state.max_games = cli.max_games;
+    program_state.deck_name = cli.deck.as_deref().map(file_stem).unwrap_or_default();
+    program_state.profile_dir = cli.profiles.clone();
+    input::set_profile_dir(&program_state.profile_dir);
+    if let Some(name) = &cli.profile
+        && let Err(e) = Profile::load(&program_state.profile_dir, name)
+            .and_then(|profile| profile.apply(&mut program_state))
+    {
+        vlog!(
+            ELoggingVerbosity::Error,
+            "Can't load profile '{}': {}",
+            name,
+            e
+        );
+        return;
+    }
+    if let Some(path) = &cli.banned_list {
+        match std::fs::read_to_string(path) {
+            Ok(text) => program_state
+                .rules
+                .banned
+                .extend(deck::parse_banned_list(&text)),
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't read banned list {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        }
+    }
 
-    // Initialize background music
+    // Initialize background music (interactive sessions only)
     let music_config = MusicConfig {
-        fade_duration_ms: 1500,      // 1.5 second fade between songs
+        fade_duration_ms: 1500,       // 1.5 second fade between songs
         delay_between_songs_ms: 2000, // 2 second delay between songs
         volume: 0.3,                  // 30% volume
     };
     let music_path = music_dir_path();
     let _music_player = MusicPlayer::new(music_path.to_str().unwrap_or("web/music"), music_config);
-    _music_player.start();
+    if program_state.interactive {
+        _music_player.start();
 
-    let mut program_state = ProgramState::new();
+        println!("TCG Simulator");
+        println!("{}", input::help());
+        println!();
+    }
 
-    println!("TCG Simulator");
-    println!("Commands:");
-    println!("  s  -> step one phase");
-    println!("  t  -> step one whole turn");
-    println!("  g  -> run the current game to completion");
-    println!("  d  -> run the simulation to completion for the current deck");
-    println!("  r  -> run the whole simulation to completion (all decks)");
-    println!("  q  -> quit");
-    println!();
+    println!("Seed: {}", seed);
 
-    let mut current_lands = 28;
-    let mut current_nonlands = 32;
-    let change_size = 1;
+    let mut card_db = CardDb::builtin().clone();
+    for path in cli.card_db.iter() {
+        match card_db.load_path(path) {
+            Ok(count) => vlog!(
+                ELoggingVerbosity::Verbose,
+                "Loaded {} card(s) from {}",
+                count,
+                path.display()
+            ),
+            Err(e) => {
+                vlog!(ELoggingVerbosity::Error, "Can't load card data: {}", e);
+                return;
+            }
+        }
+    }
 
-    program_state.step_mode = sim::parse_command(&read_line().trim());
+    #[cfg(feature = "scryfall")]
+    if let Some(path) = &cli.scryfall {
+        match engine::scryfall::import_bulk_file(path, &mut card_db) {
+            Ok(report) => vlog!(
+                ELoggingVerbosity::Normal,
+                "Imported {} card(s) from Scryfall data",
+                report.imported
+            ),
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't import Scryfall data: {}",
+                    e
+                );
+                return;
+            }
+        }
+    }
 
-    // Hill-climbing algorithm: track results and find consensus among 3+ runs
-    let mut result_history: HashMap<(u32, u32), Vec<f64>> = HashMap::new();
-    let mut iteration = 1;
+    if let Some(path) = &cli.opponent
+        && let Err(e) = load_opponent(path, &card_db, &mut program_state)
+    {
+        vlog!(
+            ELoggingVerbosity::Error,
+            "Can't load {}: {}",
+            path.display(),
+            e
+        );
+        return;
+    }
 
-    let mut win_counts: HashMap<(u32, u32), u32> = HashMap::new();
+    let deck_files: Vec<&Path> = cli
+        .deck
+        .iter()
+        .chain(cli.opponent.iter())
+        .chain(cli.gauntlet.iter())
+        .map(PathBuf::as_path)
+        .collect();
+    input::set_deck_files(&deck_files);
+    program_state.step_mode = if program_state.interactive {
+        read_command(&card_db, &mut program_state)
+    } else {
+        StepCommand::RunAll
+    };
 
-    loop
-    {
-        if program_state.step_mode == StepCommand::Quit
-        {
-            break;
-        }
+    if !cli.gauntlet.is_empty() {
+        run_gauntlet(&cli.gauntlet, &card_db, &program_state);
+        return;
+    }
 
-        println!("\n=== Iteration {} ===", iteration);
-        println!("Testing land/nonland ratios centered around {} lands, {} nonlands", current_lands, current_nonlands);
+    if cli.sweep {
+        let min_lands = cli.min_lands.unwrap_or(cli.lands.saturating_sub(4));
+        let max_lands = cli.max_lands.unwrap_or(cli.lands + 4);
+        let sweep = sim::run_sweep(
+            min_lands..=max_lands,
+            cli.lands + cli.nonlands,
+            &mut program_state,
+        );
+        println!("\n{}", sweep);
+        return;
+    }
 
-        // Test three configurations: current, +1 lands, -1 lands
-        let result0 = sim::try_scenario(current_lands, current_nonlands, &mut program_state);
-        if program_state.step_mode == StepCommand::RunDeck
-        {
-            program_state.step_mode = sim::parse_command(&read_line().trim());
-        }
+    let mut start = if let Some(path) = &cli.deck {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| deck::parse_decklist_entries(&text).map_err(|e| e.to_string()))
+            .and_then(|list| {
+                DeckVector::from_decklist(&list, |name| card_db.get(name), cli.max_copies)
+            });
 
-        if program_state.step_mode == StepCommand::Quit
-        {
-            break;
+        match parsed {
+            Ok(deck) => deck,
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't load {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
         }
-
-        let result1 = sim::try_scenario(current_lands + change_size, current_nonlands - change_size, &mut program_state);
-        if program_state.step_mode == StepCommand::RunDeck
-        {
-            program_state.step_mode = sim::parse_command(&read_line().trim());
+    } else if cli.pool.is_empty() {
+        DeckVector::land_split(cli.lands, cli.nonlands)
+    } else {
+        let pool: Vec<String> = cli
+            .pool
+            .iter()
+            .map(|name| name.trim().to_string())
+            .collect();
+        match DeckVector::with_pool("Forest", cli.lands, &pool, cli.nonlands, cli.max_copies) {
+            Ok(deck) => deck,
+            Err(e) => {
+                vlog!(ELoggingVerbosity::Error, "Invalid --pool: {}", e);
+                return;
+            }
         }
+    };
 
-        if program_state.step_mode == StepCommand::Quit
-        {
-            break;
+    let objective = if cli.manabase {
+        let land_pool: Vec<String> = cli
+            .land_pool
+            .iter()
+            .map(|name| name.trim().to_string())
+            .collect();
+        if let Some(name) = land_pool.iter().find(|name| {
+            !card_db
+                .get(name)
+                .is_some_and(|card| card.is_type(CardType::Land))
+        }) {
+            vlog!(
+                ELoggingVerbosity::Error,
+                "Invalid --land-pool: '{}' is not a known land",
+                name
+            );
+            return;
         }
+        start = start.with_extra_lands(&land_pool).with_fixed_nonlands();
+        Objective::ColorScrew
+    } else if program_state.opponent_deck.is_some() {
+        Objective::LossRate
+    } else if let Some(percentile) = cli.percentile {
+        Objective::TurnsPercentile(percentile)
+    } else if let Some(weights) = cli.objective_weights {
+        Objective::Weighted(weights)
+    } else {
+        Objective::TurnsToKill
+    };
 
-        let result2 = sim::try_scenario(current_lands - change_size, current_nonlands + change_size, &mut program_state);
-        if program_state.step_mode == StepCommand::RunDeck
-        {
-            program_state.step_mode = sim::parse_command(&read_line().trim());
+    match start.to_deck(|name| card_db.get(name)) {
+        Ok(deck) => {
+            let errors = deck::validate(&deck, &program_state.rules);
+            if !errors.is_empty() && program_state.legality == deck::Legality::Refuse {
+                for e in &errors {
+                    vlog!(
+                        ELoggingVerbosity::Error,
+                        "The starting deck isn't legal in this format: {}",
+                        e
+                    );
+                }
+                return;
+            }
+        }
+        Err(e) => {
+            vlog!(
+                ELoggingVerbosity::Error,
+                "Can't build the starting deck: {}",
+                e
+            );
+            return;
+        }
+    }
+    let mut optimizer: Box<dyn DeckOptimizer> = match cli.optimizer {
+        OptimizerKind::HillClimb => Box::new(HillClimb {
+            significance: cli.significance,
+            max_iterations: cli.iterations,
+            ..HillClimb::default()
+        }),
+        OptimizerKind::Grid => Box::new(GridSearch {
+            min_lands: cli.min_lands.unwrap_or(start.lands().saturating_sub(4)),
+            max_lands: cli.max_lands.unwrap_or(start.lands() + 4),
+            max_candidates: 500,
+        }),
+        OptimizerKind::Annealing => {
+            let mut annealing = SimulatedAnnealing::new(seed);
+            if let Some(iterations) = cli.iterations {
+                annealing.iterations = iterations;
+            }
+            Box::new(annealing)
+        }
+        OptimizerKind::Genetic => {
+            let mut genetic = Genetic {
+                population: cli.population as usize,
+                ..Genetic::new(seed)
+            };
+            if let Some(iterations) = cli.iterations {
+                genetic.generations = iterations;
+            }
+            Box::new(genetic)
         }
+        OptimizerKind::Bayesian => {
+            let mut bayesian = Bayesian::new(seed);
+            if let Some(iterations) = cli.iterations {
+                bayesian.evaluations = iterations;
+            }
+            Box::new(bayesian)
+        }
+    };
+
+    vlog!(
+        ELoggingVerbosity::Verbose,
+        "Using the {} optimizer",
+        optimizer.name()
+    );
 
-        if program_state.step_mode == StepCommand::Quit
+    let checkpoint = match resume {
+        Some(checkpoint)
+            if checkpoint.optimizer != optimizer.name()
+                || checkpoint.objective != objective.label() =>
         {
-            break;
+            vlog!(
+                ELoggingVerbosity::Error,
+                "The checkpoint is from the {} optimizer minimizing {}, not the {} optimizer minimizing {}",
+                checkpoint.optimizer,
+                checkpoint.objective,
+                optimizer.name(),
+                objective.label()
+            );
+            return;
         }
+        Some(checkpoint) => {
+            vlog!(
+                ELoggingVerbosity::Normal,
+                "Resuming after {} saved evaluations",
+                checkpoint.evaluations.len()
+            );
+            checkpoint
+        }
+        None => Checkpoint::new(optimizer.name(), objective, seed),
+    };
 
-        // Track results
-        result_history.entry((current_lands, current_nonlands)).or_insert_with(Vec::new).push(result0);
-        result_history.entry((current_lands + change_size, current_nonlands - change_size)).or_insert_with(Vec::new).push(result1);
-        result_history.entry((current_lands - change_size, current_nonlands + change_size)).or_insert_with(Vec::new).push(result2);
+    let mut interactive = InteractiveEvaluator {
+        program_state,
+        card_db,
+        objective,
+    };
+    let mut evaluator = Checkpointing::new(
+        &mut interactive,
+        checkpoint,
+        cli.checkpoint.clone().or(cli.resume.clone()),
+    );
+    if let Some(outcome) = optimizer.optimize(start, &mut evaluator) {
+        let report = OptimizationReport::new(
+            optimizer.name(),
+            objective,
+            seed,
+            &outcome,
+            evaluator.checkpoint.results(),
+        );
+        if let Some(path) = &cli.report_json {
+            match report
+                .to_json()
+                .map_err(|e| e.to_string())
+                .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()))
+            {
+                Ok(()) => vlog!(
+                    ELoggingVerbosity::Normal,
+                    "Wrote the optimizer report to {}",
+                    path.display()
+                ),
+                Err(e) => vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't write the optimizer report to {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        if let Some(path) = &cli.report_html {
+            match std::fs::write(path, report::render_html(&report)) {
+                Ok(()) => vlog!(
+                    ELoggingVerbosity::Normal,
+                    "Wrote the HTML report to {}",
+                    path.display()
+                ),
+                Err(e) => vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't write the HTML report to {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
 
-        // Determine which configuration was best
-        let smallest_turns_to_death = result0.min(result1).min(result2);
+        if cli.pareto {
+            vlog!(ELoggingVerbosity::Normal, "Pareto-optimal configurations:");
+            for evaluation in report.pareto_front.iter().map(|&i| &report.evaluations[i]) {
+                vlog!(
+                    ELoggingVerbosity::Normal,
+                    "  {}: {:.4} avg turns, {:.1} turns at p90, {:.1}% mana screw",
+                    evaluation.deck.describe(),
+                    evaluation.result.mean(),
+                    evaluation.result.percentile(90),
+                    evaluation.result.screw_rate() * 100.0
+                );
+            }
+        }
 
-        let (best_config_name, best_lands, best_nonlands) = if result0 == smallest_turns_to_death
-        {
-            ("Current ratio (no change)", current_lands, current_nonlands)
+        let caveat = if outcome.converged {
+            ""
+        } else {
+            ", may not be optimal yet"
+        };
+        match objective {
+            Objective::ColorScrew => {
+                vlog!(
+                    ELoggingVerbosity::Normal,
+                    "Final suggestion: {} ({:.1}% color-screwed games{})",
+                    outcome.best.describe(),
+                    outcome.best_mean * 100.0,
+                    caveat
+                );
+                return;
+            }
+            Objective::LossRate => vlog!(
+                ELoggingVerbosity::Normal,
+                "Final suggestion: {} lands, {} nonlands ({:.1}% win rate{})",
+                outcome.best.lands(),
+                outcome.best.nonlands(),
+                (1.0 - outcome.best_mean) * 100.0,
+                caveat
+            ),
+            Objective::TurnsToKill => vlog!(
+                ELoggingVerbosity::Normal,
+                "Final suggestion: {} lands, {} nonlands ({:.4} avg turns{})",
+                outcome.best.lands(),
+                outcome.best.nonlands(),
+                outcome.best_mean,
+                caveat
+            ),
+            Objective::TurnsPercentile(percentile) => vlog!(
+                ELoggingVerbosity::Normal,
+                "Final suggestion: {} lands, {} nonlands ({:.1} turns at p{}{})",
+                outcome.best.lands(),
+                outcome.best.nonlands(),
+                outcome.best_mean,
+                percentile,
+                caveat
+            ),
+            Objective::Weighted(_) => vlog!(
+                ELoggingVerbosity::Normal,
+                "Final suggestion: {} lands, {} nonlands ({:.4} weighted score{})",
+                outcome.best.lands(),
+                outcome.best.nonlands(),
+                outcome.best_mean,
+                caveat
+            ),
         }
-        else if result1 == smallest_turns_to_death
-        {
-            ("More lands", current_lands + change_size, current_nonlands - change_size)
+        if !cli.pool.is_empty() || cli.deck.is_some() {
+            for entry in outcome.best.entries.iter().filter(|e| e.count > 0) {
+                vlog!(
+                    ELoggingVerbosity::Normal,
+                    "  {} {}",
+                    entry.count,
+                    entry.name
+                );
+            }
         }
-        else
-        {
-            ("More nonlands", current_lands - change_size, current_nonlands + change_size)
-        };
+    }
+}
 
-        let winner_key = (best_lands, best_nonlands);
-        let wins = win_counts.entry(winner_key).or_insert(0);
-        *wins += 1;
+// Play against the decklist at `path`, named after its file
+fn load_opponent(
+    path: &Path,
+    card_db: &CardDb,
+    program_state: &mut ProgramState,
+) -> Result<(), String>
+{
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let opponent =
+        deck::parse_decklist_with(&text, |name| card_db.get(name)).map_err(|e| e.to_string())?;
+    program_state.opponent_deck = Some(opponent);
+    program_state.opponent_name = file_stem(path);
+    Ok(())
+}
 
-        println!("\nIteration {} Results:", iteration);
-        println!("  Current:     {} lands, {} nonlands -> {} avg turns", current_lands, current_nonlands, result0);
-        println!("  More lands:  {} lands, {} nonlands -> {} avg turns", current_lands + change_size, current_nonlands - change_size, result1);
-        println!("  More nonlands: {} lands, {} nonlands -> {} avg turns", current_lands - change_size, current_nonlands + change_size, result2);
-        println!("\nBest configuration: {} ({} lands, {} nonlands) -> {} avg turns (total wins: {})",
-            best_config_name, best_lands, best_nonlands, smallest_turns_to_death, *wins);
+// Name a deck after its file, for tables and sideboard plans
+fn file_stem(path: &std::path::Path) -> String
+{
+    path.file_stem().map_or_else(
+        || path.display().to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    )
+}
 
-        // Find decks that have reached 3 wins
-        let winners: Vec<_> = win_counts
-            .iter()
-            .filter(|(_, count)| **count >= 3)
-            .map(|(&(l, nl), _)| (l, nl))
-            .collect();
+// Load every gauntlet decklist, named after its file, and print how they fare against each other
+fn run_gauntlet(paths: &[PathBuf], card_db: &CardDb, program_state: &ProgramState)
+{
+    if paths.len() < 2 {
+        vlog!(
+            ELoggingVerbosity::Error,
+            "A gauntlet needs at least two decks"
+        );
+        return;
+    }
 
-        if winners.is_empty() 
-        {
-            // Continue hill-climbing
-            current_lands = best_lands;
-            current_nonlands = best_nonlands;
-        } 
-        else if winners.len() == 1 
-        {
-            // Clear winner
-            let (l, nl) = winners[0];
-            println!("\n=== Optimization Complete ===");
-            vlog!(
-                ELoggingVerbosity::Normal,
-                "Final suggestion: {} lands, {} nonlands (3 wins)",
-                l,
-                nl
-            );
-            break;
-        } 
-        else 
-        {
-            // Multiple decks reached 3 wins simultaneously → tiebreaker
-            println!("\nTiebreaker needed between {} decks!", winners.len());
+    let mut decks = Vec::new();
+    for path in paths {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                deck::parse_decklist_with(&text, |name| card_db.get(name))
+                    .map_err(|e| e.to_string())
+            });
+        let name = file_stem(path);
+        match parsed {
+            Ok(cards) => {
+                let errors = deck::validate(&cards, &program_state.rules);
+                if !errors.is_empty() && program_state.legality != deck::Legality::Ignore {
+                    let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                    vlog!(
+                        ELoggingVerbosity::Warning,
+                        "{} isn't legal in this format: {}",
+                        name,
+                        errors.join("; ")
+                    );
+                    if program_state.legality == deck::Legality::Refuse {
+                        return;
+                    }
+                }
+                decks.push((name, cards));
+            }
+            Err(e) => {
+                vlog!(
+                    ELoggingVerbosity::Error,
+                    "Can't load {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        }
+    }
 
-            let mut tiebreaker_results = Vec::new();
+    println!("{}", sim::run_gauntlet(&decks, program_state));
+}
 
-            for (l, nl) in winners 
-            {
-                let r = sim::try_scenario(l, nl, &mut program_state);
-                tiebreaker_results.push((l, nl, r));
-            }
+// Runs scenarios for the optimizer and asks for a new command whenever a deck finishes in `d` mode
+struct InteractiveEvaluator
+{
+    program_state: ProgramState,
+    card_db: CardDb,
+    objective: Objective,
+}
 
-            let winner = tiebreaker_results
-                .iter()
-                .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
-                .unwrap();
+impl ScenarioEvaluator for InteractiveEvaluator
+{
+    fn evaluate(&mut self, deck: &DeckVector) -> Option<SimulationResult>
+    {
+        if self.program_state.step_mode == StepCommand::Quit {
+            return None;
+        }
 
-            println!(
-                "\nTiebreaker winner: {} lands, {} nonlands -> {:.4}",
-                winner.0, winner.1, winner.2);
+        // Every card was checked against the card list before the search started
+        let cards = deck.to_deck(|name| self.card_db.get(name)).ok()?;
+        let description = format!("{} lands and {} nonlands", deck.lands(), deck.nonlands());
+        let result = sim::try_deck(&cards, &description, &mut self.program_state);
+        if self.program_state.step_mode == StepCommand::RunDeck {
+            self.program_state.step_mode = read_command(&self.card_db, &mut self.program_state);
+        }
 
-            vlog!(
-                ELoggingVerbosity::Normal,
-                "Final suggestion: {} lands, {} nonlands",
-                winner.0,
-                winner.1
-            );
-            break;
+        if self.program_state.step_mode == StepCommand::Quit {
+            return None;
         }
 
-        iteration += 1;
+        Some(result)
+    }
+
+    fn objective(&self) -> Objective
+    {
+        self.objective
     }
 }
 
-fn read_line() -> String
+// Read step commands, carrying out profile, replay and opponent commands in between
+fn read_command(card_db: &CardDb, program_state: &mut ProgramState) -> StepCommand
 {
-    use std::io::{self, Write};
-    print!("> ");
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    input
+    loop {
+        let Some(input) = input::prompt() else {
+            return StepCommand::Quit;
+        };
+        if let Some(path) = input.strip_prefix("replay ") {
+            replay(Path::new(path.trim()));
+            continue;
+        }
+        if let Some(path) = input.strip_prefix("opponent ") {
+            let path = Path::new(path.trim());
+            match load_opponent(path, card_db, program_state) {
+                Ok(()) => println!("Playing against {}", program_state.opponent_name),
+                Err(e) => println!("Can't load {}: {}", path.display(), e),
+            }
+            continue;
+        }
+        match profile::run_command(&input, program_state) {
+            Some(message) => println!("{}", message),
+            None => return sim::parse_command(&input),
+        }
+    }
+}
+
+// Step through a recorded game
+fn replay(path: &Path)
+{
+    match GameRecord::load(path) {
+        Ok(record) => replay::play_back(&record, || {
+            input::prompt().map_or(StepCommand::Quit, |input| sim::parse_command(&input))
+        }),
+        Err(e) => vlog!(
+            ELoggingVerbosity::Error,
+            "Can't load the recording {}: {}",
+            path.display(),
+            e
+        ),
+    }
 }
@@ -0,0 +1,492 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::card::{Card, CardFragmentKind, ManaSourceFragment};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ManaColor
+{
+    #[serde(rename = "W")]
+    White,
+    #[serde(rename = "U")]
+    Blue,
+    #[serde(rename = "B")]
+    Black,
+    #[serde(rename = "R")]
+    Red,
+    #[serde(rename = "G")]
+    Green,
+    #[serde(rename = "C")]
+    Colorless,
+}
+
+impl ManaColor
+{
+    pub const ALL: [ManaColor; 6] = [
+        ManaColor::White,
+        ManaColor::Blue,
+        ManaColor::Black,
+        ManaColor::Red,
+        ManaColor::Green,
+        ManaColor::Colorless,
+    ];
+
+    pub fn from_symbol(symbol: char) -> Option<ManaColor>
+    {
+        match symbol.to_ascii_uppercase() {
+            'W' => Some(ManaColor::White),
+            'U' => Some(ManaColor::Blue),
+            'B' => Some(ManaColor::Black),
+            'R' => Some(ManaColor::Red),
+            'G' => Some(ManaColor::Green),
+            'C' => Some(ManaColor::Colorless),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(self) -> char
+    {
+        match self {
+            ManaColor::White => 'W',
+            ManaColor::Blue => 'U',
+            ManaColor::Black => 'B',
+            ManaColor::Red => 'R',
+            ManaColor::Green => 'G',
+            ManaColor::Colorless => 'C',
+        }
+    }
+
+    fn index(self) -> usize
+    {
+        self as usize
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ManaError
+{
+    InvalidSymbol(String),
+}
+
+impl fmt::Display for ManaError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            ManaError::InvalidSymbol(symbol) => write!(f, "invalid mana symbol '{}'", symbol),
+        }
+    }
+}
+
+impl std::error::Error for ManaError {}
+
+/// A mana cost: generic mana plus colored (and specifically colorless) pips, and any number of
+/// X symbols whose amount the caster picks.
+///
+/// Parses both the compact form ("1WW") and Scryfall's braced form ("{2}{U}{U}"). In card files
+/// a plain number is still accepted and means generic mana only.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "ManaCostRepr", into = "String")]
+pub struct ManaCost
+{
+    pub generic: u32,
+    pips: [u32; 6],
+    // X symbols: "XXR" has two, each paid with the same amount
+    pub x: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ManaCostRepr
+{
+    Value(u32),
+    Text(String),
+}
+
+impl TryFrom<ManaCostRepr> for ManaCost
+{
+    type Error = ManaError;
+
+    fn try_from(repr: ManaCostRepr) -> Result<Self, Self::Error>
+    {
+        match repr {
+            ManaCostRepr::Value(generic) => Ok(ManaCost::generic(generic)),
+            ManaCostRepr::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<ManaCost> for String
+{
+    fn from(cost: ManaCost) -> String
+    {
+        cost.to_string()
+    }
+}
+
+impl ManaCost
+{
+    pub fn generic(amount: u32) -> Self
+    {
+        ManaCost {
+            generic: amount,
+            pips: [0; 6],
+            x: 0,
+        }
+    }
+
+    pub fn pips(&self, color: ManaColor) -> u32
+    {
+        self.pips[color.index()]
+    }
+
+    pub fn add_pips(&mut self, color: ManaColor, amount: u32)
+    {
+        self.pips[color.index()] += amount;
+    }
+
+    /// The cost once X is chosen: every X paid as `x` generic mana.
+    pub fn with_x(&self, x: u32) -> ManaCost
+    {
+        ManaCost {
+            generic: self.generic + self.x * x,
+            x: 0,
+            ..*self
+        }
+    }
+
+    /// Total amount of mana needed (a.k.a. converted mana cost), with X counting as 0.
+    pub fn mana_value(&self) -> u32
+    {
+        self.generic + self.pips.iter().sum::<u32>()
+    }
+
+    pub fn colors(&self) -> Vec<ManaColor>
+    {
+        ManaColor::ALL
+            .iter()
+            .copied()
+            .filter(|c| *c != ManaColor::Colorless && self.pips(*c) > 0)
+            .collect()
+    }
+}
+
+impl FromStr for ManaCost
+{
+    type Err = ManaError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err>
+    {
+        let mut cost = ManaCost::default();
+
+        // "{2}{U}{U}" is handled the same way as "2UU", but lets generic costs above 9 through
+        let symbols: Vec<String> = if text.contains('{') {
+            text.split(['{', '}'])
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| s.trim().to_string())
+                .collect()
+        } else {
+            let mut symbols = Vec::new();
+            let mut digits = String::new();
+            for c in text.chars().filter(|c| !c.is_whitespace()) {
+                if c.is_ascii_digit() {
+                    digits.push(c);
+                    continue;
+                }
+                if !digits.is_empty() {
+                    symbols.push(std::mem::take(&mut digits));
+                }
+                symbols.push(c.to_string());
+            }
+            if !digits.is_empty() {
+                symbols.push(digits);
+            }
+            symbols
+        };
+
+        for symbol in symbols.iter() {
+            if let Ok(amount) = symbol.parse::<u32>() {
+                cost.generic += amount;
+                continue;
+            }
+
+            if symbol.eq_ignore_ascii_case("x") {
+                cost.x += 1;
+                continue;
+            }
+
+            let mut chars = symbol.chars();
+            match (chars.next().and_then(ManaColor::from_symbol), chars.next()) {
+                (Some(color), None) => cost.add_pips(color, 1),
+                _ => return Err(ManaError::InvalidSymbol(symbol.clone())),
+            }
+        }
+
+        Ok(cost)
+    }
+}
+
+impl fmt::Display for ManaCost
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        for _ in 0..self.x {
+            write!(f, "{{X}}")?;
+        }
+        if self.generic > 0 || (self.mana_value() == 0 && self.x == 0) {
+            write!(f, "{{{}}}", self.generic)?;
+        }
+        for color in ManaColor::ALL.iter() {
+            for _ in 0..self.pips(*color) {
+                write!(f, "{{{}}}", color.symbol())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mana a player has produced but not spent yet. Empties between steps.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManaPool
+{
+    amounts: [u32; 6],
+}
+
+impl ManaPool
+{
+    pub fn add(&mut self, color: ManaColor, amount: u32)
+    {
+        self.amounts[color.index()] += amount;
+    }
+
+    pub fn amount(&self, color: ManaColor) -> u32
+    {
+        self.amounts[color.index()]
+    }
+
+    pub fn total(&self) -> u32
+    {
+        self.amounts.iter().sum()
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.amounts = [0; 6];
+    }
+
+    pub fn can_pay(&self, cost: &ManaCost) -> bool
+    {
+        ManaColor::ALL
+            .iter()
+            .all(|c| self.amount(*c) >= cost.pips(*c))
+            && self.total() >= cost.mana_value()
+    }
+
+    /// What's left of `cost` after spending everything in the pool that helps pay it.
+    pub fn remaining_cost(&self, cost: &ManaCost) -> ManaCost
+    {
+        let mut remaining = ManaCost::default();
+        let mut spare = 0;
+        for color in ManaColor::ALL.iter() {
+            let available = self.amount(*color);
+            remaining.add_pips(*color, cost.pips(*color).saturating_sub(available));
+            spare += available.saturating_sub(cost.pips(*color));
+        }
+        remaining.generic = cost.generic.saturating_sub(spare);
+        remaining
+    }
+
+    /// Spend mana for `cost`: pips from their own color, generic from whatever is most plentiful.
+    pub fn pay(&mut self, cost: &ManaCost) -> bool
+    {
+        if !self.can_pay(cost) {
+            return false;
+        }
+
+        for color in ManaColor::ALL.iter() {
+            self.amounts[color.index()] -= cost.pips(*color);
+        }
+        for _ in 0..cost.generic {
+            let most = (0..self.amounts.len())
+                .max_by_key(|i| self.amounts[*i])
+                .unwrap();
+            self.amounts[most] -= 1;
+        }
+        true
+    }
+}
+
+// Lands without explicit mana production tap for their basic land type's color
+pub fn basic_land_color(name: &str) -> Option<ManaColor>
+{
+    match name {
+        "Plains" => Some(ManaColor::White),
+        "Island" => Some(ManaColor::Blue),
+        "Swamp" => Some(ManaColor::Black),
+        "Mountain" => Some(ManaColor::Red),
+        "Forest" => Some(ManaColor::Green),
+        "Wastes" => Some(ManaColor::Colorless),
+        _ => None,
+    }
+}
+
+pub fn is_mana_source(card: &Card) -> bool
+{
+    card.fragments.contains_key(&CardFragmentKind::ManaSource)
+}
+
+/// Colors of mana a card can tap for (empty for non-sources).
+pub fn produced_colors(card: &Card) -> Vec<ManaColor>
+{
+    card.fragment::<ManaSourceFragment>()
+        .map(|mf| mf.colors.clone())
+        .unwrap_or_default()
+}
+
+/// Mana a source adds each time it's tapped (zero for non-sources).
+pub fn produced_amount(card: &Card) -> u32
+{
+    card.fragment::<ManaSourceFragment>()
+        .map(|mf| mf.amount)
+        .unwrap_or(0)
+}
+
+/// Decide which sources to tap for `cost`. `sources[i]` lists the colors source `i` can produce.
+///
+/// Returns the sources to tap with the color each one should produce, or `None` if the cost
+/// can't be paid. Sources that produce fewer colors are used first so flexible ones stay untapped.
+pub fn plan_payment(cost: &ManaCost, sources: &[Vec<ManaColor>])
+-> Option<Vec<(usize, ManaColor)>>
+{
+    if cost.mana_value() as usize > sources.len() {
+        return None;
+    }
+
+    let mut order: Vec<usize> = (0..sources.len())
+        .filter(|i| !sources[*i].is_empty())
+        .collect();
+    order.sort_by_key(|i| sources[*i].len());
+
+    let pips: Vec<ManaColor> = ManaColor::ALL
+        .iter()
+        .flat_map(|c| std::iter::repeat_n(*c, cost.pips(*c) as usize))
+        .collect();
+
+    let mut used = vec![false; sources.len()];
+    let mut plan = Vec::new();
+    if !assign_pips(&pips, sources, &order, &mut used, &mut plan) {
+        return None;
+    }
+
+    for index in order.iter() {
+        if plan.len() as u32 == cost.mana_value() {
+            break;
+        }
+        if !used[*index] {
+            used[*index] = true;
+            plan.push((*index, sources[*index][0]));
+        }
+    }
+
+    (plan.len() as u32 == cost.mana_value()).then_some(plan)
+}
+
+// Backtracking match of colored pips to sources; there are only ever a handful of both
+fn assign_pips(
+    pips: &[ManaColor],
+    sources: &[Vec<ManaColor>],
+    order: &[usize],
+    used: &mut [bool],
+    plan: &mut Vec<(usize, ManaColor)>,
+) -> bool
+{
+    let Some((pip, rest)) = pips.split_first() else {
+        return true;
+    };
+
+    for index in order.iter() {
+        if used[*index] || !sources[*index].contains(pip) {
+            continue;
+        }
+
+        used[*index] = true;
+        plan.push((*index, *pip));
+        if assign_pips(rest, sources, order, used, plan) {
+            return true;
+        }
+        plan.pop();
+        used[*index] = false;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parses_compact_and_braced_costs()
+    {
+        let compact: ManaCost = "1WW".parse().unwrap();
+        assert_eq!(compact.generic, 1);
+        assert_eq!(compact.pips(ManaColor::White), 2);
+        assert_eq!(compact.mana_value(), 3);
+
+        let braced: ManaCost = "{2}{U}{U}".parse().unwrap();
+        assert_eq!(braced.to_string(), "{2}{U}{U}");
+        assert_eq!(braced, "2UU".parse().unwrap());
+        assert_eq!("{12}".parse::<ManaCost>().unwrap().generic, 12);
+        assert_eq!("".parse::<ManaCost>().unwrap(), ManaCost::default());
+
+        let fireball: ManaCost = "{X}{R}".parse().unwrap();
+        assert_eq!((fireball.x, fireball.mana_value()), (1, 1));
+        assert_eq!(fireball, "XR".parse().unwrap());
+        assert_eq!(fireball.to_string(), "{X}{R}");
+        assert_eq!(fireball.with_x(4).to_string(), "{4}{R}");
+        assert_eq!(
+            "2Q".parse::<ManaCost>(),
+            Err(ManaError::InvalidSymbol(String::from("Q")))
+        );
+    }
+
+    #[test]
+    fn pool_pays_colored_pips_first()
+    {
+        let mut pool = ManaPool::default();
+        pool.add(ManaColor::Green, 2);
+        pool.add(ManaColor::Red, 1);
+
+        assert!(!pool.can_pay(&"RR".parse().unwrap()));
+        assert!(pool.pay(&"1G".parse().unwrap()));
+        assert_eq!(pool.amount(ManaColor::Red), 1);
+        assert_eq!(pool.total(), 1);
+
+        // Floating red covers the pip, not the green one
+        assert_eq!(
+            pool.remaining_cost(&"2RG".parse().unwrap()),
+            "2G".parse().unwrap()
+        );
+        assert_eq!(
+            pool.remaining_cost(&"1".parse().unwrap()),
+            ManaCost::default()
+        );
+    }
+
+    #[test]
+    fn payment_plan_keeps_flexible_sources_for_colored_pips()
+    {
+        use ManaColor::*;
+        // A dual listed first must not be spent on the generic part
+        let sources = vec![vec![Red, Green], vec![Green], vec![Green]];
+
+        let plan = plan_payment(&"1R".parse().unwrap(), &sources).unwrap();
+        assert!(plan.contains(&(0, Red)));
+        assert_eq!(plan.len(), 2);
+
+        assert!(plan_payment(&"RR".parse().unwrap(), &sources).is_none());
+        assert!(plan_payment(&"3".parse().unwrap(), &sources).is_some());
+    }
+}
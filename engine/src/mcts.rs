@@ -0,0 +1,462 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::card::Card;
+use crate::game::{GameState, Zone};
+use crate::mulligan::{KeepAll, MulliganStrategy};
+use crate::strategy::{Cast, CurveOut, PlayerStrategy};
+
+/// How long `Mcts` searches each decision. Iteration budgets keep games reproducible from their
+/// seed; time budgets don't.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MctsBudget
+{
+    Iterations(u32),
+    Time(Duration),
+}
+
+impl MctsBudget
+{
+    fn allows(self, iterations: u32, started: Instant) -> bool
+    {
+        match self {
+            MctsBudget::Iterations(limit) => iterations < limit,
+            MctsBudget::Time(limit) => started.elapsed() < limit,
+        }
+    }
+}
+
+impl Default for MctsBudget
+{
+    fn default() -> Self
+    {
+        MctsBudget::Iterations(100)
+    }
+}
+
+impl FromStr for MctsBudget
+{
+    type Err = String;
+
+    // "200" rollouts per decision, or "50ms" of search
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let parsed = match s.strip_suffix("ms") {
+            Some(ms) => ms
+                .parse()
+                .map(|ms| MctsBudget::Time(Duration::from_millis(ms))),
+            None => s.parse().map(MctsBudget::Iterations),
+        };
+        parsed.map_err(|_| format!("invalid MCTS budget '{}' (expected a number of rollouts or milliseconds like 50ms)", s))
+    }
+}
+
+// One answer to a decision, replayed at the start of a rollout
+#[derive(Clone, Debug, PartialEq)]
+enum Choice
+{
+    Land(Option<usize>),
+    Cast(Option<Cast>),
+    Attack(Vec<usize>),
+    Block(Vec<(usize, usize)>),
+}
+
+/// Monte Carlo search over the player's main-phase and combat decisions: every legal answer (or,
+/// for combat, a few sensible ones) is tried in rollouts that play a copy of the game onwards
+/// with `CurveOut`, picking which answer to roll out next by UCB1, and the answer with the best
+/// average reward is played. Libraries are reshuffled in every rollout so the search doesn't
+/// see the real draws. Instants and discards are left to `CurveOut`.
+#[derive(Clone, Debug)]
+pub struct Mcts
+{
+    pub budget: MctsBudget,
+    // Turns of each player a rollout plays before it's scored as unfinished
+    pub horizon: u32,
+    // UCB1 exploration constant
+    pub exploration: f64,
+    pub mulligan: Arc<dyn MulliganStrategy>,
+}
+
+impl Default for Mcts
+{
+    fn default() -> Self
+    {
+        Mcts {
+            budget: MctsBudget::default(),
+            horizon: 10,
+            exploration: std::f64::consts::SQRT_2,
+            mulligan: Arc::new(KeepAll),
+        }
+    }
+}
+
+// Plays `choice` the first time the player faces that kind of decision, passing on main-phase
+// decisions asked before it (the rollout replays the current step from its start), then
+// follows `policy`
+struct Forced
+{
+    choice: Mutex<Option<Choice>>,
+    policy: CurveOut,
+}
+
+impl Forced
+{
+    fn take<T>(&self, pick: impl FnOnce(&Choice) -> Option<T>) -> Option<T>
+    {
+        let mut choice = self.choice.lock().unwrap();
+        let answer = choice.as_ref().and_then(pick);
+        if answer.is_some() {
+            *choice = None;
+        }
+        answer
+    }
+
+    fn pending(&self) -> bool
+    {
+        self.choice.lock().unwrap().is_some()
+    }
+}
+
+impl PlayerStrategy for Forced
+{
+    fn name(&self) -> String
+    {
+        String::from("forced")
+    }
+
+    fn mulligan(&self) -> &dyn MulliganStrategy
+    {
+        self.policy.mulligan()
+    }
+
+    fn land_drop(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<usize>
+    {
+        match self.take(|c| {
+            if let Choice::Land(land) = c {
+                Some(*land)
+            } else {
+                None
+            }
+        }) {
+            Some(land) => land,
+            None if self.pending() => None,
+            None => self.policy.land_drop(game, player, rng),
+        }
+    }
+
+    fn main_phase_cast(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Option<Cast>
+    {
+        match self.take(|c| {
+            if let Choice::Cast(cast) = c {
+                Some(cast.clone())
+            } else {
+                None
+            }
+        }) {
+            Some(cast) => cast,
+            None => self.policy.main_phase_cast(game, player, rng),
+        }
+    }
+
+    fn respond(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<Cast>
+    {
+        self.policy.respond(game, player, rng)
+    }
+
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        self.take(|c| {
+            if let Choice::Attack(attackers) = c {
+                Some(attackers.clone())
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.policy.attackers(game, player, defending_player, rng))
+    }
+
+    fn blockers(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Vec<(usize, usize)>
+    {
+        self.take(|c| {
+            if let Choice::Block(blocks) = c {
+                Some(blocks.clone())
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.policy.blockers(game, player, rng))
+    }
+}
+
+impl Mcts
+{
+    // The candidate with the best average reward after spending the budget on rollouts
+    fn search(
+        &self,
+        game: &GameState,
+        player: usize,
+        mut candidates: Vec<Choice>,
+        rng: &mut ChaCha8Rng,
+    ) -> Choice
+    {
+        if candidates.len() == 1 {
+            return candidates.remove(0);
+        }
+
+        let mut visits = vec![0u32; candidates.len()];
+        let mut rewards = vec![0.0; candidates.len()];
+        let started = Instant::now();
+        let mut iterations = 0;
+        while self.budget.allows(iterations, started) {
+            let total = iterations.max(1) as f64;
+            let pick = match visits.iter().position(|&v| v == 0) {
+                Some(unvisited) => unvisited,
+                None => (0..candidates.len())
+                    .max_by(|&a, &b| {
+                        let ucb = |i: usize| {
+                            rewards[i] / visits[i] as f64
+                                + self.exploration * (total.ln() / visits[i] as f64).sqrt()
+                        };
+                        ucb(a).total_cmp(&ucb(b))
+                    })
+                    .unwrap(),
+            };
+            rewards[pick] += self.rollout(game, player, candidates[pick].clone(), rng);
+            visits[pick] += 1;
+            iterations += 1;
+        }
+
+        let mean = |i: usize| {
+            if visits[i] == 0 {
+                0.0
+            } else {
+                rewards[i] / visits[i] as f64
+            }
+        };
+        let best = (0..candidates.len())
+            .max_by(|&a, &b| mean(a).total_cmp(&mean(b)))
+            .unwrap();
+        candidates.swap_remove(best)
+    }
+
+    // Play a copy of the game from this decision on and score it for `player`: wins between
+    // 0.5 and 1, sooner being better; losses 0; games still going at the horizon by how much of
+    // the next opponent's life is gone, up to 0.5
+    fn rollout(&self, game: &GameState, player: usize, choice: Choice, rng: &mut ChaCha8Rng)
+    -> f64
+    {
+        let mut sim = game.clone_for_rollout();
+        sim.rng = ChaCha8Rng::seed_from_u64(rng.r#gen());
+        for p in 0..sim.players.len() {
+            sim.shuffle_library(p);
+        }
+        let policy: Arc<dyn PlayerStrategy> = Arc::new(CurveOut::default());
+        sim.strategies = (0..sim.players.len())
+            .map(|p| {
+                if p == player {
+                    Arc::new(Forced {
+                        choice: Mutex::new(Some(choice.clone())),
+                        policy: CurveOut::default(),
+                    })
+                } else {
+                    policy.clone()
+                }
+            })
+            .collect();
+
+        let start = sim.turns;
+        let limit = start + self.horizon * sim.players.len() as u32;
+        while !sim.is_game_over() && sim.turns <= limit {
+            sim.step();
+        }
+
+        match sim.winner() {
+            Some(winner) if winner == player => {
+                1.0 - 0.5 * sim.turns.saturating_sub(start) as f64 / (limit - start + 1) as f64
+            }
+            Some(_) => 0.0,
+            None if sim.players[player].has_lost => 0.0,
+            None => {
+                let opponent = &sim.players[(player + 1) % sim.players.len()];
+                let lost = 1.0 - opponent.life as f64 / sim.rules.starting_life.max(1) as f64;
+                0.5 * lost.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+// Casts of different copies of the same card lead to the same game, so only the first is kept
+fn distinct_casts(hand: &[Card], casts: Vec<Cast>) -> Vec<Cast>
+{
+    let mut seen: Vec<(&str, usize)> = Vec::new();
+    casts
+        .into_iter()
+        .filter(|cast| {
+            if cast.zone != Zone::Hand {
+                return true;
+            }
+            let spell = (hand[cast.index].name.as_str(), cast.face);
+            let new = !seen.contains(&spell);
+            seen.push(spell);
+            new
+        })
+        .collect()
+}
+
+impl PlayerStrategy for Mcts
+{
+    fn name(&self) -> String
+    {
+        match self.budget {
+            MctsBudget::Iterations(n) => format!("mcts-{}", n),
+            MctsBudget::Time(limit) => format!("mcts-{}ms", limit.as_millis()),
+        }
+    }
+
+    fn mulligan(&self) -> &dyn MulliganStrategy
+    {
+        self.mulligan.as_ref()
+    }
+
+    fn land_drop(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<usize>
+    {
+        let hand = game.players[player].zones.get(&Zone::Hand).unwrap();
+        let mut names: Vec<&str> = Vec::new();
+        let mut candidates = vec![Choice::Land(None)];
+        for (i, card) in hand
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| crate::faces::can_be_land(c))
+        {
+            if !names.contains(&card.name.as_str()) {
+                names.push(&card.name);
+                candidates.push(Choice::Land(Some(i)));
+            }
+        }
+        match self.search(game, player, candidates, rng) {
+            Choice::Land(land) => land,
+            _ => unreachable!(),
+        }
+    }
+
+    fn main_phase_cast(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Option<Cast>
+    {
+        let hand = game.players[player].zones.get(&Zone::Hand).unwrap();
+        let mut candidates = vec![Choice::Cast(None)];
+        candidates.extend(
+            distinct_casts(hand, game.castable_spells(player))
+                .into_iter()
+                .map(|cast| Choice::Cast(Some(cast))),
+        );
+        match self.search(game, player, candidates, rng) {
+            Choice::Cast(cast) => cast,
+            _ => unreachable!(),
+        }
+    }
+
+    fn respond(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<Cast>
+    {
+        CurveOut::default().respond(game, player, rng)
+    }
+
+    // Candidates: what `CurveOut` would attack with, everything that can attack, and nothing
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        let everything = game.components(player).ready_attackers();
+        let mut candidates = vec![Choice::Attack(CurveOut::default().attackers(
+            game,
+            player,
+            defending_player,
+            rng,
+        ))];
+        for attack in [everything, Vec::new()] {
+            if !candidates.contains(&Choice::Attack(attack.clone())) {
+                candidates.push(Choice::Attack(attack));
+            }
+        }
+        match self.search(game, player, candidates, rng) {
+            Choice::Attack(attackers) => attackers,
+            _ => unreachable!(),
+        }
+    }
+
+    // Candidates: `CurveOut`'s blocks, or taking all the damage
+    fn blockers(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Vec<(usize, usize)>
+    {
+        let blocks = CurveOut::default().blockers(game, player, rng);
+        let candidates = if blocks.is_empty() {
+            vec![Choice::Block(blocks)]
+        } else {
+            vec![Choice::Block(blocks), Choice::Block(Vec::new())]
+        };
+        match self.search(game, player, candidates, rng) {
+            Choice::Block(blocks) => blocks,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{Deck, forest, grizzly_bears};
+    use crate::game::GameStep;
+
+    #[test]
+    fn search_prefers_developing_the_board()
+    {
+        let deck = Deck::example();
+        let mut game = GameState::with_decks(&[&deck, &Deck::goldfish()], 5);
+        let zones = &mut game.players[0].zones;
+        // Two Forests can't pay for the Wurm, so the only play is the Bears
+        *zones.get_mut(&Zone::Hand).unwrap() = vec![
+            crate::card::card_by_name("Craw Wurm").unwrap(),
+            grizzly_bears(),
+        ];
+        *zones.get_mut(&Zone::Battlefield).unwrap() = vec![forest(), forest()];
+        game.step = GameStep::Main;
+
+        let mcts = Mcts {
+            budget: MctsBudget::Iterations(40),
+            ..Mcts::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let cast = mcts
+            .main_phase_cast(&game, 0, &mut rng)
+            .expect("casting the bears beats passing");
+        assert_eq!((cast.zone, cast.index, cast.face), (Zone::Hand, 1, 0));
+        assert!(!cast.alternative);
+        let mut sources: Vec<usize> = cast.plan.iter().map(|&(source, _)| source).collect();
+        sources.sort();
+        assert_eq!(sources, vec![0, 1], "both Forests pay for it");
+        assert_eq!(mcts.name(), "mcts-40");
+
+        assert_eq!("250".parse::<MctsBudget>(), Ok(MctsBudget::Iterations(250)));
+        assert_eq!(
+            "20ms".parse::<MctsBudget>(),
+            Ok(MctsBudget::Time(Duration::from_millis(20)))
+        );
+        assert!("soon".parse::<MctsBudget>().is_err());
+    }
+}
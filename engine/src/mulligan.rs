@@ -0,0 +1,386 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::card::{Card, CardType};
+
+/// Mulligans after which a hand is always kept; going lower would leave nothing to play.
+pub const MAX_MULLIGANS: u32 = 6;
+
+/// Decides whether an opening hand is kept and, under the London mulligan, which cards go to the
+/// bottom of the library afterwards.
+pub trait MulliganStrategy: Send + Sync
+{
+    /// Short name recorded in simulation results, e.g. "keep-if-2-to-5-lands".
+    fn name(&self) -> String;
+
+    /// Whether to keep the freshly drawn `hand` after `mulligans` earlier mulligans.
+    fn keep(&self, hand: &[Card], mulligans: u32) -> bool;
+
+    /// Indices of `count` cards of a kept hand to put on the bottom; see `least_useful`.
+    fn bottom(&self, hand: &[Card], count: usize) -> Vec<usize>
+    {
+        least_useful(hand, count)
+    }
+}
+
+/// Indices of the `count` cards a hand can best do without: surplus lands while more than half
+/// the hand is lands, then the most expensive spells. Also picks cleanup discards.
+pub fn least_useful(hand: &[Card], count: usize) -> Vec<usize>
+{
+    let mut remaining: Vec<usize> = (0..hand.len()).collect();
+    let mut picked = Vec::new();
+    for _ in 0..count.min(hand.len()) {
+        let lands = remaining
+            .iter()
+            .filter(|&&i| hand[i].is_type(CardType::Land))
+            .count();
+        let pick = if lands * 2 > remaining.len() {
+            remaining
+                .iter()
+                .position(|&i| hand[i].is_type(CardType::Land))
+        } else {
+            remaining
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| !hand[**i].is_type(CardType::Land))
+                .max_by_key(|(_, i)| hand[**i].cost.mana_value())
+                .map(|(pos, _)| pos)
+        };
+        picked.push(remaining.remove(pick.unwrap_or(0)));
+    }
+    picked
+}
+
+impl fmt::Debug for dyn MulliganStrategy
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Never mulligans; every game starts with the first seven cards.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct KeepAll;
+
+impl MulliganStrategy for KeepAll
+{
+    fn name(&self) -> String
+    {
+        String::from("keep-all")
+    }
+
+    fn keep(&self, _hand: &[Card], _mulligans: u32) -> bool
+    {
+        true
+    }
+}
+
+/// Keeps hands with between `min` and `max` lands (inclusive), counting spells with a land face.
+#[derive(Copy, Clone, Debug)]
+pub struct LandRange
+{
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Default for LandRange
+{
+    fn default() -> Self
+    {
+        LandRange { min: 2, max: 5 }
+    }
+}
+
+impl MulliganStrategy for LandRange
+{
+    fn name(&self) -> String
+    {
+        format!("keep-if-{}-to-{}-lands", self.min, self.max)
+    }
+
+    fn keep(&self, hand: &[Card], _mulligans: u32) -> bool
+    {
+        let lands = hand.iter().filter(|c| crate::faces::can_be_land(c)).count() as u32;
+        (self.min..=self.max).contains(&lands)
+    }
+}
+
+/// A strategy from a closure, for experiments that don't deserve a type of their own.
+pub struct CustomMulligan<F>
+where
+    F: Fn(&[Card], u32) -> bool + Send + Sync,
+{
+    pub name: String,
+    pub keep: F,
+}
+
+impl<F> MulliganStrategy for CustomMulligan<F>
+where
+    F: Fn(&[Card], u32) -> bool + Send + Sync,
+{
+    fn name(&self) -> String
+    {
+        self.name.clone()
+    }
+
+    fn keep(&self, hand: &[Card], mulligans: u32) -> bool
+    {
+        (self.keep)(hand, mulligans)
+    }
+}
+
+/// Serde adapter storing a strategy by its name, for `#[serde(with = "crate::mulligan::by_name")]`.
+pub mod by_name
+{
+    use serde::{Deserialize, Deserializer, Serializer, de};
+    use std::sync::Arc;
+
+    use super::MulliganStrategy;
+
+    pub fn serialize<S: Serializer>(
+        strategy: &Arc<dyn MulliganStrategy>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    {
+        serializer.serialize_str(&strategy.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Arc<dyn MulliganStrategy>, D::Error>
+    {
+        let name = String::deserialize(deserializer)?;
+        super::parse_strategy(&name).map_err(de::Error::custom)
+    }
+}
+
+/// Parses a strategy name as printed by `MulliganStrategy::name`: "keep-all" or
+/// "keep-if-MIN-to-MAX-lands".
+pub fn parse_strategy(s: &str) -> Result<Arc<dyn MulliganStrategy>, String>
+{
+    if s == "keep-all" {
+        return Ok(Arc::new(KeepAll));
+    }
+
+    let range = s
+        .strip_prefix("keep-if-")
+        .and_then(|rest| rest.strip_suffix("-lands"))
+        .and_then(|range| range.split_once("-to-"));
+    match range.and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?))) {
+        Some((min, max)) if min <= max => Ok(Arc::new(LandRange { min, max })),
+        _ => Err(format!(
+            "unknown mulligan strategy '{}' (expected keep-all or keep-if-MIN-to-MAX-lands)",
+            s
+        )),
+    }
+}
+
+/// Library (top last), hand and number of mulligans taken.
+pub struct OpeningHand
+{
+    pub library: Vec<Card>,
+    pub hand: Vec<Card>,
+    pub mulligans: u32,
+}
+
+/// London mulligan: shuffle and draw `hand_size` cards until `strategy` keeps (or
+/// `MAX_MULLIGANS` is reached), then put one card on the bottom for every mulligan taken.
+pub fn london<R: Rng>(
+    cards: Vec<Card>,
+    hand_size: u32,
+    rng: &mut R,
+    strategy: &dyn MulliganStrategy,
+) -> OpeningHand
+{
+    let max_mulligans = MAX_MULLIGANS.min(hand_size.saturating_sub(1));
+    let mut library = cards;
+    let mut mulligans = 0;
+    loop {
+        library.shuffle(rng);
+        let hand = library.split_off(library.len().saturating_sub(hand_size as usize));
+        let mut hand: Vec<Card> = hand.into_iter().rev().collect();
+
+        if mulligans >= max_mulligans || strategy.keep(&hand, mulligans) {
+            let mut bottomed = strategy.bottom(&hand, mulligans as usize);
+            bottomed.sort_unstable_by(|a, b| b.cmp(a));
+            for index in bottomed {
+                library.insert(0, hand.remove(index));
+            }
+            return OpeningHand {
+                library,
+                hand,
+                mulligans,
+            };
+        }
+
+        library.append(&mut hand);
+        mulligans += 1;
+    }
+}
+
+/// Keep and mulligan statistics over sample opening hands, from `sample_hands`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HandSample
+{
+    pub strategy: String,
+    pub hand_size: u32,
+    pub hands: u32,
+    // Hands kept after each number of mulligans
+    pub mulligans: BTreeMap<u32, u32>,
+    // Kept hands by how many lands they hold, spells with a land face included
+    pub lands: BTreeMap<u32, u32>,
+}
+
+/// Deal `count` opening hands from `cards` under the London mulligan, without playing any games.
+pub fn sample_hands<R: Rng>(
+    cards: &[Card],
+    hand_size: u32,
+    count: u32,
+    rng: &mut R,
+    strategy: &dyn MulliganStrategy,
+) -> HandSample
+{
+    let mut sample = HandSample {
+        strategy: strategy.name(),
+        hand_size,
+        hands: count,
+        ..HandSample::default()
+    };
+    for _ in 0..count {
+        let opening = london(cards.to_vec(), hand_size, rng, strategy);
+        let lands = opening
+            .hand
+            .iter()
+            .filter(|c| crate::faces::can_be_land(c))
+            .count() as u32;
+        *sample.mulligans.entry(opening.mulligans).or_insert(0) += 1;
+        *sample.lands.entry(lands).or_insert(0) += 1;
+    }
+    sample
+}
+
+impl HandSample
+{
+    /// Share of hands kept without a mulligan.
+    pub fn keep_rate(&self) -> f64
+    {
+        self.mulligans.get(&0).copied().unwrap_or(0) as f64 / self.hands.max(1) as f64
+    }
+}
+
+impl fmt::Display for HandSample
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let share = |hands: u32| hands as f64 * 100.0 / self.hands.max(1) as f64;
+        let kept: Vec<String> = self
+            .mulligans
+            .iter()
+            .map(|(mulligans, hands)| {
+                format!(
+                    "{} cards {:.1}%",
+                    self.hand_size.saturating_sub(*mulligans),
+                    share(*hands)
+                )
+            })
+            .collect();
+        let lands: Vec<String> = self
+            .lands
+            .iter()
+            .map(|(lands, hands)| format!("{} {:.1}%", lands, share(*hands)))
+            .collect();
+
+        writeln!(
+            f,
+            "{} opening hands ({}): {:.1}% kept without a mulligan",
+            self.hands,
+            self.strategy,
+            self.keep_rate() * 100.0
+        )?;
+        writeln!(f, "  kept at {}", kept.join(", "))?;
+        write!(f, "  lands in kept hands: {}", lands.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn london_mulligan_redraws_and_bottoms_cards()
+    {
+        let mut cards: Vec<Card> = (0..10).map(|_| forest()).collect();
+        cards.extend((0..30).map(|_| grizzly_bears()));
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+
+        let kept = london(cards.clone(), 7, &mut rng, &KeepAll);
+        assert_eq!(
+            (kept.hand.len(), kept.library.len(), kept.mulligans),
+            (7, 33, 0)
+        );
+
+        // Only keep all-spell hands after one mulligan: exactly one card goes to the bottom
+        let picky = CustomMulligan {
+            name: String::from("mull-once"),
+            keep: |_: &[Card], mulligans| mulligans == 1,
+        };
+        let kept = london(cards.clone(), 7, &mut rng, &picky);
+        assert_eq!(
+            (kept.hand.len(), kept.library.len(), kept.mulligans),
+            (6, 34, 1)
+        );
+
+        // A deck that can never satisfy the strategy still ends at the limit
+        let lands: Vec<Card> = (0..40).map(|_| forest()).collect();
+        let kept = london(lands, 7, &mut rng, &LandRange::default());
+        assert_eq!((kept.hand.len(), kept.mulligans), (1, MAX_MULLIGANS));
+        assert_eq!(
+            parse_strategy(&LandRange::default().name()).unwrap().name(),
+            "keep-if-2-to-5-lands"
+        );
+        assert!(parse_strategy("keep-if-5-to-2-lands").is_err());
+    }
+
+    #[test]
+    fn sample_hands_counts_keeps_and_lands()
+    {
+        let mut cards: Vec<Card> = (0..24).map(|_| forest()).collect();
+        cards.extend((0..36).map(|_| grizzly_bears()));
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+
+        let sample = sample_hands(&cards, 7, 500, &mut rng, &LandRange::default());
+        assert_eq!(sample.mulligans.values().sum::<u32>(), 500);
+        assert_eq!(sample.lands.values().sum::<u32>(), 500);
+        assert!(sample.keep_rate() > 0.7 && sample.keep_rate() < 1.0);
+
+        let all = sample_hands(&cards, 7, 100, &mut rng, &KeepAll);
+        assert_eq!(all.keep_rate(), 1.0);
+        assert!(
+            all.to_string()
+                .starts_with("100 opening hands (keep-all): 100.0% kept")
+        );
+    }
+
+    #[test]
+    fn default_bottoming_evens_out_the_hand()
+    {
+        let mut hand: Vec<Card> = (0..5).map(|_| forest()).collect();
+        hand.push(grizzly_bears());
+        hand.push(crate::card::card_by_name("Craw Wurm").unwrap());
+
+        let bottomed = KeepAll.bottom(&hand, 3);
+        assert_eq!(bottomed.len(), 3);
+        assert!(bottomed.iter().all(|&i| hand[i].is_type(CardType::Land)));
+
+        let bottomed = KeepAll.bottom(&hand[3..], 2);
+        assert_eq!(bottomed, vec![3, 0]);
+    }
+}
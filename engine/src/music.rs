@@ -1,3 +1,4 @@
+use rand::seq::SliceRandom;
 use rodio::{Decoder, OutputStream, Sink, Source};
 use std::fs::File;
 use std::io::BufReader;
@@ -6,12 +7,12 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use walkdir::WalkDir;
-use rand::seq::SliceRandom;
 
 /// Find the web directory by searching upward from the current directory
-pub fn find_web_dir() -> PathBuf {
+pub fn find_web_dir() -> PathBuf
+{
     let mut current = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    
+
     loop {
         let web_path = current.join("web");
         if web_path.exists() && web_path.is_dir() {
@@ -21,19 +22,21 @@ pub fn find_web_dir() -> PathBuf {
             break;
         }
     }
-    
+
     // Fallback to current directory
     std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
 }
 
 /// Get the path to a resource relative to the web directory
-pub fn music_dir_path() -> PathBuf {
+pub fn music_dir_path() -> PathBuf
+{
     find_web_dir().join("web/music")
 }
 
 /// Configuration for music playback
 #[derive(Clone)]
-pub struct MusicConfig {
+pub struct MusicConfig
+{
     /// Fade duration in milliseconds when transitioning between songs
     pub fade_duration_ms: u64,
     /// Delay in milliseconds between songs
@@ -42,8 +45,10 @@ pub struct MusicConfig {
     pub volume: f32,
 }
 
-impl Default for MusicConfig {
-    fn default() -> Self {
+impl Default for MusicConfig
+{
+    fn default() -> Self
+    {
         Self {
             fade_duration_ms: 1000,
             delay_between_songs_ms: 2000,
@@ -53,21 +58,27 @@ impl Default for MusicConfig {
 }
 
 /// Music player for background music using rodio
-pub struct MusicPlayer {
+pub struct MusicPlayer
+{
     config: Arc<Mutex<MusicConfig>>,
     music_files: Vec<PathBuf>,
     is_running: Arc<Mutex<bool>>,
 }
 
-impl MusicPlayer {
+impl MusicPlayer
+{
     /// Create a new music player that loads music from the specified directory
-    pub fn new(music_dir: &str, config: MusicConfig) -> Self {
+    pub fn new(music_dir: &str, config: MusicConfig) -> Self
+    {
         let music_files = Self::load_music_files(music_dir);
-        
+
         if music_files.is_empty() {
             println!("No music files found in {}", music_dir);
         } else {
-            println!("Loaded {} music file(s) for background playback", music_files.len());
+            println!(
+                "Loaded {} music file(s) for background playback",
+                music_files.len()
+            );
         }
 
         Self {
@@ -78,7 +89,8 @@ impl MusicPlayer {
     }
 
     /// Load all audio files from a directory (recursively)
-    fn load_music_files(music_dir: &str) -> Vec<PathBuf> {
+    fn load_music_files(music_dir: &str) -> Vec<PathBuf>
+    {
         let mut files = Vec::new();
         let supported_extensions = ["mp3", "wav", "flac", "ogg"];
 
@@ -88,12 +100,10 @@ impl MusicPlayer {
             .filter(|e| e.path().is_file())
         {
             let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if let Some(ext_str) = ext.to_str() {
-                    if supported_extensions.contains(&ext_str.to_lowercase().as_str()) {
-                        files.push(path.to_path_buf());
-                    }
-                }
+            if let Some(ext_str) = path.extension().and_then(|ext| ext.to_str())
+                && supported_extensions.contains(&ext_str.to_lowercase().as_str())
+            {
+                files.push(path.to_path_buf());
             }
         }
 
@@ -103,7 +113,8 @@ impl MusicPlayer {
     }
 
     /// Start playing background music in a background thread
-    pub fn start(&self) {
+    pub fn start(&self)
+    {
         if self.music_files.is_empty() {
             return;
         }
@@ -117,71 +128,72 @@ impl MusicPlayer {
         thread::spawn(move || {
             // Try to create output stream, but don't fail if no audio device is available
             let audio_available = OutputStream::try_default().is_ok();
-            
+
             if audio_available {
-                if let Ok((_stream, stream_handle)) = OutputStream::try_default() {
-                    if let Ok(sink) = Sink::try_new(&stream_handle) {
-                        let mut current_index = 0;
-
-                        while *is_running.lock().unwrap() {
-                            let current_file = &music_files[current_index % music_files.len()];
-
-                            // Load and play the file
-                            if let Ok(file) = File::open(current_file) {
-                                let reader = BufReader::new(file);
-                                if let Ok(source) = Decoder::new(reader) {
-                                    let config_lock = config.lock().unwrap();
-                                    let volume = config_lock.volume;
-                                    let fade_duration = config_lock.fade_duration_ms;
-                                    let delay_ms = config_lock.delay_between_songs_ms;
-                                    drop(config_lock);
-
-                                    // Set volume and add source to sink
-                                    sink.set_volume(volume);
-                                    sink.append(source);
-                                    
-                                    // Wait for playback to complete
-                                    sink.sleep_until_end();
-
-                                    // Fade-out effect by reducing volume gradually
-                                    if fade_duration > 0 {
-                                        let steps = 20;
-                                        let step_duration = Duration::from_millis(fade_duration / steps);
-                                        
-                                        for i in 1..=steps {
-                                            let progress = i as f32 / steps as f32;
-                                            let new_volume = volume * (1.0 - progress);
-                                            sink.set_volume(new_volume.max(0.0));
-                                            thread::sleep(step_duration);
-                                        }
-                                        
-                                        sink.set_volume(0.0);
-                                    }
+                if let Ok((_stream, stream_handle)) = OutputStream::try_default()
+                    && let Ok(sink) = Sink::try_new(&stream_handle)
+                {
+                    let mut current_index = 0;
+
+                    while *is_running.lock().unwrap() {
+                        let current_file = &music_files[current_index % music_files.len()];
+
+                        // Load and play the file
+                        if let Ok(file) = File::open(current_file) {
+                            let reader = BufReader::new(file);
+                            if let Ok(source) = Decoder::new(reader) {
+                                let config_lock = config.lock().unwrap();
+                                let volume = config_lock.volume;
+                                let fade_duration = config_lock.fade_duration_ms;
+                                let delay_ms = config_lock.delay_between_songs_ms;
+                                drop(config_lock);
+
+                                // Set volume and add source to sink
+                                sink.set_volume(volume);
+                                sink.append(source);
+
+                                // Wait for playback to complete
+                                sink.sleep_until_end();
 
-                                    // Clear the sink for next song
-                                    sink.clear();
-                                    sink.set_volume(volume); // Reset volume
+                                // Fade-out effect by reducing volume gradually
+                                if fade_duration > 0 {
+                                    let steps = 20;
+                                    let step_duration =
+                                        Duration::from_millis(fade_duration / steps);
 
-                                    // Delay before next song
-                                    if delay_ms > 0 {
-                                        thread::sleep(Duration::from_millis(delay_ms));
+                                    for i in 1..=steps {
+                                        let progress = i as f32 / steps as f32;
+                                        let new_volume = volume * (1.0 - progress);
+                                        sink.set_volume(new_volume.max(0.0));
+                                        thread::sleep(step_duration);
                                     }
+
+                                    sink.set_volume(0.0);
                                 }
-                            }
 
-                            current_index += 1;
+                                // Clear the sink for next song
+                                sink.clear();
+                                sink.set_volume(volume); // Reset volume
+
+                                // Delay before next song
+                                if delay_ms > 0 {
+                                    thread::sleep(Duration::from_millis(delay_ms));
+                                }
+                            }
                         }
 
-                        sink.stop();
+                        current_index += 1;
                     }
+
+                    sink.stop();
                 }
             } else {
                 // No audio device available - simulate playback by waiting for song durations
                 let mut current_index = 0;
-                
+
                 while *is_running.lock().unwrap() {
                     let current_file = &music_files[current_index % music_files.len()];
-                    
+
                     // Try to estimate song duration by reading metadata
                     if let Ok(file) = File::open(current_file) {
                         let reader = BufReader::new(file);
@@ -198,21 +210,21 @@ impl MusicPlayer {
                             thread::sleep(Duration::from_millis(100));
                         }
                     }
-                    
+
                     // Apply fade and delay settings
                     let config_lock = config.lock().unwrap();
                     let fade_duration = config_lock.fade_duration_ms;
                     let delay_ms = config_lock.delay_between_songs_ms;
                     drop(config_lock);
-                    
+
                     if fade_duration > 0 {
                         thread::sleep(Duration::from_millis(fade_duration));
                     }
-                    
+
                     if delay_ms > 0 {
                         thread::sleep(Duration::from_millis(delay_ms));
                     }
-                    
+
                     current_index += 1;
                 }
             }
@@ -220,26 +232,30 @@ impl MusicPlayer {
     }
 
     /// Stop playing music
-    pub fn stop(&self) {
+    pub fn stop(&self)
+    {
         *self.is_running.lock().unwrap() = false;
     }
 
     /// Update the music configuration
-    pub fn update_config(&self, config: MusicConfig) {
+    pub fn update_config(&self, config: MusicConfig)
+    {
         *self.config.lock().unwrap() = config;
     }
 
     /// Get the current music configuration
-    pub fn get_config(&self) -> MusicConfig {
+    pub fn get_config(&self) -> MusicConfig
+    {
         self.config.lock().unwrap().clone()
     }
 }
 
-impl Drop for MusicPlayer {
-    fn drop(&mut self) {
+impl Drop for MusicPlayer
+{
+    fn drop(&mut self)
+    {
         self.stop();
         // Give the thread a moment to shut down cleanly
         thread::sleep(Duration::from_millis(100));
     }
 }
-
@@ -0,0 +1,1869 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::ELoggingVerbosity;
+use crate::card::{Card, Deck};
+use crate::deck::Decklist;
+use crate::result::SimulationResult;
+use crate::sim;
+use crate::stats::{GaussianProcess, Sample, expected_improvement, welch_p_value};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DeckEntry
+{
+    pub name: String,
+    pub count: u32,
+    pub min: u32,
+    pub max: u32,
+    pub is_land: bool,
+}
+
+/// A point in the optimizer's search space: how many copies of each card the deck runs.
+/// Mutations move copies between entries, so the deck size never changes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DeckVector
+{
+    pub entries: Vec<DeckEntry>,
+    // Commander decks: names of the commanders, which the search never changes
+    pub commanders: Vec<String>,
+}
+
+impl DeckVector
+{
+    // The classic search: Forests vs Grizzly Bears with no copy limit
+    pub fn land_split(lands: u32, nonlands: u32) -> Self
+    {
+        let size = lands + nonlands;
+        DeckVector {
+            entries: vec![
+                DeckEntry {
+                    name: String::from("Forest"),
+                    count: lands,
+                    min: 0,
+                    max: size,
+                    is_land: true,
+                },
+                DeckEntry {
+                    name: String::from("Grizzly Bears"),
+                    count: nonlands,
+                    min: 0,
+                    max: size,
+                    is_land: false,
+                },
+            ],
+            commanders: Vec::new(),
+        }
+    }
+
+    /// `lands` copies of a basic land plus `nonlands` copies spread as evenly as possible over
+    /// `pool`, each capped at `max_copies`.
+    pub fn with_pool(
+        land: &str,
+        lands: u32,
+        pool: &[String],
+        nonlands: u32,
+        max_copies: u32,
+    ) -> Result<Self, String>
+    {
+        if pool.is_empty() {
+            return Err(String::from("the card pool is empty"));
+        }
+        if nonlands > pool.len() as u32 * max_copies {
+            return Err(format!(
+                "{} nonlands don't fit in {} cards with at most {} copies each",
+                nonlands,
+                pool.len(),
+                max_copies
+            ));
+        }
+
+        let size = lands + nonlands;
+        let mut entries = vec![DeckEntry {
+            name: String::from(land),
+            count: lands,
+            min: 0,
+            max: size,
+            is_land: true,
+        }];
+        for (i, name) in pool.iter().enumerate() {
+            let count =
+                nonlands / pool.len() as u32 + u32::from((i as u32) < nonlands % pool.len() as u32);
+            entries.push(DeckEntry {
+                name: name.clone(),
+                count,
+                min: 0,
+                max: max_copies,
+                is_land: false,
+            });
+        }
+
+        Ok(DeckVector {
+            entries,
+            commanders: Vec::new(),
+        })
+    }
+
+    /// Start from a parsed decklist. Lands may take any count; every other card may go from zero up
+    /// to `max_copies` (or its current count, if that is already higher).
+    pub fn from_decklist(
+        list: &Decklist,
+        lookup: impl Fn(&str) -> Option<Card>,
+        max_copies: u32,
+    ) -> Result<Self, String>
+    {
+        let size = list.main.iter().map(|e| e.count).sum();
+        let mut entries: Vec<DeckEntry> = Vec::new();
+
+        for item in list.main.iter() {
+            if let Some(existing) = entries.iter_mut().find(|e| e.name == item.name) {
+                existing.count += item.count;
+                existing.max = existing.max.max(existing.count);
+                continue;
+            }
+
+            let card = lookup(&item.name).ok_or_else(|| format!("unknown card '{}'", item.name))?;
+            let is_land = crate::faces::can_be_land(&card);
+            let max = if is_land {
+                size
+            } else {
+                max_copies.max(item.count)
+            };
+            entries.push(DeckEntry {
+                name: item.name.clone(),
+                count: item.count,
+                min: 0,
+                max,
+                is_land,
+            });
+        }
+
+        Ok(DeckVector {
+            entries,
+            commanders: list.commander.iter().map(|e| e.name.clone()).collect(),
+        })
+    }
+
+    pub fn size(&self) -> u32
+    {
+        self.entries.iter().map(|e| e.count).sum()
+    }
+
+    pub fn lands(&self) -> u32
+    {
+        self.entries
+            .iter()
+            .filter(|e| e.is_land)
+            .map(|e| e.count)
+            .sum()
+    }
+
+    pub fn nonlands(&self) -> u32
+    {
+        self.size() - self.lands()
+    }
+
+    /// Mutation: move `n` copies from entry `from` to entry `to`, if the bounds allow it.
+    pub fn moved(&self, from: usize, to: usize, n: u32) -> Option<DeckVector>
+    {
+        if from == to || from >= self.entries.len() || to >= self.entries.len() {
+            return None;
+        }
+
+        let mut next = self.clone();
+        next.entries[from].count = next.entries[from]
+            .count
+            .checked_sub(n)
+            .filter(|&c| c >= next.entries[from].min)?;
+        next.entries[to].count =
+            Some(next.entries[to].count + n).filter(|&c| c <= next.entries[to].max)?;
+        Some(next)
+    }
+
+    /// Every single mutation of `n` copies, in a stable order.
+    pub fn neighbors(&self, n: u32) -> Vec<DeckVector>
+    {
+        let len = self.entries.len();
+        (0..len)
+            .flat_map(|to| (0..len).map(move |from| (from, to)))
+            .filter_map(|(from, to)| self.moved(from, to, n))
+            .collect()
+    }
+
+    /// Freeze every nonland entry so only the mix of lands can change (manabase search).
+    pub fn with_fixed_nonlands(mut self) -> Self
+    {
+        for entry in self.entries.iter_mut().filter(|e| !e.is_land) {
+            entry.min = entry.count;
+            entry.max = entry.count;
+        }
+        self
+    }
+
+    /// Add lands the search may swap in, starting from zero copies.
+    pub fn with_extra_lands(mut self, names: &[String]) -> Self
+    {
+        let lands = self.lands();
+        for name in names.iter() {
+            if !self
+                .entries
+                .iter()
+                .any(|e| e.name.eq_ignore_ascii_case(name))
+            {
+                self.entries.push(DeckEntry {
+                    name: name.clone(),
+                    count: 0,
+                    min: 0,
+                    max: lands,
+                    is_land: true,
+                });
+            }
+        }
+        self
+    }
+
+    pub fn random_neighbor<R: Rng>(&self, rng: &mut R, max_step: u32) -> Option<DeckVector>
+    {
+        // Entries pinned to a single count can never take part in a move
+        let movable: Vec<usize> = (0..self.entries.len())
+            .filter(|i| self.entries[*i].min < self.entries[*i].max)
+            .collect();
+        if movable.len() < 2 {
+            return None;
+        }
+
+        // Random picks can hit a bound; give up after a few attempts
+        for _ in 0..32 {
+            let from = movable[rng.gen_range(0..movable.len())];
+            let to = movable[rng.gen_range(0..movable.len())];
+            let n = rng.gen_range(1..=max_step.max(1));
+            if let Some(next) = self.moved(from, to, n) {
+                return Some(next);
+            }
+        }
+
+        None
+    }
+
+    /// Uniform crossover: every entry takes its count from one parent or the other, then random
+    /// copies are added or removed within the bounds until the deck has `self`'s size again.
+    /// Both parents must come from the same search.
+    pub fn crossover<R: Rng>(&self, other: &DeckVector, rng: &mut R) -> DeckVector
+    {
+        let mut child = self.clone();
+        for (entry, theirs) in child.entries.iter_mut().zip(other.entries.iter()) {
+            if rng.gen_bool(0.5) {
+                entry.count = theirs.count;
+            }
+        }
+
+        let target = self.size();
+        while child.size() != target {
+            let grow = child.size() < target;
+            let adjustable: Vec<usize> = (0..child.entries.len())
+                .filter(|&i| {
+                    if grow {
+                        child.entries[i].count < child.entries[i].max
+                    } else {
+                        child.entries[i].count > child.entries[i].min
+                    }
+                })
+                .collect();
+            if adjustable.is_empty() {
+                return self.clone();
+            }
+            let entry = &mut child.entries[adjustable[rng.gen_range(0..adjustable.len())]];
+            if grow {
+                entry.count += 1
+            } else {
+                entry.count -= 1
+            }
+        }
+        child
+    }
+
+    /// Per-card mutation: each entry has a `rate` chance of moving one copy to another entry.
+    pub fn mutated<R: Rng>(&self, rng: &mut R, rate: f64) -> DeckVector
+    {
+        let mut next = self.clone();
+        for from in 0..self.entries.len() {
+            if rng.gen_bool(rate) {
+                let to = rng.gen_range(0..self.entries.len());
+                if let Some(moved) = next.moved(from, to, 1) {
+                    next = moved;
+                }
+            }
+        }
+        next
+    }
+
+    /// All vectors of the same deck size that respect every entry's bounds, or `None` if there
+    /// are more than `limit` of them.
+    pub fn enumerate(&self, limit: usize) -> Option<Vec<DeckVector>>
+    {
+        fn fill(
+            template: &DeckVector,
+            index: usize,
+            remaining: u32,
+            current: &mut Vec<u32>,
+            out: &mut Vec<DeckVector>,
+            limit: usize,
+        ) -> bool
+        {
+            let entry = &template.entries[index];
+            if index == template.entries.len() - 1 {
+                if remaining >= entry.min && remaining <= entry.max {
+                    if out.len() == limit {
+                        return false;
+                    }
+                    let mut v = template.clone();
+                    for (e, &c) in v
+                        .entries
+                        .iter_mut()
+                        .zip(current.iter().chain(std::iter::once(&remaining)))
+                    {
+                        e.count = c;
+                    }
+                    out.push(v);
+                }
+                return true;
+            }
+
+            for count in entry.min..=entry.max.min(remaining) {
+                current.push(count);
+                let ok = fill(template, index + 1, remaining - count, current, out, limit);
+                current.pop();
+                if !ok {
+                    return false;
+                }
+            }
+            true
+        }
+
+        if self.entries.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let mut out = Vec::new();
+        fill(self, 0, self.size(), &mut Vec::new(), &mut out, limit).then_some(out)
+    }
+
+    pub fn to_deck(&self, lookup: impl Fn(&str) -> Option<Card>) -> Result<Deck, String>
+    {
+        let mut cards = Vec::with_capacity(self.size() as usize);
+        for entry in self.entries.iter() {
+            let card =
+                lookup(&entry.name).ok_or_else(|| format!("unknown card '{}'", entry.name))?;
+            cards.extend(std::iter::repeat_n(card, entry.count as usize));
+        }
+
+        let commanders = self
+            .commanders
+            .iter()
+            .map(|name| lookup(name).ok_or_else(|| format!("unknown card '{}'", name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Deck {
+            cards,
+            commanders,
+            ..Deck::default()
+        })
+    }
+
+    // "24 Forest, 4 Grizzly Bears, ..." (entries with no copies are left out)
+    pub fn describe(&self) -> String
+    {
+        self.entries
+            .iter()
+            .filter(|e| e.count > 0)
+            .map(|e| format!("{} {}", e.count, e.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // Short label for the difference to `base`, e.g. "+1 Forest, -1 Grizzly Bears"
+    fn change_from(&self, base: &DeckVector) -> String
+    {
+        let changes: Vec<String> = self
+            .entries
+            .iter()
+            .zip(base.entries.iter())
+            .filter(|(a, b)| a.count != b.count)
+            .map(|(a, b)| format!("{:+} {}", a.count as i64 - b.count as i64, a.name))
+            .collect();
+
+        if changes.is_empty() {
+            String::from("No change")
+        } else {
+            changes.join(", ")
+        }
+    }
+}
+
+/// The number an optimizer tries to minimize for each configuration.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Objective
+{
+    // Average turns until the opponent is dead
+    #[default]
+    TurnsToKill,
+    // Share of games with a castable-by-count spell stuck in hand for lack of the right colors
+    ColorScrew,
+    // Share of games lost against the opponent deck
+    LossRate,
+    // Turns that the given percentage of games finish within, e.g. 90 for the worst case but outliers
+    TurnsPercentile(u32),
+    // Speed and consistency traded off against each other
+    Weighted(ObjectiveWeights),
+}
+
+/// Weights of a combined objective: the score is `mean_turns` times the average turns, plus
+/// `p90_turns` times the 90th percentile, plus `screw_rate` times the share of mana-screwed games.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ObjectiveWeights
+{
+    pub mean_turns: f64,
+    pub p90_turns: f64,
+    pub screw_rate: f64,
+}
+
+impl FromStr for ObjectiveWeights
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let weights: Vec<f64> = s
+            .split(',')
+            .map(|w| w.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| {
+                format!(
+                    "unknown objective weights '{}' (expected three numbers, e.g. 1,0.5,10)",
+                    s
+                )
+            })?;
+        match weights[..] {
+            [mean_turns, p90_turns, screw_rate] => Ok(ObjectiveWeights {
+                mean_turns,
+                p90_turns,
+                screw_rate,
+            }),
+            _ => Err(format!(
+                "unknown objective weights '{}' (expected three numbers, e.g. 1,0.5,10)",
+                s
+            )),
+        }
+    }
+}
+
+impl Objective
+{
+    pub fn score(self, result: &SimulationResult) -> f64
+    {
+        self.sample(result).mean
+    }
+
+    /// The score with the spread of the per-game values behind it, for significance tests.
+    pub fn sample(self, result: &SimulationResult) -> Sample
+    {
+        // Refused (illegal) configurations never win a comparison
+        if result.games == 0 {
+            return Sample {
+                mean: f64::INFINITY,
+                variance: 0.0,
+                count: 0,
+            };
+        }
+        match self {
+            Objective::TurnsToKill => Sample {
+                mean: result.mean(),
+                variance: result.stddev_turns.powi(2),
+                count: result.games,
+            },
+            Objective::ColorScrew => Sample::proportion(result.color_screw_games, result.games),
+            Objective::LossRate => Sample::proportion(result.games - result.wins, result.games),
+            Objective::TurnsPercentile(percentile) => {
+                let standard_error = result.percentile_standard_error(percentile);
+                Sample {
+                    mean: result.percentile(percentile),
+                    variance: standard_error.powi(2) * result.games as f64,
+                    count: result.games,
+                }
+            }
+            Objective::Weighted(weights) => {
+                // The parts' variances add up as if they were independent, which is close enough
+                // for significance tests
+                let parts = [
+                    (weights.mean_turns, Objective::TurnsToKill.sample(result)),
+                    (
+                        weights.p90_turns,
+                        Objective::TurnsPercentile(90).sample(result),
+                    ),
+                    (
+                        weights.screw_rate,
+                        Sample::proportion(result.mana_screw_games, result.games),
+                    ),
+                ];
+                Sample {
+                    mean: parts.iter().map(|(weight, part)| weight * part.mean).sum(),
+                    variance: parts
+                        .iter()
+                        .map(|(weight, part)| weight * weight * part.variance)
+                        .sum(),
+                    count: result.games,
+                }
+            }
+        }
+    }
+
+    pub fn label(self) -> String
+    {
+        match self {
+            Objective::TurnsToKill => String::from("avg turns"),
+            Objective::ColorScrew => String::from("color-screw rate"),
+            Objective::LossRate => String::from("loss rate"),
+            Objective::TurnsPercentile(percentile) => format!("p{} turns", percentile),
+            Objective::Weighted(_) => String::from("weighted score"),
+        }
+    }
+}
+
+/// Runs the simulations an optimizer asks for.
+pub trait ScenarioEvaluator
+{
+    /// Simulate one configuration; `None` means the user asked to stop.
+    fn evaluate(&mut self, deck: &DeckVector) -> Option<SimulationResult>;
+
+    fn objective(&self) -> Objective
+    {
+        Objective::TurnsToKill
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimizationOutcome
+{
+    pub best: DeckVector,
+    // The objective's score for `best` (mean turns unless the evaluator says otherwise)
+    pub best_mean: f64,
+    // false when the optimizer ran out of budget before settling on an answer
+    pub converged: bool,
+    // Every evaluation in the order it was made
+    pub history: Vec<(DeckVector, f64)>,
+}
+
+/// One configuration an optimizer tried, with everything its simulation reported.
+#[derive(Clone, Debug, Serialize)]
+pub struct Evaluation
+{
+    pub deck: DeckVector,
+    pub score: f64,
+    pub result: SimulationResult,
+}
+
+/// A whole optimizer run in machine-readable form, for archiving or later analysis.
+#[derive(Clone, Debug, Serialize)]
+pub struct OptimizationReport
+{
+    pub optimizer: String,
+    pub objective: String,
+    pub seed: u64,
+    // Every evaluation in the order it was made
+    pub evaluations: Vec<Evaluation>,
+    pub best: DeckVector,
+    pub best_description: String,
+    pub best_score: f64,
+    pub converged: bool,
+    // Indices into `evaluations` of the configurations on the speed/consistency Pareto front
+    pub pareto_front: Vec<usize>,
+}
+
+impl OptimizationReport
+{
+    /// `results` are the evaluator's results in the order it produced them, one per entry of
+    /// `outcome.history`.
+    pub fn new(
+        optimizer: &str,
+        objective: Objective,
+        seed: u64,
+        outcome: &OptimizationOutcome,
+        results: Vec<SimulationResult>,
+    ) -> Self
+    {
+        let evaluations: Vec<Evaluation> = outcome
+            .history
+            .iter()
+            .zip(results)
+            .map(|((deck, score), result)| Evaluation {
+                deck: deck.clone(),
+                score: *score,
+                result,
+            })
+            .collect();
+        OptimizationReport {
+            optimizer: optimizer.to_string(),
+            objective: objective.label(),
+            seed,
+            pareto_front: pareto_front(&evaluations),
+            evaluations,
+            best: outcome.best.clone(),
+            best_description: outcome.best.describe(),
+            best_score: outcome.best_mean,
+            converged: outcome.converged,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error>
+    {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// What the Pareto front trades off, all to be minimized: average turns, 90th-percentile turns
+/// and the share of mana-screwed games.
+pub fn pareto_criteria(result: &SimulationResult) -> [f64; 3]
+{
+    [result.mean(), result.percentile(90), result.screw_rate()]
+}
+
+/// Indices of the evaluations no other evaluation matches or beats on every `pareto_criteria` at
+/// once, fastest first. Only each deck's latest evaluation counts, and refused decks never do.
+pub fn pareto_front(evaluations: &[Evaluation]) -> Vec<usize>
+{
+    let latest: Vec<usize> = (0..evaluations.len())
+        .filter(|&i| {
+            evaluations[i].result.games > 0
+                && !evaluations[i + 1..]
+                    .iter()
+                    .any(|later| later.deck == evaluations[i].deck)
+        })
+        .collect();
+    let criteria: Vec<[f64; 3]> = evaluations
+        .iter()
+        .map(|e| pareto_criteria(&e.result))
+        .collect();
+    let dominates =
+        |a: &[f64; 3], b: &[f64; 3]| a.iter().zip(b.iter()).all(|(x, y)| x <= y) && a != b;
+
+    let mut front: Vec<usize> = latest
+        .iter()
+        .copied()
+        .filter(|&i| {
+            !latest
+                .iter()
+                .any(|&j| dominates(&criteria[j], &criteria[i]))
+        })
+        .collect();
+    front.sort_by(|&a, &b| criteria[a][0].total_cmp(&criteria[b][0]));
+    front
+}
+
+/// One evaluation as saved in a checkpoint. Unlike reports, it keeps every game's turn count so
+/// a replayed result scores the same under percentile objectives.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointEntry
+{
+    pub deck: DeckVector,
+    pub result: SimulationResult,
+    pub turns: Vec<u32>,
+}
+
+impl CheckpointEntry
+{
+    pub fn new(deck: &DeckVector, result: &SimulationResult) -> Self
+    {
+        CheckpointEntry {
+            deck: deck.clone(),
+            result: result.clone(),
+            turns: result.turns.clone(),
+        }
+    }
+
+    pub fn result(&self) -> SimulationResult
+    {
+        SimulationResult {
+            turns: self.turns.clone(),
+            ..self.result.clone()
+        }
+    }
+}
+
+/// Everything an interrupted optimizer run needs to pick up where it stopped: the evaluations so
+/// far, and the seed and game index the next simulation continues from.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Checkpoint
+{
+    pub optimizer: String,
+    pub objective: String,
+    pub seed: u64,
+    // Index of the next game to seed after the last saved evaluation
+    pub next_game: u64,
+    // Every evaluation in the order it was made
+    pub evaluations: Vec<CheckpointEntry>,
+}
+
+impl Checkpoint
+{
+    pub fn new(optimizer: &str, objective: Objective, seed: u64) -> Self
+    {
+        Checkpoint {
+            optimizer: optimizer.to_string(),
+            objective: objective.label(),
+            seed,
+            ..Checkpoint::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String>
+    {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    }
+
+    /// Write through a temporary file, so an interruption mid-write leaves the last checkpoint intact.
+    pub fn save(&self, path: &Path) -> io::Result<()>
+    {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        std::fs::write(&temporary, serde_json::to_string(self)?)?;
+        std::fs::rename(&temporary, path)
+    }
+
+    /// Results of every saved evaluation, in order.
+    pub fn results(&self) -> Vec<SimulationResult>
+    {
+        self.evaluations
+            .iter()
+            .map(CheckpointEntry::result)
+            .collect()
+    }
+}
+
+/// Evaluator that records every evaluation into a checkpoint (saved after each one when given a
+/// path), and replays the checkpoint's evaluations while the optimizer asks for the same decks
+/// in the same order. Optimizers are deterministic given their seed and results, so a resumed
+/// run retraces its steps without simulating and carries on from where it stopped.
+pub struct Checkpointing<'a>
+{
+    inner: &'a mut dyn ScenarioEvaluator,
+    pub checkpoint: Checkpoint,
+    path: Option<PathBuf>,
+    // Evaluations of `checkpoint` already handed to the optimizer
+    position: usize,
+}
+
+impl<'a> Checkpointing<'a>
+{
+    pub fn new(
+        inner: &'a mut dyn ScenarioEvaluator,
+        checkpoint: Checkpoint,
+        path: Option<PathBuf>,
+    ) -> Self
+    {
+        Checkpointing {
+            inner,
+            checkpoint,
+            path,
+            position: 0,
+        }
+    }
+}
+
+impl ScenarioEvaluator for Checkpointing<'_>
+{
+    fn evaluate(&mut self, deck: &DeckVector) -> Option<SimulationResult>
+    {
+        if let Some(entry) = self.checkpoint.evaluations.get(self.position) {
+            if entry.deck == *deck {
+                self.position += 1;
+                return Some(entry.result());
+            }
+
+            // The search took another path than the saved one; the rest of it no longer applies
+            vlog!(
+                ELoggingVerbosity::Warning,
+                "The search left the checkpoint's path after {} evaluations",
+                self.position
+            );
+            self.checkpoint.evaluations.truncate(self.position);
+        }
+
+        let result = self.inner.evaluate(deck)?;
+        self.checkpoint
+            .evaluations
+            .push(CheckpointEntry::new(deck, &result));
+        self.checkpoint.next_game = sim::next_game_index();
+        self.position += 1;
+        if let Some(path) = &self.path
+            && let Err(e) = self.checkpoint.save(path)
+        {
+            vlog!(
+                ELoggingVerbosity::Warning,
+                "Can't save the checkpoint to {}: {}",
+                path.display(),
+                e
+            );
+        }
+        Some(result)
+    }
+
+    fn objective(&self) -> Objective
+    {
+        self.inner.objective()
+    }
+}
+
+pub trait DeckOptimizer
+{
+    fn name(&self) -> &'static str;
+
+    /// Search for the configuration with the lowest score under the evaluator's objective.
+    /// Returns `None` if the evaluator was stopped before anything useful could be reported.
+    fn optimize(
+        &mut self,
+        start: DeckVector,
+        evaluator: &mut dyn ScenarioEvaluator,
+    ) -> Option<OptimizationOutcome>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OptimizerKind
+{
+    HillClimb,
+    Grid,
+    Annealing,
+    Genetic,
+    Bayesian,
+}
+
+impl FromStr for OptimizerKind
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s {
+            "hill-climb" | "hill" => Ok(OptimizerKind::HillClimb),
+            "grid" => Ok(OptimizerKind::Grid),
+            "annealing" | "anneal" => Ok(OptimizerKind::Annealing),
+            "genetic" | "ga" => Ok(OptimizerKind::Genetic),
+            "bayesian" | "bo" => Ok(OptimizerKind::Bayesian),
+            _ => Err(format!(
+                "unknown optimizer '{}' (expected hill-climb, grid, annealing, genetic or bayesian)",
+                s
+            )),
+        }
+    }
+}
+
+// Evaluate and record; shared by all optimizers
+fn evaluate_into(
+    evaluator: &mut dyn ScenarioEvaluator,
+    deck: &DeckVector,
+    history: &mut Vec<(DeckVector, f64)>,
+) -> Option<Sample>
+{
+    let result = evaluator.evaluate(deck)?;
+    let sample = evaluator.objective().sample(&result);
+    history.push((deck.clone(), sample.mean));
+    Some(sample)
+}
+
+fn best_of(history: &[(DeckVector, f64)]) -> Option<(DeckVector, f64)>
+{
+    history.iter().min_by(|a, b| a.1.total_cmp(&b.1)).cloned()
+}
+
+/// Compare the current deck against every single-step mutation and move to the best one while
+/// a one-sided Welch's t-test says it's better at the `significance` level, split evenly across
+/// the mutations tested (Bonferroni), so that one of many mutations looking better by chance
+/// doesn't move the climb. Settles on the current deck once no mutation is significantly better.
+pub struct HillClimb
+{
+    pub step: u32,
+    pub significance: f64,
+    pub max_iterations: Option<u32>,
+}
+
+impl Default for HillClimb
+{
+    fn default() -> Self
+    {
+        HillClimb {
+            step: 1,
+            significance: 0.05,
+            max_iterations: None,
+        }
+    }
+}
+
+impl DeckOptimizer for HillClimb
+{
+    fn name(&self) -> &'static str
+    {
+        "hill-climb"
+    }
+
+    fn optimize(
+        &mut self,
+        start: DeckVector,
+        evaluator: &mut dyn ScenarioEvaluator,
+    ) -> Option<OptimizationOutcome>
+    {
+        let unit = evaluator.objective().label();
+        let mut history = Vec::new();
+        let mut current = start;
+        let mut current_sample = None;
+        let mut iteration = 1;
+
+        loop {
+            if self.max_iterations.is_some_and(|max| iteration > max) {
+                // Iteration budget exhausted: the deck reached so far may still be improvable
+                println!("\n=== Iteration limit reached ===");
+                let best_mean = current_sample.map(|s: Sample| s.mean)?;
+                return Some(OptimizationOutcome {
+                    best: current,
+                    best_mean,
+                    converged: false,
+                    history,
+                });
+            }
+
+            println!("\n=== Iteration {} ===", iteration);
+            println!(
+                "Testing decks centered around {} lands, {} nonlands ({})",
+                current.lands(),
+                current.nonlands(),
+                current.describe()
+            );
+
+            // Test the current deck and every mutation of it
+            let current_result = evaluate_into(evaluator, &current, &mut history)?;
+            let mut results = Vec::new();
+            for deck in current.neighbors(self.step) {
+                let sample = evaluate_into(evaluator, &deck, &mut history)?;
+                results.push((deck, sample, welch_p_value(&sample, &current_result)));
+            }
+
+            println!("\nIteration {} Results:", iteration);
+            println!(
+                "  {}: {} lands, {} nonlands -> {} {}",
+                current.change_from(&current),
+                current.lands(),
+                current.nonlands(),
+                current_result.mean,
+                unit
+            );
+            for (deck, sample, p) in results.iter() {
+                println!(
+                    "  {}: {} lands, {} nonlands -> {} {} (p = {:.3})",
+                    deck.change_from(&current),
+                    deck.lands(),
+                    deck.nonlands(),
+                    sample.mean,
+                    unit,
+                    p
+                );
+            }
+
+            // Move to the best mutation, if it's significantly better (earlier mutations win ties)
+            let threshold = self.significance / results.len().max(1) as f64;
+            let best = results
+                .into_iter()
+                .fold(
+                    None,
+                    |acc: Option<(DeckVector, Sample, f64)>, r| match acc {
+                        Some(a) if a.1.mean <= r.1.mean => Some(a),
+                        _ => Some(r),
+                    },
+                )
+                .filter(|(_, _, p)| *p < threshold);
+
+            match best {
+                Some((deck, sample, p)) => {
+                    println!(
+                        "\nBest configuration: {} ({} lands, {} nonlands) -> {} {} (p = {:.3})",
+                        deck.change_from(&current),
+                        deck.lands(),
+                        deck.nonlands(),
+                        sample.mean,
+                        unit,
+                        p
+                    );
+                    current = deck;
+                    current_sample = Some(sample);
+                }
+                None => {
+                    println!("\n=== Optimization Complete ===");
+                    println!(
+                        "No mutation is significantly better than {} lands, {} nonlands (p < {:.4})",
+                        current.lands(),
+                        current.nonlands(),
+                        threshold
+                    );
+                    return Some(OptimizationOutcome {
+                        best: current,
+                        best_mean: current_result.mean,
+                        converged: true,
+                        history,
+                    });
+                }
+            }
+
+            iteration += 1;
+        }
+    }
+}
+
+/// Evaluate every deck whose land count is in a range (deck size stays fixed).
+pub struct GridSearch
+{
+    pub min_lands: u32,
+    pub max_lands: u32,
+    // Refuse to run when the grid would contain more decks than this
+    pub max_candidates: usize,
+}
+
+impl DeckOptimizer for GridSearch
+{
+    fn name(&self) -> &'static str
+    {
+        "grid"
+    }
+
+    fn optimize(
+        &mut self,
+        start: DeckVector,
+        evaluator: &mut dyn ScenarioEvaluator,
+    ) -> Option<OptimizationOutcome>
+    {
+        let Some(all) = start.enumerate(self.max_candidates * 16) else {
+            println!("Grid search: too many candidate decks, narrow the card bounds");
+            return None;
+        };
+
+        let grid: Vec<DeckVector> = all
+            .into_iter()
+            .filter(|deck| (self.min_lands..=self.max_lands).contains(&deck.lands()))
+            .collect();
+
+        if grid.len() > self.max_candidates {
+            println!(
+                "Grid search: {} candidate decks exceeds the limit of {}",
+                grid.len(),
+                self.max_candidates
+            );
+            return None;
+        }
+
+        println!(
+            "\n=== Grid search: {} decks with {} to {} lands ===",
+            grid.len(),
+            self.min_lands,
+            self.max_lands
+        );
+
+        let mut history = Vec::new();
+        for deck in grid.iter() {
+            evaluate_into(evaluator, deck, &mut history)?;
+        }
+
+        // Every deck in the grid was tried, so there's no budget to run out of
+        let (best, best_mean) = best_of(&history)?;
+        println!("\n=== Optimization Complete ===");
+        Some(OptimizationOutcome {
+            best,
+            best_mean,
+            converged: true,
+            history,
+        })
+    }
+}
+
+/// Random walk that accepts worse neighbours with a probability that shrinks as it cools down,
+/// which lets it escape the local optima a hill-climber gets stuck in.
+pub struct SimulatedAnnealing
+{
+    pub iterations: u32,
+    pub initial_temperature: f64,
+    pub cooling_rate: f64,
+    pub max_step: u32,
+    pub seed: u64,
+}
+
+impl SimulatedAnnealing
+{
+    pub fn new(seed: u64) -> Self
+    {
+        SimulatedAnnealing {
+            iterations: 30,
+            initial_temperature: 1.0,
+            cooling_rate: 0.9,
+            max_step: 2,
+            seed,
+        }
+    }
+}
+
+impl DeckOptimizer for SimulatedAnnealing
+{
+    fn name(&self) -> &'static str
+    {
+        "annealing"
+    }
+
+    fn optimize(
+        &mut self,
+        start: DeckVector,
+        evaluator: &mut dyn ScenarioEvaluator,
+    ) -> Option<OptimizationOutcome>
+    {
+        let unit = evaluator.objective().label();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut history = Vec::new();
+        let mut temperature = self.initial_temperature;
+
+        let mut current_mean = evaluate_into(evaluator, &start, &mut history)?.mean;
+        let mut current = start;
+        // Only a walk with nowhere left to go stops before its iterations run out
+        let mut converged = false;
+
+        for iteration in 1..=self.iterations {
+            let Some(candidate) = current.random_neighbor(&mut rng, self.max_step) else {
+                converged = true;
+                break;
+            };
+            let candidate_mean = evaluate_into(evaluator, &candidate, &mut history)?.mean;
+
+            let accept = candidate_mean <= current_mean
+                || rng.gen_bool(
+                    (-(candidate_mean - current_mean) / temperature.max(1e-9))
+                        .exp()
+                        .min(1.0),
+                );
+
+            println!(
+                "\n=== Annealing step {} (T = {:.3}) ===",
+                iteration, temperature
+            );
+            println!(
+                "  {} -> {:.4} {} ({})",
+                candidate.change_from(&current),
+                candidate_mean,
+                unit,
+                if accept { "accepted" } else { "rejected" }
+            );
+
+            if accept {
+                current = candidate;
+                current_mean = candidate_mean;
+            }
+
+            temperature *= self.cooling_rate;
+        }
+
+        let (best, best_mean) = best_of(&history)?;
+        println!("\n=== Optimization Complete ===");
+        Some(OptimizationOutcome {
+            best,
+            best_mean,
+            converged,
+            history,
+        })
+    }
+}
+
+/// Evolves a population of decks. Each generation keeps its `elite` best decks and breeds the
+/// rest from parents picked by tournament, through crossover and per-card mutation. Searches wide
+/// card pools better than a hill-climber, which stops at the first local optimum. Decks that come
+/// back in a later generation keep their first score instead of being simulated again.
+pub struct Genetic
+{
+    pub population: usize,
+    pub generations: u32,
+    pub elite: usize,
+    // Chance of each entry of a child moving a copy elsewhere
+    pub mutation_rate: f64,
+    // Decks drawn for each parent; the best of them breeds
+    pub tournament: usize,
+    pub seed: u64,
+}
+
+impl Genetic
+{
+    pub fn new(seed: u64) -> Self
+    {
+        Genetic {
+            population: 12,
+            generations: 10,
+            elite: 2,
+            mutation_rate: 0.1,
+            tournament: 3,
+            seed,
+        }
+    }
+}
+
+impl DeckOptimizer for Genetic
+{
+    fn name(&self) -> &'static str
+    {
+        "genetic"
+    }
+
+    fn optimize(
+        &mut self,
+        start: DeckVector,
+        evaluator: &mut dyn ScenarioEvaluator,
+    ) -> Option<OptimizationOutcome>
+    {
+        let unit = evaluator.objective().label();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut history = Vec::new();
+        let mut scores: HashMap<DeckVector, f64> = HashMap::new();
+        let size = self.population.max(2);
+
+        // The first generation: the starting deck and random walks away from it
+        let mut population = vec![start.clone()];
+        while population.len() < size {
+            let mut deck = start.clone();
+            for _ in 0..rng.gen_range(1..=4) {
+                deck = deck.random_neighbor(&mut rng, 2).unwrap_or(deck);
+            }
+            population.push(deck);
+        }
+
+        // Settled only if the whole population bred true to one deck before the generations ran out
+        let mut converged = false;
+        for generation in 0..=self.generations {
+            for deck in population.iter() {
+                if !scores.contains_key(deck) {
+                    let mean = evaluate_into(evaluator, deck, &mut history)?.mean;
+                    scores.insert(deck.clone(), mean);
+                }
+            }
+            population.sort_by(|a, b| scores[a].total_cmp(&scores[b]));
+
+            println!("\n=== Generation {} ===", generation);
+            println!(
+                "  best {} -> {:.4} {}",
+                population[0].describe(),
+                scores[&population[0]],
+                unit
+            );
+            if population.iter().all(|deck| *deck == population[0]) {
+                converged = true;
+                break;
+            }
+            if generation == self.generations {
+                break;
+            }
+
+            // The population is sorted, so the lowest index drawn wins the tournament
+            let pick = |rng: &mut ChaCha8Rng| {
+                (0..self.tournament.max(1))
+                    .map(|_| rng.gen_range(0..population.len()))
+                    .min()
+                    .unwrap_or(0)
+            };
+            let mut next: Vec<DeckVector> = population
+                .iter()
+                .take(self.elite.min(size))
+                .cloned()
+                .collect();
+            while next.len() < size {
+                let (a, b) = (pick(&mut rng), pick(&mut rng));
+                next.push(
+                    population[a]
+                        .crossover(&population[b], &mut rng)
+                        .mutated(&mut rng, self.mutation_rate),
+                );
+            }
+            population = next;
+        }
+
+        let (best, best_mean) = best_of(&history)?;
+        println!("\n=== Optimization Complete ===");
+        Some(OptimizationOutcome {
+            best,
+            best_mean,
+            converged,
+            history,
+        })
+    }
+}
+
+/// Bayesian optimization: models the score as a Gaussian process over the card counts, fitted
+/// to every batch so far with each batch's standard error as its noise, and simulates the
+/// candidate with the highest expected improvement next. Candidates are the untested neighbours
+/// of tested decks plus random walks from the best one. Needs far fewer batches than
+/// hill-climbing when each batch is expensive.
+pub struct Bayesian
+{
+    // Simulation batches in total, the first `initial` of them spread around the start
+    pub evaluations: u32,
+    pub initial: u32,
+    // How many copies apart two decks can be and still score alike
+    pub length_scale: f64,
+    // Random walks from the best deck added to the candidates every round
+    pub random_candidates: usize,
+    pub seed: u64,
+}
+
+impl Bayesian
+{
+    pub fn new(seed: u64) -> Self
+    {
+        Bayesian {
+            evaluations: 20,
+            initial: 5,
+            length_scale: 2.0,
+            random_candidates: 32,
+            seed,
+        }
+    }
+}
+
+impl DeckOptimizer for Bayesian
+{
+    fn name(&self) -> &'static str
+    {
+        "bayesian"
+    }
+
+    fn optimize(
+        &mut self,
+        start: DeckVector,
+        evaluator: &mut dyn ScenarioEvaluator,
+    ) -> Option<OptimizationOutcome>
+    {
+        let unit = evaluator.objective().label();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut history = Vec::new();
+        // Tested decks with their samples; refused (infinite) ones stay out of the model
+        let mut tested: Vec<(DeckVector, Sample)> = Vec::new();
+        let counts = |deck: &DeckVector| {
+            deck.entries
+                .iter()
+                .map(|e| e.count as f64)
+                .collect::<Vec<f64>>()
+        };
+
+        // Settled only if every candidate was tested before the evaluations ran out
+        let mut converged = false;
+        let mut next = Some(start.clone());
+        while let Some(deck) = next.take() {
+            let sample = evaluate_into(evaluator, &deck, &mut history)?;
+            println!("\n=== Bayesian step {} ===", history.len());
+            println!("  {} -> {:.4} {}", deck.describe(), sample.mean, unit);
+            tested.push((deck, sample));
+            if history.len() as u32 >= self.evaluations {
+                break;
+            }
+
+            let best = tested
+                .iter()
+                .min_by(|a, b| a.1.mean.total_cmp(&b.1.mean))
+                .map(|(deck, _)| deck.clone())?;
+            let walk = |from: &DeckVector, rng: &mut ChaCha8Rng| {
+                (0..rng.gen_range(1..=4)).fold(from.clone(), |deck, _| {
+                    deck.random_neighbor(rng, 2).unwrap_or(deck)
+                })
+            };
+            let mut candidates: Vec<DeckVector> = tested
+                .iter()
+                .flat_map(|(deck, _)| deck.neighbors(1))
+                .collect();
+            candidates.extend((0..self.random_candidates).map(|_| walk(&best, &mut rng)));
+            candidates.retain(|c| !tested.iter().any(|(deck, _)| deck == c));
+            candidates.sort();
+            candidates.dedup();
+            if candidates.is_empty() {
+                converged = true;
+                break;
+            }
+
+            // Spread the first batches at random before trusting the model
+            if (tested.len() as u32) < self.initial {
+                next = Some(candidates.swap_remove(rng.gen_range(0..candidates.len())));
+                continue;
+            }
+
+            let finite: Vec<&(DeckVector, Sample)> =
+                tested.iter().filter(|(_, s)| s.mean.is_finite()).collect();
+            let points: Vec<Vec<f64>> = finite.iter().map(|(deck, _)| counts(deck)).collect();
+            let values: Vec<f64> = finite.iter().map(|(_, s)| s.mean).collect();
+            let noise: Vec<f64> = finite
+                .iter()
+                .map(|(_, s)| s.variance / s.count.max(1) as f64)
+                .collect();
+            next = match GaussianProcess::fit(&points, &values, &noise, self.length_scale) {
+                Some(model) => {
+                    let incumbent = values.iter().copied().fold(f64::INFINITY, f64::min);
+                    candidates
+                        .into_iter()
+                        .map(|c| {
+                            let (mean, variance) = model.predict(&counts(&c));
+                            (expected_improvement(incumbent, mean, variance.sqrt()), c)
+                        })
+                        .max_by(|a, b| a.0.total_cmp(&b.0))
+                        .map(|(_, c)| c)
+                }
+                None => candidates.pop(),
+            };
+        }
+
+        let (best, best_mean) = best_of(&history)?;
+        println!("\n=== Optimization Complete ===");
+        Some(OptimizationOutcome {
+            best,
+            best_mean,
+            converged,
+            history,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // Deterministic bowl with its minimum at 24 lands, and Craw Wurms being slightly worse
+    struct Bowl;
+
+    impl ScenarioEvaluator for Bowl
+    {
+        fn evaluate(&mut self, deck: &DeckVector) -> Option<SimulationResult>
+        {
+            let wurms = deck
+                .entries
+                .iter()
+                .find(|e| e.name == "Craw Wurm")
+                .map(|e| e.count)
+                .unwrap_or(0);
+            let mean_turns = 8.0 + (deck.lands() as f64 - 24.0).powi(2) * 0.1 + wurms as f64 * 0.05;
+            Some(SimulationResult {
+                games: 1,
+                mean_turns,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn hill_climb_finds_minimum()
+    {
+        let outcome = HillClimb::default()
+            .optimize(DeckVector::land_split(28, 32), &mut Bowl)
+            .unwrap();
+        assert_eq!(outcome.best, DeckVector::land_split(24, 36));
+        assert!(outcome.converged);
+    }
+
+    // Every deck is as fast as any other, but 25 lands happens to look a little faster
+    struct Flat;
+
+    impl ScenarioEvaluator for Flat
+    {
+        fn evaluate(&mut self, deck: &DeckVector) -> Option<SimulationResult>
+        {
+            let mean_turns = if deck.lands() == 25 { 9.87 } else { 10.0 };
+            Some(SimulationResult {
+                games: 400,
+                mean_turns,
+                stddev_turns: 1.0,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn hill_climb_corrects_for_testing_every_mutation()
+    {
+        // p is about 0.03 against one mutation, but not below 0.05 split between both
+        let outcome = HillClimb::default()
+            .optimize(DeckVector::land_split(24, 36), &mut Flat)
+            .unwrap();
+        assert_eq!(outcome.best, DeckVector::land_split(24, 36));
+        let outcome = HillClimb {
+            significance: 0.1,
+            ..HillClimb::default()
+        }
+        .optimize(DeckVector::land_split(24, 36), &mut Flat)
+        .unwrap();
+        assert_eq!(outcome.best, DeckVector::land_split(25, 35));
+
+        // Stopping right after a move reports the new deck's score, not the old one's
+        let outcome = HillClimb {
+            significance: 0.1,
+            max_iterations: Some(1),
+            ..HillClimb::default()
+        }
+        .optimize(DeckVector::land_split(24, 36), &mut Flat)
+        .unwrap();
+        assert_eq!(outcome.best, DeckVector::land_split(25, 35));
+        assert_eq!(outcome.best_mean, 9.87);
+        assert!(!outcome.converged);
+    }
+
+    #[test]
+    fn percentile_objective_scores_the_tail()
+    {
+        // Usually fast but sometimes bricks: a fine average, a bad worst case
+        let turns: Vec<u32> = std::iter::repeat_n(8, 80)
+            .chain(std::iter::repeat_n(20, 20))
+            .collect();
+        let result = SimulationResult {
+            games: 100,
+            mean_turns: 10.4,
+            turns,
+            ..Default::default()
+        };
+
+        assert_eq!(Objective::TurnsToKill.score(&result), 10.4);
+        assert_eq!(Objective::TurnsPercentile(90).score(&result), 20.0);
+        assert_eq!(Objective::TurnsPercentile(50).score(&result), 8.0);
+        assert_eq!(Objective::TurnsPercentile(90).label(), "p90 turns");
+    }
+
+    #[test]
+    fn pareto_front_keeps_every_tradeoff()
+    {
+        let evaluation = |lands, mean_turns, p90, screwed| {
+            let turns = vec![p90; 10];
+            let result = SimulationResult {
+                games: 10,
+                mean_turns,
+                turns,
+                mana_screw_games: screwed,
+                ..Default::default()
+            };
+            Evaluation {
+                deck: DeckVector::land_split(lands, 60 - lands),
+                score: mean_turns,
+                result,
+            }
+        };
+        let mut evaluations = vec![
+            evaluation(22, 9.0, 14, 3),
+            evaluation(24, 9.5, 12, 1),
+            // Slower and less consistent than 24 lands
+            evaluation(25, 9.8, 13, 2),
+            evaluation(26, 10.0, 12, 0),
+            // 22 lands again, now dominated by 24 lands; only this latest result counts
+            evaluation(22, 9.6, 14, 3),
+            evaluation(20, 0.0, 0, 0),
+        ];
+        evaluations[5].result.games = 0;
+        assert_eq!(pareto_front(&evaluations), vec![1, 3]);
+
+        let weights: ObjectiveWeights = "1, 0.5, 10".parse().unwrap();
+        let result = &evaluations[1].result;
+        assert!((Objective::Weighted(weights).score(result) - (9.5 + 6.0 + 1.0)).abs() < 1e-9);
+        assert!("1,2".parse::<ObjectiveWeights>().is_err());
+    }
+
+    #[test]
+    fn report_pairs_every_evaluation_with_its_result()
+    {
+        let outcome = HillClimb::default()
+            .optimize(DeckVector::land_split(26, 34), &mut Bowl)
+            .unwrap();
+        let results: Vec<SimulationResult> = outcome
+            .history
+            .iter()
+            .map(|(deck, _)| Bowl.evaluate(deck).unwrap())
+            .collect();
+        let report =
+            OptimizationReport::new("hill-climb", Objective::TurnsToKill, 7, &outcome, results);
+
+        assert_eq!(report.evaluations.len(), outcome.history.len());
+        assert_eq!(report.best_description, "24 Forest, 36 Grizzly Bears");
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["objective"], "avg turns");
+        assert_eq!(json["evaluations"][0]["deck"]["entries"][0]["count"], 26);
+        assert_eq!(json["evaluations"][0]["result"]["mean_turns"], 8.4);
+    }
+
+    // Counts the simulations it was asked for
+    struct CountingBowl(u32);
+
+    impl ScenarioEvaluator for CountingBowl
+    {
+        fn evaluate(&mut self, deck: &DeckVector) -> Option<SimulationResult>
+        {
+            self.0 += 1;
+            Bowl.evaluate(deck)
+        }
+    }
+
+    #[test]
+    fn resumed_search_replays_the_checkpoint()
+    {
+        let start = DeckVector::land_split(28, 32);
+        let mut full = CountingBowl(0);
+        let mut recording = Checkpointing::new(
+            &mut full,
+            Checkpoint::new("hill-climb", Objective::TurnsToKill, 7),
+            None,
+        );
+        let expected = HillClimb::default()
+            .optimize(start.clone(), &mut recording)
+            .unwrap();
+        let mut checkpoint = recording.checkpoint;
+        assert_eq!(checkpoint.evaluations.len(), expected.history.len());
+
+        // Interrupted halfway, then saved and loaded again
+        checkpoint.evaluations.truncate(expected.history.len() / 2);
+        let path =
+            std::env::temp_dir().join(format!("tcg-sim-checkpoint-{}.json", std::process::id()));
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut rest = CountingBowl(0);
+        let mut resumed = Checkpointing::new(&mut rest, loaded, None);
+        let outcome = HillClimb::default().optimize(start, &mut resumed).unwrap();
+        assert_eq!(outcome.best, expected.best);
+        assert_eq!(outcome.history, expected.history);
+        assert_eq!(resumed.checkpoint.results().len(), expected.history.len());
+        assert_eq!(
+            full.0 as usize - expected.history.len() / 2,
+            rest.0 as usize
+        );
+    }
+
+    #[test]
+    fn grid_search_finds_minimum()
+    {
+        let mut grid = GridSearch {
+            min_lands: 20,
+            max_lands: 30,
+            max_candidates: 100,
+        };
+        let outcome = grid
+            .optimize(DeckVector::land_split(28, 32), &mut Bowl)
+            .unwrap();
+        assert_eq!(outcome.best, DeckVector::land_split(24, 36));
+        assert_eq!(outcome.history.len(), 11);
+    }
+
+    #[test]
+    fn annealing_finds_minimum()
+    {
+        let mut annealing = SimulatedAnnealing::new(7);
+        annealing.iterations = 80;
+        let outcome = annealing
+            .optimize(DeckVector::land_split(28, 32), &mut Bowl)
+            .unwrap();
+        assert_eq!(outcome.best.lands(), 24);
+        assert!(!outcome.converged, "the walk used up its iterations");
+    }
+
+    #[test]
+    fn genetic_search_evolves_a_card_pool()
+    {
+        let pool = vec![
+            String::from("Grizzly Bears"),
+            String::from("Centaur Courser"),
+            String::from("Craw Wurm"),
+        ];
+        let start = DeckVector::with_pool("Forest", 20, &pool, 12, 4).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let other = start.random_neighbor(&mut rng, 2).unwrap();
+        for _ in 0..50 {
+            let child = start.crossover(&other, &mut rng).mutated(&mut rng, 0.5);
+            assert_eq!(child.size(), start.size());
+            assert!(
+                child
+                    .entries
+                    .iter()
+                    .all(|e| e.count >= e.min && e.count <= e.max)
+            );
+        }
+
+        let mut genetic = Genetic {
+            generations: 30,
+            ..Genetic::new(7)
+        };
+        let outcome = genetic.optimize(start, &mut Bowl).unwrap();
+        assert_eq!(outcome.best.lands(), 24);
+        assert!(outcome.history.len() < 12 * 31);
+    }
+
+    #[test]
+    fn bayesian_search_needs_few_batches()
+    {
+        let mut bayesian = Bayesian {
+            evaluations: 12,
+            ..Bayesian::new(5)
+        };
+        let outcome = bayesian
+            .optimize(DeckVector::land_split(30, 30), &mut Bowl)
+            .unwrap();
+        assert_eq!(outcome.best.lands(), 24);
+        assert_eq!(outcome.history.len(), 12);
+        let tested: std::collections::HashSet<&DeckVector> =
+            outcome.history.iter().map(|(deck, _)| deck).collect();
+        assert_eq!(tested.len(), 12);
+        assert!(!outcome.converged, "the evaluations ran out");
+
+        // With budget for every split of 60 cards it tries them all and settles
+        let mut bayesian = Bayesian {
+            evaluations: 100,
+            ..Bayesian::new(5)
+        };
+        let outcome = bayesian
+            .optimize(DeckVector::land_split(30, 30), &mut Bowl)
+            .unwrap();
+        assert_eq!(outcome.history.len(), 61);
+        assert!(outcome.converged);
+    }
+
+    #[test]
+    fn per_card_search_respects_copy_limits()
+    {
+        let pool = vec![
+            String::from("Grizzly Bears"),
+            String::from("Centaur Courser"),
+            String::from("Craw Wurm"),
+        ];
+        let start = DeckVector::with_pool("Forest", 22, &pool, 10, 4).unwrap();
+        assert_eq!(
+            start.describe(),
+            "22 Forest, 4 Grizzly Bears, 3 Centaur Courser, 3 Craw Wurm"
+        );
+
+        let outcome = HillClimb::default().optimize(start, &mut Bowl).unwrap();
+        assert_eq!(outcome.best.lands(), 24);
+        assert!(outcome.best.entries.iter().all(|e| e.count <= e.max));
+        assert_eq!(
+            outcome
+                .best
+                .entries
+                .iter()
+                .find(|e| e.name == "Craw Wurm")
+                .unwrap()
+                .count,
+            0
+        );
+    }
+
+    #[test]
+    fn decklist_becomes_search_start()
+    {
+        let list = crate::deck::parse_decklist_entries(
+            "24 Forest\n4 Grizzly Bears\n2 Craw Wurm\n2 Craw Wurm\n",
+        )
+        .unwrap();
+        let start = DeckVector::from_decklist(&list, crate::card::card_by_name, 4).unwrap();
+
+        assert_eq!(start.describe(), "24 Forest, 4 Grizzly Bears, 4 Craw Wurm");
+        assert_eq!((start.lands(), start.nonlands()), (24, 8));
+        assert_eq!(start.entries[0].max, 32);
+    }
+
+    #[test]
+    fn modal_double_faced_lands_count_as_lands()
+    {
+        let bears = crate::card::card_by_name("Grizzly Bears").unwrap();
+        let forest = crate::card::card_by_name("Forest").unwrap();
+        let mut front = bears.clone();
+        front.name = String::from("Bears // Forest");
+        let mdfc = crate::faces::with_faces(
+            front,
+            crate::card::FaceLayout::ModalDoubleFaced,
+            vec![bears, forest],
+        );
+        let lookup = |name: &str| {
+            if name == mdfc.name {
+                Some(mdfc.clone())
+            } else {
+                crate::card::card_by_name(name)
+            }
+        };
+
+        let list =
+            crate::deck::parse_decklist_entries("20 Forest\n4 Bears // Forest\n4 Grizzly Bears\n")
+                .unwrap();
+        let start = DeckVector::from_decklist(&list, lookup, 4).unwrap();
+        assert_eq!((start.lands(), start.nonlands()), (24, 4));
+    }
+
+    // Penalizes every Forest, so a manabase search should swap them all for Mountains
+    struct ForestHater;
+
+    impl ScenarioEvaluator for ForestHater
+    {
+        fn evaluate(&mut self, deck: &DeckVector) -> Option<SimulationResult>
+        {
+            let forests = deck
+                .entries
+                .iter()
+                .find(|e| e.name == "Forest")
+                .map(|e| e.count)
+                .unwrap_or(0);
+            Some(SimulationResult {
+                games: 10000,
+                color_screw_games: forests * 1000,
+                mean_turns: 9.0,
+                ..Default::default()
+            })
+        }
+
+        fn objective(&self) -> Objective
+        {
+            Objective::ColorScrew
+        }
+    }
+
+    #[test]
+    fn manabase_search_only_changes_lands()
+    {
+        let list =
+            crate::deck::parse_decklist_entries("10 Forest\n4 Grizzly Bears\n4 Hill Giant\n")
+                .unwrap();
+        let start = DeckVector::from_decklist(&list, crate::card::card_by_name, 4)
+            .unwrap()
+            .with_extra_lands(&[String::from("Mountain")])
+            .with_fixed_nonlands();
+
+        let outcome = HillClimb {
+            step: 2,
+            ..HillClimb::default()
+        }
+        .optimize(start, &mut ForestHater)
+        .unwrap();
+        assert_eq!(
+            outcome.best.describe(),
+            "4 Grizzly Bears, 4 Hill Giant, 10 Mountain"
+        );
+        assert_eq!(outcome.best_mean, 0.0);
+    }
+
+    #[test]
+    fn mutations_keep_deck_size_and_bounds()
+    {
+        let deck = DeckVector::land_split(1, 2);
+        assert!(deck.neighbors(1).iter().all(|d| d.size() == 3));
+        assert_eq!(deck.moved(0, 1, 2), None);
+        assert_eq!(deck.enumerate(10).unwrap().len(), 4);
+        assert_eq!(deck.enumerate(3), None);
+    }
+
+    #[test]
+    fn optimizer_kind_parses()
+    {
+        assert_eq!("grid".parse::<OptimizerKind>(), Ok(OptimizerKind::Grid));
+        assert_eq!(
+            "genetic".parse::<OptimizerKind>(),
+            Ok(OptimizerKind::Genetic)
+        );
+        assert!("tabu".parse::<OptimizerKind>().is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, CardFragmentKind, PlaneswalkerFragment};
+use crate::effect::{self, Effect};
+use crate::game::GameState;
+
+/// A loyalty ability: what it does, and the loyalty it adds (a "+1") or costs (a "−3").
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoyaltyAbility
+{
+    pub loyalty: i32,
+    pub effect: Effect,
+}
+
+pub fn is_planeswalker(card: &Card) -> bool
+{
+    card.fragments.contains_key(&CardFragmentKind::Planeswalker)
+}
+
+pub fn loyalty(card: &Card) -> Option<u32>
+{
+    card.fragment::<PlaneswalkerFragment>().map(|p| p.loyalty)
+}
+
+pub fn abilities(card: &Card) -> &[LoyaltyAbility]
+{
+    card.fragment::<PlaneswalkerFragment>()
+        .map_or(&[], |p| &p.abilities)
+}
+
+/// Reset a planeswalker's loyalty as it enters the battlefield.
+pub fn enter(card: &mut Card)
+{
+    if let Some(walker) = card.fragment_mut::<PlaneswalkerFragment>() {
+        walker.loyalty = walker.starting_loyalty;
+        walker.activated_turn = None;
+    }
+}
+
+/// Remove `amount` loyalty for damage dealt to the planeswalker.
+pub fn deal_damage(card: &mut Card, amount: u32)
+{
+    if let Some(walker) = card.fragment_mut::<PlaneswalkerFragment>() {
+        walker.loyalty = walker.loyalty.saturating_sub(amount);
+    }
+}
+
+/// Whether a planeswalker has no loyalty left, so goes to the graveyard.
+pub fn is_out_of_loyalty(card: &Card) -> bool
+{
+    loyalty(card) == Some(0)
+}
+
+/// Whether one of the planeswalker's abilities may still be activated on `turn`.
+pub fn can_activate(card: &Card, turn: u32) -> bool
+{
+    card.fragment::<PlaneswalkerFragment>()
+        .is_some_and(|p| p.activated_turn != Some(turn))
+}
+
+/// Indices of the planeswalker's abilities its loyalty can pay for whose effect has something
+/// to act on.
+pub fn usable_abilities(game: &GameState, player: usize, card: &Card) -> Vec<usize>
+{
+    let loyalty = loyalty(card).unwrap_or(0) as i32;
+    abilities(card)
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| loyalty + a.loyalty >= 0 && effect::has_target(game, player, &a.effect))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Pay for the ability at `index`, marking the planeswalker as used this `turn`, and return it.
+pub fn activate(card: &mut Card, index: usize, turn: u32) -> Option<LoyaltyAbility>
+{
+    let walker = card.fragment_mut::<PlaneswalkerFragment>()?;
+    let ability = walker.abilities.get(index)?.clone();
+    walker.loyalty = (walker.loyalty as i32 + ability.loyalty).max(0) as u32;
+    walker.activated_turn = Some(turn);
+    Some(ability)
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::deck::Legality;
+use crate::game::{PlayDraw, ProgramState};
+use crate::mulligan;
+use crate::rules::GameRules;
+use crate::sim::ManaThresholds;
+use crate::strategy::Pilot;
+
+/// Simulation settings saved under a name ("mono-red goldfish", "EDH ramp"...) so a session can
+/// switch between them without entering every option again. The deck being tuned and the
+/// opponent deck aren't part of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile
+{
+    pub games_per_scenario: u32,
+    // Name of the mulligan strategy, as `mulligan::parse_strategy` reads it
+    pub mulligan: String,
+    pub play_draw: PlayDraw,
+    pub pilot: Pilot,
+    pub rules: GameRules,
+    pub legality: Legality,
+    pub mana_thresholds: ManaThresholds,
+    pub card_report: bool,
+    pub curve_report: bool,
+    pub epsilon: Option<f64>,
+    pub max_games: u32,
+    pub best_of_three: bool,
+}
+
+impl Profile
+{
+    pub fn from_state(state: &ProgramState) -> Self
+    {
+        Profile {
+            games_per_scenario: state.games_per_scenario,
+            mulligan: state.mulligan.name(),
+            play_draw: state.play_draw,
+            pilot: state.pilot,
+            rules: state.rules.clone(),
+            legality: state.legality,
+            mana_thresholds: state.mana_thresholds,
+            card_report: state.card_report,
+            curve_report: state.curve_report,
+            epsilon: state.epsilon,
+            max_games: state.max_games,
+            best_of_three: state.best_of_three,
+        }
+    }
+
+    /// Switch `state` to these settings; scenarios simulated from now on use them.
+    pub fn apply(&self, state: &mut ProgramState) -> Result<(), String>
+    {
+        state.mulligan = mulligan::parse_strategy(&self.mulligan)?;
+        state.games_per_scenario = self.games_per_scenario;
+        state.play_draw = self.play_draw;
+        state.pilot = self.pilot;
+        state.rules = self.rules.clone();
+        state.legality = self.legality;
+        state.mana_thresholds = self.mana_thresholds;
+        state.card_report = self.card_report;
+        state.curve_report = self.curve_report;
+        state.epsilon = self.epsilon;
+        state.max_games = self.max_games;
+        state.best_of_three = self.best_of_three;
+        Ok(())
+    }
+
+    pub fn save(&self, dir: &Path, name: &str) -> io::Result<()>
+    {
+        let path =
+            profile_path(dir, name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(dir: &Path, name: &str) -> Result<Self, String>
+    {
+        let text = std::fs::read_to_string(profile_path(dir, name)?).map_err(|e| e.to_string())?;
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    }
+}
+
+/// Names of the profiles saved in `dir`, sorted.
+pub fn list(dir: &Path) -> Vec<String>
+{
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+// Profiles are files named after them; names can't reach outside the directory
+fn profile_path(dir: &Path, name: &str) -> Result<PathBuf, String>
+{
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("'{}' isn't a valid profile name", name));
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+/// Handle `profile save NAME`, `profile load NAME` or `profile list` typed at a prompt, using
+/// `state.profile_dir`. `None` if `input` isn't a profile command, otherwise what to tell the user.
+pub fn run_command(input: &str, state: &mut ProgramState) -> Option<String>
+{
+    let rest = input.strip_prefix("profile")?;
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+
+    let (verb, name) = rest
+        .trim()
+        .split_once(' ')
+        .map_or((rest.trim(), ""), |(verb, name)| (verb, name.trim()));
+    let dir = state.profile_dir.clone();
+    Some(match verb {
+        "save" => match Profile::from_state(state).save(&dir, name) {
+            Ok(()) => format!("Saved profile '{}'", name),
+            Err(e) => format!("Can't save profile '{}': {}", name, e),
+        },
+        "load" => match Profile::load(&dir, name).and_then(|profile| profile.apply(state)) {
+            Ok(()) => format!(
+                "Loaded profile '{}'; it applies from the next deck on",
+                name
+            ),
+            Err(e) => format!("Can't load profile '{}': {}", name, e),
+        },
+        "list" => match list(&dir) {
+            names if names.is_empty() => format!("No profiles in {}", dir.display()),
+            names => format!("Profiles: {}", names.join(", ")),
+        },
+        _ => String::from("Usage: profile save NAME, profile load NAME or profile list"),
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn profiles_save_and_restore_settings()
+    {
+        let dir = std::env::temp_dir().join(format!("tcg-sim-profiles-{}", std::process::id()));
+        let mut state = ProgramState {
+            profile_dir: dir.clone(),
+            games_per_scenario: 500,
+            play_draw: PlayDraw::Alternate,
+            epsilon: Some(0.1),
+            ..ProgramState::new()
+        };
+        state.rules = "commander".parse().unwrap();
+        state.mulligan = mulligan::parse_strategy("keep-if-3-to-4-lands").unwrap();
+
+        assert_eq!(
+            run_command("profile save EDH ramp", &mut state).unwrap(),
+            "Saved profile 'EDH ramp'"
+        );
+        let mut other = ProgramState {
+            profile_dir: dir.clone(),
+            ..ProgramState::new()
+        };
+        let listed = run_command("profile list", &mut other).unwrap();
+        let loaded = run_command("profile load EDH ramp", &mut other).unwrap();
+        let missing = run_command("profile load goldfish", &mut other).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(listed, "Profiles: EDH ramp");
+        assert!(loaded.starts_with("Loaded profile 'EDH ramp'"));
+        assert!(missing.starts_with("Can't load profile 'goldfish'"));
+        assert_eq!(Profile::from_state(&other), Profile::from_state(&state));
+        assert!(
+            run_command("profile save ../escape", &mut other)
+                .unwrap()
+                .starts_with("Can't save")
+        );
+        assert_eq!(run_command("profiles", &mut other), None);
+        assert_eq!(run_command("r", &mut other), None);
+    }
+}
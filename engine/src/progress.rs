@@ -0,0 +1,166 @@
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Width of the bar, in characters
+const BAR_WIDTH: usize = 24;
+// Redraw at most this often, however fast games finish
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress line on stderr for a batch of games: how many are done out of how many, games per
+/// second and the time left. Safe to tick from every worker thread; a hidden one only counts.
+pub struct Progress
+{
+    label: String,
+    // What is being counted, e.g. "games" or "matches"
+    unit: &'static str,
+    total: AtomicU64,
+    done: AtomicU64,
+    start: Instant,
+    last_draw: Mutex<Instant>,
+    visible: bool,
+}
+
+impl Progress
+{
+    pub fn new(label: &str, unit: &'static str, total: u64, visible: bool) -> Self
+    {
+        let start = Instant::now();
+        Progress {
+            label: label.to_string(),
+            unit,
+            total: AtomicU64::new(total),
+            done: AtomicU64::new(0),
+            start,
+            last_draw: Mutex::new(start),
+            visible,
+        }
+    }
+
+    /// Counts without ever drawing.
+    pub fn hidden() -> Self
+    {
+        Self::new("", "games", 0, false)
+    }
+
+    /// More work was queued after the progress started, e.g. another adaptive batch.
+    pub fn extend(&self, more: u64)
+    {
+        self.total.fetch_add(more, Ordering::Relaxed);
+        self.draw();
+    }
+
+    /// One more game (or match) is done.
+    pub fn tick(&self)
+    {
+        self.done.fetch_add(1, Ordering::Relaxed);
+        if !self.visible {
+            return;
+        }
+
+        // Skip the redraw when another thread is drawing or one happened just now
+        if let Ok(mut last_draw) = self.last_draw.try_lock()
+            && last_draw.elapsed() >= REDRAW_INTERVAL
+        {
+            *last_draw = Instant::now();
+            self.draw();
+        }
+    }
+
+    pub fn done(&self) -> u64
+    {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Draw the final state and move to the next line, so the summary prints below the bar.
+    pub fn finish(&self)
+    {
+        if self.visible {
+            self.draw();
+            eprintln!();
+        }
+    }
+
+    fn draw(&self)
+    {
+        if self.visible {
+            let line = render(
+                &self.label,
+                self.unit,
+                self.done(),
+                self.total.load(Ordering::Relaxed),
+                self.start.elapsed(),
+            );
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r{}\x1b[K", line);
+            let _ = stderr.flush();
+        }
+    }
+}
+
+// e.g. "24 lands [############------------] 1500/3000 games, 2400 games/s, ETA 1s"
+fn render(label: &str, unit: &str, done: u64, total: u64, elapsed: Duration) -> String
+{
+    let total = total.max(done);
+    let filled = if total == 0 {
+        BAR_WIDTH
+    } else {
+        (done as usize * BAR_WIDTH) / total as usize
+    };
+    let bar = format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+    let seconds = elapsed.as_secs_f64();
+    let rate = if seconds > 0.0 {
+        done as f64 / seconds
+    } else {
+        0.0
+    };
+
+    let eta = if done == total {
+        format!("done in {}", format_duration(seconds))
+    } else if rate > 0.0 {
+        format!("ETA {}", format_duration((total - done) as f64 / rate))
+    } else {
+        String::from("ETA ?")
+    };
+    format!(
+        "{} [{}] {}/{} {}, {:.0} {}/s, {}",
+        label, bar, done, total, unit, rate, unit, eta
+    )
+}
+
+fn format_duration(seconds: f64) -> String
+{
+    let seconds = seconds.ceil() as u64;
+    if seconds >= 60 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn progress_line_shows_rate_and_time_left()
+    {
+        assert_eq!(
+            render("24 lands", "games", 1500, 3000, Duration::from_millis(500)),
+            "24 lands [############------------] 1500/3000 games, 3000 games/s, ETA 1s"
+        );
+        assert_eq!(
+            render("x", "matches", 10, 10, Duration::from_secs(75)),
+            "x [########################] 10/10 matches, 0 matches/s, done in 1m15s"
+        );
+        assert!(render("x", "games", 0, 100, Duration::ZERO).ends_with("ETA ?"));
+
+        let progress = Progress::hidden();
+        progress.extend(2);
+        progress.tick();
+        progress.tick();
+        assert_eq!(progress.done(), 2);
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, CardId, ReplacementFragment};
+use crate::effect::Effect;
+use crate::game::{GameState, Zone};
+
+/// A replacement effect: while its source is on the battlefield, it changes an event before the
+/// event happens, or has something else happen instead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ReplacementEffect
+{
+    // If its controller would draw a card, the effect happens instead, e.g. Thought Reflection's
+    // { "DrawInstead": { "DrawCards": 2 } }. Draws the effect makes aren't replaced again.
+    DrawInstead(Effect),
+    // The card itself enters the battlefield with this many +1/+1 counters
+    EntersWithCounters(u32),
+    // Putting +1/+1 counters on a creature its controller controls puts this many more, e.g.
+    // Hardened Scales' { "AdditionalCounters": 1 }
+    AdditionalCounters(u32),
+    // Damage that would be dealt to its controller is reduced by this much, e.g. Urza's Armor's
+    // { "PreventDamage": 1 }
+    PreventDamage(u32),
+}
+
+/// An event about to happen, for replacement effects to change first.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PendingEvent
+{
+    Draw
+    {
+        player: usize
+    },
+    // +1/+1 counters going on one of `player`'s creatures
+    PutCounters
+    {
+        player: usize,
+        card: CardId,
+        amount: u32,
+    },
+    DamagePlayer
+    {
+        player: usize, amount: u32
+    },
+}
+
+/// What becomes of a pending event: it happens (perhaps changed), or an effect happens instead.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome
+{
+    Happens(PendingEvent),
+    Instead
+    {
+        controller: usize,
+        effect: Effect,
+    },
+}
+
+pub fn replacements(card: &Card) -> &[ReplacementEffect]
+{
+    card.fragment::<ReplacementFragment>()
+        .map_or(&[], |f| &f.replacements)
+}
+
+/// How many +1/+1 counters `card` enters the battlefield with of its own accord.
+pub fn enters_with_counters(card: &Card) -> u32
+{
+    replacements(card)
+        .iter()
+        .map(|r| {
+            if let ReplacementEffect::EntersWithCounters(amount) = r {
+                *amount
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+// The replacement effects of the permanents `player` controls
+fn in_force(game: &GameState, player: usize) -> impl Iterator<Item = &ReplacementEffect>
+{
+    game.players[player]
+        .zones
+        .get(&Zone::Battlefield)
+        .into_iter()
+        .flatten()
+        .flat_map(replacements)
+}
+
+/// Apply the replacement effects in force to `event`. Each one applies once: modifications add
+/// up, and the first effect replacing it outright wins.
+pub fn replace(game: &GameState, event: PendingEvent) -> Outcome
+{
+    match event {
+        PendingEvent::Draw { player } => {
+            let instead = in_force(game, player).find_map(|r| {
+                if let ReplacementEffect::DrawInstead(effect) = r {
+                    Some(effect)
+                } else {
+                    None
+                }
+            });
+            match instead {
+                Some(effect) if !game.replacing_draw => Outcome::Instead {
+                    controller: player,
+                    effect: effect.clone(),
+                },
+                _ => Outcome::Happens(event),
+            }
+        }
+        PendingEvent::PutCounters {
+            player,
+            card,
+            amount,
+        } if amount > 0 => {
+            let more: u32 = in_force(game, player)
+                .map(|r| {
+                    if let ReplacementEffect::AdditionalCounters(more) = r {
+                        *more
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+            Outcome::Happens(PendingEvent::PutCounters {
+                player,
+                card,
+                amount: amount + more,
+            })
+        }
+        PendingEvent::DamagePlayer { player, amount } => {
+            let prevented: u32 = in_force(game, player)
+                .map(|r| {
+                    if let ReplacementEffect::PreventDamage(prevented) = r {
+                        *prevented
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+            Outcome::Happens(PendingEvent::DamagePlayer {
+                player,
+                amount: amount.saturating_sub(prevented),
+            })
+        }
+        _ => Outcome::Happens(event),
+    }
+}
@@ -0,0 +1,753 @@
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::ELoggingVerbosity;
+use crate::card::Card;
+use crate::effect::ModalEffect;
+use crate::game::{GameState, GameStep, StepCommand};
+use crate::mulligan::MulliganStrategy;
+use crate::strategy::{Cast, CurveOut, PlayerStrategy};
+
+/// One answer a strategy gave.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Choice
+{
+    Land(Option<usize>),
+    Cast(Option<Cast>),
+    Respond(Option<Cast>),
+    Attack(Vec<usize>),
+    Block(Vec<(usize, usize)>),
+    Discard(Vec<usize>),
+    KeepOnTop(Vec<usize>),
+    Tutor(Option<usize>),
+    Mode(usize),
+    X(u32),
+    Loyalty(Option<usize>),
+    AttackPlaneswalkers(Vec<(usize, usize)>),
+    ActivateSacrifice(bool),
+    Kick(bool),
+    ExiledLand(Option<usize>),
+    CascadeInto(bool),
+}
+
+/// A decision made during a recorded game: who made it, what they chose, and how far the game's
+/// RNG had advanced once they had, so strategies that roll dice replay exactly.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Decision
+{
+    pub player: usize,
+    pub choice: Choice,
+    pub rng_position: u128,
+}
+
+/// A full game: its state once opening hands were kept, and every decision from then on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRecord
+{
+    pub seed: u64,
+    pub start: GameState,
+    pub decisions: Vec<Decision>,
+}
+
+impl GameRecord
+{
+    pub fn load(path: &Path) -> io::Result<Self>
+    {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()>
+    {
+        let json = serde_json::to_string(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+
+    /// The game at its start, with every player's strategy replaced by their recorded decisions.
+    pub fn replay(&self) -> GameState
+    {
+        let mut game = self.start.clone();
+        game.strategies = (0..game.players.len())
+            .map(|player| {
+                Arc::new(Replayed::new(
+                    self.decisions
+                        .iter()
+                        .filter(|d| d.player == player)
+                        .cloned()
+                        .collect(),
+                )) as Arc<dyn PlayerStrategy>
+            })
+            .collect();
+        game
+    }
+}
+
+/// Records the decisions of a game from now on; see `start`.
+pub struct Recorder
+{
+    start: GameState,
+    decisions: Arc<Mutex<Vec<Decision>>>,
+}
+
+impl Recorder
+{
+    /// Start recording `game`, which should not have taken a step yet: its strategies are wrapped
+    /// so every answer they give is written down.
+    pub fn start(game: &mut GameState) -> Self
+    {
+        let decisions = Arc::new(Mutex::new(Vec::new()));
+        let strategies = (0..game.players.len())
+            .map(|player| {
+                let inner = game
+                    .strategies
+                    .get(player)
+                    .cloned()
+                    .unwrap_or_else(|| Arc::new(CurveOut::default()));
+                Arc::new(Recording {
+                    player,
+                    inner,
+                    decisions: decisions.clone(),
+                }) as Arc<dyn PlayerStrategy>
+            })
+            .collect();
+        let start = game.clone();
+        game.strategies = strategies;
+        Recorder { start, decisions }
+    }
+
+    /// How many decisions have been recorded so far.
+    pub fn recorded(&self) -> usize
+    {
+        self.decisions.lock().unwrap().len()
+    }
+
+    /// Forget every decision after the first `count`, when the game is taken back to the point
+    /// where only those had been made.
+    pub fn rewind(&self, count: usize)
+    {
+        self.decisions.lock().unwrap().truncate(count);
+    }
+
+    pub fn finish(self) -> GameRecord
+    {
+        let decisions = std::mem::take(&mut *self.decisions.lock().unwrap());
+        GameRecord {
+            seed: self.start.seed,
+            start: self.start,
+            decisions,
+        }
+    }
+}
+
+// Passes every decision to `inner` and writes down the answer
+struct Recording
+{
+    player: usize,
+    inner: Arc<dyn PlayerStrategy>,
+    decisions: Arc<Mutex<Vec<Decision>>>,
+}
+
+impl Recording
+{
+    fn record<T: Clone>(
+        &self,
+        rng: &mut ChaCha8Rng,
+        answer: T,
+        choice: impl FnOnce(T) -> Choice,
+    ) -> T
+    {
+        let decision = Decision {
+            player: self.player,
+            choice: choice(answer.clone()),
+            rng_position: rng.get_word_pos(),
+        };
+        self.decisions.lock().unwrap().push(decision);
+        answer
+    }
+}
+
+impl PlayerStrategy for Recording
+{
+    fn name(&self) -> String
+    {
+        self.inner.name()
+    }
+
+    fn mulligan(&self) -> &dyn MulliganStrategy
+    {
+        self.inner.mulligan()
+    }
+
+    fn land_drop(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<usize>
+    {
+        let answer = self.inner.land_drop(game, player, rng);
+        self.record(rng, answer, Choice::Land)
+    }
+
+    fn main_phase_cast(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Option<Cast>
+    {
+        let answer = self.inner.main_phase_cast(game, player, rng);
+        self.record(rng, answer, Choice::Cast)
+    }
+
+    fn respond(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<Cast>
+    {
+        let answer = self.inner.respond(game, player, rng);
+        self.record(rng, answer, Choice::Respond)
+    }
+
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        let answer = self.inner.attackers(game, player, defending_player, rng);
+        self.record(rng, answer, Choice::Attack)
+    }
+
+    fn blockers(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Vec<(usize, usize)>
+    {
+        let answer = self.inner.blockers(game, player, rng);
+        self.record(rng, answer, Choice::Block)
+    }
+
+    fn discards(&self, hand: &[Card], count: usize, rng: &mut ChaCha8Rng) -> Vec<usize>
+    {
+        let answer = self.inner.discards(hand, count, rng);
+        self.record(rng, answer, Choice::Discard)
+    }
+
+    fn keep_on_top(
+        &self,
+        game: &GameState,
+        player: usize,
+        cards: &[Card],
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        let answer = self.inner.keep_on_top(game, player, cards, rng);
+        self.record(rng, answer, Choice::KeepOnTop)
+    }
+
+    fn tutor(
+        &self,
+        game: &GameState,
+        player: usize,
+        candidates: &[usize],
+        rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        let answer = self.inner.tutor(game, player, candidates, rng);
+        self.record(rng, answer, Choice::Tutor)
+    }
+
+    fn choose_mode(
+        &self,
+        game: &GameState,
+        player: usize,
+        modal: &ModalEffect,
+        rng: &mut ChaCha8Rng,
+    ) -> usize
+    {
+        let answer = self.inner.choose_mode(game, player, modal, rng);
+        self.record(rng, answer, Choice::Mode)
+    }
+
+    fn choose_x(
+        &self,
+        game: &GameState,
+        player: usize,
+        card: &Card,
+        max_x: u32,
+        rng: &mut ChaCha8Rng,
+    ) -> u32
+    {
+        let answer = self.inner.choose_x(game, player, card, max_x, rng);
+        self.record(rng, answer, Choice::X)
+    }
+
+    fn loyalty_ability(
+        &self,
+        game: &GameState,
+        player: usize,
+        planeswalker: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        let answer = self.inner.loyalty_ability(game, player, planeswalker, rng);
+        self.record(rng, answer, Choice::Loyalty)
+    }
+
+    fn attack_planeswalkers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        attackers: &[usize],
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<(usize, usize)>
+    {
+        let answer =
+            self.inner
+                .attack_planeswalkers(game, player, defending_player, attackers, rng);
+        self.record(rng, answer, Choice::AttackPlaneswalkers)
+    }
+
+    fn kick(&self, game: &GameState, player: usize, card: &Card, rng: &mut ChaCha8Rng) -> bool
+    {
+        let answer = self.inner.kick(game, player, card, rng);
+        self.record(rng, answer, Choice::Kick)
+    }
+
+    fn activate_sacrifice(
+        &self,
+        game: &GameState,
+        player: usize,
+        token: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> bool
+    {
+        let answer = self.inner.activate_sacrifice(game, player, token, rng);
+        self.record(rng, answer, Choice::ActivateSacrifice)
+    }
+
+    fn exiled_land_drop(
+        &self,
+        game: &GameState,
+        player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        let answer = self.inner.exiled_land_drop(game, player, rng);
+        self.record(rng, answer, Choice::ExiledLand)
+    }
+
+    fn cascade_into(
+        &self,
+        game: &GameState,
+        player: usize,
+        card: &Card,
+        rng: &mut ChaCha8Rng,
+    ) -> bool
+    {
+        let answer = self.inner.cascade_into(game, player, card, rng);
+        self.record(rng, answer, Choice::CascadeInto)
+    }
+}
+
+// Gives one player's recorded answers back in order. If the game asks for a different kind of
+// decision than the next one recorded, it has diverged from the recording (the engine changed
+// since), and the player curves out from there.
+struct Replayed
+{
+    decisions: Mutex<VecDeque<Decision>>,
+    fallback: CurveOut,
+}
+
+impl Replayed
+{
+    fn new(decisions: VecDeque<Decision>) -> Self
+    {
+        Replayed {
+            decisions: Mutex::new(decisions),
+            fallback: CurveOut::default(),
+        }
+    }
+
+    fn next<T>(&self, rng: &mut ChaCha8Rng, pick: impl FnOnce(Choice) -> Option<T>) -> Option<T>
+    {
+        let mut decisions = self.decisions.lock().unwrap();
+        let decision = decisions.pop_front()?;
+        let rng_position = decision.rng_position;
+        let answer = pick(decision.choice);
+        match answer {
+            Some(_) => rng.set_word_pos(rng_position),
+            None => {
+                vlog!(
+                    ELoggingVerbosity::Warning,
+                    "The game no longer follows its recording; playing on without it"
+                );
+                decisions.clear();
+            }
+        }
+        answer
+    }
+}
+
+impl PlayerStrategy for Replayed
+{
+    fn name(&self) -> String
+    {
+        String::from("replay")
+    }
+
+    fn mulligan(&self) -> &dyn MulliganStrategy
+    {
+        self.fallback.mulligan()
+    }
+
+    fn land_drop(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<usize>
+    {
+        self.next(rng, |c| {
+            if let Choice::Land(land) = c {
+                Some(land)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.land_drop(game, player, rng))
+    }
+
+    fn main_phase_cast(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Option<Cast>
+    {
+        self.next(rng, |c| {
+            if let Choice::Cast(cast) = c {
+                Some(cast)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.main_phase_cast(game, player, rng))
+    }
+
+    fn respond(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<Cast>
+    {
+        self.next(rng, |c| {
+            if let Choice::Respond(cast) = c {
+                Some(cast)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.respond(game, player, rng))
+    }
+
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        self.next(rng, |c| {
+            if let Choice::Attack(attackers) = c {
+                Some(attackers)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.attackers(game, player, defending_player, rng))
+    }
+
+    fn blockers(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Vec<(usize, usize)>
+    {
+        self.next(rng, |c| {
+            if let Choice::Block(blocks) = c {
+                Some(blocks)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.blockers(game, player, rng))
+    }
+
+    fn discards(&self, hand: &[Card], count: usize, rng: &mut ChaCha8Rng) -> Vec<usize>
+    {
+        self.next(rng, |c| {
+            if let Choice::Discard(discards) = c {
+                Some(discards)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.discards(hand, count, rng))
+    }
+
+    fn keep_on_top(
+        &self,
+        game: &GameState,
+        player: usize,
+        cards: &[Card],
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        self.next(rng, |c| {
+            if let Choice::KeepOnTop(kept) = c {
+                Some(kept)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.keep_on_top(game, player, cards, rng))
+    }
+
+    fn tutor(
+        &self,
+        game: &GameState,
+        player: usize,
+        candidates: &[usize],
+        rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        self.next(rng, |c| {
+            if let Choice::Tutor(found) = c {
+                Some(found)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.tutor(game, player, candidates, rng))
+    }
+
+    fn choose_mode(
+        &self,
+        game: &GameState,
+        player: usize,
+        modal: &ModalEffect,
+        rng: &mut ChaCha8Rng,
+    ) -> usize
+    {
+        self.next(rng, |c| {
+            if let Choice::Mode(mode) = c {
+                Some(mode)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.choose_mode(game, player, modal, rng))
+    }
+
+    fn choose_x(
+        &self,
+        game: &GameState,
+        player: usize,
+        card: &Card,
+        max_x: u32,
+        rng: &mut ChaCha8Rng,
+    ) -> u32
+    {
+        self.next(rng, |c| if let Choice::X(x) = c { Some(x) } else { None })
+            .unwrap_or_else(|| self.fallback.choose_x(game, player, card, max_x, rng))
+    }
+
+    fn loyalty_ability(
+        &self,
+        game: &GameState,
+        player: usize,
+        planeswalker: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        self.next(rng, |c| {
+            if let Choice::Loyalty(ability) = c {
+                Some(ability)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| {
+            self.fallback
+                .loyalty_ability(game, player, planeswalker, rng)
+        })
+    }
+
+    fn attack_planeswalkers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        attackers: &[usize],
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<(usize, usize)>
+    {
+        self.next(rng, |c| {
+            if let Choice::AttackPlaneswalkers(pairs) = c {
+                Some(pairs)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| {
+            self.fallback
+                .attack_planeswalkers(game, player, defending_player, attackers, rng)
+        })
+    }
+
+    fn kick(&self, game: &GameState, player: usize, card: &Card, rng: &mut ChaCha8Rng) -> bool
+    {
+        self.next(rng, |c| {
+            if let Choice::Kick(kick) = c {
+                Some(kick)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.kick(game, player, card, rng))
+    }
+
+    fn activate_sacrifice(
+        &self,
+        game: &GameState,
+        player: usize,
+        token: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> bool
+    {
+        self.next(rng, |c| {
+            if let Choice::ActivateSacrifice(activate) = c {
+                Some(activate)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.activate_sacrifice(game, player, token, rng))
+    }
+
+    fn exiled_land_drop(
+        &self,
+        game: &GameState,
+        player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        self.next(rng, |c| {
+            if let Choice::ExiledLand(land) = c {
+                Some(land)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.exiled_land_drop(game, player, rng))
+    }
+
+    fn cascade_into(
+        &self,
+        game: &GameState,
+        player: usize,
+        card: &Card,
+        rng: &mut ChaCha8Rng,
+    ) -> bool
+    {
+        self.next(rng, |c| {
+            if let Choice::CascadeInto(cast) = c {
+                Some(cast)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.fallback.cascade_into(game, player, card, rng))
+    }
+}
+
+/// Step through a recorded game with the usual commands (s, t, g, g N, b, q).
+pub fn play_back(record: &GameRecord, mut read_command: impl FnMut() -> StepCommand)
+{
+    let _log = crate::GameLogScope::enter(record.seed);
+    let mut game = record.replay();
+    println!(
+        "Replaying game {} ({} decisions recorded)",
+        record.seed,
+        record.decisions.len()
+    );
+    game.describe(true);
+
+    while !game.is_game_over() {
+        match read_command() {
+            StepCommand::StepPhase | StepCommand::StepAction | StepCommand::StepPriority => {
+                game.step()
+            }
+            StepCommand::StepTurn => {
+                game.step();
+                while game.step != GameStep::StartTurn && !game.is_game_over() {
+                    game.step();
+                }
+            }
+            StepCommand::RunGame | StepCommand::RunDeck | StepCommand::RunAll => {
+                while !game.is_game_over() {
+                    game.step();
+                }
+            }
+            StepCommand::RunToTurn(turn) => crate::sim::run_to_turn(&mut game, turn),
+            StepCommand::Quit => return,
+            StepCommand::Board => {
+                println!("{}", game.board());
+                continue;
+            }
+            StepCommand::OpeningHands(_)
+            | StepCommand::Undo
+            | StepCommand::RunToBreakpoint
+            | StepCommand::Invalid => {
+                println!(
+                    "Use s (step), t (turn), g (to the end), g N (to turn N), b (board) or q (stop replaying)"
+                );
+                continue;
+            }
+        }
+        game.describe(true);
+    }
+    println!("Game over in {} turns.", game.turns);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::Deck;
+    use crate::sim::BatchConfig;
+    use crate::strategy::Pilot;
+
+    fn play_out(game: &mut GameState)
+    {
+        while !game.is_game_over() {
+            game.step();
+        }
+    }
+
+    #[test]
+    fn replayed_game_matches_the_recorded_one()
+    {
+        // Random moves draw on the game's RNG, so the replay has to put it back where it was
+        let config = BatchConfig {
+            pilot: Pilot::Random,
+            ..BatchConfig::with_opponent(Deck::example(), Deck::example())
+        };
+        let mut game = config.new_game(11, true);
+        let recorder = Recorder::start(&mut game);
+        play_out(&mut game);
+        let record = recorder.finish();
+        assert!(!record.decisions.is_empty());
+
+        let path = std::env::temp_dir().join(format!("tcg-sim-replay-{}.json", std::process::id()));
+        record.save(&path).unwrap();
+        let loaded = GameRecord::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.decisions, record.decisions);
+
+        let mut replay = loaded.replay();
+        play_out(&mut replay);
+        assert_eq!(replay.turns, game.turns);
+        assert_eq!(replay.winner(), game.winner());
+        for (replayed, played) in replay.players.iter().zip(game.players.iter()) {
+            assert_eq!(replayed.life, played.life);
+            for (zone, cards) in played.zones.iter() {
+                let names =
+                    |cards: &[Card]| cards.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+                assert_eq!(names(&replayed.zones[zone]), names(cards));
+            }
+        }
+    }
+}
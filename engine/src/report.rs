@@ -0,0 +1,332 @@
+use std::fmt::Write;
+
+use crate::optimizer::{Evaluation, OptimizationReport};
+
+// Size of every chart, and the space left around the plot for labels
+const CHART_WIDTH: f64 = 640.0;
+const CHART_HEIGHT: f64 = 240.0;
+const MARGIN: f64 = 40.0;
+
+// Fill colors of the data series, in order
+const SERIES_COLORS: [&str; 3] = ["#4c78a8", "#f58518", "#54a24b"];
+
+const STYLE: &str =
+    "body { font-family: sans-serif; margin: 2em auto; max-width: 720px; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+td, th { border-bottom: 1px solid #ddd; padding: 4px 8px; text-align: left; }
+tr.best { font-weight: bold; }
+svg text { font-size: 11px; fill: #444; }";
+
+/// Render an optimizer run as a standalone HTML page: the score of every evaluation in order,
+/// then the kill-turn histogram and curve of the suggested configuration, then a table of every
+/// configuration tried. Charts are inline SVG, so the file needs nothing else to display.
+pub fn render_html(report: &OptimizationReport) -> String
+{
+    let mut html = String::new();
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Optimizer report</title>\n<style>\n{}\n</style>\n</head>\n<body>",
+        STYLE
+    );
+    let _ = writeln!(html, "<h1>Optimizer report</h1>");
+    let _ = writeln!(
+        html,
+        "<p>Suggestion: <strong>{}</strong> ({:.4} {}{}), found by the {} optimizer with seed {} after {} evaluations.</p>",
+        escape(&report.best_description),
+        report.best_score,
+        escape(&report.objective),
+        if report.converged {
+            ""
+        } else {
+            ", may not be optimal yet"
+        },
+        escape(&report.optimizer),
+        report.seed,
+        report.evaluations.len()
+    );
+
+    let _ = writeln!(html, "<h2>Trajectory</h2>");
+    let scores: Vec<f64> = report.evaluations.iter().map(|e| e.score).collect();
+    html.push_str(&line_chart(&scores, &report.objective));
+
+    if let Some(best) = report
+        .evaluations
+        .iter()
+        .rev()
+        .find(|e| e.deck == report.best)
+    {
+        html.push_str(&best_charts(best));
+    }
+
+    let _ = writeln!(
+        html,
+        "<h2>Every configuration</h2>\n<table>\n<tr><th>#</th><th>Deck</th><th>{}</th><th>Games</th></tr>",
+        escape(&report.objective)
+    );
+    for (index, evaluation) in report.evaluations.iter().enumerate() {
+        let class = if evaluation.deck == report.best {
+            " class=\"best\""
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            html,
+            "<tr{}><td>{}</td><td>{}</td><td>{:.4}</td><td>{}</td></tr>",
+            class,
+            index + 1,
+            escape(&evaluation.deck.describe()),
+            evaluation.score,
+            evaluation.result.games
+        );
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+// Kill-turn histogram and curve chart of one configuration
+fn best_charts(best: &Evaluation) -> String
+{
+    let mut html = String::new();
+    let result = &best.result;
+    if !result.kill_turns.is_empty() {
+        let labels: Vec<String> = result
+            .kill_turns
+            .keys()
+            .map(|turn| format!("T{}", turn))
+            .collect();
+        let shares: Vec<f64> = result
+            .kill_turns
+            .values()
+            .map(|games| *games as f64 / result.games.max(1) as f64)
+            .collect();
+        let _ = writeln!(html, "<h2>Kill turns</h2>");
+        html.push_str(&bar_chart(&labels, &[("games won", shares)]));
+    }
+    if !result.curve.is_empty() {
+        let labels: Vec<String> = (1..=result.curve.len())
+            .map(|turn| format!("T{}", turn))
+            .collect();
+        let lands = result
+            .curve
+            .iter()
+            .map(|step| step.land_drop_rate())
+            .collect();
+        let spells = result
+            .curve
+            .iter()
+            .map(|step| step.on_curve_rate())
+            .collect();
+        let _ = writeln!(html, "<h2>Curve</h2>");
+        html.push_str(&bar_chart(
+            &labels,
+            &[("land drop", lands), ("spell on curve", spells)],
+        ));
+    }
+    html
+}
+
+// Grouped bars of shares between 0 and 1, one group per label and one bar per series
+fn bar_chart(labels: &[String], series: &[(&str, Vec<f64>)]) -> String
+{
+    let mut svg = svg_open();
+    let plot_height = CHART_HEIGHT - 2.0 * MARGIN;
+    let group_width = (CHART_WIDTH - 2.0 * MARGIN) / labels.len().max(1) as f64;
+    let bar_width = group_width * 0.8 / series.len().max(1) as f64;
+
+    for (i, label) in labels.iter().enumerate() {
+        let group_x = MARGIN + i as f64 * group_width + group_width * 0.1;
+        for (s, (name, values)) in series.iter().enumerate() {
+            let value = values.get(i).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+            let height = value * plot_height;
+            let x = group_x + s as f64 * bar_width;
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{} {}: {:.1}%</title></rect>",
+                x,
+                CHART_HEIGHT - MARGIN - height,
+                bar_width,
+                height,
+                SERIES_COLORS[s % SERIES_COLORS.len()],
+                escape(label),
+                escape(name),
+                value * 100.0
+            );
+        }
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            group_x + group_width * 0.4,
+            CHART_HEIGHT - MARGIN + 16.0,
+            escape(label)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\">100%</text>",
+        4.0,
+        MARGIN + 4.0
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\">0%</text>",
+        4.0,
+        CHART_HEIGHT - MARGIN
+    );
+    for (s, (name, _)) in series.iter().enumerate() {
+        let x = MARGIN + s as f64 * 140.0;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"8\" width=\"10\" height=\"10\" fill=\"{}\"/><text x=\"{:.1}\" y=\"17\">{}</text>",
+            x,
+            SERIES_COLORS[s % SERIES_COLORS.len()],
+            x + 14.0,
+            escape(name)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Scores in evaluation order, scaled between their smallest and largest finite value
+fn line_chart(values: &[f64], unit: &str) -> String
+{
+    let mut svg = svg_open();
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let low = finite.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if finite.is_empty() {
+        svg.push_str("</svg>\n");
+        return svg;
+    }
+
+    let span = (high - low).max(1e-9);
+    let step = (CHART_WIDTH - 2.0 * MARGIN) / (values.len().max(2) - 1) as f64;
+    let point = |i: usize, value: f64| {
+        (
+            MARGIN + i as f64 * step,
+            CHART_HEIGHT - MARGIN - (value - low) / span * (CHART_HEIGHT - 2.0 * MARGIN),
+        )
+    };
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v.is_finite())
+        .map(|(i, v)| {
+            let (x, y) = point(i, *v);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect();
+
+    let _ = writeln!(
+        svg,
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>",
+        points.join(" "),
+        SERIES_COLORS[0]
+    );
+    for (i, value) in values.iter().enumerate().filter(|(_, v)| v.is_finite()) {
+        let (x, y) = point(i, *value);
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"><title>#{}: {:.4} {}</title></circle>",
+            x,
+            y,
+            SERIES_COLORS[0],
+            i + 1,
+            value,
+            escape(unit)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"4\" y=\"{:.1}\">{:.2}</text>",
+        MARGIN + 4.0,
+        high
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"4\" y=\"{:.1}\">{:.2}</text>",
+        CHART_HEIGHT - MARGIN,
+        low
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">evaluation</text>",
+        CHART_WIDTH / 2.0,
+        CHART_HEIGHT - 8.0
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn svg_open() -> String
+{
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        CHART_WIDTH, CHART_HEIGHT, CHART_WIDTH, CHART_HEIGHT
+    )
+}
+
+fn escape(text: &str) -> String
+{
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::optimizer::DeckVector;
+    use crate::result::{CurveStep, SimulationResult};
+
+    #[test]
+    fn html_report_has_every_chart_and_configuration()
+    {
+        let evaluation = |lands, score| {
+            let mut result = SimulationResult {
+                games: 10,
+                ..SimulationResult::default()
+            };
+            result.kill_turns.insert(5, 4);
+            result.kill_turns.insert(6, 6);
+            result.curve = vec![
+                CurveStep {
+                    games: 10,
+                    land_drops: 9,
+                    on_curve: 0
+                };
+                3
+            ];
+            Evaluation {
+                deck: DeckVector::land_split(lands, 60 - lands),
+                score,
+                result,
+            }
+        };
+        let report = OptimizationReport {
+            optimizer: String::from("hill-climb"),
+            objective: String::from("avg turns"),
+            seed: 1,
+            evaluations: vec![
+                evaluation(24, 11.5),
+                evaluation(23, 11.0),
+                evaluation(22, f64::INFINITY),
+            ],
+            best: DeckVector::land_split(23, 37),
+            best_description: String::from("23 Forest, 37 Grizzly Bears"),
+            best_score: 11.0,
+            converged: true,
+            pareto_front: vec![1],
+        };
+
+        let html = render_html(&report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html.matches("<svg").count(), 3);
+        assert_eq!(html.matches("<circle").count(), 2);
+        assert!(html.contains("<tr class=\"best\"><td>2</td><td>23 Forest, 37 Grizzly Bears</td>"));
+        assert!(html.contains("T6 games won: 60.0%"));
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
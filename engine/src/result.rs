@@ -0,0 +1,740 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::sim::{GameOutcome, MatchOutcome};
+use crate::wincondition::WinCondition;
+
+// Standard normal quantile for a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// How often the first player hit each step of their curve on one turn, out of the games that
+/// lasted until their turn of that number.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurveStep
+{
+    pub games: u32,
+    pub land_drops: u32,
+    // Games where they cast a spell with mana value equal to the turn number
+    pub on_curve: u32,
+}
+
+impl CurveStep
+{
+    pub fn land_drop_rate(&self) -> f64
+    {
+        self.land_drops as f64 / self.games.max(1) as f64
+    }
+
+    pub fn on_curve_rate(&self) -> f64
+    {
+        self.on_curve as f64 / self.games.max(1) as f64
+    }
+}
+
+fn curve_steps(outcomes: &[GameOutcome]) -> Vec<CurveStep>
+{
+    let mut steps: Vec<CurveStep> = Vec::new();
+    for outcome in outcomes {
+        for (turn, &(land, spell)) in outcome.curve.iter().enumerate() {
+            if steps.len() <= turn {
+                steps.push(CurveStep::default());
+            }
+            steps[turn].games += 1;
+            steps[turn].land_drops += land as u32;
+            steps[turn].on_curve += spell as u32;
+        }
+    }
+    steps
+}
+
+/// How games went when a card was drawn early (see `sim::EARLY_CARD_TURNS`) versus when it wasn't.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CardImpact
+{
+    pub name: String,
+    pub games_drawn: u32,
+    pub mean_turns_drawn: f64,
+    pub mean_turns_not_drawn: f64,
+    pub win_rate_drawn: f64,
+    pub win_rate_not_drawn: f64,
+}
+
+impl CardImpact
+{
+    /// Extra turns the games took when the card was drawn early; positive means it slowed the deck down.
+    pub fn turn_impact(&self) -> f64
+    {
+        self.mean_turns_drawn - self.mean_turns_not_drawn
+    }
+}
+
+// Split the games by whether each card was drawn early, for every card drawn early in some games
+// but not all of them; sorted with the cards whose games took longest relative to the rest first
+fn card_impact(outcomes: &[GameOutcome]) -> Vec<CardImpact>
+{
+    let names: BTreeSet<&String> = outcomes.iter().flat_map(|o| o.early_cards.iter()).collect();
+    let mut impacts: Vec<CardImpact> = names
+        .into_iter()
+        .filter_map(|name| {
+            let (drawn, not_drawn): (Vec<&GameOutcome>, Vec<&GameOutcome>) =
+                outcomes.iter().partition(|o| o.early_cards.contains(name));
+            if not_drawn.is_empty() {
+                return None;
+            }
+            let mean_turns = |games: &[&GameOutcome]| {
+                games.iter().map(|o| o.turns as f64).sum::<f64>() / games.len().max(1) as f64
+            };
+            let win_rate = |games: &[&GameOutcome]| {
+                games.iter().filter(|o| o.won).count() as f64 / games.len().max(1) as f64
+            };
+            Some(CardImpact {
+                name: name.clone(),
+                games_drawn: drawn.len() as u32,
+                mean_turns_drawn: mean_turns(&drawn),
+                mean_turns_not_drawn: mean_turns(&not_drawn),
+                win_rate_drawn: win_rate(&drawn),
+                win_rate_not_drawn: win_rate(&not_drawn),
+            })
+        })
+        .collect();
+    impacts.sort_by(|a, b| b.turn_impact().total_cmp(&a.turn_impact()));
+    impacts
+}
+
+/// Aggregate statistics for one simulated scenario (one deck configuration).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulationResult
+{
+    pub games: u32,
+    pub mean_turns: f64,
+    pub median_turns: f64,
+    // Every game's turn count, sorted, for percentiles
+    #[serde(skip)]
+    pub turns: Vec<u32>,
+    // How games went with and without each card drawn early, most harmful first
+    pub card_impact: Vec<CardImpact>,
+    // Land drops and on-curve spells on each of the first player's first turns
+    pub curve: Vec<CurveStep>,
+    pub stddev_turns: f64,
+    pub min_turns: u32,
+    pub max_turns: u32,
+    pub mulligans: u32,
+    // Cards the first player drew after their opening hands, over all games
+    #[serde(default)]
+    pub cards_drawn: u32,
+    // Spells the first player cast on their own turns, how many of those turns there were, and
+    // the most cast in any one of them, over all games
+    #[serde(default)]
+    pub spells_cast: u32,
+    #[serde(default)]
+    pub spell_turns: u32,
+    #[serde(default)]
+    pub most_spells_in_turn: u32,
+    pub mana_screw_games: u32,
+    pub mana_flood_games: u32,
+    pub color_screw_games: u32,
+    // Games won by the first player (the deck being tuned)
+    pub wins: u32,
+    // The same turn counts split by whether the first player went first or second
+    pub games_on_play: u32,
+    pub mean_turns_on_play: f64,
+    pub games_on_draw: u32,
+    pub mean_turns_on_draw: f64,
+    // Games per condition the losing player lost to
+    pub ended_by: BTreeMap<WinCondition, u32>,
+    // Games the first player won, by which of their own turns they won on
+    pub kill_turns: BTreeMap<u32, u32>,
+    // Name of the mulligan strategy the games were played with
+    pub mulligan_strategy: String,
+    // Name of the strategy that piloted the deck
+    pub pilot: String,
+    // Why the deck isn't legal in the simulated format; no games are played when refused
+    pub legality_errors: Vec<String>,
+    // Best-of-three matches the games were played in (none for single games) and how many the
+    // first player won
+    pub matches: u32,
+    pub match_wins: u32,
+}
+
+impl SimulationResult
+{
+    pub fn from_outcomes(outcomes: &[GameOutcome]) -> Self
+    {
+        if outcomes.is_empty() {
+            return SimulationResult::default();
+        }
+
+        let games = outcomes.len() as u32;
+        let mut turns: Vec<u32> = outcomes.iter().map(|o| o.turns).collect();
+        turns.sort_unstable();
+
+        let mean = turns.iter().map(|&t| t as f64).sum::<f64>() / games as f64;
+        let variance = if games > 1 {
+            turns
+                .iter()
+                .map(|&t| (t as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (games - 1) as f64
+        } else {
+            0.0
+        };
+
+        let (on_play, on_draw): (Vec<&GameOutcome>, Vec<&GameOutcome>) =
+            outcomes.iter().partition(|o| o.on_play);
+        let mean_of = |games: &[&GameOutcome]| {
+            games.iter().map(|o| o.turns as f64).sum::<f64>() / games.len().max(1) as f64
+        };
+
+        let mid = turns.len() / 2;
+        let median = if turns.len().is_multiple_of(2) {
+            (turns[mid - 1] + turns[mid]) as f64 / 2.0
+        } else {
+            turns[mid] as f64
+        };
+
+        SimulationResult {
+            games,
+            mean_turns: mean,
+            median_turns: median,
+            turns: turns.clone(),
+            // Empty unless the games kept their early cards for a card report
+            card_impact: card_impact(outcomes),
+            curve: curve_steps(outcomes),
+            stddev_turns: variance.sqrt(),
+            min_turns: turns[0],
+            max_turns: turns[turns.len() - 1],
+            mulligans: outcomes.iter().map(|o| o.mulligans).sum(),
+            cards_drawn: outcomes.iter().map(|o| o.cards_drawn).sum(),
+            spells_cast: outcomes.iter().flat_map(|o| o.spells_per_turn.iter()).sum(),
+            spell_turns: outcomes
+                .iter()
+                .map(|o| o.spells_per_turn.len() as u32)
+                .sum(),
+            most_spells_in_turn: outcomes
+                .iter()
+                .flat_map(|o| o.spells_per_turn.iter())
+                .copied()
+                .max()
+                .unwrap_or(0),
+            mana_screw_games: outcomes.iter().filter(|o| o.mana_screwed).count() as u32,
+            mana_flood_games: outcomes.iter().filter(|o| o.mana_flooded).count() as u32,
+            color_screw_games: outcomes.iter().filter(|o| o.color_screwed).count() as u32,
+            wins: outcomes.iter().filter(|o| o.won).count() as u32,
+            games_on_play: on_play.len() as u32,
+            mean_turns_on_play: mean_of(&on_play),
+            games_on_draw: on_draw.len() as u32,
+            mean_turns_on_draw: mean_of(&on_draw),
+            ended_by: outcomes.iter().filter_map(|o| o.ended_by).fold(
+                BTreeMap::new(),
+                |mut counts, condition| {
+                    *counts.entry(condition).or_insert(0) += 1;
+                    counts
+                },
+            ),
+            kill_turns: outcomes.iter().filter_map(|o| o.kill_turn).fold(
+                BTreeMap::new(),
+                |mut counts, turn| {
+                    *counts.entry(turn).or_insert(0) += 1;
+                    counts
+                },
+            ),
+            mulligan_strategy: String::new(),
+            pilot: String::new(),
+            legality_errors: Vec::new(),
+            matches: 0,
+            match_wins: 0,
+        }
+    }
+
+    /// Statistics over every game of the matches, plus the match record.
+    pub fn from_matches(matches: &[MatchOutcome]) -> Self
+    {
+        let games: Vec<GameOutcome> = matches
+            .iter()
+            .flat_map(|m| m.games.iter().cloned())
+            .collect();
+        SimulationResult {
+            matches: matches.len() as u32,
+            match_wins: matches.iter().filter(|m| m.won).count() as u32,
+            ..SimulationResult::from_outcomes(&games)
+        }
+    }
+
+    // Convenience for callers that only compare averages (e.g. the hill-climber)
+    pub fn mean(&self) -> f64
+    {
+        self.mean_turns
+    }
+
+    /// Half-width of the 95% confidence interval on the mean turn count.
+    pub fn margin_of_error(&self) -> f64
+    {
+        Z_95 * self.stddev_turns / (self.games.max(1) as f64).sqrt()
+    }
+
+    /// Turn count that `percentile` percent of games took at most, interpolating between games.
+    pub fn percentile(&self, percentile: u32) -> f64
+    {
+        self.turns_at_rank(percentile as f64 / 100.0 * self.turns.len().saturating_sub(1) as f64)
+    }
+
+    /// Standard error of `percentile(percentile)`, from the spread of the games ranked within a
+    /// 95% binomial interval around it.
+    pub fn percentile_standard_error(&self, percentile: u32) -> f64
+    {
+        let n = self.turns.len() as f64;
+        let q = percentile as f64 / 100.0;
+        let rank = q * (n - 1.0).max(0.0);
+        let half_width = Z_95 * (n * q * (1.0 - q)).sqrt();
+        (self.turns_at_rank(rank + half_width) - self.turns_at_rank(rank - half_width))
+            / (2.0 * Z_95)
+    }
+
+    // Linear interpolation between the sorted turn counts around a fractional rank
+    fn turns_at_rank(&self, rank: f64) -> f64
+    {
+        let Some(&last) = self.turns.last() else {
+            return 0.0;
+        };
+        let rank = rank.clamp(0.0, (self.turns.len() - 1) as f64);
+        let (below, fraction) = (rank.floor() as usize, rank.fract());
+        let above = self.turns.get(below + 1).copied().unwrap_or(last);
+        self.turns[below] as f64 * (1.0 - fraction) + above as f64 * fraction
+    }
+
+    /// Share of games won on each turn, like "T4 12.0%, T5 38.0%, no kill 3.0%".
+    pub fn kill_turn_histogram(&self) -> String
+    {
+        let share = |games: u32| games as f64 * 100.0 / self.games.max(1) as f64;
+        let mut bins: Vec<String> = self
+            .kill_turns
+            .iter()
+            .map(|(turn, games)| format!("T{} {:.1}%", turn, share(*games)))
+            .collect();
+        let unkilled = self.games - self.kill_turns.values().sum::<u32>();
+        if unkilled > 0 {
+            bins.push(format!("no kill {:.1}%", share(unkilled)));
+        }
+        bins.join(", ")
+    }
+
+    /// `turn,games,share` CSV rows: one per kill turn, then a `none` row for the games without a
+    /// kill.
+    pub fn kill_turn_rows(&self) -> Vec<String>
+    {
+        let killed: u32 = self.kill_turns.values().sum();
+        self.kill_turns
+            .iter()
+            .map(|(turn, games)| (turn.to_string(), *games))
+            .chain(std::iter::once((String::from("none"), self.games - killed)))
+            .map(|(turn, games)| {
+                format!(
+                    "{},{},{:.4}",
+                    turn,
+                    games,
+                    games as f64 / self.games.max(1) as f64
+                )
+            })
+            .collect()
+    }
+
+    pub fn mean_cards_drawn(&self) -> f64
+    {
+        self.cards_drawn as f64 / self.games.max(1) as f64
+    }
+
+    /// Spells the first player cast per turn of theirs, a measure of how much a combo deck does.
+    pub fn mean_spells_per_turn(&self) -> f64
+    {
+        self.spells_cast as f64 / self.spell_turns.max(1) as f64
+    }
+
+    pub fn screw_rate(&self) -> f64
+    {
+        self.mana_screw_games as f64 / self.games.max(1) as f64
+    }
+
+    pub fn flood_rate(&self) -> f64
+    {
+        self.mana_flood_games as f64 / self.games.max(1) as f64
+    }
+
+    pub fn color_screw_rate(&self) -> f64
+    {
+        self.color_screw_games as f64 / self.games.max(1) as f64
+    }
+
+    pub fn win_rate(&self) -> f64
+    {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    pub fn match_win_rate(&self) -> f64
+    {
+        self.match_wins as f64 / self.matches.max(1) as f64
+    }
+}
+
+/// Head-to-head results of a gauntlet, from `sim::run_gauntlet`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GauntletResult
+{
+    pub decks: Vec<String>,
+    // Best-of-three matches played in each matchup
+    pub matches: u32,
+    // match_wins[i][j]: matches deck i won against deck j
+    pub match_wins: Vec<Vec<u32>>,
+    // The same for single games, and how many games each matchup took
+    pub game_wins: Vec<Vec<u32>>,
+    pub games: Vec<Vec<u32>>,
+}
+
+impl GauntletResult
+{
+    /// Share of the matches against `opponent` that `deck` won.
+    pub fn win_rate(&self, deck: usize, opponent: usize) -> f64
+    {
+        self.match_wins[deck][opponent] as f64 / self.matches.max(1) as f64
+    }
+
+    pub fn game_win_rate(&self, deck: usize, opponent: usize) -> f64
+    {
+        self.game_wins[deck][opponent] as f64 / self.games[deck][opponent].max(1) as f64
+    }
+
+    /// Average win rate of `deck` over all of its matchups.
+    pub fn overall_win_rate(&self, deck: usize) -> f64
+    {
+        let opponents = self.decks.len().saturating_sub(1).max(1);
+        (0..self.decks.len())
+            .filter(|o| *o != deck)
+            .map(|o| self.win_rate(deck, o))
+            .sum::<f64>()
+            / opponents as f64
+    }
+}
+
+impl GauntletResult
+{
+    // One table of win rates: rows are the deck, columns the opponent it played against
+    fn write_table(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        title: &str,
+        rate: impl Fn(usize, usize) -> f64,
+    ) -> fmt::Result
+    {
+        let width = self.decks.iter().map(String::len).max().unwrap_or(0).max(7);
+        write!(f, "{:width$}", title, width = width)?;
+        for name in self.decks.iter() {
+            write!(f, "  {:>width$}", name, width = width)?;
+        }
+        writeln!(f, "  {:>width$}", "overall", width = width)?;
+
+        for (i, name) in self.decks.iter().enumerate() {
+            write!(f, "{:width$}", name, width = width)?;
+            for j in 0..self.decks.len() {
+                if i == j {
+                    write!(f, "  {:>width$}", "-", width = width)?;
+                } else {
+                    write!(
+                        f,
+                        "  {:>width$}",
+                        format!("{:.1}%", rate(i, j) * 100.0),
+                        width = width
+                    )?;
+                }
+            }
+            let others = (0..self.decks.len()).filter(|j| *j != i);
+            let overall =
+                others.clone().map(|j| rate(i, j)).sum::<f64>() / others.count().max(1) as f64;
+            writeln!(
+                f,
+                "  {:>width$}",
+                format!("{:.1}%", overall * 100.0),
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GauntletResult
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        self.write_table(f, "matches", |i, j| self.win_rate(i, j))?;
+        writeln!(f)?;
+        self.write_table(f, "games", |i, j| self.game_win_rate(i, j))?;
+        write!(
+            f,
+            "({} best-of-three matches per matchup, sideboarding after game 1)",
+            self.matches
+        )
+    }
+}
+
+// Heatmap shades by the smallest share of games they stand for; turns nobody won on stay blank
+const HEATMAP_SHADES: [(f64, char); 4] = [(0.5, '█'), (0.3, '▓'), (0.15, '▒'), (0.0, '░')];
+
+/// The same deck simulated at every land count in a range, for the whole curve rather than the
+/// single point an optimizer settles on.
+#[derive(Clone, Debug, Default)]
+pub struct SweepResult
+{
+    // Land count and its result, in increasing land count
+    pub rows: Vec<(u32, SimulationResult)>,
+}
+
+impl SweepResult
+{
+    /// Land count with the lowest average turns.
+    pub fn best(&self) -> Option<u32>
+    {
+        self.rows
+            .iter()
+            .filter(|(_, r)| r.games > 0)
+            .min_by(|a, b| a.1.mean().total_cmp(&b.1.mean()))
+            .map(|(lands, _)| *lands)
+    }
+}
+
+impl fmt::Display for SweepResult
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let best = self.best();
+        writeln!(f, "lands  games  avg turns   p90  mana screw  mana flood")?;
+        for (lands, result) in self.rows.iter() {
+            writeln!(
+                f,
+                "{:>5}{} {:>5}  {:>9.4}  {:>4.1}  {:>9.1}%  {:>9.1}%",
+                lands,
+                if best == Some(*lands) { "*" } else { " " },
+                result.games,
+                result.mean(),
+                result.percentile(90),
+                result.screw_rate() * 100.0,
+                result.flood_rate() * 100.0
+            )?;
+        }
+
+        // Shade of the share of games won on each turn
+        let turns: BTreeSet<u32> = self
+            .rows
+            .iter()
+            .flat_map(|(_, r)| r.kill_turns.keys().copied())
+            .collect();
+        let (Some(first), Some(last)) = (turns.first(), turns.last()) else {
+            return Ok(());
+        };
+        writeln!(
+            f,
+            "\nkill turns (share of games won on each turn: ░ under 15%, ▒ 15%+, ▓ 30%+, █ 50%+)"
+        )?;
+        write!(f, "lands ")?;
+        for turn in *first..=*last {
+            write!(f, " {:>3}", format!("T{}", turn))?;
+        }
+        writeln!(f)?;
+        for (lands, result) in self.rows.iter() {
+            write!(f, "{:>5} ", lands)?;
+            for turn in *first..=*last {
+                let shade = result.kill_turns.get(&turn).map_or(' ', |games| {
+                    let share = *games as f64 / result.games.max(1) as f64;
+                    HEATMAP_SHADES
+                        .iter()
+                        .find(|(threshold, _)| share >= *threshold)
+                        .map_or('░', |(_, shade)| *shade)
+                });
+                write!(f, "   {}", shade)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn outcome(turns: u32, mana_screwed: bool) -> GameOutcome
+    {
+        GameOutcome {
+            seed: 0,
+            turns,
+            on_play: turns < 12,
+            mulligans: 0,
+            cards_drawn: 0,
+            spells_per_turn: Vec::new(),
+            mana_screwed,
+            mana_flooded: false,
+            color_screwed: false,
+            won: mana_screwed,
+            lost: !mana_screwed,
+            ended_by: Some(WinCondition::LifeTotal),
+            kill_turn: mana_screwed.then_some(turns / 2),
+            early_cards: Vec::new(),
+            curve: Vec::new(),
+            lands_seen: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn summary_statistics_from_outcomes()
+    {
+        let outcomes = vec![
+            outcome(8, false),
+            outcome(10, true),
+            outcome(12, false),
+            outcome(14, false),
+        ];
+        let result = SimulationResult::from_outcomes(&outcomes);
+
+        assert_eq!(result.games, 4);
+        assert_eq!(result.mean(), 11.0);
+        assert_eq!(result.median_turns, 11.0);
+        assert_eq!(result.percentile(50), result.median_turns);
+        assert_eq!(
+            (
+                result.percentile(0),
+                result.percentile(90),
+                result.percentile(100)
+            ),
+            (8.0, 13.4, 14.0)
+        );
+        assert!((result.stddev_turns - (20.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert_eq!((result.min_turns, result.max_turns), (8, 14));
+        assert_eq!(result.mana_screw_games, 1);
+        assert_eq!(result.screw_rate(), 0.25);
+        assert!((result.margin_of_error() - 1.96 * (20.0f64 / 3.0).sqrt() / 2.0).abs() < 1e-9);
+        assert_eq!(result.win_rate(), 0.25);
+        assert_eq!(result.ended_by.get(&WinCondition::LifeTotal), Some(&4));
+        assert_eq!((result.games_on_play, result.mean_turns_on_play), (2, 9.0));
+        assert_eq!((result.games_on_draw, result.mean_turns_on_draw), (2, 13.0));
+        assert_eq!(result.kill_turn_histogram(), "T5 25.0%, no kill 75.0%");
+    }
+
+    #[test]
+    fn kill_turns_are_bucketed_by_turn()
+    {
+        let outcomes: Vec<GameOutcome> = [8, 10, 10, 11, 14]
+            .into_iter()
+            .map(|turns| outcome(turns, turns < 12))
+            .collect();
+        let result = SimulationResult::from_outcomes(&outcomes);
+
+        assert_eq!(result.kill_turns, BTreeMap::from([(4, 1), (5, 3)]));
+        assert_eq!(
+            result.kill_turn_histogram(),
+            "T4 20.0%, T5 60.0%, no kill 20.0%"
+        );
+        assert_eq!(
+            result.kill_turn_rows(),
+            ["4,1,0.2000", "5,3,0.6000", "none,1,0.2000"]
+        );
+    }
+
+    #[test]
+    fn sweep_table_marks_the_fastest_land_count()
+    {
+        let row = |lands, mean_turns, kills: &[(u32, u32)]| {
+            (
+                lands,
+                SimulationResult {
+                    games: 10,
+                    mean_turns,
+                    kill_turns: kills.iter().copied().collect(),
+                    ..SimulationResult::default()
+                },
+            )
+        };
+        let sweep = SweepResult {
+            rows: vec![
+                row(20, 10.5, &[(5, 1), (7, 9)]),
+                row(21, 9.5, &[(5, 6), (6, 2)]),
+            ],
+        };
+
+        assert_eq!(sweep.best(), Some(21));
+        let text = sweep.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[1].starts_with("   20  "));
+        assert!(lines[2].starts_with("   21* "));
+        assert_eq!(lines[5], "lands   T5  T6  T7");
+        assert_eq!(lines[6], "   20    ░       █");
+        assert_eq!(lines[7], "   21    █   ▒    ");
+    }
+
+    #[test]
+    fn match_record_alongside_game_statistics()
+    {
+        let matches = vec![
+            MatchOutcome {
+                games: vec![outcome(8, true), outcome(10, true)],
+                won: true,
+                lost: false,
+            },
+            MatchOutcome {
+                games: vec![outcome(8, true), outcome(10, false), outcome(12, false)],
+                won: false,
+                lost: true,
+            },
+        ];
+        let result = SimulationResult::from_matches(&matches);
+
+        assert_eq!((result.matches, result.match_wins), (2, 1));
+        assert_eq!(result.match_win_rate(), 0.5);
+        assert_eq!(result.games, 5);
+        assert_eq!(result.win_rate(), 0.6);
+    }
+
+    #[test]
+    fn cards_drawn_in_slow_games_are_cut_candidates()
+    {
+        let with = |turns, cards: &[&str]| GameOutcome {
+            early_cards: cards.iter().map(|c| c.to_string()).collect(),
+            ..outcome(turns, false)
+        };
+        let outcomes = vec![
+            with(10, &["Forest", "Grizzly Bears"]),
+            with(14, &["Forest", "Craw Wurm"]),
+            with(10, &["Forest"]),
+            with(12, &["Craw Wurm", "Mountain"]),
+            with(11, &["Mountain"]),
+        ];
+        let result = SimulationResult::from_outcomes(&outcomes);
+
+        let names: Vec<&str> = result.card_impact.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Craw Wurm", "Mountain", "Forest", "Grizzly Bears"]
+        );
+        let wurm = &result.card_impact[0];
+        assert_eq!(
+            (
+                wurm.games_drawn,
+                wurm.mean_turns_drawn,
+                wurm.mean_turns_not_drawn
+            ),
+            (2, 13.0, 31.0 / 3.0)
+        );
+
+        // Drawn every game: nothing to compare against
+        let always =
+            SimulationResult::from_outcomes(&[with(10, &["Forest"]), with(12, &["Forest"])]);
+        assert!(always.card_impact.is_empty());
+    }
+
+    #[test]
+    fn empty_batch_is_all_zero()
+    {
+        assert_eq!(
+            SimulationResult::from_outcomes(&[]),
+            SimulationResult::default()
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::wincondition::WinCondition;
+
+/// Format-level numbers the engine plays by.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRules
+{
+    pub starting_life: i32,
+    pub starting_hand_size: u32,
+    // Cards a player may keep at cleanup; no limit when unset
+    pub max_hand_size: Option<u32>,
+    pub min_deck_size: u32,
+    // Exact-size formats like Commander set both
+    pub max_deck_size: Option<u32>,
+    // Copies allowed of each card other than basic lands; no limit when unset
+    pub max_copies: Option<u32>,
+    // Cards that may not be played at all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub banned: Vec<String>,
+    // Decks name one or two commanders, which start in the command zone and set the colors the
+    // rest of the deck may use
+    #[serde(default)]
+    pub commander: bool,
+    // Ways a player can lose, checked in this order
+    #[serde(default = "all_win_conditions")]
+    pub win_conditions: Vec<WinCondition>,
+}
+
+fn all_win_conditions() -> Vec<WinCondition>
+{
+    WinCondition::ALL.to_vec()
+}
+
+impl GameRules
+{
+    /// Constructed formats: 20 life, 60-card decks.
+    pub fn constructed() -> Self
+    {
+        GameRules {
+            starting_life: 20,
+            starting_hand_size: 7,
+            max_hand_size: None,
+            min_deck_size: 60,
+            max_deck_size: None,
+            max_copies: Some(4),
+            banned: Vec::new(),
+            commander: false,
+            win_conditions: all_win_conditions(),
+        }
+    }
+
+    /// Sealed and draft: 40-card decks with any number of copies.
+    pub fn limited() -> Self
+    {
+        GameRules {
+            min_deck_size: 40,
+            max_copies: None,
+            ..GameRules::constructed()
+        }
+    }
+
+    /// 40 life and a 100-card singleton deck.
+    pub fn commander() -> Self
+    {
+        GameRules {
+            starting_life: 40,
+            min_deck_size: 100,
+            max_deck_size: Some(100),
+            max_copies: Some(1),
+            commander: true,
+            ..GameRules::constructed()
+        }
+    }
+}
+
+impl Default for GameRules
+{
+    fn default() -> Self
+    {
+        GameRules::constructed()
+    }
+}
+
+impl FromStr for GameRules
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s {
+            "constructed" | "standard" => Ok(GameRules::constructed()),
+            "limited" => Ok(GameRules::limited()),
+            "commander" | "edh" => Ok(GameRules::commander()),
+            _ => Err(format!(
+                "unknown format '{}' (expected constructed, limited or commander)",
+                s
+            )),
+        }
+    }
+}
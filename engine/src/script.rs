@@ -0,0 +1,220 @@
+use rhai::{Engine, INT, Scope};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::ELoggingVerbosity;
+use crate::card::{Card, CardType, ScriptFragment};
+use crate::effect::Effect;
+use crate::game::{GameState, Zone};
+
+// Keeps a runaway script from stalling a whole batch of games
+const MAX_OPERATIONS: u64 = 10_000;
+
+/// Scripts see the board as read-only numbers and act only by asking for effects, which the game
+/// applies afterwards like any other effect:
+///
+/// - `deal_damage(n)`, `damage_opponents(n)`, `destroy_target()`, `pump(power, toughness)`
+/// - `draw(n)`, `gain_life(n)`, `create_tokens(count, power, toughness)`
+/// - `life`, `opponent_life`, `hand_size`, `opponent_hand_size`, `library_size`, `creatures`,
+///   `opponent_creatures`, `lands`
+fn engine(effects: Rc<RefCell<Vec<Effect>>>) -> Engine
+{
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(16);
+
+    let amount = |n: INT| n.clamp(0, u32::MAX as INT) as u32;
+    let stat = |n: INT| n.clamp(0, u8::MAX as INT) as u8;
+
+    let e = effects.clone();
+    engine.register_fn("deal_damage", move |n: INT| {
+        e.borrow_mut().push(Effect::DealDamage(amount(n)))
+    });
+    let e = effects.clone();
+    engine.register_fn("damage_opponents", move |n: INT| {
+        e.borrow_mut().push(Effect::DamageOpponents(amount(n)))
+    });
+    let e = effects.clone();
+    engine.register_fn("destroy_target", move || {
+        e.borrow_mut().push(Effect::DestroyTarget)
+    });
+    let e = effects.clone();
+    engine.register_fn("pump", move |power: INT, toughness: INT| {
+        e.borrow_mut().push(Effect::PumpCreature {
+            power: power as i32,
+            toughness: toughness as i32,
+            keywords: Vec::new(),
+        })
+    });
+    let e = effects.clone();
+    engine.register_fn("draw", move |n: INT| {
+        e.borrow_mut().push(Effect::DrawCards(amount(n)))
+    });
+    let e = effects.clone();
+    engine.register_fn("discard", move |n: INT| {
+        e.borrow_mut().push(Effect::Discard(amount(n)))
+    });
+    let e = effects.clone();
+    engine.register_fn("gain_life", move |n: INT| {
+        e.borrow_mut().push(Effect::GainLife(amount(n)))
+    });
+    let e = effects.clone();
+    engine.register_fn("scry", move |n: INT| {
+        e.borrow_mut().push(Effect::Scry(amount(n)))
+    });
+    let e = effects.clone();
+    engine.register_fn("surveil", move |n: INT| {
+        e.borrow_mut().push(Effect::Surveil(amount(n)))
+    });
+    let e = effects;
+    engine.register_fn(
+        "create_tokens",
+        move |count: INT, power: INT, toughness: INT| {
+            e.borrow_mut().push(Effect::CreateTokens {
+                count: amount(count),
+                power: stat(power),
+                toughness: stat(toughness),
+                keywords: Vec::new(),
+            })
+        },
+    );
+
+    engine
+}
+
+fn board_scope(game: &GameState, controller: usize) -> Scope<'static>
+{
+    let opponent = (controller + 1) % game.players.len();
+    let count = |player: usize, zone: Zone, filter: &dyn Fn(&Card) -> bool| -> INT {
+        game.players[player]
+            .zones
+            .get(&zone)
+            .map_or(0, |cards| cards.iter().filter(|c| filter(c)).count() as INT)
+    };
+
+    let mut scope = Scope::new();
+    scope.push_constant("life", game.players[controller].life as INT);
+    scope.push_constant("opponent_life", game.players[opponent].life as INT);
+    scope.push_constant("hand_size", count(controller, Zone::Hand, &|_| true));
+    scope.push_constant("opponent_hand_size", count(opponent, Zone::Hand, &|_| true));
+    scope.push_constant("library_size", count(controller, Zone::Library, &|_| true));
+    scope.push_constant(
+        "creatures",
+        count(controller, Zone::Battlefield, &crate::creature::is_creature),
+    );
+    scope.push_constant(
+        "opponent_creatures",
+        count(opponent, Zone::Battlefield, &crate::creature::is_creature),
+    );
+    scope.push_constant(
+        "lands",
+        count(controller, Zone::Battlefield, &|c| {
+            c.is_type(CardType::Land)
+        }),
+    );
+    scope
+}
+
+/// Make sure a script compiles, so broken card files are caught when they're loaded.
+pub fn check(source: &str) -> Result<(), String>
+{
+    engine(Rc::default())
+        .compile(source)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Run a script for `controller` and return the effects it asked for, in order. A script that
+/// fails at run time does nothing.
+pub fn run(game: &GameState, controller: usize, source: &str) -> Vec<Effect>
+{
+    let effects = Rc::new(RefCell::new(Vec::new()));
+    let engine = engine(effects.clone());
+    let mut scope = board_scope(game, controller);
+
+    if let Err(e) = engine.run_with_scope(&mut scope, source) {
+        vlog!(ELoggingVerbosity::Warning, "Card script failed: {}", e);
+        return Vec::new();
+    }
+    effects.take()
+}
+
+fn scripts(card: &Card) -> Option<&ScriptFragment>
+{
+    card.fragment::<ScriptFragment>()
+}
+
+pub fn on_cast(card: &Card) -> Option<&str>
+{
+    scripts(card).and_then(|s| s.on_cast.as_deref())
+}
+
+pub fn on_etb(card: &Card) -> Option<&str>
+{
+    scripts(card).and_then(|s| s.on_etb.as_deref())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::Deck;
+
+    #[test]
+    fn scripts_ask_for_effects_based_on_the_board()
+    {
+        let deck = Deck::new(Vec::new());
+        let mut game = GameState::with_decks(&[&deck, &deck], 0);
+        game.players[1].life = 4;
+
+        let effects = run(
+            &game,
+            0,
+            "if opponent_life <= 5 { damage_opponents(5); } else { draw(1); } gain_life(hand_size);",
+        );
+        assert_eq!(
+            effects,
+            vec![Effect::DamageOpponents(5), Effect::GainLife(0)]
+        );
+
+        assert!(check("deal_damage(").is_err());
+        // Runaway scripts are stopped and have no effect
+        assert!(run(&game, 0, "draw(1); loop { }").is_empty());
+    }
+
+    #[test]
+    fn example_cards_load_and_run_their_scripts()
+    {
+        let mut db = crate::carddb::CardDb::new();
+        db.load_json(
+            include_str!("../data/scripted_cards.json"),
+            std::path::Path::new("data/scripted_cards.json"),
+        )
+        .unwrap();
+
+        let deck = Deck::new(Vec::new());
+        let mut game = GameState::with_decks(&[&deck, &deck], 0);
+        let mut forest = crate::card::forest();
+        forest.id = game.new_card_id();
+        game.players[0]
+            .zones
+            .get_mut(&Zone::Library)
+            .unwrap()
+            .push(forest);
+        let mut mulldrifter = db.get("Mulldrifter").unwrap();
+        mulldrifter.id = game.new_card_id();
+        let card = mulldrifter.id;
+        game.players[0]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(mulldrifter);
+        game.events
+            .emit(crate::event::GameEvent::CardEntered { player: 0, card });
+        game.process_events();
+
+        // One card drawn, and the second draw from the now empty library loses
+        assert_eq!(game.players[0].zones.get(&Zone::Hand).unwrap().len(), 1);
+        assert!(game.players[0].has_lost);
+    }
+}
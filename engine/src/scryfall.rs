@@ -0,0 +1,300 @@
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::ELoggingVerbosity;
+use crate::card::CardType;
+use crate::carddb::{CardDb, CardDbError, CardDefinition};
+use crate::keyword::Keyword;
+use crate::mana::{ManaColor, ManaCost};
+use crate::tappable::EnterTappedRule;
+
+/// The subset of a Scryfall card object the importer looks at.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ScryfallCard
+{
+    pub name: String,
+    #[serde(default)]
+    pub layout: String,
+    #[serde(default)]
+    pub type_line: String,
+    #[serde(default)]
+    pub cmc: f64,
+    #[serde(default)]
+    pub mana_cost: Option<String>,
+    #[serde(default)]
+    pub produced_mana: Vec<String>,
+    #[serde(default)]
+    pub oracle_text: Option<String>,
+    #[serde(default)]
+    pub power: Option<String>,
+    #[serde(default)]
+    pub toughness: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport
+{
+    pub imported: usize,
+    // (card name, reason it was skipped)
+    pub skipped: Vec<(String, String)>,
+}
+
+// Basic lands and untapped duals only have their mana ability as (reminder) text
+fn is_mana_land_text(text: &str) -> bool
+{
+    let text = text.trim().trim_start_matches('(').trim_end_matches(')');
+    text.is_empty()
+        || (text.starts_with("{T}: Add {") && text.ends_with("}.") && !text.contains('\n'))
+}
+
+// Rules text made up only of keywords the engine knows ("Flying, first strike"), reminder text aside
+fn is_keyword_text(text: &str) -> bool
+{
+    let mut stripped = String::new();
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+
+    stripped
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .all(|part| part.parse::<Keyword>().is_ok())
+}
+
+fn parse_stat(value: &Option<String>) -> Option<u8>
+{
+    value.as_deref().and_then(|v| v.parse().ok())
+}
+
+/// Translate a Scryfall card into a definition the engine can simulate, or explain why not.
+pub fn translate(card: &ScryfallCard) -> Result<CardDefinition, String>
+{
+    if card.layout != "normal" && !card.layout.is_empty() {
+        return Err(format!("unsupported layout '{}'", card.layout));
+    }
+
+    let text = card.oracle_text.as_deref().unwrap_or("");
+    let front_type = card.type_line.split('—').next().unwrap_or("");
+
+    if front_type.contains("Land") {
+        // Taplands: "This land enters tapped." (or the card's name in older wordings) before the mana ability
+        let (enters_tapped, text) = match text.split_once('\n') {
+            Some((first, rest))
+                if first.ends_with(" enters tapped.")
+                    || first.ends_with(" enters the battlefield tapped.") =>
+            {
+                (EnterTappedRule::Always, rest)
+            }
+            _ => (EnterTappedRule::Never, text),
+        };
+        if front_type.contains("Creature") || !is_mana_land_text(text) {
+            return Err(String::from(
+                "lands with abilities beyond a single mana ability aren't supported",
+            ));
+        }
+
+        let produces = card
+            .produced_mana
+            .iter()
+            .map(|symbol| {
+                symbol
+                    .chars()
+                    .next()
+                    .and_then(ManaColor::from_symbol)
+                    .ok_or_else(|| format!("unknown mana symbol '{}'", symbol))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(CardDefinition {
+            name: card.name.clone(),
+            types: vec![CardType::Land],
+            cost: ManaCost::default(),
+            power: None,
+            toughness: None,
+            produces,
+            mana_per_tap: None,
+            enters_tapped,
+            keywords: Vec::new(),
+            triggers: Vec::new(),
+            effects: Vec::new(),
+            sacrifice: None,
+            kicker: None,
+            kicked_effects: Vec::new(),
+            alternative_cost: None,
+            attachment: None,
+            statics: Vec::new(),
+            replacements: Vec::new(),
+            equip: None,
+            on_cast: None,
+            on_etb: None,
+            loyalty: None,
+            loyalty_abilities: Vec::new(),
+            layout: None,
+            faces: Vec::new(),
+        });
+    }
+
+    if front_type.contains("Creature") {
+        let unsupported: Vec<&str> = card
+            .keywords
+            .iter()
+            .filter(|k| k.parse::<Keyword>().is_err())
+            .map(String::as_str)
+            .collect();
+        if !unsupported.is_empty() {
+            return Err(format!("unsupported keywords: {}", unsupported.join(", ")));
+        }
+        if !is_keyword_text(text) {
+            return Err(String::from(
+                "creatures with rules text beyond keywords aren't supported",
+            ));
+        }
+
+        let (Some(power), Some(toughness)) = (parse_stat(&card.power), parse_stat(&card.toughness))
+        else {
+            return Err(String::from("non-numeric power/toughness"));
+        };
+
+        let cost = match card.mana_cost.as_deref() {
+            Some(cost) => cost
+                .parse()
+                .map_err(|e| format!("unsupported mana cost: {}", e))?,
+            None => ManaCost::generic(card.cmc as u32),
+        };
+
+        return Ok(CardDefinition {
+            name: card.name.clone(),
+            types: vec![CardType::Creature],
+            cost,
+            power: Some(power),
+            toughness: Some(toughness),
+            produces: Vec::new(),
+            mana_per_tap: None,
+            enters_tapped: EnterTappedRule::Never,
+            keywords: card
+                .keywords
+                .iter()
+                .filter_map(|k| k.parse().ok())
+                .collect(),
+            triggers: Vec::new(),
+            effects: Vec::new(),
+            sacrifice: None,
+            kicker: None,
+            kicked_effects: Vec::new(),
+            alternative_cost: None,
+            attachment: None,
+            statics: Vec::new(),
+            replacements: Vec::new(),
+            equip: None,
+            on_cast: None,
+            on_etb: None,
+            loyalty: None,
+            loyalty_abilities: Vec::new(),
+            layout: None,
+            faces: Vec::new(),
+        });
+    }
+
+    Err(format!("unsupported card type '{}'", front_type.trim()))
+}
+
+/// Import every supported card from a Scryfall bulk-data JSON array.
+pub fn import_bulk_json<R: Read>(
+    reader: R,
+    db: &mut CardDb,
+) -> Result<ImportReport, serde_json::Error>
+{
+    let cards: Vec<ScryfallCard> = serde_json::from_reader(reader)?;
+    let mut report = ImportReport::default();
+
+    for card in cards.iter() {
+        match translate(card) {
+            Ok(definition) => {
+                db.insert(definition);
+                report.imported += 1;
+            }
+            Err(reason) => {
+                vlog!(
+                    ELoggingVerbosity::Verbose,
+                    "Skipping {}: {}",
+                    card.name,
+                    reason
+                );
+                report.skipped.push((card.name.clone(), reason));
+            }
+        }
+    }
+
+    if !report.skipped.is_empty() {
+        vlog!(
+            ELoggingVerbosity::Warning,
+            "Scryfall import skipped {} card(s) with unsupported mechanics",
+            report.skipped.len()
+        );
+    }
+
+    Ok(report)
+}
+
+pub fn import_bulk_file(path: &Path, db: &mut CardDb) -> Result<ImportReport, CardDbError>
+{
+    let file = File::open(path).map_err(|e| CardDbError::Io(path.to_path_buf(), e))?;
+    import_bulk_json(BufReader::new(file), db)
+        .map_err(|e| CardDbError::Parse(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const BULK: &str = r#"[
+        { "name": "Forest", "layout": "normal", "type_line": "Basic Land — Forest", "cmc": 0.0, "oracle_text": "({T}: Add {G}.)", "produced_mana": ["G"] },
+        { "name": "Grizzly Bears", "layout": "normal", "type_line": "Creature — Bear", "cmc": 2.0, "mana_cost": "{1}{G}", "oracle_text": "", "power": "2", "toughness": "2", "keywords": [] },
+        { "name": "Serra Angel", "layout": "normal", "type_line": "Creature — Angel", "cmc": 5.0, "mana_cost": "{3}{W}{W}", "oracle_text": "Flying, vigilance", "power": "4", "toughness": "4", "keywords": ["Flying", "Vigilance"] },
+        { "name": "Gladecover Scout", "layout": "normal", "type_line": "Creature — Elf Scout", "cmc": 1.0, "mana_cost": "{G}", "oracle_text": "Hexproof", "power": "1", "toughness": "1", "keywords": ["Hexproof"] },
+        { "name": "Wind Drake", "layout": "normal", "type_line": "Creature — Drake", "cmc": 3.0, "mana_cost": "{2}{U}", "oracle_text": "Flying (This creature can't be blocked except by creatures with flying or reach.)", "power": "2", "toughness": "2", "keywords": ["Flying"] },
+        { "name": "Gruul Guildgate", "layout": "normal", "type_line": "Land — Gate", "cmc": 0.0, "oracle_text": "This land enters tapped.\n{T}: Add {R} or {G}.", "produced_mana": ["R", "G"] },
+        { "name": "Lightning Bolt", "layout": "normal", "type_line": "Instant", "cmc": 1.0, "oracle_text": "Lightning Bolt deals 3 damage to any target." },
+        { "name": "Tarmogoyf", "layout": "normal", "type_line": "Creature — Lhurgoyf", "cmc": 2.0, "oracle_text": "", "power": "*", "toughness": "1+*" }
+    ]"#;
+
+    #[test]
+    fn imports_supported_cards_and_skips_the_rest()
+    {
+        let mut db = CardDb::new();
+        let report = import_bulk_json(BULK.as_bytes(), &mut db).unwrap();
+
+        assert_eq!(report.imported, 5);
+        assert_eq!(report.skipped.len(), 3);
+        assert_eq!(db.definition("Grizzly Bears").unwrap().power, Some(2));
+        assert_eq!(
+            db.definition("Grizzly Bears").unwrap().cost,
+            "1G".parse().unwrap()
+        );
+        assert!(db.get("Forest").unwrap().is_type(CardType::Land));
+        assert_eq!(
+            db.definition("Serra Angel").unwrap().keywords,
+            vec![Keyword::Flying, Keyword::Vigilance]
+        );
+        assert!(db.get("Gladecover Scout").is_none());
+        assert_eq!(
+            db.definition("Wind Drake").unwrap().keywords,
+            vec![Keyword::Flying]
+        );
+        assert_eq!(
+            db.definition("Gruul Guildgate").unwrap().enters_tapped,
+            EnterTappedRule::Always
+        );
+    }
+}
@@ -1,151 +1,1900 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use crate::game::{GameState, ProgramState, StepCommand, GameStep};
+use crate::ELoggingVerbosity;
+use crate::breakpoint::Breakpoint;
+use crate::cache;
 use crate::card::Deck;
+use crate::deck::{self, Legality};
+use crate::game::{GameState, GameStep, PlayDraw, ProgramState, StepCommand, Zone};
+use crate::gamelog::{GameLog, LogRecord, Watcher};
+use crate::input;
+use crate::mulligan::{self, LandRange, MulliganStrategy};
+use crate::progress::Progress;
+use crate::replay::Recorder;
+use crate::result::{GauntletResult, SimulationResult, SweepResult};
+use crate::rules::GameRules;
+use crate::strategy::{CurveOut, Pilot, PlayerStrategy};
+use crate::wincondition::WinCondition;
 
-pub fn parse_command(input: &str) -> StepCommand
+// A game counts as mana screwed if the player missed a land drop in one of their first turns
+pub const SCREW_CHECK_TURNS: usize = 3;
+// ... and as flooded if they had seen at least this many lands by the given turn
+pub const FLOOD_LAND_COUNT: u32 = 8;
+pub const FLOOD_CHECK_TURN: usize = 6;
+// Opening hands the "h" command deals when not given a count
+pub const SAMPLE_HANDS: u32 = 1000;
+// Steps the "u" command can take back
+pub const UNDO_STEPS: usize = 100;
+// Turns the curve report covers
+pub const CURVE_REPORT_TURNS: usize = 5;
+// Cards drawn by the end of this many of the player's turns count as drawn early for the
+// per-card report
+pub const EARLY_CARD_TURNS: u32 = 3;
+
+/// When a game counts as mana screwed or flooded; defaults to the constants above.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManaThresholds
+{
+    // Missed a land drop in one of this many first turns
+    pub screw_turns: usize,
+    // Had seen at least `flood_lands` lands by turn `flood_turn`
+    pub flood_lands: u32,
+    pub flood_turn: usize,
+}
+
+impl Default for ManaThresholds
+{
+    fn default() -> Self
+    {
+        ManaThresholds {
+            screw_turns: SCREW_CHECK_TURNS,
+            flood_lands: FLOOD_LAND_COUNT,
+            flood_turn: FLOOD_CHECK_TURN,
+        }
+    }
+}
+
+static BASE_SEED: AtomicU64 = AtomicU64::new(0);
+static SEED_IS_SET: AtomicBool = AtomicBool::new(false);
+static NEXT_GAME_INDEX: AtomicU64 = AtomicU64::new(0);
+
+/// Make every subsequent game reproducible: game N after this call is always seeded the same way.
+pub fn set_seed(seed: u64)
+{
+    BASE_SEED.store(seed, Ordering::Relaxed);
+    NEXT_GAME_INDEX.store(0, Ordering::Relaxed);
+    SEED_IS_SET.store(true, Ordering::Relaxed);
+}
+
+/// Seed for the game with the given index, derived from the base seed (SplitMix64 mixing).
+pub fn game_seed(game_index: u64) -> u64
+{
+    let mut z = BASE_SEED.load(Ordering::Relaxed).wrapping_add(
+        game_index
+            .wrapping_add(1)
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    );
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// The base seed; picks a random one if none was set.
+fn base_seed() -> u64
+{
+    if !SEED_IS_SET.load(Ordering::Relaxed) {
+        set_seed(rand::random());
+    }
+
+    BASE_SEED.load(Ordering::Relaxed)
+}
+
+// Reserve a contiguous block of game indices
+fn reserve_game_indices(count: u64) -> u64
+{
+    base_seed();
+    NEXT_GAME_INDEX.fetch_add(count, Ordering::Relaxed)
+}
+
+/// Index of the next game to be seeded, to save a run's position.
+pub fn next_game_index() -> u64
+{
+    NEXT_GAME_INDEX.load(Ordering::Relaxed)
+}
+
+/// Continue seeding from a saved position; call after `set_seed`.
+pub fn set_next_game_index(index: u64)
+{
+    NEXT_GAME_INDEX.store(index, Ordering::Relaxed);
+}
+
+/// Seed for the next game.
+pub fn next_game_seed() -> u64
+{
+    game_seed(reserve_game_indices(1))
+}
+
+#[derive(Clone)]
+pub struct BatchConfig
+{
+    pub deck: Deck,
+    // Deck for every other player; a copy of `deck` when unset
+    pub opponent: Option<Deck>,
+    pub player_count: usize,
+    pub mulligan: Arc<dyn MulliganStrategy>,
+    // How every other player mulligans, when not like the first
+    pub opponent_mulligan: Option<Arc<dyn MulliganStrategy>>,
+    // Who pilots `deck`; every other player curves out
+    pub pilot: Pilot,
+    pub play_draw: PlayDraw,
+    pub rules: GameRules,
+    pub thresholds: ManaThresholds,
+    // Where every game's actions are logged, if anywhere
+    pub game_log: Option<Arc<GameLog>>,
+    // Seed of a game to record for replaying, and where to save it
+    pub record_game: Option<(u64, PathBuf)>,
+    // Whether outcomes keep the cards drawn early, for the card report
+    pub card_report: bool,
+}
+
+impl BatchConfig
+{
+    pub fn new(deck: Deck) -> Self
+    {
+        BatchConfig {
+            deck,
+            opponent: None,
+            player_count: 2,
+            mulligan: Arc::new(LandRange::default()),
+            opponent_mulligan: None,
+            pilot: Pilot::default(),
+            play_draw: PlayDraw::Play,
+            rules: GameRules::default(),
+            thresholds: ManaThresholds::default(),
+            game_log: None,
+            record_game: None,
+            card_report: false,
+        }
+    }
+
+    pub fn with_opponent(deck: Deck, opponent: Deck) -> Self
+    {
+        BatchConfig {
+            opponent: Some(opponent),
+            ..BatchConfig::new(deck)
+        }
+    }
+
+    /// The same games after sideboarding: `deck` follows its plan against `versus`, the opponent
+    /// its plan against `opponent_versus`.
+    pub fn sideboarded(&self, versus: &str, opponent_versus: &str) -> BatchConfig
+    {
+        BatchConfig {
+            deck: deck::sideboard_against(&self.deck, versus),
+            opponent: self
+                .opponent
+                .as_ref()
+                .map(|opponent| deck::sideboard_against(opponent, opponent_versus)),
+            ..self.clone()
+        }
+    }
+
+    /// A game of the config's decks from `seed`, with the first player going first when `on_play`.
+    pub fn new_game(&self, seed: u64, on_play: bool) -> GameState
+    {
+        let opponent = self.opponent.as_ref().unwrap_or(&self.deck);
+
+        let mut decks = vec![&self.deck];
+        decks.extend(std::iter::repeat_n(opponent, self.player_count.max(2) - 1));
+        let mut game = GameState::with_rules(&decks, seed, &self.rules, &self.strategies());
+        if !on_play {
+            game.current_player_index = 1;
+        }
+        game.log = self.game_log.clone();
+        game
+    }
+
+    /// Who decides for each player: the pilot for the first, curving out for the others.
+    pub fn strategies(&self) -> Vec<Arc<dyn PlayerStrategy>>
+    {
+        let mut strategies = vec![self.pilot.strategy(self.mulligan.clone())];
+        let mulligan = self.opponent_mulligan.as_ref().unwrap_or(&self.mulligan);
+        strategies.resize_with(self.player_count.max(2), || {
+            Arc::new(CurveOut {
+                mulligan: mulligan.clone(),
+            })
+        });
+        strategies
+    }
+
+    // Start recording the game if it's the one to record
+    fn start_recording(&self, game: &mut GameState) -> Option<Recorder>
+    {
+        self.record_game
+            .as_ref()
+            .filter(|(seed, _)| *seed == game.seed)
+            .map(|_| Recorder::start(game))
+    }
+
+    fn save_recording(&self, recorder: Option<Recorder>)
+    {
+        if let (Some(recorder), Some((seed, path))) = (recorder, &self.record_game) {
+            match recorder.finish().save(path) {
+                Ok(()) => vlog!(
+                    ELoggingVerbosity::Normal,
+                    "Recorded game {} to {}",
+                    seed,
+                    path.display()
+                ),
+                Err(e) => vlog!(
+                    ELoggingVerbosity::Warning,
+                    "Can't save the recording to {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameOutcome
+{
+    pub seed: u64,
+    // Turns taken by all players together
+    pub turns: u32,
+    // Whether the first player went first
+    pub on_play: bool,
+    pub mulligans: u32,
+    // Cards the first player drew after their opening hand
+    pub cards_drawn: u32,
+    // Spells the first player cast on each of their turns
+    pub spells_per_turn: Vec<u32>,
+    pub mana_screwed: bool,
+    pub mana_flooded: bool,
+    pub color_screwed: bool,
+    pub won: bool,
+    // Whether the first player lost; neither won nor lost is a draw
+    pub lost: bool,
+    // How the losing player lost; unset for games that hit the turn limit
+    pub ended_by: Option<WinCondition>,
+    // The first player's own turn number on which they won; unset unless they won
+    pub kill_turn: Option<u32>,
+    // Cards the first player had in their opening hand or drew in their first turns
+    pub early_cards: Vec<String>,
+    // For each of the first player's first `CURVE_REPORT_TURNS` turns they got to: whether they
+    // made their land drop and whether they cast a spell on curve
+    pub curve: Vec<(bool, bool)>,
+    // ... and how many lands they had seen by the main phase of each of those turns
+    pub lands_seen: Vec<u32>,
+}
+
+impl GameOutcome
 {
-    match input
+    // Diagnostics are reported for the first player, the one whose deck is being tuned
+    pub fn from_game(game: &GameState, config: &BatchConfig) -> Self
     {
+        let thresholds = &config.thresholds;
+        let stats = &game.players[0].stats;
+        let mana_screwed = stats
+            .land_drops
+            .iter()
+            .take(thresholds.screw_turns)
+            .any(|played| !played);
+        let mana_flooded = stats
+            .lands_seen
+            .iter()
+            .take(thresholds.flood_turn)
+            .next_back()
+            .is_some_and(|&lands| lands >= thresholds.flood_lands);
+
+        GameOutcome {
+            seed: game.seed,
+            turns: game.turns,
+            on_play: game.players[0].stats.went_first,
+            mulligans: stats.mulligans,
+            cards_drawn: stats.cards_drawn,
+            spells_per_turn: stats.spells_per_turn.clone(),
+            mana_screwed,
+            mana_flooded,
+            color_screwed: stats.color_screwed_turns > 0,
+            won: game.winner() == Some(0),
+            lost: game.players[0].has_lost,
+            ended_by: game.players.iter().find_map(|p| p.lost_to),
+            kill_turn: (game.winner() == Some(0)).then_some(stats.turns_taken),
+            early_cards: if config.card_report {
+                stats.early_cards.clone()
+            } else {
+                Vec::new()
+            },
+            lands_seen: stats
+                .lands_seen
+                .iter()
+                .take(CURVE_REPORT_TURNS)
+                .copied()
+                .collect(),
+            curve: stats
+                .land_drops
+                .iter()
+                .zip(stats.on_curve.iter())
+                .take(CURVE_REPORT_TURNS)
+                .map(|(&land, &spell)| (land, spell))
+                .collect(),
+        }
+    }
+}
+
+/// Play the game with index `game_index` in the run without any interaction.
+pub fn run_game(config: &BatchConfig, game_index: u64) -> GameOutcome
+{
+    let seed = game_seed(game_index);
+    let _log = crate::GameLogScope::enter(seed);
+    let mut game = config.new_game(seed, config.play_draw.on_play(game_index));
+    let recorder = config.start_recording(&mut game);
+    while !game.is_game_over() {
+        game.step();
+    }
+    config.save_recording(recorder);
+
+    GameOutcome::from_game(&game, config)
+}
+
+/// Play `n_games` across all cores. Every game gets its own RNG stream derived from the
+/// global seed, so the outcomes (returned in game order) don't depend on thread scheduling.
+pub fn run_batch_parallel(config: &BatchConfig, n_games: u32) -> Vec<GameOutcome>
+{
+    run_batch_with_progress(config, n_games, &Progress::hidden())
+}
+
+// The same, ticking `progress` as each game finishes
+fn run_batch_with_progress(
+    config: &BatchConfig,
+    n_games: u32,
+    progress: &Progress,
+) -> Vec<GameOutcome>
+{
+    let first_index = reserve_game_indices(n_games as u64);
+    (0..n_games as u64)
+        .into_par_iter()
+        .map(|i| {
+            let outcome = run_game(config, first_index + i);
+            progress.tick();
+            outcome
+        })
+        .collect()
+}
+
+pub fn parse_command(input: &str) -> StepCommand
+{
+    match input {
         "s" => StepCommand::StepPhase,
+        "a" => StepCommand::StepAction,
+        "p" => StepCommand::StepPriority,
         "t" => StepCommand::StepTurn,
         "g" => StepCommand::RunGame,
         "d" => StepCommand::RunDeck,
         "r" => StepCommand::RunAll,
         "q" => StepCommand::Quit,
-        _   => StepCommand::Invalid,
+        "h" => StepCommand::OpeningHands(SAMPLE_HANDS),
+        "b" => StepCommand::Board,
+        "u" => StepCommand::Undo,
+        _ if input.starts_with("g ") => match input["g ".len()..].trim().parse() {
+            Ok(turn) if turn > 0 => StepCommand::RunToTurn(turn),
+            _ => StepCommand::Invalid,
+        },
+        _ => match input.strip_prefix("h ").map(|count| count.trim().parse()) {
+            Some(Ok(count)) if count > 0 => StepCommand::OpeningHands(count),
+            _ => StepCommand::Invalid,
+        },
     }
 }
 
-fn wait_for_command() -> StepCommand
+// Read the next step command, saving the game to a file or replacing it with a saved one on
+// "save FILE" and "load FILE", and setting `breakpoint` on "break when EXPR"
+fn wait_for_command(
+    game: &mut GameState,
+    config: &BatchConfig,
+    breakpoint: &mut Option<Breakpoint>,
+) -> StepCommand
 {
-    print!("> ");
-    io::stdout().flush().unwrap();
+    loop {
+        let Some(input) = input::prompt() else {
+            return StepCommand::Quit;
+        };
+        let input = input.as_str();
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
+        if let Some(path) = input.strip_prefix("save ") {
+            match game.save(std::path::Path::new(path.trim())) {
+                Ok(()) => println!("Saved the game to {}", path.trim()),
+                Err(e) => vlog!(
+                    ELoggingVerbosity::Warning,
+                    "Can't save the game to {}: {}",
+                    path.trim(),
+                    e
+                ),
+            }
+        } else if let Some(path) = input.strip_prefix("load ") {
+            match GameState::load(std::path::Path::new(path.trim())) {
+                Ok(loaded) => {
+                    *game = loaded;
+                    game.strategies = config.strategies();
+                    game.log = config.game_log.clone();
+                    game.describe(true);
+                }
+                Err(e) => vlog!(
+                    ELoggingVerbosity::Warning,
+                    "Can't load a game from {}: {}",
+                    path.trim(),
+                    e
+                ),
+            }
+        } else if let Some(condition) = input.strip_prefix("break when ") {
+            match condition.parse() {
+                Ok(parsed) => {
+                    *breakpoint = Some(parsed);
+                    return StepCommand::RunToBreakpoint;
+                }
+                Err(e) => println!("Can't set the breakpoint: {}", e),
+            }
+        } else {
+            return parse_command(input);
+        }
+    }
+}
 
-    parse_command(input.trim())
+// The game as it was before each of the last `UNDO_STEPS` steps, oldest first, with how many
+// decisions its recording held then so the decisions of undone steps are dropped too
+#[derive(Default)]
+struct History
+{
+    states: VecDeque<(GameState, usize)>,
 }
 
-pub fn simulate_game(deck: &Deck, step_mode: StepCommand) -> (u32, StepCommand)
+impl History
 {
-    let mut game = GameState::new(2, deck); // Default 2 players
-    let mut mode = step_mode;
+    fn push(&mut self, game: &GameState, recorder: Option<&Recorder>)
+    {
+        if self.states.len() == UNDO_STEPS {
+            self.states.pop_front();
+        }
+        self.states
+            .push_back((game.clone(), recorder.map_or(0, Recorder::recorded)));
+    }
 
-    loop
+    // Put the game back to before the last step; false if there's nothing left to undo
+    fn undo(&mut self, game: &mut GameState, recorder: Option<&Recorder>) -> bool
     {
-        match mode
-        {
-            StepCommand::StepPhase =>
-            {
-                if game.is_game_over()
-                {
+        let Some((previous, recorded)) = self.states.pop_back() else {
+            return false;
+        };
+        *game = previous;
+        if let Some(recorder) = recorder {
+            recorder.rewind(recorded);
+        }
+        true
+    }
+}
+
+/// Play `game` on to the start of `turn`, or to its end if that comes first.
+pub fn run_to_turn(game: &mut GameState, turn: u32)
+{
+    while game.turns < turn && !game.is_game_over() {
+        game.step();
+    }
+}
+
+// Pauses a stepped game inside a phase, after every action ("a") or before every priority pass
+// ("p"), and asks how to go on: "a" or "p" to keep pausing, "b" to see the board, anything else
+// to play the rest of the phase
+struct StepWatcher
+{
+    pause_at: Mutex<Option<StepCommand>>,
+    paused: AtomicBool,
+}
+
+impl StepWatcher
+{
+    fn new(pause_at: StepCommand) -> Self
+    {
+        StepWatcher {
+            pause_at: Mutex::new(Some(pause_at)),
+            paused: AtomicBool::new(false),
+        }
+    }
+
+    fn pausing_at(&self, command: StepCommand) -> bool
+    {
+        *self.pause_at.lock().unwrap() == Some(command)
+    }
+
+    fn pause(&self, game: &GameState)
+    {
+        self.paused.store(true, Ordering::Relaxed);
+        loop {
+            // Out of input: play on, and the step loop quits at its next prompt
+            match input::prompt().map_or(StepCommand::Quit, |input| parse_command(&input)) {
+                StepCommand::Board => println!("{}", game.board()),
+                command @ (StepCommand::StepAction | StepCommand::StepPriority) => {
+                    *self.pause_at.lock().unwrap() = Some(command);
+                    return;
+                }
+                _ => {
+                    *self.pause_at.lock().unwrap() = None;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Watcher for StepWatcher
+{
+    fn action(&self, game: &GameState, record: &LogRecord)
+    {
+        if self.pausing_at(StepCommand::StepAction) {
+            println!("Player {} {}", record.player + 1, record.event);
+            self.pause(game);
+        }
+    }
+
+    fn priority(&self, game: &GameState, player: usize)
+    {
+        if self.pausing_at(StepCommand::StepPriority) {
+            // Each player's Stack zone holds their spells in stack order, bottom first
+            let mut taken = vec![0; game.players.len()];
+            let stack: Vec<&str> = game
+                .stack
+                .iter()
+                .map(|&controller| {
+                    taken[controller] += 1;
+                    game.players[controller].zones[&Zone::Stack][taken[controller] - 1]
+                        .name
+                        .as_str()
+                })
+                .collect();
+            match stack.is_empty() {
+                true => println!(
+                    "Player {} has priority, {:?} step, stack empty",
+                    player + 1,
+                    game.step
+                ),
+                false => println!(
+                    "Player {} has priority, {:?} step, stack (top last): {}",
+                    player + 1,
+                    game.step,
+                    stack.join(", ")
+                ),
+            }
+            self.pause(game);
+        }
+    }
+}
+
+pub fn simulate_game(config: &BatchConfig, step_mode: StepCommand) -> (GameOutcome, StepCommand)
+{
+    let game_index = reserve_game_indices(1);
+    let seed = game_seed(game_index);
+    let _log = crate::GameLogScope::enter(seed);
+    let mut game = config.new_game(seed, config.play_draw.on_play(game_index));
+    let recorder = config.start_recording(&mut game);
+    let mut history = History::default();
+    let mut breakpoint = None;
+    let mut mode = step_mode;
+
+    loop {
+        match mode {
+            StepCommand::StepPhase => {
+                if game.is_game_over() {
                     break;
                 }
 
+                history.push(&game, recorder.as_ref());
                 game.step();
                 game.describe(true);
 
                 // get new command
-                mode = wait_for_command();
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
+            }
+
+            StepCommand::StepAction | StepCommand::StepPriority => {
+                if game.is_game_over() {
+                    break;
+                }
+
+                history.push(&game, recorder.as_ref());
+                let watcher = Arc::new(StepWatcher::new(mode));
+                game.watcher = Some(watcher.clone());
+                // Phases where nothing happens go by without stopping
+                while !watcher.paused.load(Ordering::Relaxed) && !game.is_game_over() {
+                    game.step();
+                }
+                game.watcher = None;
+                game.describe(true);
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
             }
 
-            StepCommand::StepTurn =>
-            {
+            StepCommand::StepTurn => {
                 // Step one whole turn (StartTurn -> EndTurn)
-                if game.is_game_over()
-                {
+                if game.is_game_over() {
                     break;
                 }
 
-                loop
-                {
+                history.push(&game, recorder.as_ref());
+                loop {
                     game.step();
-                    if game.step == GameStep::StartTurn || game.is_game_over()
-                    {
+                    if game.step == GameStep::StartTurn || game.is_game_over() {
                         break;
                     }
                 }
 
                 game.describe(true);
-                mode = wait_for_command();
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
             }
 
-            StepCommand::RunGame | StepCommand::RunDeck | StepCommand::RunAll =>
-            {
-                while !game.is_game_over()
-                {
+            StepCommand::RunGame | StepCommand::RunDeck | StepCommand::RunAll => {
+                if mode == StepCommand::RunGame {
+                    history.push(&game, recorder.as_ref());
+                }
+                while !game.is_game_over() {
                     game.step();
                 }
 
-                if mode == StepCommand::RunGame
-                {
+                if mode == StepCommand::RunGame {
                     game.describe(true);
                     println!("Game over in {} turns.", game.turns);
 
                     // get next command
-                    mode = wait_for_command();
+                    mode = wait_for_command(&mut game, config, &mut breakpoint);
+                    if mode == StepCommand::Undo {
+                        continue;
+                    }
                 }
 
                 // exit after running to completion
                 break;
             }
 
-            StepCommand::Quit =>
-            {
+            StepCommand::Quit => {
                 break;
             }
 
-            StepCommand::Invalid =>
-            {
-                mode = wait_for_command();
+            StepCommand::OpeningHands(count) => {
+                let mut rng = ChaCha8Rng::seed_from_u64(next_game_seed());
+                let sample = mulligan::sample_hands(
+                    &config.deck.cards,
+                    config.rules.starting_hand_size,
+                    count,
+                    &mut rng,
+                    config.mulligan.as_ref(),
+                );
+                println!("{}", sample);
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
+            }
+
+            StepCommand::RunToTurn(turn) => {
+                if game.turns >= turn {
+                    println!("Already on turn {}", game.turns);
+                } else {
+                    history.push(&game, recorder.as_ref());
+                    run_to_turn(&mut game, turn);
+                    game.describe(true);
+                }
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
+            }
+
+            StepCommand::RunToBreakpoint => {
+                if let Some(condition) = breakpoint.take() {
+                    history.push(&game, recorder.as_ref());
+                    let mut hit = false;
+                    while !hit && !game.is_game_over() {
+                        game.step();
+                        hit = condition.hit(&game);
+                    }
+                    game.describe(true);
+                    if hit {
+                        println!("Stopped: {}", condition);
+                    } else {
+                        println!("Game over in {} turns without {}", game.turns, condition);
+                    }
+                }
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
+            }
+
+            StepCommand::Undo => {
+                if history.undo(&mut game, recorder.as_ref()) {
+                    game.describe(true);
+                } else {
+                    println!("Nothing to undo");
+                }
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
+            }
+
+            StepCommand::Board => {
+                println!("{}", game.board());
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
+            }
+
+            StepCommand::Invalid => {
+                mode = wait_for_command(&mut game, config, &mut breakpoint);
             }
         }
     }
 
-    (game.turns, mode)
+    config.save_recording(recorder);
+    (GameOutcome::from_game(&game, config), mode)
 }
 
-pub fn try_scenario(lands: u32, nonlands: u32, program_state: &mut ProgramState) -> f64
+/// Simulate a deck of Forests and Grizzly Bears, with the simulated player on the play, on the
+/// draw or alternating between the two.
+pub fn try_scenario(
+    lands: u32,
+    nonlands: u32,
+    play_draw: PlayDraw,
+    program_state: &mut ProgramState,
+) -> SimulationResult
 {
     let mut cards = Vec::new();
 
-    for _ in 0..lands
-    {
+    for _ in 0..lands {
         cards.push(crate::card::forest());
     }
 
-    for _ in 0..nonlands
-    {
+    for _ in 0..nonlands {
         cards.push(crate::card::grizzly_bears());
     }
 
-    let deck = Deck { cards };
-    let games = 3000;
-    let mut total_turns = 0;
+    let deck = Deck::new(cards);
+    let config = BatchConfig {
+        play_draw,
+        ..batch_config(&deck, program_state)
+    };
+    run_scenario(
+        &config,
+        &format!("{} lands and {} nonlands", lands, nonlands),
+        program_state,
+    )
+}
+
+/// Simulate a `deck_size`-card deck of Forests and Grizzly Bears at every land count in `lands`,
+/// with `program_state.games_per_scenario` games each. Stops early if the user quits.
+pub fn run_sweep(
+    lands: std::ops::RangeInclusive<u32>,
+    deck_size: u32,
+    program_state: &mut ProgramState,
+) -> SweepResult
+{
+    let mut sweep = SweepResult::default();
+    for land_count in lands.filter(|&l| l <= deck_size) {
+        let result = try_scenario(
+            land_count,
+            deck_size - land_count,
+            program_state.play_draw,
+            program_state,
+        );
+        if program_state.step_mode == StepCommand::Quit {
+            break;
+        }
+        sweep.rows.push((land_count, result));
+    }
+    sweep
+}
 
-    for _ in 0..games
+fn batch_config(deck: &Deck, program_state: &ProgramState) -> BatchConfig
+{
+    // A goldfish has nothing to mulligan for
+    let goldfish = program_state.goldfish && program_state.opponent_deck.is_none();
+    BatchConfig {
+        opponent: program_state
+            .opponent_deck
+            .clone()
+            .or_else(|| goldfish.then(Deck::goldfish)),
+        mulligan: program_state.mulligan.clone(),
+        opponent_mulligan: goldfish
+            .then(|| Arc::new(mulligan::KeepAll) as Arc<dyn MulliganStrategy>),
+        pilot: program_state.pilot,
+        play_draw: program_state.play_draw,
+        rules: program_state.rules.clone(),
+        thresholds: program_state.mana_thresholds,
+        game_log: program_state.game_log.clone(),
+        record_game: program_state.record_game.clone(),
+        card_report: program_state.card_report,
+        ..BatchConfig::new(deck.clone())
+    }
+}
+
+/// Simulate `program_state.games_per_scenario` games of a deck and print a summary.
+pub fn try_deck(
+    deck: &Deck,
+    description: &str,
+    program_state: &mut ProgramState,
+) -> SimulationResult
+{
+    run_scenario(
+        &batch_config(deck, program_state),
+        description,
+        program_state,
+    )
+}
+
+fn run_scenario(
+    config: &BatchConfig,
+    description: &str,
+    program_state: &mut ProgramState,
+) -> SimulationResult
+{
+    let legality_errors: Vec<String> = match program_state.legality {
+        Legality::Ignore => Vec::new(),
+        Legality::Warn | Legality::Refuse => deck::validate(&config.deck, &config.rules)
+            .iter()
+            .map(|e| e.to_string())
+            .collect(),
+    };
+    if !legality_errors.is_empty() {
+        if program_state.legality == Legality::Refuse
+            || program_state.first_legality_warning(&config.deck)
+        {
+            vlog!(
+                ELoggingVerbosity::Warning,
+                "Deck with {} isn't legal: {}",
+                description,
+                legality_errors.join("; ")
+            );
+        }
+        if program_state.legality == Legality::Refuse {
+            return SimulationResult {
+                legality_errors,
+                ..SimulationResult::default()
+            };
+        }
+    }
+
+    // Cached results stand in for games nobody is watching or exporting
+    let cache_entry = program_state
+        .result_cache
+        .clone()
+        .filter(|_| {
+            !program_state.bench_mode
+                && program_state.game_export.is_none()
+                && program_state.game_log.is_none()
+                && program_state.record_game.is_none()
+                && program_state.capture.is_none()
+                && matches!(
+                    program_state.step_mode,
+                    StepCommand::RunDeck | StepCommand::RunAll
+                )
+        })
+        .map(|dir| (dir, cache_key(config, program_state)))
+        .filter(|(_, key)| program_state.cache_keys_seen.insert(key.clone()));
+    let cached = cache_entry
+        .as_ref()
+        .and_then(|(dir, key)| cache::load(dir, key));
+    if cached.is_some() {
+        vlog!(
+            ELoggingVerbosity::Verbose,
+            "Using the cached result for {}",
+            description
+        );
+    }
+
+    let started = Instant::now();
+    let mut result = if let Some(cached) = cached {
+        // Use up the seeds its games had, so later scenarios play the games an uncached run would
+        reserve_game_indices(if cached.matches > 0 {
+            3 * cached.matches as u64
+        } else {
+            cached.games as u64
+        });
+        cached
+    } else if program_state.best_of_three && config.opponent.is_some() {
+        let matches = play_matches(config, description, program_state);
+        export_games(
+            program_state,
+            description,
+            matches
+                .iter()
+                .enumerate()
+                .flat_map(|(index, m)| m.games.iter().map(move |game| (Some(index), game))),
+        );
+        SimulationResult::from_matches(&matches)
+    } else {
+        let outcomes = play_games(config, description, program_state);
+        export_games(
+            program_state,
+            description,
+            outcomes.iter().map(|game| (None, game)),
+        );
+        if let Some(rules) = &program_state.capture {
+            rules.capture_batch(config, &outcomes);
+        }
+        SimulationResult::from_outcomes(&outcomes)
+    };
+    let elapsed = started.elapsed();
+    result.mulligan_strategy = config.mulligan.name();
+    result.pilot = config.pilot.strategy(config.mulligan.clone()).name();
+    result.legality_errors = legality_errors;
+
+    if let Some(log) = &config.game_log
+        && let Err(e) = log.flush()
+    {
+        vlog!(
+            ELoggingVerbosity::Warning,
+            "Can't write the game log: {}",
+            e
+        );
+    }
+
+    if let Some((dir, key)) = &cache_entry
+        && program_state.step_mode != StepCommand::Quit
+        && let Err(e) = cache::store(dir, key, &result)
+    {
+        vlog!(
+            ELoggingVerbosity::Warning,
+            "Can't cache the result in {}: {}",
+            dir.display(),
+            e
+        );
+    }
+
+    if program_state.step_mode != StepCommand::Quit {
+        println!(
+            "Average turns to death for deck with {} over {} games: {:.4}",
+            description,
+            result.games,
+            result.mean()
+        );
+        println!(
+            "  95% confidence interval {:.4} +/- {:.4}",
+            result.mean(),
+            result.margin_of_error()
+        );
+        println!(
+            "  p10 {:.1}, median {:.1}, p90 {:.1}, stddev {:.2}, range {}-{}, mana screw {:.1}%, mana flood {:.1}%, color screw {:.1}%",
+            result.percentile(10),
+            result.median_turns,
+            result.percentile(90),
+            result.stddev_turns,
+            result.min_turns,
+            result.max_turns,
+            result.screw_rate() * 100.0,
+            result.flood_rate() * 100.0,
+            result.color_screw_rate() * 100.0
+        );
+        if result.games_on_play > 0 && result.games_on_draw > 0 {
+            println!(
+                "  on the play {:.4} avg turns ({} games), on the draw {:.4} ({} games)",
+                result.mean_turns_on_play,
+                result.games_on_play,
+                result.mean_turns_on_draw,
+                result.games_on_draw
+            );
+        }
+        let endings: Vec<String> = result
+            .ended_by
+            .iter()
+            .map(|(condition, games)| {
+                format!(
+                    "{} {:.1}%",
+                    condition,
+                    *games as f64 * 100.0 / result.games.max(1) as f64
+                )
+            })
+            .collect();
+        if !endings.is_empty() {
+            println!("  games ended by {}", endings.join(", "));
+        }
+        if !result.kill_turns.is_empty() {
+            println!("  kill turns: {}", result.kill_turn_histogram());
+        }
+        if program_state.curve_report && !result.curve.is_empty() {
+            let turns: Vec<String> = result
+                .curve
+                .iter()
+                .enumerate()
+                .map(|(turn, step)| {
+                    format!(
+                        "T{} land {:.1}% spell {:.1}%",
+                        turn + 1,
+                        step.land_drop_rate() * 100.0,
+                        step.on_curve_rate() * 100.0
+                    )
+                })
+                .collect();
+            println!("  curve: {}", turns.join(", "));
+        }
+        if program_state.card_report && !result.card_impact.is_empty() {
+            println!(
+                "  cut candidates (drawn by turn {} vs not, worst first):",
+                EARLY_CARD_TURNS
+            );
+            for impact in result.card_impact.iter() {
+                print!(
+                    "    {}: {:.2} vs {:.2} avg turns ({:+.2}) over {} games drawn",
+                    impact.name,
+                    impact.mean_turns_drawn,
+                    impact.mean_turns_not_drawn,
+                    impact.turn_impact(),
+                    impact.games_drawn
+                );
+                if config.opponent.is_some() {
+                    print!(
+                        ", {:.1}% vs {:.1}% win rate",
+                        impact.win_rate_drawn * 100.0,
+                        impact.win_rate_not_drawn * 100.0
+                    );
+                }
+                println!();
+            }
+        }
+        if let Some(path) = &program_state.histogram_export
+            && let Err(e) = export_kill_turns(path, description, &result)
+        {
+            vlog!(
+                ELoggingVerbosity::Warning,
+                "Can't export kill turns to {}: {}",
+                path.display(),
+                e
+            );
+        }
+        println!(
+            "  {:.2} mulligans per game ({}), piloted by {}",
+            result.mulligans as f64 / result.games.max(1) as f64,
+            result.mulligan_strategy,
+            result.pilot
+        );
+        println!("  {:.1} cards drawn per game", result.mean_cards_drawn());
+        println!(
+            "  {:.2} spells cast per turn, up to {} in one turn",
+            result.mean_spells_per_turn(),
+            result.most_spells_in_turn
+        );
+        if result.matches > 0 {
+            println!(
+                "  match win rate {:.1}% over {} best-of-three matches, game win rate {:.1}%",
+                result.match_win_rate() * 100.0,
+                result.matches,
+                result.win_rate() * 100.0
+            );
+        } else if config.opponent.is_some() {
+            println!(
+                "  win rate {:.1}% against the opponent",
+                result.win_rate() * 100.0
+            );
+        }
+        if program_state.bench_mode {
+            println!(
+                "  {} games in {:.2}s, {:.0} games per second",
+                result.games,
+                elapsed.as_secs_f64(),
+                result.games as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+            );
+        }
+    }
+
+    result
+}
+
+// Everything a scenario's result depends on, as the result cache's key. Decks go in as their
+// serialized cards, every fragment included, so a card redefined under the same name gets a new key.
+// Where the run is in the seed stream isn't part of it, so the cache only stands in for the first
+// time a run simulates a scenario: simulating it again, like a hill climb re-testing its current
+// deck, plays new games for an independent sample and leaves the cached result alone.
+fn cache_key(config: &BatchConfig, program_state: &ProgramState) -> String
+{
+    let deck = |deck: &Deck| serde_json::to_string(deck).unwrap_or_default();
+    format!(
+        "{} {} {:?} {} {} {:?} {:?} {:?} {:?} cards {} games {} epsilon {:?} max {} bo3 {} {:?} vs {:?} seed {}",
+        env!("CARGO_PKG_VERSION"),
+        deck(&config.deck),
+        config.opponent.as_ref().map(deck),
+        config.player_count,
+        config.mulligan.name(),
+        config.pilot,
+        config.play_draw,
+        config.rules,
+        config.thresholds,
+        config.card_report,
+        program_state.games_per_scenario,
+        program_state.epsilon,
+        program_state.max_games,
+        program_state.best_of_three,
+        program_state.deck_name,
+        program_state.opponent_name,
+        base_seed()
+    )
+}
+
+// Append one `scenario,turn,games,share` row per kill turn (and a `none` row for games without
+// a kill), writing the header if the file is new
+fn export_kill_turns(
+    path: &std::path::Path,
+    description: &str,
+    result: &SimulationResult,
+) -> io::Result<()>
+{
+    let is_new = !path.exists();
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    if is_new {
+        writeln!(file, "scenario,turn,games,share")?;
+    }
+
+    for row in result.kill_turn_rows() {
+        writeln!(file, "{},{}", csv_quote(description), row)?;
+    }
+    Ok(())
+}
+
+// Append the games to `program_state.game_export`, if set
+fn export_games<'a>(
+    program_state: &ProgramState,
+    description: &str,
+    games: impl Iterator<Item = (Option<usize>, &'a GameOutcome)>,
+)
+{
+    if let Some(path) = &program_state.game_export
+        && let Err(e) = write_games(path, description, games)
+    {
+        vlog!(
+            ELoggingVerbosity::Warning,
+            "Can't export games to {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+// One row per game, with the index of the match it was part of in best-of-three runs; writes the
+// header if the file is new
+fn write_games<'a>(
+    path: &std::path::Path,
+    description: &str,
+    games: impl Iterator<Item = (Option<usize>, &'a GameOutcome)>,
+) -> io::Result<()>
+{
+    let is_new = !path.exists();
+    let mut file = io::BufWriter::new(
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?,
+    );
+    if is_new {
+        writeln!(
+            file,
+            "scenario,match,seed,turns,kill_turn,on_play,won,lost,mulligans,cards_drawn,lands_seen,mana_screwed,mana_flooded,color_screwed,ended_by"
+        )?;
+    }
+
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    for (index, game) in games {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_quote(description),
+            optional(index.map(|i| i.to_string())),
+            game.seed,
+            game.turns,
+            optional(game.kill_turn.map(|t| t.to_string())),
+            game.on_play,
+            game.won,
+            game.lost,
+            game.mulligans,
+            game.cards_drawn,
+            optional(game.lands_seen.last().map(|l| l.to_string())),
+            game.mana_screwed,
+            game.mana_flooded,
+            game.color_screwed,
+            optional(game.ended_by.map(|c| c.to_string()))
+        )?;
+    }
+    file.flush()
+}
+
+fn csv_quote(field: &str) -> String
+{
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+// Play `program_state.games_per_scenario` games, stepping through them while the user asks to
+fn play_games(
+    config: &BatchConfig,
+    description: &str,
+    program_state: &mut ProgramState,
+) -> Vec<GameOutcome>
+{
+    let games = program_state.games_per_scenario.max(1);
+    let mut outcomes = Vec::with_capacity(games as usize);
+
+    // Play games one by one while the user is stepping through them
+    while (outcomes.len() as u32) < games
+        && !matches!(
+            program_state.step_mode,
+            StepCommand::RunDeck | StepCommand::RunAll | StepCommand::Quit
+        )
     {
-        let (turns, new_mode) = simulate_game(&deck, program_state.step_mode);
-        total_turns += turns;
+        let (outcome, new_mode) = simulate_game(config, program_state.step_mode);
 
         // update ProgramState after simulate_game
         program_state.step_mode = new_mode;
+        if new_mode != StepCommand::Quit {
+            outcomes.push(outcome);
+        }
     }
 
-    let avg_turns_to_death = total_turns as f64 / games as f64;
+    // Once nothing needs to be shown, run the rest of the batch in parallel
+    let played = outcomes.len() as u32;
+    if played == games || program_state.step_mode == StepCommand::Quit {
+        return outcomes;
+    }
+    let progress = Progress::new(
+        description,
+        "games",
+        (games - played) as u64,
+        program_state.progress,
+    );
+    outcomes.extend(run_batch_with_progress(config, games - played, &progress));
+
+    // Then keep adding batches until the mean is known precisely enough
+    loop {
+        let more = more_games(
+            &SimulationResult::from_outcomes(&outcomes),
+            outcomes.len() as u32,
+            program_state,
+        );
+        if more == 0 {
+            break;
+        }
+        progress.extend(more as u64);
+        outcomes.extend(run_batch_with_progress(config, more, &progress));
+    }
+    progress.finish();
+
+    outcomes
+}
+
+// How many more games (or matches) to play before the 95% confidence interval on the mean is
+// narrower than `program_state.epsilon` on either side; none without an epsilon or at the cap
+fn more_games(result: &SimulationResult, played: u32, program_state: &ProgramState) -> u32
+{
+    let Some(epsilon) = program_state.epsilon else {
+        return 0;
+    };
+    if program_state.step_mode == StepCommand::Quit || result.margin_of_error() <= epsilon {
+        return 0;
+    }
+    program_state
+        .games_per_scenario
+        .max(1)
+        .min(program_state.max_games.saturating_sub(played))
+}
+
+// The same for best-of-three matches; the opponent sideboards against `program_state.deck_name`
+// and the deck against `program_state.opponent_name`
+fn play_matches(
+    config: &BatchConfig,
+    description: &str,
+    program_state: &mut ProgramState,
+) -> Vec<MatchOutcome>
+{
+    let postboard = config.sideboarded(&program_state.opponent_name, &program_state.deck_name);
+    let matches = program_state.games_per_scenario.max(1);
+    let mut outcomes = Vec::with_capacity(matches as usize);
 
-    if program_state.step_mode != StepCommand::Quit
+    while (outcomes.len() as u32) < matches
+        && !matches!(
+            program_state.step_mode,
+            StepCommand::RunDeck | StepCommand::RunAll | StepCommand::Quit
+        )
     {
-        println!(
-            "Average turns to death for deck with {} lands and {} nonlands over {} games: {:.4}",
-            lands,
-            nonlands,
-            games,
-            avg_turns_to_death
+        let (outcome, new_mode) = simulate_match(config, &postboard, program_state.step_mode);
+        program_state.step_mode = new_mode;
+        outcomes.extend(outcome);
+    }
+
+    let played = outcomes.len() as u32;
+    if played == matches || program_state.step_mode == StepCommand::Quit {
+        return outcomes;
+    }
+    let progress = Progress::new(
+        description,
+        "matches",
+        (matches - played) as u64,
+        program_state.progress,
+    );
+    outcomes.extend(run_matches_with_progress(
+        config,
+        &postboard,
+        matches - played,
+        &progress,
+    ));
+
+    loop {
+        let more = more_games(
+            &SimulationResult::from_matches(&outcomes),
+            outcomes.len() as u32,
+            program_state,
         );
+        if more == 0 {
+            break;
+        }
+        progress.extend(more as u64);
+        outcomes.extend(run_matches_with_progress(
+            config, &postboard, more, &progress,
+        ));
     }
+    progress.finish();
 
-    avg_turns_to_death
+    outcomes
+}
+
+/// Games of a best-of-three match, seen from the first deck.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchOutcome
+{
+    pub games: Vec<GameOutcome>,
+    pub won: bool,
+    pub lost: bool,
+}
+
+// Play games of a best-of-three with `play(game number, config)` until someone has won two;
+// `None` from `play` abandons the match
+fn play_match(
+    preboard: &BatchConfig,
+    postboard: &BatchConfig,
+    mut play: impl FnMut(usize, &BatchConfig) -> Option<GameOutcome>,
+) -> Option<MatchOutcome>
+{
+    let mut games: Vec<GameOutcome> = Vec::with_capacity(3);
+    for game_number in 0..3 {
+        let game = match games.last() {
+            None => play(game_number, preboard)?,
+            Some(last) => {
+                let play_draw = if last.lost {
+                    PlayDraw::Play
+                } else if last.won {
+                    PlayDraw::Draw
+                } else if last.on_play {
+                    PlayDraw::Play
+                } else {
+                    PlayDraw::Draw
+                };
+                play(
+                    game_number,
+                    &BatchConfig {
+                        play_draw,
+                        ..postboard.clone()
+                    },
+                )?
+            }
+        };
+        games.push(game);
+
+        let wins = games.iter().filter(|g| g.won).count();
+        let losses = games.iter().filter(|g| g.lost && !g.won).count();
+        if wins == 2 || losses == 2 {
+            break;
+        }
+    }
+
+    let wins = games.iter().filter(|g| g.won).count();
+    let losses = games.iter().filter(|g| g.lost && !g.won).count();
+    Some(MatchOutcome {
+        games,
+        won: wins >= 2,
+        lost: losses >= 2,
+    })
+}
+
+/// Play a best-of-three: game 1 with `preboard`, later games with `postboard` (both decks
+/// sideboarded against each other). Game 1 follows the config's play/draw setting; after that the
+/// loser of the previous game goes first. A match nobody has won twice after three games is a draw.
+pub fn run_match(
+    preboard: &BatchConfig,
+    postboard: &BatchConfig,
+    game_indices: [u64; 3],
+) -> MatchOutcome
+{
+    play_match(preboard, postboard, |game_number, config| {
+        Some(run_game(config, game_indices[game_number]))
+    })
+    .expect("games without interaction always finish")
+}
+
+/// Play `n_matches` best-of-three matches across all cores, seeded like `run_batch_parallel`.
+pub fn run_matches_parallel(
+    preboard: &BatchConfig,
+    postboard: &BatchConfig,
+    n_matches: u32,
+) -> Vec<MatchOutcome>
+{
+    run_matches_with_progress(preboard, postboard, n_matches, &Progress::hidden())
+}
+
+fn run_matches_with_progress(
+    preboard: &BatchConfig,
+    postboard: &BatchConfig,
+    n_matches: u32,
+    progress: &Progress,
+) -> Vec<MatchOutcome>
+{
+    let first_index = reserve_game_indices(3 * n_matches as u64);
+    (0..n_matches as u64)
+        .into_par_iter()
+        .map(|m| {
+            let outcome = run_match(
+                preboard,
+                postboard,
+                [0, 1, 2].map(|g| first_index + 3 * m + g),
+            );
+            progress.tick();
+            outcome
+        })
+        .collect()
+}
+
+/// Step through a best-of-three like `simulate_game`; the match is dropped if the user quits.
+pub fn simulate_match(
+    preboard: &BatchConfig,
+    postboard: &BatchConfig,
+    step_mode: StepCommand,
+) -> (Option<MatchOutcome>, StepCommand)
+{
+    let mut mode = step_mode;
+    let outcome = play_match(preboard, postboard, |_, config| {
+        if mode == StepCommand::Quit {
+            return None;
+        }
+        let (game, new_mode) = simulate_game(config, mode);
+        mode = new_mode;
+        (mode != StepCommand::Quit).then_some(game)
+    });
+    (outcome, mode)
+}
+
+/// Play `program_state.games_per_scenario` best-of-three matches between every pair of decks
+/// (game 1 alternating who goes first, sideboarding for games 2 and 3) and collect the match and
+/// game win rates. Decks are named for the printed table and for their sideboard plans.
+pub fn run_gauntlet(decks: &[(String, Deck)], program_state: &ProgramState) -> GauntletResult
+{
+    let matches = program_state.games_per_scenario.max(1);
+    let mut result = GauntletResult {
+        decks: decks.iter().map(|(name, _)| name.clone()).collect(),
+        matches,
+        match_wins: vec![vec![0; decks.len()]; decks.len()],
+        game_wins: vec![vec![0; decks.len()]; decks.len()],
+        games: vec![vec![0; decks.len()]; decks.len()],
+    };
+    for i in 0..decks.len() {
+        for j in (i + 1)..decks.len() {
+            let ((name, deck), (opponent_name, opponent)) = (&decks[i], &decks[j]);
+            let preboard = BatchConfig {
+                opponent: Some(opponent.clone()),
+                play_draw: PlayDraw::Alternate,
+                ..batch_config(deck, program_state)
+            };
+            let postboard = preboard.sideboarded(opponent_name, name);
+            let outcomes = run_matches_parallel(&preboard, &postboard, matches);
+
+            result.match_wins[i][j] = outcomes.iter().filter(|m| m.won).count() as u32;
+            result.match_wins[j][i] = outcomes.iter().filter(|m| m.lost).count() as u32;
+            let games: Vec<&GameOutcome> = outcomes.iter().flat_map(|m| m.games.iter()).collect();
+            result.game_wins[i][j] = games.iter().filter(|g| g.won).count() as u32;
+            result.game_wins[j][i] = games.iter().filter(|g| g.lost && !g.won).count() as u32;
+            result.games[i][j] = games.len() as u32;
+            result.games[j][i] = games.len() as u32;
+            vlog!(
+                ELoggingVerbosity::Verbose,
+                "{} vs {}: {}-{} in matches",
+                name,
+                opponent_name,
+                result.match_wins[i][j],
+                result.match_wins[j][i]
+            );
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn g_with_a_turn_runs_to_the_start_of_that_turn()
+    {
+        assert_eq!(parse_command("g 7"), StepCommand::RunToTurn(7));
+        assert_eq!(parse_command("g"), StepCommand::RunGame);
+        assert_eq!(parse_command("g 0"), StepCommand::Invalid);
+        assert_eq!(parse_command("g seven"), StepCommand::Invalid);
+
+        let mut game =
+            BatchConfig::with_opponent(Deck::example(), Deck::example()).new_game(2, true);
+        run_to_turn(&mut game, 7);
+        assert_eq!((game.turns, game.step), (7, GameStep::Untap));
+    }
+
+    #[test]
+    fn undo_takes_back_steps_and_their_recorded_decisions()
+    {
+        let config = BatchConfig::with_opponent(Deck::example(), Deck::example());
+        let mut game = config.new_game(5, true);
+        let recorder = Recorder::start(&mut game);
+        let mut history = History::default();
+        let snapshot = |game: &GameState| {
+            (
+                game.turns,
+                game.step,
+                game.players
+                    .iter()
+                    .map(|p| p.zones[&Zone::Hand].len())
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let mut before = Vec::new();
+        while game.turns < 4 {
+            before.push((snapshot(&game), recorder.recorded()));
+            history.push(&game, Some(&recorder));
+            game.step();
+        }
+        assert!(recorder.recorded() > 0);
+
+        // Back through every step, newest first
+        while let Some(expected) = before.pop() {
+            assert!(history.undo(&mut game, Some(&recorder)));
+            assert_eq!((snapshot(&game), recorder.recorded()), expected);
+        }
+        assert!(!history.undo(&mut game, Some(&recorder)));
+
+        // Only the most recent steps are kept
+        for _ in 0..UNDO_STEPS + 5 {
+            history.push(&game, None);
+        }
+        assert_eq!(history.states.len(), UNDO_STEPS);
+    }
+
+    #[test]
+    fn parallel_games_match_serial_games_with_the_same_seeds()
+    {
+        let config = BatchConfig::new(Deck::example());
+        let indices: Vec<u64> = (0..64).collect();
+
+        let serial: Vec<GameOutcome> = indices.iter().map(|&i| run_game(&config, i)).collect();
+        let parallel: Vec<GameOutcome> =
+            indices.par_iter().map(|&i| run_game(&config, i)).collect();
+
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn creature_deck_beats_a_deck_of_lands()
+    {
+        let config = BatchConfig::with_opponent(Deck::example(), Deck::goldfish());
+        let outcomes: Vec<GameOutcome> = (0..20).map(|i| run_game(&config, i)).collect();
+
+        assert!(outcomes.iter().all(|o| o.won));
+        assert_eq!(SimulationResult::from_outcomes(&outcomes).win_rate(), 1.0);
+    }
+
+    #[test]
+    fn early_cards_are_kept_only_for_a_card_report()
+    {
+        let mut config = BatchConfig::new(Deck::example());
+        assert!(run_game(&config, 0).early_cards.is_empty());
+
+        config.card_report = true;
+        let outcomes: Vec<GameOutcome> = (0..20).map(|i| run_game(&config, i)).collect();
+        assert!(outcomes.iter().all(|o| !o.early_cards.is_empty()));
+    }
+
+    #[test]
+    fn opponents_play_the_deck_itself_unless_given_one()
+    {
+        let game = BatchConfig::new(Deck::example()).new_game(game_seed(0), true);
+        assert!(
+            game.players[1].zones[&Zone::Library]
+                .iter()
+                .any(|c| c.name == "Grizzly Bears")
+        );
+
+        let mut state = ProgramState {
+            goldfish: true,
+            ..ProgramState::new()
+        };
+        let game = batch_config(&Deck::example(), &state).new_game(game_seed(0), true);
+        assert!(
+            game.players[1].zones[&Zone::Library]
+                .iter()
+                .all(|c| c.name == "Forest")
+        );
+        assert_eq!(
+            game.players[1].zones[&Zone::Hand].len(),
+            7,
+            "the goldfish keeps seven Forests"
+        );
+        state.opponent_deck = Some(Deck::example());
+        assert!(
+            batch_config(&Deck::example(), &state)
+                .opponent
+                .is_some_and(|d| d.cards.iter().any(|c| c.name == "Grizzly Bears"))
+        );
+    }
+
+    #[test]
+    fn play_draw_decides_who_goes_first()
+    {
+        let on_draw = BatchConfig {
+            play_draw: PlayDraw::Draw,
+            ..BatchConfig::new(Deck::example())
+        };
+        assert!((0..10).all(|i| !run_game(&on_draw, i).on_play));
+
+        let alternate = BatchConfig {
+            play_draw: PlayDraw::Alternate,
+            ..BatchConfig::new(Deck::example())
+        };
+        let outcomes: Vec<GameOutcome> = (0..40).map(|i| run_game(&alternate, i)).collect();
+        assert!(
+            outcomes
+                .iter()
+                .enumerate()
+                .all(|(i, o)| o.on_play == (i % 2 == 0))
+        );
+        let result = SimulationResult::from_outcomes(&outcomes);
+        assert_eq!((result.games_on_play, result.games_on_draw), (20, 20));
+    }
+
+    #[test]
+    fn gauntlet_fills_the_win_rate_matrix()
+    {
+        let program_state = ProgramState {
+            games_per_scenario: 10,
+            ..ProgramState::new()
+        };
+        let decks = vec![
+            (String::from("bears"), Deck::example()),
+            (String::from("lands"), Deck::goldfish()),
+            (String::from("bears 2"), Deck::example()),
+        ];
+        let result = run_gauntlet(&decks, &program_state);
+
+        // A sweep takes two games
+        assert_eq!(result.win_rate(0, 1), 1.0);
+        assert_eq!(result.win_rate(1, 0), 0.0);
+        assert_eq!((result.game_wins[0][1], result.games[0][1]), (20, 20));
+        assert_eq!(result.match_wins[0][0], 0);
+        assert!(result.match_wins[0][2] + result.match_wins[2][0] <= 10);
+        assert!((20..=30).contains(&result.games[0][2]));
+        assert!(result.overall_win_rate(1) < result.overall_win_rate(2));
+    }
+
+    #[test]
+    fn illegal_scenarios_can_be_refused()
+    {
+        let mut program_state = ProgramState {
+            legality: Legality::Refuse,
+            ..ProgramState::new()
+        };
+        let result = try_scenario(10, 10, PlayDraw::Play, &mut program_state);
+        assert_eq!(result.games, 0);
+        assert_eq!(
+            result.legality_errors,
+            vec![
+                String::from("deck has 20 cards, needs at least 60"),
+                String::from("10 copies of 'Grizzly Bears', at most 4 allowed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn illegal_decks_are_warned_about_once()
+    {
+        let mut program_state = ProgramState {
+            games_per_scenario: 5,
+            step_mode: StepCommand::RunAll,
+            ..ProgramState::new()
+        };
+        for play_draw in [PlayDraw::Play, PlayDraw::Draw] {
+            let result = try_scenario(24, 36, play_draw, &mut program_state);
+            assert_eq!(result.games, 5);
+            assert_eq!(result.legality_errors.len(), 1);
+        }
+        assert_eq!(program_state.legality_warned.len(), 1);
+    }
+
+    #[test]
+    fn mana_thresholds_decide_screw_and_flood()
+    {
+        let keep = |deck: Deck| BatchConfig {
+            mulligan: Arc::new(crate::mulligan::KeepAll),
+            ..BatchConfig::new(deck)
+        };
+        let lands = keep(Deck::new(vec![crate::card::forest(); 60]));
+        assert!(run_game(&lands, 1).mana_flooded);
+        let strict = BatchConfig {
+            thresholds: ManaThresholds {
+                flood_lands: 30,
+                ..ManaThresholds::default()
+            },
+            ..lands
+        };
+        assert!(!run_game(&strict, 1).mana_flooded);
+
+        let spells = keep(Deck::new(vec![crate::card::grizzly_bears(); 60]));
+        assert!(run_game(&spells, 1).mana_screwed);
+        let lenient = BatchConfig {
+            thresholds: ManaThresholds {
+                screw_turns: 0,
+                ..ManaThresholds::default()
+            },
+            ..spells
+        };
+        assert!(!run_game(&lenient, 1).mana_screwed);
+    }
+
+    #[test]
+    fn curve_counts_land_drops_and_spells_on_curve()
+    {
+        let mut cards = vec![crate::card::forest(); 30];
+        cards.extend(vec![crate::card::grizzly_bears(); 30]);
+        let config = BatchConfig::new(Deck::new(cards));
+        let outcomes: Vec<GameOutcome> = (0..20).map(|i| run_game(&config, i)).collect();
+        let result = SimulationResult::from_outcomes(&outcomes);
+
+        // Only two-drops in the deck
+        assert_eq!(result.curve.len(), CURVE_REPORT_TURNS);
+        assert_eq!(result.curve[0].games, 20);
+        assert_eq!((result.curve[0].on_curve, result.curve[2].on_curve), (0, 0));
+        assert!(result.curve[1].on_curve > 0);
+        assert!(result.curve[1].on_curve <= result.curve[1].games);
+        // Some turns go by without a Bears, others cast two
+        assert!(result.most_spells_in_turn >= 2);
+        assert!(
+            result.mean_spells_per_turn() > 0.0
+                && result.mean_spells_per_turn() < result.most_spells_in_turn as f64
+        );
+    }
+
+    #[test]
+    fn every_game_is_exported_as_a_csv_row()
+    {
+        let path = std::env::temp_dir().join(format!("tcg-sim-games-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut program_state = ProgramState {
+            games_per_scenario: 5,
+            step_mode: StepCommand::RunAll,
+            game_export: Some(path.clone()),
+            ..ProgramState::new()
+        };
+        try_scenario(24, 36, PlayDraw::Play, &mut program_state);
+        try_scenario(23, 37, PlayDraw::Play, &mut program_state);
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert!(lines[0].starts_with("scenario,match,seed,turns"));
+        assert!(lines[1].starts_with("\"24 lands and 36 nonlands\",,"));
+        assert!(lines[10].starts_with("\"23 lands and 37 nonlands\",,"));
+        assert_eq!(lines[1].split(',').count(), lines[0].split(',').count());
+    }
+
+    #[test]
+    fn cached_scenarios_are_not_simulated_again()
+    {
+        let dir = std::env::temp_dir().join(format!("tcg-sim-result-cache-{}", std::process::id()));
+        let mut program_state = ProgramState {
+            games_per_scenario: 5,
+            step_mode: StepCommand::RunAll,
+            result_cache: Some(dir.clone()),
+            ..ProgramState::new()
+        };
+        let first = try_scenario(24, 36, PlayDraw::Play, &mut program_state);
+
+        // Tamper with the only entry to tell a cache hit from a rerun
+        let entry = std::fs::read_dir(&dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let text =
+            std::fs::read_to_string(&entry)
+                .unwrap()
+                .replacen("\"games\":5", "\"games\":1234", 1);
+        std::fs::write(&entry, text).unwrap();
+        let mut rerun = ProgramState {
+            games_per_scenario: 5,
+            step_mode: StepCommand::RunAll,
+            result_cache: Some(dir.clone()),
+            ..ProgramState::new()
+        };
+        let next_game = next_game_index();
+        let second = try_scenario(24, 36, PlayDraw::Play, &mut rerun);
+        assert!(
+            next_game_index() >= next_game + 1234,
+            "a cache hit still uses up its games' seeds"
+        );
+        // Running it again in the same run is a new sample
+        let resampled = try_scenario(24, 36, PlayDraw::Play, &mut rerun);
+        let other = try_scenario(23, 37, PlayDraw::Play, &mut program_state);
+        let entries = std::fs::read_dir(&dir).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first.games, 5);
+        assert_eq!(second.games, 1234);
+        assert_eq!(second.turns, first.turns);
+        assert_eq!(resampled.games, 5);
+        assert_eq!(other.games, 5);
+        assert_eq!(entries, 2);
+
+        // Redefining a card under the same name makes it a different scenario
+        let config = BatchConfig::new(Deck::example());
+        let mut stronger = config.clone();
+        let bears = stronger
+            .deck
+            .cards
+            .iter_mut()
+            .find(|c| c.name == "Grizzly Bears")
+            .unwrap();
+        crate::creature::pump(bears, 1, 1, &[]);
+        assert_ne!(
+            cache_key(&config, &program_state),
+            cache_key(&stronger, &program_state)
+        );
+    }
+
+    #[test]
+    fn sweep_covers_every_land_count()
+    {
+        let mut program_state = ProgramState {
+            games_per_scenario: 5,
+            step_mode: StepCommand::RunAll,
+            ..ProgramState::new()
+        };
+        let sweep = run_sweep(22..=25, 24, &mut program_state);
+        let lands: Vec<u32> = sweep.rows.iter().map(|(lands, _)| *lands).collect();
+        assert_eq!(lands, vec![22, 23, 24]);
+        assert!(sweep.rows.iter().all(|(_, result)| result.games == 5));
+    }
+
+    #[test]
+    fn games_continue_until_the_mean_is_precise()
+    {
+        let mut program_state = ProgramState {
+            games_per_scenario: 20,
+            step_mode: StepCommand::RunAll,
+            ..ProgramState::new()
+        };
+        assert_eq!(
+            try_scenario(8, 52, PlayDraw::Play, &mut program_state).games,
+            20
+        );
+
+        // An unreachable precision stops at the cap
+        program_state.epsilon = Some(1e-9);
+        program_state.max_games = 50;
+        assert_eq!(
+            try_scenario(8, 52, PlayDraw::Play, &mut program_state).games,
+            50
+        );
+
+        program_state.epsilon = Some(100.0);
+        let result = try_scenario(8, 52, PlayDraw::Play, &mut program_state);
+        assert_eq!(result.games, 20);
+        assert!(result.margin_of_error() <= 100.0);
+    }
 }
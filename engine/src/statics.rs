@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use crate::attachment::AttachmentBonus;
+use crate::card::{ArtifactFragment, Card, CardType, EnchantmentFragment};
+use crate::game::{GameState, Zone};
+use crate::mana::ManaCost;
+
+/// A continuous effect of an enchantment or artifact, in force while it's on the battlefield.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StaticEffect
+{
+    // Creatures its controller controls get the bonus, e.g. Glorious Anthem's
+    // { "Anthem": { "power": 1, "toughness": 1 } }
+    Anthem(AttachmentBonus),
+    // Spells cost this much more generic mana, every player's alike, e.g. Sphere of Resistance's
+    // { "Tax": { "amount": 1 } }
+    Tax
+    {
+        amount: u32,
+        #[serde(default)]
+        noncreature_only: bool,
+    },
+    // Each noncreature artifact is an artifact creature with power and toughness each equal to
+    // its mana value, as with March of the Machines; see layers.rs
+    AnimateArtifacts,
+}
+
+/// The static effects of a permanent, if it's an enchantment or artifact with any.
+pub fn statics(card: &Card) -> &[StaticEffect]
+{
+    card.fragment::<EnchantmentFragment>()
+        .map(|f| f.statics.as_slice())
+        .or_else(|| {
+            card.fragment::<ArtifactFragment>()
+                .map(|f| f.statics.as_slice())
+        })
+        .unwrap_or_default()
+}
+
+/// When `card` entered the battlefield, if it's an enchantment or artifact with static effects.
+pub fn timestamp(card: &Card) -> u64
+{
+    card.fragment::<EnchantmentFragment>()
+        .map(|f| f.timestamp)
+        .or_else(|| card.fragment::<ArtifactFragment>().map(|f| f.timestamp))
+        .unwrap_or_default()
+}
+
+/// Stamp an enchantment or artifact with the `timestamp` it enters the battlefield at.
+pub fn enter(card: &mut Card, timestamp: u64)
+{
+    if let Some(f) = card.fragment_mut::<EnchantmentFragment>() {
+        f.timestamp = timestamp;
+    } else if let Some(f) = card.fragment_mut::<ArtifactFragment>() {
+        f.timestamp = timestamp;
+    }
+}
+
+/// Every static effect on the battlefield with the player controlling its source.
+pub fn in_force(game: &GameState) -> Vec<(usize, &StaticEffect)>
+{
+    game.players
+        .iter()
+        .enumerate()
+        .flat_map(|(player, p)| {
+            p.zones
+                .get(&Zone::Battlefield)
+                .into_iter()
+                .flatten()
+                .flat_map(move |card| statics(card).iter().map(move |s| (player, s)))
+        })
+        .collect()
+}
+
+/// What every creature `player` controls gets from the anthems in `effects`.
+pub fn creature_bonus(effects: &[(usize, &StaticEffect)], player: usize) -> AttachmentBonus
+{
+    let mut total = AttachmentBonus::default();
+    for (_, effect) in effects
+        .iter()
+        .filter(|(controller, _)| *controller == player)
+    {
+        if let StaticEffect::Anthem(bonus) = effect {
+            total.power += bonus.power;
+            total.toughness += bonus.toughness;
+            total.keywords.extend(bonus.keywords.iter().copied());
+        }
+    }
+    total
+}
+
+/// `cost` for casting `card` with the taxes in `effects` added as generic mana.
+pub fn taxed(effects: &[(usize, &StaticEffect)], card: &Card, cost: ManaCost) -> ManaCost
+{
+    let mut taxed = cost;
+    taxed.generic += effects
+        .iter()
+        .map(|(_, effect)| match effect {
+            StaticEffect::Tax {
+                amount,
+                noncreature_only,
+            } if !(*noncreature_only && card.is_type(CardType::Creature)) => *amount,
+            _ => 0,
+        })
+        .sum::<u32>();
+    taxed
+}
@@ -0,0 +1,395 @@
+/// What a batch of simulated games says about one score: its mean, the sample variance of the
+/// per-game values it averages, and how many games it's based on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sample
+{
+    pub mean: f64,
+    pub variance: f64,
+    pub count: u32,
+}
+
+impl Sample
+{
+    /// Share of `count` games where something happened, as the mean of 0/1 values.
+    pub fn proportion(hits: u32, count: u32) -> Self
+    {
+        let p = hits as f64 / count.max(1) as f64;
+        let variance = if count > 1 {
+            p * (1.0 - p) * count as f64 / (count - 1) as f64
+        } else {
+            0.0
+        };
+        Sample {
+            mean: p,
+            variance,
+            count,
+        }
+    }
+
+    // Squared standard error of the mean
+    fn standard_error_squared(&self) -> f64
+    {
+        self.variance / self.count.max(1) as f64
+    }
+}
+
+/// One-sided Welch's t-test: the probability of `a`'s mean coming out at least this far below
+/// `b`'s if both were really equal. Small values mean `a` is significantly lower.
+pub fn welch_p_value(a: &Sample, b: &Sample) -> f64
+{
+    // Infinite scores (no games played) lose to anything
+    if a.mean.is_infinite() || b.mean.is_infinite() {
+        return if a.mean < b.mean { 0.0 } else { 1.0 };
+    }
+
+    let (sa, sb) = (a.standard_error_squared(), b.standard_error_squared());
+    let se2 = sa + sb;
+    if se2 == 0.0 {
+        return if a.mean < b.mean { 0.0 } else { 1.0 };
+    }
+
+    // Welch–Satterthwaite degrees of freedom
+    let dof = |s: f64, count: u32| s * s / count.saturating_sub(1).max(1) as f64;
+    let df = se2 * se2 / (dof(sa, a.count) + dof(sb, b.count));
+    student_t_cdf((a.mean - b.mean) / se2.sqrt(), df)
+}
+
+/// Cumulative distribution function of Student's t distribution with `df` degrees of freedom.
+pub fn student_t_cdf(t: f64, df: f64) -> f64
+{
+    let tail = 0.5 * incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+    if t < 0.0 { tail } else { 1.0 - tail }
+}
+
+/// Probability of exactly `k` hits among `draws` cards drawn from `population` cards of which
+/// `successes` are hits (the hypergeometric distribution).
+pub fn hypergeometric(population: u32, successes: u32, draws: u32, k: u32) -> f64
+{
+    if successes > population
+        || draws > population
+        || k > successes
+        || k > draws
+        || draws - k > population - successes
+    {
+        return 0.0;
+    }
+    (ln_choose(successes, k) + ln_choose(population - successes, draws - k)
+        - ln_choose(population, draws))
+    .exp()
+}
+
+/// Probability of at least `k` hits; see `hypergeometric`.
+pub fn hypergeometric_at_least(population: u32, successes: u32, draws: u32, k: u32) -> f64
+{
+    (k..=draws)
+        .map(|hits| hypergeometric(population, successes, draws, hits))
+        .sum::<f64>()
+        .min(1.0)
+}
+
+/// Cumulative distribution function of the standard normal distribution.
+pub fn normal_cdf(x: f64) -> f64
+{
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Density of the standard normal distribution.
+pub fn normal_pdf(x: f64) -> f64
+{
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Expected amount by which a normally distributed score with this mean and standard deviation
+/// comes out below `best`: how promising an untested point is when minimizing.
+pub fn expected_improvement(best: f64, mean: f64, sd: f64) -> f64
+{
+    if sd <= 0.0 {
+        return (best - mean).max(0.0);
+    }
+    let z = (best - mean) / sd;
+    (best - mean) * normal_cdf(z) + sd * normal_pdf(z)
+}
+
+/// Gaussian-process regression with a squared-exponential kernel and a known noise variance per
+/// observation: a smooth guess of a noisy function between the points it was measured at, with
+/// how unsure the guess is.
+pub struct GaussianProcess
+{
+    points: Vec<Vec<f64>>,
+    length_scale: f64,
+    signal_variance: f64,
+    prior_mean: f64,
+    // Cholesky factor of the kernel matrix plus noise, and that matrix's inverse times the
+    // centered values
+    factor: Vec<Vec<f64>>,
+    weights: Vec<f64>,
+}
+
+impl GaussianProcess
+{
+    /// Fit to `values` measured at `points` with the given noise variances. The prior mean and
+    /// signal variance come from the values themselves. `None` without points.
+    pub fn fit(
+        points: &[Vec<f64>],
+        values: &[f64],
+        noise: &[f64],
+        length_scale: f64,
+    ) -> Option<Self>
+    {
+        if points.is_empty() {
+            return None;
+        }
+        let n = values.len() as f64;
+        let prior_mean = values.iter().sum::<f64>() / n;
+        let signal_variance =
+            (values.iter().map(|v| (v - prior_mean).powi(2)).sum::<f64>() / n).max(1e-6);
+
+        let mut gp = GaussianProcess {
+            points: points.to_vec(),
+            length_scale,
+            signal_variance,
+            prior_mean,
+            factor: Vec::new(),
+            weights: Vec::new(),
+        };
+        let matrix: Vec<Vec<f64>> = points
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                points
+                    .iter()
+                    .enumerate()
+                    .map(|(j, b)| {
+                        gp.kernel(a, b)
+                            + if i == j {
+                                noise[i] + 1e-9 * signal_variance
+                            } else {
+                                0.0
+                            }
+                    })
+                    .collect()
+            })
+            .collect();
+        gp.factor = cholesky(&matrix)?;
+        let centered: Vec<f64> = values.iter().map(|v| v - prior_mean).collect();
+        gp.weights = back_substitute(&gp.factor, &forward_substitute(&gp.factor, &centered));
+        Some(gp)
+    }
+
+    /// Posterior mean and variance at `x`.
+    pub fn predict(&self, x: &[f64]) -> (f64, f64)
+    {
+        let k: Vec<f64> = self.points.iter().map(|p| self.kernel(p, x)).collect();
+        let mean = self.prior_mean
+            + k.iter()
+                .zip(self.weights.iter())
+                .map(|(a, b)| a * b)
+                .sum::<f64>();
+        let v = forward_substitute(&self.factor, &k);
+        let variance = (self.signal_variance - v.iter().map(|x| x * x).sum::<f64>()).max(0.0);
+        (mean, variance)
+    }
+
+    fn kernel(&self, a: &[f64], b: &[f64]) -> f64
+    {
+        let distance: f64 = a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum();
+        self.signal_variance * (-distance / (2.0 * self.length_scale.powi(2))).exp()
+    }
+}
+
+// Lower-triangular L with L Lᵀ = `matrix`; `None` if it isn't positive definite
+fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>>
+{
+    let n = matrix.len();
+    let mut l = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| l[i][k] * l[j][k]).sum();
+            if i == j {
+                let diagonal = matrix[i][i] - sum;
+                if diagonal <= 0.0 {
+                    return None;
+                }
+                l[i][i] = diagonal.sqrt();
+            } else {
+                l[i][j] = (matrix[i][j] - sum) / l[j][j];
+            }
+        }
+    }
+    Some(l)
+}
+
+// Solve L y = b
+fn forward_substitute(l: &[Vec<f64>], b: &[f64]) -> Vec<f64>
+{
+    let mut y = vec![0.0; b.len()];
+    for i in 0..b.len() {
+        y[i] = (b[i] - (0..i).map(|k| l[i][k] * y[k]).sum::<f64>()) / l[i][i];
+    }
+    y
+}
+
+// Solve Lᵀ x = y
+fn back_substitute(l: &[Vec<f64>], y: &[f64]) -> Vec<f64>
+{
+    let mut x = vec![0.0; y.len()];
+    for i in (0..y.len()).rev() {
+        x[i] = (y[i] - (i + 1..y.len()).map(|k| l[k][i] * x[k]).sum::<f64>()) / l[i][i];
+    }
+    x
+}
+
+// Abramowitz and Stegun 7.1.26; accurate to about 1.5e-7
+fn erf(x: f64) -> f64
+{
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - polynomial * (-x * x).exp();
+    if x < 0.0 { -y } else { y }
+}
+
+fn ln_choose(n: u32, k: u32) -> f64
+{
+    ln_gamma(n as f64 + 1.0) - ln_gamma(k as f64 + 1.0) - ln_gamma((n - k) as f64 + 1.0)
+}
+
+// Regularized incomplete beta function I_x(a, b)
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64
+{
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly on this side; use the symmetry otherwise
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// Lentz's method for the continued fraction of the incomplete beta function
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64
+{
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    d = 1.0 / if d.abs() < TINY { TINY } else { d };
+    let mut h = d;
+
+    for m in 1..200 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        for coefficient in [even, odd] {
+            d = 1.0 + coefficient * d;
+            d = 1.0 / if d.abs() < TINY { TINY } else { d };
+            c = 1.0 + coefficient / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+// Lanczos approximation of ln Γ(x) for x > 0
+fn ln_gamma(x: f64) -> f64
+{
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000000000190015, |sum, (i, c)| {
+            sum + c / (x + 1.0 + i as f64)
+        });
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn t_distribution_matches_tables()
+    {
+        // One degree of freedom is the Cauchy distribution
+        assert!((student_t_cdf(1.0, 1.0) - 0.75).abs() < 1e-9);
+        assert!((student_t_cdf(2.228, 10.0) - 0.975).abs() < 1e-3);
+        assert!((student_t_cdf(-1.96, 1e6) - 0.025).abs() < 1e-3);
+        assert_eq!(student_t_cdf(0.0, 5.0), 0.5);
+    }
+
+    #[test]
+    fn gaussian_process_interpolates_and_knows_its_uncertainty()
+    {
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert_eq!(expected_improvement(1.0, 2.0, 0.0), 0.0);
+
+        let points = vec![vec![0.0], vec![2.0], vec![4.0]];
+        let gp = GaussianProcess::fit(&points, &[4.0, 0.0, 4.0], &[0.0; 3], 1.5).unwrap();
+        let (mean, variance) = gp.predict(&[2.0]);
+        assert!(mean.abs() < 1e-3 && variance < 1e-3);
+        let (between, _) = gp.predict(&[1.0]);
+        assert!(between > 0.0 && between < 4.0);
+        assert!(gp.predict(&[10.0]).1 > gp.predict(&[3.0]).1);
+    }
+
+    #[test]
+    fn hypergeometric_matches_closed_form()
+    {
+        // At least 3 lands in an opening seven with 24 lands in 60 cards
+        assert!((hypergeometric_at_least(60, 24, 7, 3) - 0.5879295).abs() < 1e-6);
+        assert!((hypergeometric(60, 24, 7, 0) - 0.0216145).abs() < 1e-6);
+        assert!(((0..=7).map(|k| hypergeometric(60, 24, 7, k)).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(hypergeometric(60, 4, 7, 5), 0.0);
+        assert_eq!(hypergeometric_at_least(40, 40, 7, 7), 1.0);
+    }
+
+    #[test]
+    fn welch_test_needs_a_real_difference()
+    {
+        let fast = Sample {
+            mean: 9.0,
+            variance: 4.0,
+            count: 1000,
+        };
+        let slow = Sample {
+            mean: 9.5,
+            variance: 4.0,
+            count: 1000,
+        };
+        assert!(welch_p_value(&fast, &slow) < 1e-6);
+        assert!(welch_p_value(&slow, &fast) > 0.99);
+
+        // The same gap over a handful of games could easily be noise
+        let few = |mean| Sample {
+            mean,
+            variance: 4.0,
+            count: 10,
+        };
+        assert!(welch_p_value(&few(9.0), &few(9.5)) > 0.05);
+
+        assert_eq!(Sample::proportion(1, 4).variance, 0.25);
+    }
+}
@@ -0,0 +1,802 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::card::{Card, CardType};
+use crate::combat;
+use crate::effect::{self, Effect, ModalEffect};
+use crate::game::{GameState, GameStep, Zone};
+use crate::heuristic::{Heuristic, HeuristicWeights};
+use crate::keyword;
+use crate::mana::{self, ManaColor};
+use crate::mcts::{Mcts, MctsBudget};
+use crate::mulligan::{KeepAll, MulliganStrategy};
+
+/// A spell to cast: where it is, and the (battlefield index, color) pairs paying for it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cast
+{
+    pub zone: Zone,
+    pub index: usize,
+    // 0 for the front; see faces.rs
+    #[serde(default)]
+    pub face: usize,
+    pub plan: Vec<(usize, ManaColor)>,
+    // Paying the spell's alternative cost instead of its mana cost, with `plan` for its mana
+    #[serde(default)]
+    pub alternative: bool,
+}
+
+/// Every choice a player makes during a game. The engine asks the strategy of the player whose
+/// decision it is and only checks the answer against the rules; `rng` is the game's own, so
+/// random strategies stay reproducible from the game seed.
+pub trait PlayerStrategy: Send + Sync
+{
+    /// Short name recorded in simulation results, e.g. "curve-out".
+    fn name(&self) -> String;
+
+    /// How opening hands are kept and bottomed.
+    fn mulligan(&self) -> &dyn MulliganStrategy;
+
+    /// Land to play from hand this turn, if any.
+    fn land_drop(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<usize>;
+
+    /// Next spell to cast in the main phase; `None` ends the main phase.
+    fn main_phase_cast(
+        &self,
+        game: &GameState,
+        player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Option<Cast>;
+
+    /// Instant to cast with priority; `None` passes.
+    fn respond(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<Cast>;
+
+    /// Battlefield indices of the creatures that attack `defending_player`.
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>;
+
+    /// (blocker, attacker) pairs against the attack declared in `game.combat`.
+    fn blockers(
+        &self,
+        game: &GameState,
+        player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<(usize, usize)>;
+
+    /// Hand indices of `count` cards to discard at cleanup.
+    fn discards(&self, hand: &[Card], count: usize, _rng: &mut ChaCha8Rng) -> Vec<usize>
+    {
+        crate::mulligan::least_useful(hand, count)
+    }
+
+    /// Which of the cards on top of the library (`cards`, top first) a scry or surveil keeps
+    /// there, as indices into `cards` in the order they end up, top first. The rest go to the
+    /// bottom or the graveyard.
+    fn keep_on_top(
+        &self,
+        game: &GameState,
+        player: usize,
+        cards: &[Card],
+        _rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        let mut kept: Vec<(usize, u32)> = cards
+            .iter()
+            .enumerate()
+            .filter_map(|(i, card)| draw_value(game, player, card).map(|value| (i, value)))
+            .collect();
+        kept.sort_by_key(|(_, value)| std::cmp::Reverse(*value));
+        kept.into_iter().map(|(i, _)| i).collect()
+    }
+
+    /// Index of the mode a "choose one" effect resolves with: by default the first that has
+    /// something to act on.
+    fn choose_mode(
+        &self,
+        game: &GameState,
+        player: usize,
+        modal: &ModalEffect,
+        _rng: &mut ChaCha8Rng,
+    ) -> usize
+    {
+        (0..modal.modes.len())
+            .find(|&mode| modal.mode_has_target(game, player, mode))
+            .unwrap_or(0)
+    }
+
+    /// Whether to pay the kicker of `card`, a spell `player` is casting and has the mana left to
+    /// kick: by default always.
+    fn kick(&self, _game: &GameState, _player: usize, _card: &Card, _rng: &mut ChaCha8Rng) -> bool
+    {
+        true
+    }
+
+    /// X for a spell with X in its cost, up to the `max_x` that `player` can pay: by default just
+    /// enough to finish the opponent off when X is damage and that's in reach, otherwise all of it.
+    fn choose_x(
+        &self,
+        game: &GameState,
+        player: usize,
+        card: &Card,
+        max_x: u32,
+        _rng: &mut ChaCha8Rng,
+    ) -> u32
+    {
+        let burn = is_x_burn(card);
+        match effect::opponent(game, player)
+            .map(|opponent| game.players[opponent].life.max(0) as u32)
+        {
+            Some(life) if burn && life <= max_x => life,
+            _ => max_x,
+        }
+    }
+
+    /// Which loyalty ability to activate of the planeswalker at `planeswalker` on `player`'s
+    /// battlefield, if any: by default the ultimate (the last and priciest of three or more) as
+    /// soon as it can be paid, otherwise the one adding the most loyalty.
+    fn loyalty_ability(
+        &self,
+        game: &GameState,
+        player: usize,
+        planeswalker: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        let card = &game.players[player].zones.get(&Zone::Battlefield)?[planeswalker];
+        let usable = crate::planeswalker::usable_abilities(game, player, card);
+        let abilities = crate::planeswalker::abilities(card);
+        let ultimate = (abilities.len() > 2)
+            .then(|| (0..abilities.len()).min_by_key(|&i| abilities[i].loyalty))
+            .flatten()
+            .filter(|&i| abilities[i].loyalty < 0 && usable.contains(&i));
+        ultimate.or_else(|| usable.iter().copied().max_by_key(|&i| abilities[i].loyalty))
+    }
+
+    /// (attacker, planeswalker) pairs of `attackers` that go after one of `defending_player`'s
+    /// planeswalkers instead of the player; see `combat::choose_planeswalker_attacks`.
+    fn attack_planeswalkers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        attackers: &[usize],
+        _rng: &mut ChaCha8Rng,
+    ) -> Vec<(usize, usize)>
+    {
+        let defender = &game.players[defending_player];
+        combat::choose_planeswalker_attacks(
+            game.players[player].zones.get(&Zone::Battlefield).unwrap(),
+            attackers,
+            defender.zones.get(&Zone::Battlefield).unwrap(),
+            defender.life,
+        )
+    }
+
+    /// Battlefield indices of the `count` creatures `player` sacrifices, for a cost or an effect:
+    /// by default the least valuable.
+    fn sacrifice(
+        &self,
+        game: &GameState,
+        player: usize,
+        count: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        sacrifice_fodder(
+            game.players[player]
+                .zones
+                .get(&Zone::Battlefield)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            count,
+        )
+    }
+
+    /// Whether `player` sacrifices the Clue, Food or Blood at `token` on their battlefield for
+    /// its ability, with the mana to pay for it: by default to draw whenever they can, and for
+    /// life once they're at 10 or less.
+    fn activate_sacrifice(
+        &self,
+        game: &GameState,
+        player: usize,
+        token: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> bool
+    {
+        let Some(ability) = game.players[player]
+            .zones
+            .get(&Zone::Battlefield)
+            .and_then(|b| b.get(token))
+            .and_then(crate::token::sacrifice_ability)
+        else {
+            return false;
+        };
+        ability.effects.iter().any(|effect| match effect {
+            Effect::DrawCards(_) => true,
+            Effect::GainLife(_) => game.players[player].life <= 10,
+            _ => false,
+        })
+    }
+
+    /// Exile index of the land `player` plays this turn out of those exiled cards they may play,
+    /// or None to play from hand instead.
+    fn exiled_land_drop(
+        &self,
+        game: &GameState,
+        player: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        game.playable_lands_in_exile(player).first().copied()
+    }
+
+    /// Whether `player` casts the spell `card` that cascade found for free.
+    fn cascade_into(
+        &self,
+        _game: &GameState,
+        _player: usize,
+        _card: &Card,
+        _rng: &mut ChaCha8Rng,
+    ) -> bool
+    {
+        true
+    }
+
+    /// Library index of the card a tutor takes, out of the `candidates` it can find.
+    fn tutor(
+        &self,
+        game: &GameState,
+        player: usize,
+        candidates: &[usize],
+        _rng: &mut ChaCha8Rng,
+    ) -> Option<usize>
+    {
+        let library = game.players[player].zones.get(&Zone::Library)?;
+        candidates.iter().copied().max_by_key(|&i| {
+            (
+                draw_value(game, player, &library[i]),
+                library[i].cost.mana_value(),
+            )
+        })
+    }
+}
+
+// A player short of lands wants them before anything else while they have fewer than this
+const LANDS_WANTED: u32 = 4;
+
+/// How much `player` wants to draw `card` next, or `None` if it's better off elsewhere. Lands come
+/// first while they have fewer in play and in hand than their priciest spell costs (and
+/// `LANDS_WANTED`), then spells their mana sources can soon pay for, pricier first.
+pub fn draw_value(game: &GameState, player: usize, card: &Card) -> Option<u32>
+{
+    let zones = &game.players[player].zones;
+    let hand = zones
+        .get(&Zone::Hand)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let battlefield = zones
+        .get(&Zone::Battlefield)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let lands = battlefield
+        .iter()
+        .chain(hand)
+        .filter(|c| c.is_type(CardType::Land))
+        .count() as u32;
+    if card.is_type(CardType::Land) {
+        let priciest = hand
+            .iter()
+            .filter(|c| !c.is_type(CardType::Land))
+            .map(|c| c.cost.mana_value())
+            .max()
+            .unwrap_or(0);
+        return (lands < priciest.max(LANDS_WANTED)).then_some(u32::MAX);
+    }
+    let sources = battlefield
+        .iter()
+        .filter(|c| mana::is_mana_source(c))
+        .count() as u32;
+    let mana_value = card.cost.mana_value();
+    (mana_value <= sources + 2).then_some(1 + mana_value)
+}
+
+/// The `count` creatures of `battlefield` least worth keeping: tokens first, then the cheapest,
+/// then the weakest.
+pub fn sacrifice_fodder(battlefield: &[Card], count: usize) -> Vec<usize>
+{
+    let mut creatures: Vec<usize> = (0..battlefield.len())
+        .filter(|&i| crate::creature::is_creature(&battlefield[i]))
+        .collect();
+    creatures.sort_by_key(|&i| {
+        let card = &battlefield[i];
+        (
+            !crate::token::is_token(card),
+            card.cost.mana_value(),
+            crate::creature::effective_stats(card).map_or(0, |s| s.power),
+        )
+    });
+    creatures.truncate(count);
+    creatures
+}
+
+impl fmt::Debug for dyn PlayerStrategy
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Mana rocks, mana creatures and spells that fetch lands
+fn is_ramp(card: &Card) -> bool
+{
+    (mana::is_mana_source(card) && !card.is_type(CardType::Land))
+        || effect::spell_effects(card)
+            .iter()
+            .any(|e| matches!(e, Effect::FetchBasicLand { .. }))
+}
+
+// Spells dealing X damage, like Fireball
+fn is_x_burn(card: &Card) -> bool
+{
+    effect::spell_effects(card).iter().any(|e| match e {
+        Effect::WithX(inner) => {
+            matches!(**inner, Effect::DealDamage(_) | Effect::DamageOpponents(_))
+        }
+        _ => false,
+    })
+}
+
+// Mana `player` keeps open for the cheapest counterspell in their hand, if the opponent has
+// cards left to counter
+fn held_up_mana(game: &GameState, player: usize) -> u32
+{
+    let opponent_has_cards = effect::opponent(game, player)
+        .is_some_and(|o| !game.players[o].zones[&Zone::Hand].is_empty());
+    if !opponent_has_cards {
+        return 0;
+    }
+    game.players[player].zones[&Zone::Hand]
+        .iter()
+        .filter(|card| effect::spell_effects(card).contains(&Effect::CounterSpell))
+        .map(|card| game.taxed(card, card.cost).mana_value())
+        .min()
+        .unwrap_or(0)
+}
+
+/// Goldfishes towards the fastest kill: ramp first, then as much on curve as the mana allows,
+/// holding instants for the moment they do the most. Combat follows `combat::choose_attackers`
+/// and `combat::choose_blockers`.
+#[derive(Clone, Debug)]
+pub struct CurveOut
+{
+    pub mulligan: Arc<dyn MulliganStrategy>,
+}
+
+impl Default for CurveOut
+{
+    fn default() -> Self
+    {
+        CurveOut {
+            mulligan: Arc::new(KeepAll),
+        }
+    }
+}
+
+impl PlayerStrategy for CurveOut
+{
+    fn name(&self) -> String
+    {
+        String::from("curve-out")
+    }
+
+    fn mulligan(&self) -> &dyn MulliganStrategy
+    {
+        self.mulligan.as_ref()
+    }
+
+    // One that enters untapped when the extra mana lets something be cast this turn, otherwise
+    // get a tapped land out of the way. A spell with a land face is only played as the land
+    // when there's no other, while short of `LANDS_WANTED`.
+    fn land_drop(&self, game: &GameState, player: usize, _rng: &mut ChaCha8Rng) -> Option<usize>
+    {
+        let hand = game.players[player].zones.get(&Zone::Hand).unwrap();
+        let battlefield = game.players[player].zones.get(&Zone::Battlefield).unwrap();
+        let mut lands: Vec<(usize, bool)> = hand
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_type(CardType::Land))
+            .map(|(i, c)| (i, crate::tappable::enters_tapped(c, battlefield)))
+            .collect();
+        let lands_in_play = battlefield
+            .iter()
+            .filter(|c| c.is_type(CardType::Land))
+            .count() as u32;
+        if lands.is_empty() && lands_in_play < LANDS_WANTED {
+            lands = hand
+                .iter()
+                .enumerate()
+                .filter_map(|(i, c)| {
+                    crate::faces::land_face(c).map(|face| {
+                        (
+                            i,
+                            crate::tappable::enters_tapped(
+                                &crate::faces::faces(c)[face],
+                                battlefield,
+                            ),
+                        )
+                    })
+                })
+                .collect();
+        }
+
+        let available = game.available_mana(player);
+        let wants_mana = hand
+            .iter()
+            .any(|c| !c.is_type(CardType::Land) && c.cost.mana_value() == available + 1);
+        lands
+            .iter()
+            .find(|(_, tapped)| *tapped != wants_mana)
+            .or(lands.first())
+            .map(|(i, _)| *i)
+    }
+
+    // A commander in the command zone comes first, tax included. Then permanents and sorceries
+    // from hand, ramp before anything else since it pays off over every later turn; instants and
+    // flash spells wait unless they're rituals that make something else affordable. While the
+    // opponent has cards to cast, nothing taps the mana a counterspell in hand needs.
+    fn main_phase_cast(
+        &self,
+        game: &GameState,
+        player: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> Option<Cast>
+    {
+        let held = held_up_mana(game, player);
+        let mut options: Vec<Cast> = game
+            .castable_spells(player)
+            .into_iter()
+            .filter(|cast| {
+                cast.zone == Zone::Command
+                    || !keyword::instant_speed(game.spell(player, cast))
+                    || game.ritual_enables(player, game.spell(player, cast))
+            })
+            .filter(|cast| {
+                held == 0
+                    || game
+                        .taxed(game.spell(player, cast), game.spell(player, cast).cost)
+                        .mana_value()
+                        + held
+                        <= game.available_mana(player)
+            })
+            .collect();
+        options.sort_by_key(|cast| {
+            (
+                cast.zone != Zone::Command,
+                !(cast.zone != Zone::Command && is_ramp(game.spell(player, cast))),
+            )
+        });
+        options.into_iter().next()
+    }
+
+    // Counters go on opposing spells, copies on whatever instant or sorcery is on the stack, pump
+    // spells on attackers once blocks are known, and the rest wait for an opponent's end step, so
+    // their mana is spent only if unused, unless casting them now pumps a prowess attacker
+    fn respond(&self, game: &GameState, player: usize, _rng: &mut ChaCha8Rng) -> Option<Cast>
+    {
+        let opposing_spell = game.stack.last().is_some_and(|top| *top != player);
+        let attacking = game.stack.is_empty()
+            && game.step == GameStep::DeclareBlockers
+            && player == game.current_player_index;
+        let opponents_end_step = game.stack.is_empty()
+            && game.step == GameStep::EndTurn
+            && player != game.current_player_index;
+        let battlefield = game.players[player].zones.get(&Zone::Battlefield).unwrap();
+        let prowess_attacking = attacking
+            && game.combat.attackers.iter().any(|&i| {
+                battlefield
+                    .get(i)
+                    .is_some_and(|card| keyword::any(card, |h| h.pumped_by_noncreature_spells()))
+            });
+
+        let good_moment = |card: &Card| {
+            let effects = effect::spell_effects(card);
+            if effects.contains(&Effect::CounterSpell) {
+                opposing_spell
+            } else if effects.contains(&Effect::CopySpell) {
+                game.spell_to_copy().is_some()
+            } else if effects
+                .iter()
+                .any(|e| matches!(e, Effect::PumpCreature { .. }))
+            {
+                attacking
+            } else if effects.contains(&Effect::Regenerate) {
+                // Only castable while something of the player's is about to be destroyed
+                true
+            } else {
+                opponents_end_step || prowess_attacking
+            }
+        };
+
+        game.castable_instants(player)
+            .into_iter()
+            .find(|cast| good_moment(game.spell(player, cast)))
+    }
+
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        defending_player: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        let defender = &game.players[defending_player];
+        combat::choose_attackers(
+            game.players[player].zones.get(&Zone::Battlefield).unwrap(),
+            defender.zones.get(&Zone::Battlefield).unwrap(),
+            defender.life,
+        )
+    }
+
+    fn blockers(
+        &self,
+        game: &GameState,
+        player: usize,
+        _rng: &mut ChaCha8Rng,
+    ) -> Vec<(usize, usize)>
+    {
+        let defender = &game.players[player];
+        combat::choose_blockers(
+            game.players[game.current_player_index]
+                .zones
+                .get(&Zone::Battlefield)
+                .unwrap(),
+            &game.combat.attackers,
+            defender.zones.get(&Zone::Battlefield).unwrap(),
+            defender.life,
+        )
+    }
+}
+
+/// Baseline pilot that picks uniformly among its legal options, passing being one of them.
+/// Opening hands are still kept by `mulligan`.
+#[derive(Clone, Debug)]
+pub struct RandomMoves
+{
+    pub mulligan: Arc<dyn MulliganStrategy>,
+}
+
+impl Default for RandomMoves
+{
+    fn default() -> Self
+    {
+        RandomMoves {
+            mulligan: Arc::new(KeepAll),
+        }
+    }
+}
+
+// One of `options` or nothing, all equally likely
+fn pick_or_pass<T>(options: Vec<T>, rng: &mut ChaCha8Rng) -> Option<T>
+{
+    let mut options = options;
+    let pick = rng.gen_range(0..=options.len());
+    (pick < options.len()).then(|| options.swap_remove(pick))
+}
+
+impl PlayerStrategy for RandomMoves
+{
+    fn name(&self) -> String
+    {
+        String::from("random")
+    }
+
+    fn mulligan(&self) -> &dyn MulliganStrategy
+    {
+        self.mulligan.as_ref()
+    }
+
+    fn land_drop(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<usize>
+    {
+        let hand = game.players[player].zones.get(&Zone::Hand).unwrap();
+        let lands: Vec<usize> = (0..hand.len())
+            .filter(|i| crate::faces::can_be_land(&hand[*i]))
+            .collect();
+        pick_or_pass(lands, rng)
+    }
+
+    fn main_phase_cast(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Option<Cast>
+    {
+        pick_or_pass(game.castable_spells(player), rng)
+    }
+
+    fn respond(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng) -> Option<Cast>
+    {
+        pick_or_pass(game.castable_instants(player), rng)
+    }
+
+    fn attackers(
+        &self,
+        game: &GameState,
+        player: usize,
+        _defending_player: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize>
+    {
+        game.components(player)
+            .ready_attackers()
+            .into_iter()
+            .filter(|_| rng.gen_bool(0.5))
+            .collect()
+    }
+
+    // Every blocker picks one of the attackers it can block, or none
+    fn blockers(&self, game: &GameState, player: usize, rng: &mut ChaCha8Rng)
+    -> Vec<(usize, usize)>
+    {
+        let attacking = game.players[game.current_player_index]
+            .zones
+            .get(&Zone::Battlefield)
+            .unwrap();
+        let defending = game.players[player].zones.get(&Zone::Battlefield).unwrap();
+        (0..defending.len())
+            .filter_map(|blocker| {
+                let options: Vec<usize> = game
+                    .combat
+                    .attackers
+                    .iter()
+                    .copied()
+                    .filter(|a| combat::can_block_attacker(&defending[blocker], &attacking[*a]))
+                    .collect();
+                pick_or_pass(options, rng).map(|attacker| (blocker, attacker))
+            })
+            .collect()
+    }
+
+    fn discards(&self, hand: &[Card], count: usize, rng: &mut ChaCha8Rng) -> Vec<usize>
+    {
+        let indices: Vec<usize> = (0..hand.len()).collect();
+        indices.choose_multiple(rng, count).copied().collect()
+    }
+}
+
+/// Pilot selected on the command line or in a batch config.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Pilot
+{
+    #[default]
+    CurveOut,
+    Random,
+    Mcts(MctsBudget),
+    Heuristic(HeuristicWeights),
+}
+
+impl Pilot
+{
+    pub fn strategy(self, mulligan: Arc<dyn MulliganStrategy>) -> Arc<dyn PlayerStrategy>
+    {
+        match self {
+            Pilot::CurveOut => Arc::new(CurveOut { mulligan }),
+            Pilot::Random => Arc::new(RandomMoves { mulligan }),
+            Pilot::Mcts(budget) => Arc::new(Mcts {
+                budget,
+                mulligan,
+                ..Mcts::default()
+            }),
+            Pilot::Heuristic(weights) => Arc::new(Heuristic { weights, mulligan }),
+        }
+    }
+}
+
+impl FromStr for Pilot
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s {
+            "curve-out" | "goldfish" => Ok(Pilot::CurveOut),
+            "random" => Ok(Pilot::Random),
+            "mcts" => Ok(Pilot::Mcts(MctsBudget::default())),
+            "heuristic" => Ok(Pilot::Heuristic(HeuristicWeights::default())),
+            _ => match s.strip_prefix("mcts-") {
+                Some(budget) => budget.parse().map(Pilot::Mcts),
+                None => Err(format!(
+                    "unknown pilot '{}' (expected curve-out, random, heuristic, mcts, mcts-ROLLOUTS or mcts-MILLISms)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{Deck, forest, grizzly_bears};
+    use crate::rules::GameRules;
+
+    fn play(strategy: Arc<dyn PlayerStrategy>, seed: u64) -> GameState
+    {
+        let deck = Deck::example();
+        let mut game = GameState::with_rules(
+            &[&deck, &Deck::goldfish()],
+            seed,
+            &GameRules::default(),
+            &[strategy, Arc::new(CurveOut::default())],
+        );
+        while !game.is_game_over() {
+            game.step();
+        }
+        game
+    }
+
+    #[test]
+    fn random_pilot_is_reproducible_and_worse_than_curving_out()
+    {
+        let random = Arc::new(RandomMoves::default());
+        assert_eq!(play(random.clone(), 3).turns, play(random.clone(), 3).turns);
+
+        let turns = |strategy: Arc<dyn PlayerStrategy>| {
+            (0..20)
+                .map(|seed| play(strategy.clone(), seed).turns)
+                .sum::<u32>()
+        };
+        assert!(turns(random) > turns(Arc::new(CurveOut::default())));
+    }
+
+    #[test]
+    fn pilots_only_make_legal_choices()
+    {
+        let deck = Deck::new(vec![
+            forest(),
+            grizzly_bears(),
+            crate::card::card_by_name("Lightning Bolt").unwrap(),
+        ]);
+        let mut game = GameState::with_decks(&[&deck, &deck], 0);
+        let mut rng = game.rng.clone();
+
+        for _ in 0..20 {
+            let land = RandomMoves::default().land_drop(&game, 0, &mut rng);
+            assert!(land.is_none_or(|i| {
+                game.players[0].zones.get(&Zone::Hand).unwrap()[i].is_type(CardType::Land)
+            }));
+            // Nothing is castable without lands on the battlefield
+            assert_eq!(
+                RandomMoves::default().main_phase_cast(&game, 0, &mut rng),
+                None
+            );
+        }
+        game.players[0]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(forest());
+        game.players[0]
+            .zones
+            .get_mut(&Zone::Battlefield)
+            .unwrap()
+            .push(forest());
+        let cast = CurveOut::default()
+            .main_phase_cast(&game, 0, &mut rng)
+            .unwrap();
+        assert_eq!(
+            game.players[0].zones.get(&Zone::Hand).unwrap()[cast.index].name,
+            "Grizzly Bears"
+        );
+        assert_eq!("random".parse::<Pilot>(), Ok(Pilot::Random));
+    }
+}
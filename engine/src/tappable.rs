@@ -1,4 +1,46 @@
-use crate::card::{Card, CardFragmentKind, TappableFragment};
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, CardFragmentKind, CardType, TappableFragment};
+use crate::mana::{self, ManaColor};
+
+/// When a permanent enters the battlefield tapped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnterTappedRule
+{
+    #[default]
+    Never,
+    Always,
+    // Tapped if its controller already has at least this many other lands
+    IfYouControlLands(u32),
+    // Untapped only if its controller has a basic land of one of these colors ("unless you
+    // control a Swamp or a Mountain")
+    Checkland(Vec<ManaColor>),
+    // Untapped only if its controller has two or fewer other lands
+    Fastland,
+}
+
+impl EnterTappedRule
+{
+    pub fn is_never(&self) -> bool
+    {
+        *self == EnterTappedRule::Never
+    }
+
+    /// Whether a permanent with this rule enters tapped onto `battlefield` (without it).
+    pub fn applies(&self, battlefield: &[Card]) -> bool
+    {
+        let lands = battlefield.iter().filter(|c| c.is_type(CardType::Land));
+        match self {
+            EnterTappedRule::Never => false,
+            EnterTappedRule::Always => true,
+            EnterTappedRule::IfYouControlLands(count) => lands.count() as u32 >= *count,
+            EnterTappedRule::Checkland(colors) => !lands
+                .filter_map(|c| mana::basic_land_color(&c.name))
+                .any(|color| colors.contains(&color)),
+            EnterTappedRule::Fastland => EnterTappedRule::IfYouControlLands(3).applies(battlefield),
+        }
+    }
+}
 
 pub fn is_tappable(card: &Card) -> bool
 {
@@ -7,18 +49,51 @@ pub fn is_tappable(card: &Card) -> bool
 
 pub fn is_tapped(card: &Card) -> bool
 {
-    card.fragments.get(&CardFragmentKind::Tappable)
-        .and_then(|f| f.as_any().downcast_ref::<TappableFragment>().map(|tf| tf.tapped))
+    card.fragment::<TappableFragment>()
+        .map(|tf| tf.tapped)
         .unwrap_or(false)
 }
 
 pub fn set_tapped(card: &mut Card, value: bool)
 {
-    if let Some(f) = card.fragments.get_mut(&CardFragmentKind::Tappable)
+    if let Some(tf) = card.fragment_mut::<TappableFragment>() {
+        tf.tapped = value;
+    }
+}
+
+pub fn enters_tapped(card: &Card, battlefield: &[Card]) -> bool
+{
+    card.fragment::<TappableFragment>()
+        .map(|tf| tf.enters_tapped.applies(battlefield))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::card_by_name;
+
+    #[test]
+    fn lands_enter_tapped_by_their_rule()
     {
-        if let Some(tf) = f.as_any_mut().downcast_mut::<TappableFragment>()
-        {
-            tf.tapped = value;
-        }
+        let forest = card_by_name("Forest").unwrap();
+        let island = card_by_name("Island").unwrap();
+        assert!(!enters_tapped(&forest, &[]));
+        assert!(enters_tapped(
+            &card_by_name("Gruul Guildgate").unwrap(),
+            &[]
+        ));
+
+        let crag = card_by_name("Rootbound Crag").unwrap();
+        assert!(enters_tapped(&crag, std::slice::from_ref(&island)));
+        assert!(!enters_tapped(&crag, &[island.clone(), forest.clone()]));
+
+        let gorge = card_by_name("Copperline Gorge").unwrap();
+        assert!(!enters_tapped(&gorge, &[forest.clone(), forest.clone()]));
+        assert!(enters_tapped(
+            &gorge,
+            &[forest.clone(), forest.clone(), island]
+        ));
     }
 }
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::card::{
+    Card, CardFragmentKind, CardId, CardType, CreatureFragment, CreatureStats, Fragment,
+    KeywordsFragment, ManaSourceFragment, SacrificeAbilityFragment, TappableFragment,
+    TokenFragment,
+};
+use crate::effect::Effect;
+use crate::keyword::Keyword;
+use crate::mana::{ManaColor, ManaCost};
+use crate::tappable::EnterTappedRule;
+
+/// The predefined artifact tokens, each with an ability that sacrifices it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArtifactToken
+{
+    // {T}, Sacrifice: Add one mana of any color
+    Treasure,
+    // {2}, Sacrifice: Draw a card
+    Clue,
+    // {2}, {T}, Sacrifice: You gain 3 life
+    Food,
+    // {1}, {T}, Discard a card, Sacrifice: Draw a card
+    Blood,
+}
+
+impl ArtifactToken
+{
+    pub fn name(self) -> &'static str
+    {
+        match self {
+            ArtifactToken::Treasure => "Treasure",
+            ArtifactToken::Clue => "Clue",
+            ArtifactToken::Food => "Food",
+            ArtifactToken::Blood => "Blood",
+        }
+    }
+
+    fn ability(self) -> SacrificeAbilityFragment
+    {
+        let (cost, tap, effects) = match self {
+            ArtifactToken::Treasure => (0, true, Vec::new()),
+            ArtifactToken::Clue => (2, false, vec![Effect::DrawCards(1)]),
+            ArtifactToken::Food => (2, true, vec![Effect::GainLife(3)]),
+            ArtifactToken::Blood => (1, true, vec![Effect::Discard(1), Effect::DrawCards(1)]),
+        };
+        SacrificeAbilityFragment {
+            cost: ManaCost::generic(cost),
+            tap,
+            effects,
+        }
+    }
+}
+
+/// A Treasure, Clue, Food or Blood token.
+pub fn artifact_token(kind: ArtifactToken) -> Card
+{
+    let mut fragments: HashMap<CardFragmentKind, Arc<dyn Fragment>> = HashMap::new();
+    fragments.insert(
+        CardFragmentKind::Tappable,
+        Arc::new(TappableFragment {
+            tapped: false,
+            enters_tapped: EnterTappedRule::Never,
+        }),
+    );
+    fragments.insert(CardFragmentKind::Token, Arc::new(TokenFragment {}));
+    fragments.insert(CardFragmentKind::SacrificeAbility, Arc::new(kind.ability()));
+    if kind == ArtifactToken::Treasure {
+        let colors = vec![
+            ManaColor::White,
+            ManaColor::Blue,
+            ManaColor::Black,
+            ManaColor::Red,
+            ManaColor::Green,
+        ];
+        fragments.insert(
+            CardFragmentKind::ManaSource,
+            Arc::new(ManaSourceFragment { colors, amount: 1 }),
+        );
+    }
+
+    Card {
+        id: CardId::default(),
+        name: kind.name().to_string(),
+        card_types: vec![CardType::Artifact],
+        cost: ManaCost::default(),
+        fragments,
+    }
+}
+
+pub fn sacrifice_ability(card: &Card) -> Option<&SacrificeAbilityFragment>
+{
+    card.fragment::<SacrificeAbilityFragment>()
+}
+
+/// Whether tapping `card` for mana sacrifices it, like a Treasure's.
+pub fn sacrificed_for_mana(card: &Card) -> bool
+{
+    sacrifice_ability(card).is_some_and(|a| a.effects.is_empty())
+}
+
+/// A creature token, e.g. "1/1 Token". Tokens are created by effects rather than drawn, and stop
+/// existing once they leave the battlefield.
+pub fn creature_token(power: u8, toughness: u8, keywords: &[Keyword]) -> Card
+{
+    let mut fragments: HashMap<CardFragmentKind, Arc<dyn Fragment>> = HashMap::new();
+    fragments.insert(
+        CardFragmentKind::Creature,
+        Arc::new(CreatureFragment {
+            stats: CreatureStats { power, toughness },
+            ..CreatureFragment::default()
+        }),
+    );
+    fragments.insert(
+        CardFragmentKind::Tappable,
+        Arc::new(TappableFragment {
+            tapped: false,
+            enters_tapped: EnterTappedRule::Never,
+        }),
+    );
+    fragments.insert(CardFragmentKind::Token, Arc::new(TokenFragment {}));
+    if !keywords.is_empty() {
+        fragments.insert(
+            CardFragmentKind::Keywords,
+            Arc::new(KeywordsFragment {
+                keywords: keywords.to_vec(),
+            }),
+        );
+    }
+
+    Card {
+        id: CardId::default(),
+        name: format!("{}/{} Token", power, toughness),
+        card_types: vec![CardType::Creature],
+        cost: ManaCost::default(),
+        fragments,
+    }
+}
+
+/// A token copy of `card`, or the copy of a spell: its copiable values, which are its name, types,
+/// cost, printed stats and abilities and the face it shows. Counters, damage, attachments, being
+/// tapped and every effect on it aren't copied. A copy of a spell ceases to exist off the stack
+/// like a token, and becomes one if it's a permanent spell.
+pub fn copy_of(card: &Card) -> Card
+{
+    let mut copy = card.clone();
+    copy.id = CardId::default();
+    copy.fragments.remove(&CardFragmentKind::Commander);
+    match card.fragment::<CreatureFragment>() {
+        // An effect that made it a creature isn't part of what's copied
+        Some(cf) if cf.animated => {
+            copy.card_types.retain(|t| *t != CardType::Creature);
+            copy.fragments.remove(&CardFragmentKind::Creature);
+        }
+        Some(cf) => {
+            copy.fragments.insert(
+                CardFragmentKind::Creature,
+                Arc::new(CreatureFragment {
+                    stats: cf.stats,
+                    ..CreatureFragment::default()
+                }),
+            );
+        }
+        None => {}
+    }
+    crate::tappable::set_tapped(&mut copy, false);
+    crate::attachment::attach(&mut copy, None);
+    crate::planeswalker::enter(&mut copy);
+    copy.fragments
+        .insert(CardFragmentKind::Token, Arc::new(TokenFragment {}));
+    copy
+}
+
+pub fn is_token(card: &Card) -> bool
+{
+    card.fragments.contains_key(&CardFragmentKind::Token)
+}
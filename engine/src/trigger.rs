@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, CardId, CardType, TriggerFragment};
+use crate::effect::Effect;
+use crate::event::GameEvent;
+use crate::game::{GameState, Zone};
+
+/// When a triggered ability fires, relative to the card that has it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerCondition
+{
+    EntersBattlefield,
+    Dies,
+    // This or any other creature dies, whoever controlled it
+    CreatureDies,
+    Attacks,
+    // Its controller gains life
+    GainLife,
+    // Its controller casts a noncreature spell
+    CastNoncreature,
+    // ... an instant or sorcery spell
+    CastInstantOrSorcery,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TriggeredAbility
+{
+    pub condition: TriggerCondition,
+    pub effect: Effect,
+}
+
+pub fn triggered_abilities(card: &Card) -> &[TriggeredAbility]
+{
+    card.fragment::<TriggerFragment>()
+        .map(|tf| tf.abilities.as_slice())
+        .unwrap_or(&[])
+}
+
+pub fn add_triggered_ability(card: &mut Card, ability: TriggeredAbility)
+{
+    card.fragment_or_insert_with(|| TriggerFragment {
+        abilities: Vec::new(),
+    })
+    .abilities
+    .push(ability);
+}
+
+/// Abilities that trigger on `event`: (controller, source card, its name, effect), in the order
+/// they should resolve.
+pub fn collect(game: &GameState, event: &GameEvent) -> Vec<(usize, CardId, String, Effect)>
+{
+    // A card that has moved on since still triggers, from wherever it is now
+    let sources: Vec<(usize, &Card, TriggerCondition)> = match event {
+        GameEvent::CardEntered { player, card } => game
+            .card(*card)
+            .map(|c| (*player, c, TriggerCondition::EntersBattlefield))
+            .into_iter()
+            .collect(),
+        GameEvent::CreatureDied { player, card } => {
+            let died = game.card(*card).into_iter().flat_map(|c| {
+                [
+                    (*player, c, TriggerCondition::Dies),
+                    (*player, c, TriggerCondition::CreatureDies),
+                ]
+            });
+            let watchers = (0..game.players.len()).flat_map(|p| {
+                game.players[p]
+                    .zones
+                    .get(&Zone::Battlefield)
+                    .into_iter()
+                    .flatten()
+                    .map(move |c| (p, c, TriggerCondition::CreatureDies))
+            });
+            died.chain(watchers).collect()
+        }
+        GameEvent::AttackDeclared { player, attackers } => attackers
+            .iter()
+            .filter_map(|id| game.card(*id))
+            .map(|c| (*player, c, TriggerCondition::Attacks))
+            .collect(),
+        GameEvent::LifeGained { player, .. } => game.players[*player]
+            .zones
+            .get(&Zone::Battlefield)
+            .into_iter()
+            .flatten()
+            .map(|c| (*player, c, TriggerCondition::GainLife))
+            .collect(),
+        GameEvent::SpellCast { player, card } => {
+            let Some(spell) = game.card(*card) else {
+                return Vec::new();
+            };
+            let mut conditions = Vec::new();
+            if !crate::creature::is_creature(spell) {
+                conditions.push(TriggerCondition::CastNoncreature);
+            }
+            if spell.is_type(CardType::Instant) || spell.is_type(CardType::Sorcery) {
+                conditions.push(TriggerCondition::CastInstantOrSorcery);
+            }
+            game.players[*player]
+                .zones
+                .get(&Zone::Battlefield)
+                .into_iter()
+                .flatten()
+                .flat_map(|c| {
+                    conditions
+                        .iter()
+                        .map(move |condition| (*player, c, *condition))
+                })
+                .collect()
+        }
+        GameEvent::ZoneChanged { .. } | GameEvent::LifeLost { .. } => Vec::new(),
+    };
+
+    let triggered: Vec<(usize, CardId, String, Effect)> = sources
+        .iter()
+        .flat_map(|(player, card, condition)| {
+            triggered_abilities(card)
+                .iter()
+                .filter(move |a| a.condition == *condition)
+                .map(move |a| (*player, card.id, card.name.clone(), a.effect.clone()))
+        })
+        .collect();
+
+    // Prowess is a keyword for the same trigger
+    let triggered: Vec<(usize, CardId, String, Effect)> = triggered
+        .into_iter()
+        .chain(
+            sources
+                .iter()
+                .filter(|(_, card, condition)| {
+                    *condition == TriggerCondition::CastNoncreature
+                        && crate::keyword::any(card, |h| h.pumped_by_noncreature_spells())
+                })
+                .map(|(player, card, _)| {
+                    (
+                        *player,
+                        card.id,
+                        card.name.clone(),
+                        Effect::PumpSelf {
+                            power: 1,
+                            toughness: 1,
+                        },
+                    )
+                }),
+        )
+        .collect();
+
+    // Scripted "enters the battlefield" effects resolve after the card's regular triggers
+    #[cfg(feature = "scripting")]
+    let triggered = triggered
+        .into_iter()
+        .chain(
+            sources
+                .iter()
+                .filter(|(_, _, condition)| *condition == TriggerCondition::EntersBattlefield)
+                .filter_map(|(player, card, _)| {
+                    crate::script::on_etb(card).map(|script| (*player, *card, script))
+                })
+                .flat_map(|(player, card, script)| {
+                    crate::script::run(game, player, script)
+                        .into_iter()
+                        .map(move |effect| (player, card.id, card.name.clone(), effect))
+                }),
+        )
+        .collect();
+
+    triggered
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::game::{Player, PlayerCounter};
+
+pub const POISON_LIMIT: u32 = 10;
+pub const COMMANDER_DAMAGE_LIMIT: u32 = 21;
+
+/// Ways a player can lose. Which ones a game checks comes from its `GameRules`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum WinCondition
+{
+    // Life total of 0 or less
+    LifeTotal,
+    // Drew from an empty library (milled out)
+    EmptyLibrary,
+    // Ten or more poison counters
+    Poison,
+    // 21 or more combat damage from a single commander
+    CommanderDamage,
+}
+
+/// State-based check behind a `WinCondition`. Adding a condition only means adding a variant and
+/// an implementation of this.
+pub trait LossCheck: Sync
+{
+    fn has_lost(&self, player: &Player) -> bool;
+}
+
+struct LifeTotal;
+struct EmptyLibrary;
+struct Poison;
+struct CommanderDamage;
+
+impl LossCheck for LifeTotal
+{
+    fn has_lost(&self, player: &Player) -> bool
+    {
+        player.life <= 0
+    }
+}
+
+impl LossCheck for EmptyLibrary
+{
+    fn has_lost(&self, player: &Player) -> bool
+    {
+        player.drew_from_empty_library
+    }
+}
+
+impl LossCheck for Poison
+{
+    fn has_lost(&self, player: &Player) -> bool
+    {
+        player.counters(PlayerCounter::Poison) >= POISON_LIMIT
+    }
+}
+
+impl LossCheck for CommanderDamage
+{
+    fn has_lost(&self, player: &Player) -> bool
+    {
+        player
+            .commander_damage
+            .iter()
+            .any(|(_, damage)| *damage >= COMMANDER_DAMAGE_LIMIT)
+    }
+}
+
+impl WinCondition
+{
+    pub const ALL: [WinCondition; 4] = [
+        WinCondition::LifeTotal,
+        WinCondition::EmptyLibrary,
+        WinCondition::Poison,
+        WinCondition::CommanderDamage,
+    ];
+
+    pub fn check(self) -> &'static dyn LossCheck
+    {
+        match self {
+            WinCondition::LifeTotal => &LifeTotal,
+            WinCondition::EmptyLibrary => &EmptyLibrary,
+            WinCondition::Poison => &Poison,
+            WinCondition::CommanderDamage => &CommanderDamage,
+        }
+    }
+}
+
+impl fmt::Display for WinCondition
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let name = match self {
+            WinCondition::LifeTotal => "life total",
+            WinCondition::EmptyLibrary => "empty library",
+            WinCondition::Poison => "poison",
+            WinCondition::CommanderDamage => "commander damage",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The first of `conditions` that `player` meets, if any.
+pub fn loss_condition(player: &Player, conditions: &[WinCondition]) -> Option<WinCondition>
+{
+    conditions
+        .iter()
+        .copied()
+        .find(|c| c.check().has_lost(player))
+}
@@ -0,0 +1,38 @@
+use std::process::{Command, Stdio};
+
+#[test]
+fn script_drives_an_interactive_session_to_the_end()
+{
+    let script = std::env::temp_dir().join(format!("tcg-sim-script-{}.txt", std::process::id()));
+    std::fs::write(
+        &script,
+        "# Step once, look at the board, then skip ahead\ns\nb\ng 3\n",
+    )
+    .unwrap();
+
+    // Stdin is closed, so every command has to come from the script
+    let output = Command::new(env!("CARGO_BIN_EXE_engine"))
+        .args([
+            "--games",
+            "10",
+            "--lands",
+            "24",
+            "--nonlands",
+            "36",
+            "--seed",
+            "1",
+            "--no-color",
+            "--script",
+        ])
+        .arg(&script)
+        .stdin(Stdio::null())
+        .output()
+        .expect("run the simulator");
+    let _ = std::fs::remove_file(&script);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("> b\n"), "script commands are echoed");
+    assert!(stdout.contains("Player 1"), "the board was shown");
+    assert!(stdout.contains("Turn: 3"), "the game ran to turn 3");
+}
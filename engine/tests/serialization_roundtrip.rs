@@ -1,7 +1,8 @@
-use engine::{GameState, Zone, CardType};
+use engine::{CardType, GameState, Zone};
 
 #[test]
-fn game_state_roundtrip_serialization() {
+fn game_state_roundtrip_serialization()
+{
     // Create a default game state, serialize to JSON, then deserialize back
     let gs = GameState::new_default();
 
@@ -9,20 +10,37 @@ fn game_state_roundtrip_serialization() {
 
     let gs2: GameState = serde_json::from_str(&json).expect("deserialize GameState");
 
+    // Maps are written in key order, so the same game always saves to the same bytes
+    assert_eq!(
+        json,
+        serde_json::to_string(&gs2).expect("serialize GameState again")
+    );
+
     // Basic structural checks
-    assert_eq!(gs.life, gs2.life);
+    assert_eq!(gs.life(), gs2.life());
     assert_eq!(gs.turns, gs2.turns);
     assert_eq!(gs.step, gs2.step);
 
-    for zone in &[Zone::Library, Zone::Hand, Zone::Battlefield, Zone::Graveyard] {
-        assert_eq!(gs.zones.get(zone).unwrap().len(), gs2.zones.get(zone).unwrap().len());
+    for zone in &[
+        Zone::Library,
+        Zone::Hand,
+        Zone::Battlefield,
+        Zone::Graveyard,
+    ] {
+        assert_eq!(
+            gs.zones().get(zone).unwrap().len(),
+            gs2.zones().get(zone).unwrap().len()
+        );
     }
 
     // Spot-check a sample card if library is non-empty
-    let lib = gs.zones.get(&Zone::Library).unwrap();
-    let lib2 = gs2.zones.get(&Zone::Library).unwrap();
+    let lib = gs.zones().get(&Zone::Library).unwrap();
+    let lib2 = gs2.zones().get(&Zone::Library).unwrap();
     if !lib.is_empty() {
         assert_eq!(lib[0].name, lib2[0].name);
-        assert_eq!(lib[0].is_type(CardType::Creature), lib2[0].is_type(CardType::Creature));
+        assert_eq!(
+            lib[0].is_type(CardType::Creature),
+            lib2[0].is_type(CardType::Creature)
+        );
     }
 }
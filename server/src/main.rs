@@ -1,78 +1,83 @@
-use axum::{routing::{get, post}, Json, Router};
-use std::sync::{Arc, Mutex};
-use engine::{GameState, GameStep};
 use axum::extract::Extension;
-use std::net::SocketAddr;
-use tokio::net::TcpListener;
-use axum::http::StatusCode;
 use axum::extract::Path;
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use axum::{
+    Json, Router,
+    routing::{get, post},
+};
+use engine::{Deck, GameState, GameStep, sim};
+use socket2::{Domain, Socket, Type};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
 use tokio::signal;
-use std::path::PathBuf;
-use socket2::{Socket, Domain, Type};
 
 /// Find the web directory relative to the project root
-fn find_web_dir() -> PathBuf {
+fn find_web_dir() -> PathBuf
+{
     let mut current = std::env::current_dir().expect("Failed to get current directory");
-    
+
     // If we're in the target directory or deeper, go up to find project root
     loop {
         let web_path = current.join("web");
         if web_path.exists() && web_path.is_dir() {
             return current;
         }
-        
+
         if !current.pop() {
             break;
         }
     }
-    
+
     // Fallback to current directory
     std::env::current_dir().expect("Failed to get current directory")
 }
 
-fn web_path(file: &str) -> String {
+fn web_path(file: &str) -> String
+{
     let web_dir = find_web_dir();
     web_dir.join(file).to_string_lossy().to_string()
 }
 
-fn create_listener_with_reuse(addr: &std::net::SocketAddr) -> std::io::Result<TcpListener> {
+fn create_listener_with_reuse(addr: &std::net::SocketAddr) -> std::io::Result<TcpListener>
+{
     let socket = Socket::new(Domain::IPV4, Type::STREAM, None)?;
     socket.set_reuse_address(true)?;
     socket.bind(&(*addr).into())?;
     socket.listen(128)?;
-    
+
     let std_listener = std::net::TcpListener::from(socket);
     std_listener.set_nonblocking(true)?;
-    Ok(TcpListener::from_std(std_listener)?)
+    TcpListener::from_std(std_listener)
 }
 
 #[cfg(unix)]
-fn kill_process_on_port(port: u16) {
+fn kill_process_on_port(port: u16)
+{
     // Use lsof to find the process using the port and kill it
     let output = std::process::Command::new("lsof")
-        .args(&["-ti", &format!(":{}", port)])
+        .args(["-ti", &format!(":{}", port)])
         .output();
-    
-    if let Ok(output) = output {
-        if output.status.success() {
-            if let Ok(pid_str) = String::from_utf8(output.stdout) {
-                let pid = pid_str.trim().parse::<u32>();
-                if let Ok(pid) = pid {
-                    let _ = std::process::Command::new("kill")
-                        .arg("-9")
-                        .arg(pid.to_string())
-                        .output();
-                    eprintln!("Killed existing process (PID: {}) on port {}", pid, port);
-                }
-            }
-        }
+
+    if let Ok(output) = output
+        && output.status.success()
+        && let Ok(pid_str) = String::from_utf8(output.stdout)
+        && let Ok(pid) = pid_str.trim().parse::<u32>()
+    {
+        let _ = std::process::Command::new("kill")
+            .arg("-9")
+            .arg(pid.to_string())
+            .output();
+        eprintln!("Killed existing process (PID: {}) on port {}", pid, port);
     }
 }
 
 #[cfg(not(unix))]
-fn kill_process_on_port(_port: u16) {
+fn kill_process_on_port(_port: u16)
+{
     // Windows would need a different approach (netstat + taskkill)
     // For now, just inform the user
     eprintln!("Port is already in use. Please close the existing process manually.");
@@ -94,16 +99,19 @@ async fn main()
         .route("/all", post(post_all))
         .route("/restart", post(post_restart))
         .route("/music-list", get(get_music_list))
-        .route("/shutdown", post({
-            let flag = shutdown_flag.clone();
-            move || {
-                let flag = flag.clone();
-                async move {
-                    flag.store(true, Ordering::Relaxed);
-                    StatusCode::OK
+        .route(
+            "/shutdown",
+            post({
+                let flag = shutdown_flag.clone();
+                move || {
+                    let flag = flag.clone();
+                    async move {
+                        flag.store(true, Ordering::Relaxed);
+                        StatusCode::OK
+                    }
                 }
-            }
-        }))
+            }),
+        )
         .layer(Extension(game.clone()));
 
     // Static routes for the web/ directory (simple handlers)
@@ -116,7 +124,7 @@ async fn main()
         .route("/music/*file", get(serve_music));
 
     let addr: SocketAddr = "0.0.0.0:3000".parse().unwrap();
-    
+
     // Try to create socket with SO_REUSEADDR enabled to allow immediate port reuse
     // If the port is already in use, kill the existing process and retry
     let listener = {
@@ -129,7 +137,9 @@ async fn main()
                 }
                 Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempt < 3 => {
                     if attempt == 1 {
-                        eprintln!("Port 3000 is already in use. Attempting to kill the existing process...");
+                        eprintln!(
+                            "Port 3000 is already in use. Attempting to kill the existing process..."
+                        );
                         kill_process_on_port(3000);
                         std::thread::sleep(std::time::Duration::from_millis(500));
                     } else {
@@ -148,7 +158,10 @@ async fn main()
     };
 
     println!("Server running at http://{}", addr);
-    println!("Press Ctrl+C to stop the server, or visit http://{}:3000 and click 'Stop Server'", addr.ip());
+    println!(
+        "Press Ctrl+C to stop the server, or visit http://{}:3000 and click 'Stop Server'",
+        addr.ip()
+    );
 
     // Spawn a background task to check for shutdown flag
     let shutdown_flag_clone = shutdown_flag.clone();
@@ -178,17 +191,20 @@ async fn main()
     }
 }
 
-async fn get_state(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState> {
+async fn get_state(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState>
+{
     Json(game.lock().unwrap().clone())
 }
 
-async fn post_step(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState> {
+async fn post_step(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState>
+{
     let mut g = game.lock().unwrap();
     g.step();
     Json(g.clone())
 }
 
-async fn post_turn(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState> {
+async fn post_turn(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState>
+{
     let mut g = game.lock().unwrap();
     let start_turn = g.turns;
     while g.turns == start_turn && g.step != GameStep::GameOver {
@@ -197,7 +213,8 @@ async fn post_turn(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<Ga
     Json(g.clone())
 }
 
-async fn post_game(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState> {
+async fn post_game(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState>
+{
     let mut g = game.lock().unwrap();
     while g.step != GameStep::GameOver {
         g.step();
@@ -205,21 +222,17 @@ async fn post_game(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<Ga
     Json(g.clone())
 }
 
-async fn post_deck(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<serde_json::Value> {
+async fn post_deck(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<serde_json::Value>
+{
     // Run 10,000 games and track average turns
-    let mut total_turns = 0;
-    for _ in 0..10000 {
-        let mut g = GameState::new_default();
-        while g.step != GameStep::GameOver {
-            g.step();
-        }
-        total_turns += g.turns as u64;
-    }
+    let config = sim::BatchConfig::new(Deck::example());
+    let outcomes = sim::run_batch_parallel(&config, 10000);
+    let total_turns: u64 = outcomes.iter().map(|o| o.turns as u64).sum();
     let avg_turns = total_turns as f64 / 10000.0;
-    
+
     let mut g = game.lock().unwrap();
     *g = GameState::new_default();
-    
+
     serde_json::json!({
         "avg_turns": avg_turns,
         "total_games": 10000,
@@ -228,21 +241,17 @@ async fn post_deck(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<se
     .into()
 }
 
-async fn post_all(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<serde_json::Value> {
+async fn post_all(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<serde_json::Value>
+{
     // For now, same as deck - could be extended to run multiple deck configs
-    let mut total_turns = 0;
-    for _ in 0..10000 {
-        let mut g = GameState::new_default();
-        while g.step != GameStep::GameOver {
-            g.step();
-        }
-        total_turns += g.turns as u64;
-    }
+    let config = sim::BatchConfig::new(Deck::example());
+    let outcomes = sim::run_batch_parallel(&config, 10000);
+    let total_turns: u64 = outcomes.iter().map(|o| o.turns as u64).sum();
     let avg_turns = total_turns as f64 / 10000.0;
-    
+
     let mut g = game.lock().unwrap();
     *g = GameState::new_default();
-    
+
     serde_json::json!({
         "avg_turns": avg_turns,
         "total_games": 10000,
@@ -251,64 +260,71 @@ async fn post_all(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<ser
     .into()
 }
 
-async fn post_restart(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState> {
+async fn post_restart(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState>
+{
     let mut g = game.lock().unwrap();
     *g = GameState::new_default();
     Json(g.clone())
 }
 
-async fn get_music_list() -> Json<serde_json::Value> {
+async fn get_music_list() -> Json<serde_json::Value>
+{
     let mut music_files = Vec::new();
     let music_dir = format!("{}/web/music", find_web_dir().to_string_lossy());
-    
+
     // List all files in the music directory
     if let Ok(entries) = std::fs::read_dir(&music_dir) {
         for entry in entries.flatten() {
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.is_file() {
-                    if let Some(file_name) = entry.file_name().to_str() {
-                        // Only include audio files
-                        if file_name.ends_with(".mp3") || file_name.ends_with(".wav") || 
-                           file_name.ends_with(".ogg") || file_name.ends_with(".flac") ||
-                           file_name.ends_with(".m4a") || file_name.ends_with(".aac") {
-                            music_files.push(file_name.to_string());
-                        }
-                    }
+            if let Ok(metadata) = entry.metadata()
+                && metadata.is_file()
+                && let Some(file_name) = entry.file_name().to_str()
+            {
+                // Only include audio files
+                if file_name.ends_with(".mp3")
+                    || file_name.ends_with(".wav")
+                    || file_name.ends_with(".ogg")
+                    || file_name.ends_with(".flac")
+                    || file_name.ends_with(".m4a")
+                    || file_name.ends_with(".aac")
+                {
+                    music_files.push(file_name.to_string());
                 }
             }
         }
     }
-    
+
     Json(serde_json::json!({
         "files": music_files
     }))
 }
 
-async fn index() -> impl IntoResponse {
+async fn index() -> impl IntoResponse
+{
     match tokio::fs::read_to_string(web_path("web/index.html")).await {
         Ok(s) => ([("content-type", "text/html; charset=utf-8")], s).into_response(),
         Err(_) => (StatusCode::NOT_FOUND, "Not Found").into_response(),
     }
 }
 
-async fn js() -> impl IntoResponse {
+async fn js() -> impl IntoResponse
+{
     match tokio::fs::read_to_string(web_path("web/app.js")).await {
         Ok(s) => ([("content-type", "application/javascript")], s).into_response(),
         Err(_) => (StatusCode::NOT_FOUND, "Not Found").into_response(),
     }
 }
 
-async fn css() -> impl IntoResponse {
+async fn css() -> impl IntoResponse
+{
     match tokio::fs::read_to_string(web_path("web/style.css")).await {
         Ok(s) => ([("content-type", "text/css")], s).into_response(),
         Err(_) => (StatusCode::NOT_FOUND, "Not Found").into_response(),
     }
 }
 
-async fn serve_card(Path(file): Path<String>) -> impl IntoResponse 
+async fn serve_card(Path(file): Path<String>) -> impl IntoResponse
 {
-    if file.contains("..") 
-    {
+    if file.contains("..") {
         return (StatusCode::BAD_REQUEST, "Invalid path").into_response();
     }
 
@@ -318,10 +334,9 @@ async fn serve_card(Path(file): Path<String>) -> impl IntoResponse
     }
 }
 
-async fn serve_music(Path(file): Path<String>) -> impl IntoResponse 
+async fn serve_music(Path(file): Path<String>) -> impl IntoResponse
 {
-    if file.contains("..") 
-    {
+    if file.contains("..") {
         return (StatusCode::BAD_REQUEST, "Invalid path").into_response();
     }
 
@@ -338,9 +353,9 @@ async fn serve_music(Path(file): Path<String>) -> impl IntoResponse
     } else {
         "audio/mpeg"
     };
-    
+
     match tokio::fs::read(web_path(&format!("web/music/{}", file))).await {
         Ok(bytes) => ([("content-type", content_type)], bytes).into_response(),
         Err(_) => (StatusCode::NOT_FOUND, "Not Found").into_response(),
     }
-}
\ No newline at end of file
+}
@@ -119,7 +119,9 @@ function formatPhase(phase) {
         "Upkeep": "Upkeep",
         "Draw": "Draw",
         "Main": "Main",
-        "Combat": "Combat",
+        "DeclareAttackers": "Declare Attackers",
+        "DeclareBlockers": "Declare Blockers",
+        "CombatDamage": "Combat Damage",
         "EndTurn": "End Turn",
         "GameOver": "Game Over"
     };